@@ -0,0 +1,19 @@
+#!/bin/sh
+# Run the bar against fake data sources in a broadway session, viewable in a
+# browser at http://127.0.0.1:8085. Pass extra arguments through, for example
+# `scripts/headless.sh --preview tray`. The tray is filled with fake items
+# covering icon names, pixmaps, attention states, tooltips and nested menus.
+# The GTK tests need a display as well, while this runs they can use it with
+# `GDK_BACKEND=broadway BROADWAY_DISPLAY=:5 cargo test`.
+set -eu
+
+display=:5
+port=$((8080 + ${display#:}))
+
+broadwayd "$display" &
+broadway_pid=$!
+trap 'kill "$broadway_pid"' EXIT INT TERM
+
+echo "Broadway display on http://127.0.0.1:$port"
+GDK_BACKEND=broadway BROADWAY_DISPLAY="$display" BLADE_BAR_MOCK=1 \
+    cargo run --features mock -- "$@"
@@ -0,0 +1,82 @@
+// Accessibility profile: high-contrast theme, larger text and icons and no animations,
+// toggled from the bar and remembered across restarts.
+
+use std::cell::{Cell, RefCell};
+use tracing::info;
+
+use crate::config::{AccessibilityConfig, Config};
+use crate::power;
+use crate::state;
+use crate::style;
+use crate::themes;
+
+/// Bundled theme the profile switches to
+const THEME: &str = "high-contrast";
+
+/// Sizes the factor is applied to when the configuration sets none
+const DEFAULT_FONT_SIZE: f64 = 11.0;
+const DEFAULT_ICON_SIZE: f64 = 16.0;
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+    /// Profile settings and the configured font size, kept for toggling
+    static SETTINGS: RefCell<(AccessibilityConfig, Option<f64>)> =
+        RefCell::new((AccessibilityConfig::default(), None));
+}
+
+/// Whether the accessibility profile is active
+pub fn is_enabled() -> bool {
+    ENABLED.with(|enabled| enabled.get())
+}
+
+/// Apply the profile as last toggled, at startup and after a configuration reload
+pub fn configure(config: &Config) {
+    SETTINGS.with(|settings| {
+        *settings.borrow_mut() = (config.accessibility.clone(), config.bar.font_size);
+    });
+    let enabled = state::get().accessibility;
+    ENABLED.with(|stored| stored.set(enabled));
+    apply();
+}
+
+/// Switch the profile on or off and remember the choice
+pub fn set_enabled(enabled: bool) {
+    if enabled == is_enabled() {
+        return;
+    }
+    ENABLED.with(|stored| stored.set(enabled));
+    info!(
+        "Accessibility profile {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    state::update(|state| state.accessibility = enabled);
+    apply();
+}
+
+fn apply() {
+    let enabled = is_enabled();
+
+    let css = if enabled {
+        SETTINGS.with(|settings| {
+            let (config, font_size) = &*settings.borrow();
+            let scale = config.scale.max(1.0);
+            format!(
+                "{}\n\
+                 .main-window, .main-window * {{ font-size: {:.1}pt; }}\n\
+                 .main-window image {{ -gtk-icon-size: {:.0}px; }}\n\
+                 .main-window * {{ transition: none; animation: none; }}\n",
+                themes::preset(THEME).unwrap_or_default(),
+                font_size.unwrap_or(DEFAULT_FONT_SIZE) * scale,
+                DEFAULT_ICON_SIZE * scale
+            )
+        })
+    } else {
+        String::new()
+    };
+    style::set_accessibility_css(&css);
+
+    // Power saving turns animations off as well, only restore them when neither wants that
+    if let Some(settings) = gtk4::Settings::default() {
+        settings.set_gtk_enable_animations(!enabled && !power::is_power_saving());
+    }
+}
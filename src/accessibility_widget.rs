@@ -0,0 +1,54 @@
+use gtk4::prelude::*;
+use gtk4::{Button, Image};
+
+use crate::accessibility;
+use crate::control;
+use crate::module::BarModule;
+
+/// One-click switch for the high-contrast, large-text accessibility profile
+pub struct AccessibilityWidget {
+    pub button: Button,
+}
+
+impl AccessibilityWidget {
+    pub fn new() -> Self {
+        let button = Button::new();
+        button.add_css_class("accessibility-button");
+        button.set_child(Some(&Image::from_icon_name(
+            "preferences-desktop-accessibility-symbolic",
+        )));
+
+        button.connect_clicked(|button| {
+            accessibility::set_enabled(!accessibility::is_enabled());
+            show(button);
+            control::module_updated("accessibility");
+        });
+        show(&button);
+
+        AccessibilityWidget { button }
+    }
+}
+
+fn show(button: &Button) {
+    if accessibility::is_enabled() {
+        button.add_css_class("active");
+        button.set_tooltip_text(Some("Accessibility profile on"));
+    } else {
+        button.remove_css_class("active");
+        button.set_tooltip_text(Some("Accessibility profile off"));
+    }
+}
+
+impl BarModule for AccessibilityWidget {
+    fn name(&self) -> &'static str {
+        "accessibility"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn refresh(&self) {
+        show(&self.button);
+    }
+}
@@ -0,0 +1,296 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, Orientation, Popover};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::mpsc::unbounded_channel;
+use tracing::{info, warn};
+
+use crate::command::{spawn_piped, watch_lines};
+use crate::config::{AudioProfile, AudioProfilesConfig};
+use crate::module::{BarModule, InitFuture};
+use crate::state;
+
+/// How long to wait for the rest of a burst of server events before querying the devices
+const EVENT_SETTLE_DELAY: Duration = Duration::from_millis(50);
+
+/// Switches default sink and source together between saved profiles
+pub struct AudioProfilesWidget {
+    pub button: Button,
+    state: Rc<AudioProfilesState>,
+}
+
+struct AudioProfilesState {
+    label: Label,
+    popover: Popover,
+    list: GtkBox,
+    profiles: Vec<AudioProfile>,
+    auto_switch: bool,
+    /// Which profiles had all their devices present on the last refresh
+    available: RefCell<Vec<bool>>,
+}
+
+impl AudioProfilesWidget {
+    pub fn new(config: &AudioProfilesConfig) -> Option<Self> {
+        if config.profiles.is_empty() {
+            warn!("Audio profiles module has no profiles configured");
+            return None;
+        }
+
+        let button = Button::new();
+        button.add_css_class("audio-profiles-button");
+
+        let label = Label::new(Some("Audio"));
+        label.add_css_class("audio-profiles-label");
+        button.set_child(Some(&label));
+
+        let popover = Popover::new();
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let list = GtkBox::new(Orientation::Vertical, 2);
+        list.add_css_class("menu");
+        popover.set_child(Some(&list));
+
+        let state = Rc::new(AudioProfilesState {
+            label,
+            popover: popover.clone(),
+            list,
+            profiles: config.profiles.clone(),
+            auto_switch: config.auto_switch,
+            available: RefCell::new(vec![false; config.profiles.len()]),
+        });
+
+        button.connect_clicked(move |_| {
+            popover.popup();
+        });
+
+        Some(AudioProfilesWidget { button, state })
+    }
+}
+
+impl AudioProfilesState {
+    async fn refresh(self: &Rc<Self>) {
+        let (sinks, sources, default_sink, default_source) = tokio::join!(
+            list_devices("sinks"),
+            list_devices("sources"),
+            get_default("get-default-sink"),
+            get_default("get-default-source"),
+        );
+
+        let available: Vec<bool> = self
+            .profiles
+            .iter()
+            .map(|profile| {
+                find_device(&sinks, profile.sink.as_deref()).is_some()
+                    && find_device(&sources, profile.source.as_deref()).is_some()
+            })
+            .collect();
+
+        // A profile whose devices just appeared means something was plugged in (docked)
+        let newly_available = {
+            let previous = self.available.borrow();
+            available
+                .iter()
+                .zip(previous.iter())
+                .position(|(now, before)| *now && !*before)
+        };
+        *self.available.borrow_mut() = available.clone();
+
+        if let Some(index) = newly_available.filter(|_| self.auto_switch) {
+            self.apply(&self.profiles[index], &sinks, &sources).await;
+            // Defaults changed, the server change event triggers another refresh
+            return;
+        }
+
+        let active = self.profiles.iter().position(|profile| {
+            matches_default(&sinks, profile.sink.as_deref(), default_sink.as_deref())
+                && matches_default(&sources, profile.source.as_deref(), default_source.as_deref())
+        });
+
+        match active {
+            Some(index) => {
+                self.label.set_text(&self.profiles[index].name);
+                self.label.set_tooltip_text(Some(&format!(
+                    "Output: {}\nInput: {}",
+                    default_sink.as_deref().unwrap_or("unknown"),
+                    default_source.as_deref().unwrap_or("unknown")
+                )));
+            }
+            None => {
+                self.label.set_text("Audio");
+                self.label.set_tooltip_text(Some("No matching audio profile"));
+            }
+        }
+
+        self.rebuild_list(&available, active);
+    }
+
+    fn rebuild_list(self: &Rc<Self>, available: &[bool], active: Option<usize>) {
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+
+        for (index, profile) in self.profiles.iter().enumerate() {
+            let item = Button::with_label(&profile.name);
+            item.add_css_class("flat");
+            item.add_css_class("menu-item");
+            if active == Some(index) {
+                item.add_css_class("active");
+            }
+            item.set_sensitive(available[index]);
+
+            let state = self.clone();
+            item.connect_clicked(move |_| {
+                state.popover.popdown();
+                let state = state.clone();
+                glib::spawn_future_local(async move {
+                    let (sinks, sources) =
+                        tokio::join!(list_devices("sinks"), list_devices("sources"));
+                    let profile = &state.profiles[index];
+                    state.apply(profile, &sinks, &sources).await;
+                    let sink = find_device(&sinks, profile.sink.as_deref())
+                        .filter(|sink| !sink.is_empty());
+                    if let Some(sink) = sink {
+                        state::update(|state| state.audio_sink = Some(sink.to_string()));
+                    }
+                });
+            });
+
+            self.list.append(&item);
+        }
+    }
+
+    async fn apply(&self, profile: &AudioProfile, sinks: &[String], sources: &[String]) {
+        info!("Switching to audio profile '{}'", profile.name);
+
+        if let Some(sink) = find_device(sinks, profile.sink.as_deref()) {
+            set_default("set-default-sink", sink).await;
+        }
+        if let Some(source) = find_device(sources, profile.source.as_deref()) {
+            set_default("set-default-source", source).await;
+        }
+    }
+}
+
+thread_local! {
+    /// Restored once per start, a reload keeps whatever was chosen since
+    static SINK_RESTORED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Make the sink picked before the last restart the default again, if it is connected
+async fn restore_sink() {
+    if SINK_RESTORED.with(|restored| restored.replace(true)) {
+        return;
+    }
+    let Some(sink) = state::get().audio_sink else {
+        return;
+    };
+    let (sinks, default_sink) =
+        tokio::join!(list_devices("sinks"), get_default("get-default-sink"));
+    if sinks.contains(&sink) && default_sink.as_deref() != Some(sink.as_str()) {
+        info!("Restoring default sink '{}'", sink);
+        set_default("set-default-sink", &sink).await;
+    }
+}
+
+impl BarModule for AudioProfilesWidget {
+    fn name(&self) -> &'static str {
+        "audio_profiles"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            restore_sink().await;
+            self.state.refresh().await;
+
+            // React to devices appearing/disappearing and default device changes
+            // (volume changes show up as 'change' on sink/source and are ignored)
+            let (changed_tx, mut changed_rx) = unbounded_channel::<()>();
+            let watch = watch_lines(&["pactl", "subscribe"], move |line| {
+                let device_added_or_removed = (line.contains("'new'") || line.contains("'remove'"))
+                    && (line.contains(" on sink ") || line.contains(" on source "));
+                let defaults_changed = line.contains(" on server ");
+
+                if device_added_or_removed || defaults_changed {
+                    let _ = changed_tx.send(());
+                }
+            });
+
+            // Ends with the watch, which owns the sender
+            let refresh = async {
+                while changed_rx.recv().await.is_some() {
+                    // A profile switch or a dock reports several devices, answer the burst once
+                    glib::timeout_future(EVENT_SETTLE_DELAY).await;
+                    while changed_rx.try_recv().is_ok() {}
+                    self.state.refresh().await;
+                }
+            };
+
+            tokio::join!(watch, refresh);
+        })
+    }
+}
+
+/// Names of all sinks or sources known to the sound server
+async fn list_devices(kind: &str) -> Vec<String> {
+    let Some(output) = pactl(&["list", "short", kind]).await else {
+        return Vec::new();
+    };
+
+    output
+        .lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .filter(|name| !name.ends_with(".monitor"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// First device whose name contains `pattern`, a missing pattern matches nothing in particular
+fn find_device<'a>(devices: &'a [String], pattern: Option<&str>) -> Option<&'a str> {
+    match pattern {
+        Some(pattern) => devices
+            .iter()
+            .find(|device| device.contains(pattern))
+            .map(String::as_str),
+        None => Some(""),
+    }
+}
+
+fn matches_default(devices: &[String], pattern: Option<&str>, default: Option<&str>) -> bool {
+    match (pattern, default) {
+        (None, _) => true,
+        (Some(_), Some(default)) => find_device(devices, pattern) == Some(default),
+        (Some(_), None) => false,
+    }
+}
+
+async fn get_default(command: &str) -> Option<String> {
+    Some(pactl(&[command]).await?.trim().to_string())
+}
+
+async fn set_default(command: &str, device: &str) {
+    if device.is_empty() {
+        return;
+    }
+    if pactl(&[command, device]).await.is_none() {
+        warn!("Failed to run pactl {} {}", command, device);
+    }
+}
+
+/// Run pactl without blocking the main loop, its output if it succeeded
+async fn pactl(args: &[&str]) -> Option<String> {
+    let argv: Vec<&str> = std::iter::once("pactl")
+        .chain(args.iter().copied())
+        .collect();
+    let process = spawn_piped(&argv)?;
+    let (stdout, _) = process.communicate_utf8_future(None).await.ok()?;
+    if !process.is_successful() {
+        return None;
+    }
+    Some(stdout.map(|stdout| stdout.to_string()).unwrap_or_default())
+}
@@ -0,0 +1,228 @@
+use gtk4::glib::Propagation;
+use gtk4::prelude::*;
+use gtk4::{
+    Box as GtkBox, Button, EventControllerScroll, EventControllerScrollFlags, GestureClick, Label,
+    Orientation, Switch,
+};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::mpsc::unbounded_channel;
+
+use crate::command::{run_shell, spawn_shell, watch_lines};
+use crate::config::AudioConfig;
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::slider_popover::SliderPopover;
+
+/// How long to wait for the rest of a burst of server events before querying the sink
+const EVENT_SETTLE_DELAY: Duration = Duration::from_millis(50);
+
+/// Default sink volume and mute state, updated from sound server events
+pub struct AudioWidget {
+    pub button: Button,
+    state: Rc<AudioState>,
+}
+
+struct AudioState {
+    label: Label,
+    slider: SliderPopover,
+    mute: Switch,
+    /// Set while the mute switch is moved to match the server
+    updating: Cell<bool>,
+    step: u32,
+    max_volume: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SinkStatus {
+    volume: u32,
+    muted: bool,
+}
+
+impl AudioWidget {
+    pub fn new(config: &AudioConfig) -> Self {
+        let button = Button::new();
+        button.add_css_class("audio-button");
+
+        let label = Label::new(Some("VOL: ---%"));
+        label.add_css_class("audio-label");
+        button.set_child(Some(&label));
+
+        // Popover with the volume slider and a mute switch
+        let slider = SliderPopover::new(&button, "Volume", config.max_volume, config.step, |volume| {
+            pactl(&["set-sink-volume", "@DEFAULT_SINK@", &format!("{}%", volume)]);
+        });
+        let mute_row = GtkBox::new(Orientation::Horizontal, 8);
+        let mute_label = Label::new(Some("Mute"));
+        mute_label.set_hexpand(true);
+        mute_label.set_halign(gtk4::Align::Start);
+        let mute = Switch::new();
+        mute_row.append(&mute_label);
+        mute_row.append(&mute);
+        slider.content.append(&mute_row);
+
+        let state = Rc::new(AudioState {
+            label,
+            slider,
+            mute,
+            updating: Cell::new(false),
+            step: config.step,
+            max_volume: config.max_volume,
+        });
+
+        let state_mute = state.clone();
+        state.mute.connect_state_set(move |_, muted| {
+            if !state_mute.updating.get() {
+                pactl(&["set-sink-mute", "@DEFAULT_SINK@", if muted { "1" } else { "0" }]);
+            }
+            Propagation::Proceed
+        });
+
+        // Left click: open the slider
+        let state_click = state.clone();
+        button.connect_clicked(move |_| {
+            state_click.slider.popup();
+        });
+
+        // Middle click: toggle mute
+        let middle_click = GestureClick::new();
+        middle_click.set_button(2);
+        middle_click.connect_released(|_, _, _, _| {
+            pactl(&["set-sink-mute", "@DEFAULT_SINK@", "toggle"]);
+        });
+        button.add_controller(middle_click);
+
+        // Scroll: change volume
+        let scroll = EventControllerScroll::new(
+            EventControllerScrollFlags::VERTICAL | EventControllerScrollFlags::DISCRETE,
+        );
+        let state_scroll = state.clone();
+        scroll.connect_scroll(move |_, _dx, dy| {
+            let state = state_scroll.clone();
+            glib::spawn_future_local(async move {
+                state.change_volume(dy < 0.0).await;
+            });
+            Propagation::Stop
+        });
+        button.add_controller(scroll);
+
+        AudioWidget { button, state }
+    }
+}
+
+impl AudioState {
+    async fn change_volume(&self, up: bool) {
+        let Some(status) = sink_status().await else {
+            return;
+        };
+
+        let volume = if up {
+            (status.volume + self.step).min(self.max_volume.max(status.volume))
+        } else {
+            status.volume.saturating_sub(self.step)
+        };
+
+        pactl(&["set-sink-volume", "@DEFAULT_SINK@", &format!("{}%", volume)]);
+    }
+
+    async fn update(&self) {
+        match sink_status().await {
+            Some(status) => {
+                let icon = if status.muted || status.volume == 0 {
+                    "🔇"
+                } else if status.volume < 50 {
+                    "🔉"
+                } else {
+                    "🔊"
+                };
+                self.label.set_text(&format!("{} {}%", icon, status.volume));
+
+                self.slider.set_max(self.max_volume.max(status.volume));
+                self.slider.set_value(status.volume);
+                self.updating.set(true);
+                self.mute.set_active(status.muted);
+                self.updating.set(false);
+
+                let tooltip = if status.muted {
+                    format!("Muted ({}%)", status.volume)
+                } else {
+                    format!("Volume: {}%", status.volume)
+                };
+                if let Some(parent) = self.label.parent() {
+                    parent.set_tooltip_text(Some(&tooltip));
+                }
+            }
+            None => {
+                self.label.set_text("VOL: N/A");
+                if let Some(parent) = self.label.parent() {
+                    parent.set_tooltip_text(Some("Sound server unavailable"));
+                }
+            }
+        }
+
+        control::module_updated("audio");
+    }
+}
+
+impl BarModule for AudioWidget {
+    fn name(&self) -> &'static str {
+        "audio"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn refresh(&self) {
+        let state = self.state.clone();
+        glib::spawn_future_local(async move {
+            state.update().await;
+        });
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            self.state.update().await;
+
+            // Only query the server when it reports a sink or default device change
+            let (changed_tx, mut changed_rx) = unbounded_channel::<()>();
+            let watch = watch_lines(&["pactl", "subscribe"], move |line| {
+                if line.contains(" on sink ") || line.contains(" on server ") {
+                    let _ = changed_tx.send(());
+                }
+            });
+
+            // Ends with the watch, which owns the sender
+            let refresh = async {
+                while changed_rx.recv().await.is_some() {
+                    // Dragging the slider reports every step, answer the burst once
+                    glib::timeout_future(EVENT_SETTLE_DELAY).await;
+                    while changed_rx.try_recv().is_ok() {}
+                    self.state.update().await;
+                }
+            };
+
+            tokio::join!(watch, refresh);
+        })
+    }
+}
+
+async fn sink_status() -> Option<SinkStatus> {
+    // "Volume: front-left: 32768 /  50% / -18.06 dB,   front-right: ..."
+    let volume_output = run_shell("pactl get-sink-volume @DEFAULT_SINK@").await?;
+    let volume = volume_output
+        .split('/')
+        .nth(1)
+        .and_then(|part| part.trim().trim_end_matches('%').parse::<u32>().ok())?;
+
+    let mute_output = run_shell("pactl get-sink-mute @DEFAULT_SINK@").await?;
+    let muted = mute_output.contains("yes");
+
+    Some(SinkStatus { volume, muted })
+}
+
+/// Change a sink setting without waiting, the server event triggers the update
+fn pactl(args: &[&str]) {
+    spawn_shell(&format!("pactl {}", args.join(" ")));
+}
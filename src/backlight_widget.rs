@@ -0,0 +1,234 @@
+use gio::{FileMonitor, FileMonitorEvent, FileMonitorFlags};
+use gtk4::glib::Propagation;
+use gtk4::prelude::*;
+use gtk4::{Button, EventControllerScroll, EventControllerScrollFlags, Label};
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use tracing::warn;
+
+use crate::config::BacklightConfig;
+use crate::control;
+use crate::module::BarModule;
+use crate::slider_popover::SliderPopover;
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+/// The login session, which may set the brightness of its seat's devices without root
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait LoginSession {
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+}
+
+/// Screen brightness of a backlight device, changed by scrolling or with a slider
+pub struct BacklightWidget {
+    pub button: Button,
+    state: Rc<BacklightState>,
+}
+
+struct BacklightState {
+    device: String,
+    path: PathBuf,
+    max_brightness: u32,
+    step: u32,
+    /// Lowest percentage scrolling goes to, so the screen never turns fully dark
+    min: u32,
+    label: Label,
+    slider: RefCell<Option<SliderPopover>>,
+    session: RefCell<Option<LoginSessionProxy<'static>>>,
+    // Keeps the brightness file watched while the module exists
+    monitor: RefCell<Option<FileMonitor>>,
+}
+
+impl BacklightWidget {
+    pub fn new(config: &BacklightConfig) -> Option<Self> {
+        let device = match &config.device {
+            Some(device) => device.clone(),
+            None => first_device()?,
+        };
+        let path = PathBuf::from(BACKLIGHT_DIR).join(&device);
+        let max_brightness = read_value(&path.join("max_brightness")).filter(|max| *max > 0)?;
+
+        let button = Button::new();
+        button.add_css_class("backlight-button");
+
+        let label = Label::new(Some("☀ --%"));
+        label.add_css_class("backlight-label");
+        button.set_child(Some(&label));
+
+        let state = Rc::new(BacklightState {
+            device,
+            path,
+            max_brightness,
+            step: config.step.max(1),
+            min: config.min.min(100),
+            label,
+            slider: RefCell::new(None),
+            session: RefCell::new(None),
+            monitor: RefCell::new(None),
+        });
+
+        let state_slider = Rc::downgrade(&state);
+        let slider = SliderPopover::new(&button, "Brightness", 100, state.step, move |percent| {
+            if let Some(state) = state_slider.upgrade() {
+                state.set_percent(percent);
+            }
+        });
+        *state.slider.borrow_mut() = Some(slider);
+
+        let state_click = state.clone();
+        button.connect_clicked(move |_| {
+            if let Some(slider) = state_click.slider.borrow().as_ref() {
+                slider.popup();
+            }
+        });
+
+        let scroll = EventControllerScroll::new(
+            EventControllerScrollFlags::VERTICAL | EventControllerScrollFlags::DISCRETE,
+        );
+        let state_scroll = state.clone();
+        scroll.connect_scroll(move |_, _dx, dy| {
+            state_scroll.change(dy < 0.0);
+            Propagation::Stop
+        });
+        button.add_controller(scroll);
+
+        state.watch();
+        state.update();
+
+        Some(BacklightWidget { button, state })
+    }
+}
+
+impl BacklightState {
+    fn percent(&self) -> Option<u32> {
+        let brightness = read_value(&self.path.join("brightness"))?;
+        Some((brightness as f64 / self.max_brightness as f64 * 100.0).round() as u32)
+    }
+
+    fn update(&self) {
+        let Some(percent) = self.percent() else {
+            self.label.set_text("☀ N/A");
+            return;
+        };
+
+        self.label.set_text(&format!("☀ {}%", percent));
+        if let Some(button) = self.label.parent() {
+            button.set_tooltip_text(Some(&format!("Brightness: {}% ({})", percent, self.device)));
+        }
+        if let Some(slider) = self.slider.borrow().as_ref() {
+            slider.set_value(percent);
+        }
+        control::module_updated("backlight");
+    }
+
+    fn change(self: &Rc<Self>, up: bool) {
+        let Some(percent) = self.percent() else {
+            return;
+        };
+        let percent = if up {
+            (percent + self.step).min(100)
+        } else {
+            percent.saturating_sub(self.step).max(self.min)
+        };
+        self.set_percent(percent);
+    }
+
+    fn set_percent(self: &Rc<Self>, percent: u32) {
+        let brightness =
+            (percent.min(100) as f64 / 100.0 * self.max_brightness as f64).round() as u32;
+        let state = self.clone();
+        glib::spawn_future_local(async move {
+            let Some(session) = state.session().await else {
+                return;
+            };
+            if let Err(e) = session
+                .set_brightness("backlight", &state.device, brightness)
+                .await
+            {
+                warn!("Failed to set brightness of {}: {}", state.device, e);
+            }
+        });
+    }
+
+    /// The logind session proxy, connected on first use
+    async fn session(&self) -> Option<LoginSessionProxy<'static>> {
+        if let Some(session) = self.session.borrow().clone() {
+            return Some(session);
+        }
+        let connection = match zbus::Connection::system().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("Failed to connect to the system bus: {}", e);
+                return None;
+            }
+        };
+        match LoginSessionProxy::new(&connection).await {
+            Ok(session) => {
+                *self.session.borrow_mut() = Some(session.clone());
+                Some(session)
+            }
+            Err(e) => {
+                warn!("Failed to reach the logind session: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Follow changes made elsewhere, such as by the brightness keys, through inotify
+    fn watch(self: &Rc<Self>) {
+        let file = gio::File::for_path(self.path.join("brightness"));
+        match file.monitor_file(FileMonitorFlags::NONE, gio::Cancellable::NONE) {
+            Ok(monitor) => {
+                let state = Rc::downgrade(self);
+                monitor.connect_changed(move |_, _, _, event| {
+                    let Some(state) = state.upgrade() else {
+                        return;
+                    };
+                    if matches!(
+                        event,
+                        FileMonitorEvent::Changed | FileMonitorEvent::ChangesDoneHint
+                    ) {
+                        state.update();
+                    }
+                });
+                *self.monitor.borrow_mut() = Some(monitor);
+            }
+            Err(e) => warn!("Failed to watch brightness of {}: {}", self.device, e),
+        }
+    }
+}
+
+/// The first backlight device by name, usually the only one
+fn first_device() -> Option<String> {
+    let mut devices: Vec<String> = fs::read_dir(BACKLIGHT_DIR)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    devices.sort();
+    devices.into_iter().next()
+}
+
+fn read_value(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+impl BarModule for BacklightWidget {
+    fn name(&self) -> &'static str {
+        "backlight"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn refresh(&self) {
+        self.state.update();
+    }
+}
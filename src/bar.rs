@@ -0,0 +1,996 @@
+use gio::{ApplicationHoldGuard, FileMonitor, FileMonitorEvent, FileMonitorFlags};
+use glib::{JoinHandle, SourceId};
+use gtk4::prelude::*;
+use gtk4::{
+    Application, ApplicationWindow, Box as GtkBox, Button, EventControllerMotion, Orientation,
+    Revealer, gdk,
+};
+use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+use tracing::{Instrument, info, info_span, warn};
+
+use crate::accessibility;
+use crate::baseline;
+use crate::color_scheme;
+use crate::config::validation::Diagnostic;
+use crate::config::{BarConfig, BarLayer, Config};
+use crate::launch;
+use crate::layout::{BarLayout, Position, Zone};
+use crate::layout_editor::{self, MoveHandler, Placement};
+use crate::module::{BarModule, create_module};
+use crate::notification_rules;
+use crate::overflow::{Candidate, Overflow};
+use crate::popover_state::PopoverState;
+use crate::scheduler;
+use crate::state;
+use crate::style;
+
+/// Editors often write a file in several steps, wait for them to settle
+const RELOAD_DELAY: Duration = Duration::from_millis(200);
+
+/// How often bars check whether their modules are still aligned and fit the output
+const LAYOUT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Configuration problems listed in the warning badge's tooltip, the rest are counted
+const LISTED_PROBLEMS: usize = 10;
+
+/// Thickness in pixels of the edge strip that reveals an auto-hidden bar
+const TRIGGER_SIZE: i32 = 2;
+
+thread_local! {
+    static BARS: RefCell<Weak<Bars>> = RefCell::new(Weak::new());
+}
+
+/// Briefly reveal auto-hidden bars, e.g. for a new notification or an urgent workspace
+pub fn request_attention() {
+    if let Some(bars) = BARS.with(|bars| bars.borrow().upgrade()) {
+        bars.request_attention();
+    }
+}
+
+/// Mark every bar as alerting, tinting its border, e.g. while a critical notification is up
+pub fn set_alert(alert: bool) {
+    if let Some(bars) = BARS.with(|bars| bars.borrow().upgrade()) {
+        bars.set_alert(alert);
+    }
+}
+
+/// Slides the bar contents out of view and back in
+struct AutoHide {
+    revealer: Revealer,
+    enabled: Cell<bool>,
+    /// Pointer is over the bar, which stays revealed until it leaves
+    hovered: Cell<bool>,
+    delay: Cell<Duration>,
+    pending_hide: RefCell<Option<SourceId>>,
+}
+
+impl AutoHide {
+    fn new() -> Rc<Self> {
+        let revealer = Revealer::new();
+        revealer.set_reveal_child(true);
+
+        Rc::new(AutoHide {
+            revealer,
+            enabled: Cell::new(false),
+            hovered: Cell::new(false),
+            delay: Cell::new(Duration::ZERO),
+            pending_hide: RefCell::new(None),
+        })
+    }
+
+    fn set_enabled(self: &Rc<Self>, enabled: bool, delay: Duration) {
+        self.enabled.set(enabled);
+        self.delay.set(delay);
+        if enabled {
+            self.hide_after(delay);
+        } else {
+            self.reveal();
+        }
+    }
+
+    fn reveal(&self) {
+        if let Some(source_id) = self.pending_hide.borrow_mut().take() {
+            source_id.remove();
+        }
+        self.revealer.set_reveal_child(true);
+    }
+
+    /// Slide out once `delay` has passed, unless the pointer is over the bar by then
+    fn hide_after(self: &Rc<Self>, delay: Duration) {
+        if !self.enabled.get() {
+            return;
+        }
+        if let Some(source_id) = self.pending_hide.borrow_mut().take() {
+            source_id.remove();
+        }
+
+        let auto_hide = Rc::downgrade(self);
+        let source_id = glib::timeout_add_local_once(delay, move || {
+            if let Some(auto_hide) = auto_hide.upgrade() {
+                auto_hide.pending_hide.borrow_mut().take();
+                if !auto_hide.hovered.get() {
+                    auto_hide.revealer.set_reveal_child(false);
+                }
+            }
+        });
+        *self.pending_hide.borrow_mut() = Some(source_id);
+    }
+
+    /// Reveal while the pointer is over `window` and hide after it leaves
+    fn track_pointer(self: &Rc<Self>, window: &ApplicationWindow, hovers: bool) {
+        let motion = EventControllerMotion::new();
+
+        let auto_hide = Rc::downgrade(self);
+        motion.connect_enter(move |_, _, _| {
+            if let Some(auto_hide) = auto_hide.upgrade() {
+                if hovers {
+                    auto_hide.hovered.set(true);
+                }
+                auto_hide.reveal();
+            }
+        });
+
+        let auto_hide = Rc::downgrade(self);
+        motion.connect_leave(move |_| {
+            if let Some(auto_hide) = auto_hide.upgrade() {
+                if hovers {
+                    auto_hide.hovered.set(false);
+                }
+                auto_hide.hide_after(auto_hide.delay.get());
+            }
+        });
+
+        window.add_controller(motion);
+    }
+}
+
+/// A module placed on the bar together with its running init task
+struct ActiveModule {
+    /// Name the module is referenced by in the layout, e.g. `custom/updates`
+    name: String,
+    /// Zone and index in the configured layout
+    placement: Placement,
+    module: Rc<dyn BarModule>,
+    task: JoinHandle<()>,
+}
+
+/// A bar window on a single output, rebuilt from the configuration on reload
+pub struct Bar {
+    monitor: gdk::Monitor,
+    window: ApplicationWindow,
+    /// Edge strip revealing the bar while auto-hide is enabled
+    trigger: RefCell<Option<ApplicationWindow>>,
+    auto_hide: Rc<AutoHide>,
+    position: Cell<Position>,
+    modules: RefCell<Vec<ActiveModule>>,
+    /// Set while the layout editor is active
+    on_move: RefCell<Option<MoveHandler>>,
+    /// Modules collapsed because the bar is longer than the output
+    overflow: Overflow,
+    /// Badge shown while the configuration file has problems
+    config_warning: Button,
+    align_baselines: Cell<bool>,
+}
+
+impl Bar {
+    pub fn new(app: &Application, monitor: &gdk::Monitor) -> Self {
+        let window = ApplicationWindow::builder()
+            .application(app)
+            .title("Wayland Bar")
+            .css_classes(["main-window"])
+            .build();
+
+        // Without layer shell support (e.g. under broadway) the bar is a plain window
+        if gtk4_layer_shell::is_supported() {
+            Self::init_layer_shell(&window, monitor);
+        }
+
+        let auto_hide = AutoHide::new();
+        window.set_child(Some(&auto_hide.revealer));
+        auto_hide.track_pointer(&window, true);
+
+        Bar {
+            monitor: monitor.clone(),
+            window,
+            trigger: RefCell::new(None),
+            auto_hide,
+            position: Cell::new(Position::default()),
+            modules: RefCell::new(Vec::new()),
+            on_move: RefCell::new(None),
+            overflow: Overflow::new(),
+            config_warning: Self::create_config_warning(),
+            align_baselines: Cell::new(true),
+        }
+    }
+
+    fn init_layer_shell(window: &ApplicationWindow, monitor: &gdk::Monitor) {
+        // Initialize layer shell for this window
+        LayerShell::init_layer_shell(window);
+        LayerShell::set_monitor(window, Some(monitor));
+
+        // Enable transparency
+        if let Some(surface) = window.surface() {
+            surface.set_opaque_region(None);
+        }
+
+        // Set the desired layer
+        LayerShell::set_layer(window, Layer::Top);
+
+        // Allow popovers with text input (search, notes) to receive keyboard focus
+        LayerShell::set_keyboard_mode(window, KeyboardMode::OnDemand);
+    }
+
+    /// Fill the bar from the configured module order, reusing modules that keep state across reloads
+    pub fn build(&self, config: &Config) {
+        let position = config.bar.position;
+        self.align_baselines.set(config.bar.align_baselines);
+        self.apply_position(position, config.bar.height);
+        self.apply_margins(&config.bar);
+        self.apply_layer(config.bar.layer);
+        self.apply_auto_hide(&config.bar);
+        self.show_problems(&config.problems);
+
+        // Widgets are laid out for one orientation, so moving to another edge recreates all of them
+        let position_changed = self.position.replace(position) != position;
+
+        // Rebuilt modules reopen what was open before, reparented ones lose it too
+        let popovers = PopoverState::capture(
+            self.modules
+                .borrow()
+                .iter()
+                .map(|active| (active.name.as_str(), active.module.widget())),
+        );
+
+        let mut previous = std::mem::take(&mut *self.modules.borrow_mut());
+        let mut modules = Vec::new();
+
+        for (zone, names) in config.layout.zones() {
+            for (index, name) in names.iter().enumerate() {
+                let kept = previous
+                    .iter()
+                    .position(|active| {
+                        !position_changed
+                            && active.module.name() == name.as_str()
+                            && active.module.keep_on_reload(config)
+                    })
+                    .map(|index| previous.remove(index));
+
+                let active = match kept {
+                    Some(active) => ActiveModule {
+                        placement: (zone, index),
+                        ..active
+                    },
+                    None => {
+                        let Some(module) = create_module(name, config) else {
+                            continue;
+                        };
+                        // Async setup runs once GTK is back in the main loop
+                        let module_init = module.clone();
+                        let span = info_span!("module", name = %name);
+                        let task = glib::spawn_future_local(
+                            async move {
+                                module_init.init().await;
+                            }
+                            .instrument(span),
+                        );
+                        ActiveModule {
+                            name: name.clone(),
+                            placement: (zone, index),
+                            module,
+                            task,
+                        }
+                    }
+                };
+
+                modules.push(active);
+            }
+        }
+
+        // Modules that were removed or will be recreated with the new settings
+        for active in previous {
+            active.task.abort();
+            active.module.shutdown();
+        }
+
+        *self.modules.borrow_mut() = modules;
+        // Placements may have changed, collapse again from scratch
+        self.overflow.clear();
+        self.arrange();
+        self.check_overflow(config);
+
+        popovers.restore(
+            self.modules
+                .borrow()
+                .iter()
+                .map(|active| (active.name.as_str(), active.module.widget())),
+        );
+    }
+
+    /// Put the module widgets into a fresh layout, with drag handles while editing
+    fn arrange(&self) {
+        let orientation = self.position.get().orientation();
+        let layout = BarLayout::new(orientation);
+        let on_move = self.on_move.borrow().clone();
+        let modules = self.modules.borrow();
+        let hidden = state::get().hidden_modules;
+
+        for active in modules.iter() {
+            // Detach from the old layout (or handle) before moving it into the new one
+            let widget = active.module.widget();
+            if let Some(parent) = widget.parent().and_downcast::<GtkBox>() {
+                parent.remove(widget);
+            }
+            // Hidden modules keep running off the bar, the layout editor shows them all
+            if on_move.is_none() && hidden.contains(&active.name) {
+                continue;
+            }
+
+            let (zone, _) = active.placement;
+            if on_move.is_none() && self.overflow.is_collapsed(active.placement) {
+                self.overflow.content().append(widget);
+            } else if on_move.is_some() {
+                let handle = layout_editor::handle(widget, active.placement, orientation);
+                layout.append(zone, &handle);
+            } else {
+                layout.append(zone, widget);
+            }
+        }
+
+        let chevron = &self.overflow.button;
+        if let Some(parent) = chevron.parent().and_downcast::<GtkBox>() {
+            parent.remove(chevron);
+        }
+        if on_move.is_none() && self.overflow.has_collapsed() {
+            layout.append(Zone::End, chevron);
+        }
+
+        // Hidden while the configuration is fine
+        let warning = &self.config_warning;
+        if let Some(parent) = warning.parent().and_downcast::<GtkBox>() {
+            parent.remove(warning);
+        }
+        layout.append(Zone::End, warning);
+
+        if let Some(on_move) = on_move {
+            for zone in [Zone::Start, Zone::Center, Zone::End] {
+                let indices = modules
+                    .iter()
+                    .filter(|active| active.placement.0 == zone)
+                    .map(|active| active.placement.1)
+                    .collect();
+                layout_editor::accept_drops(
+                    layout.zone(zone),
+                    zone,
+                    indices,
+                    orientation,
+                    on_move.clone(),
+                );
+            }
+            self.window.add_css_class("editing");
+            self.auto_hide.reveal();
+        } else {
+            self.window.remove_css_class("editing");
+        }
+
+        self.auto_hide.revealer.set_child(Some(layout.widget()));
+        self.align_baselines();
+    }
+
+    /// Line up the modules' text, again whenever they may have added widgets
+    pub fn align_baselines(&self) {
+        let horizontal = self.position.get().orientation() == Orientation::Horizontal;
+        if !self.align_baselines.get() || !horizontal {
+            return;
+        }
+        if let Some(root) = self.auto_hide.revealer.child() {
+            baseline::align(&root);
+        }
+    }
+
+    /// Collapse modules into the overflow menu, or bring them back, so the bar fits
+    /// its output
+    pub fn check_overflow(&self, config: &Config) {
+        // Every module stays in place while the layout is edited
+        if self.on_move.borrow().is_some() {
+            return;
+        }
+        let Some(root) = self.auto_hide.revealer.child() else {
+            return;
+        };
+        self.update_exclusive_zone(&config.bar);
+
+        let position = self.position.get();
+        let orientation = position.orientation();
+        let geometry = self.monitor.geometry();
+        let [before, after] = position.span_edges();
+        let length = match orientation {
+            Orientation::Vertical => geometry.height(),
+            _ => geometry.width(),
+        };
+        let available = length - margin(&config.bar, before) - margin(&config.bar, after);
+
+        let hidden = state::get().hidden_modules;
+        let candidates: Vec<Candidate> = self
+            .modules
+            .borrow()
+            .iter()
+            .filter(|active| !hidden.contains(&active.name))
+            .map(|active| Candidate {
+                placement: active.placement,
+                priority: config.layout.priority.get(&active.name).copied().unwrap_or(0),
+                widget: active.module.widget().clone(),
+            })
+            .collect();
+
+        if self
+            .overflow
+            .update(&root, orientation, available, &candidates)
+        {
+            self.arrange();
+        }
+    }
+
+    /// Show drag handles that report moved modules to `on_move`, or hide them with `None`
+    pub fn set_editing(&self, on_move: Option<MoveHandler>) {
+        *self.on_move.borrow_mut() = on_move;
+        self.arrange();
+    }
+
+    /// Anchor the window to its edge and stretch it along the two adjacent ones
+    fn apply_position(&self, position: Position, height: i32) {
+        if self.window.is_layer_window() {
+            set_anchors(&self.window, position);
+        }
+        self.auto_hide
+            .revealer
+            .set_transition_type(position.reveal_transition());
+
+        for other in [Position::Top, Position::Bottom, Position::Left, Position::Right] {
+            self.window.remove_css_class(other.css_class());
+        }
+        self.window.add_css_class(position.css_class());
+
+        // Fixed thickness, the length follows the anchors
+        match position.orientation() {
+            Orientation::Vertical => {
+                self.window.add_css_class("vertical");
+                self.window.set_default_size(height, 800);
+            }
+            _ => {
+                self.window.remove_css_class("vertical");
+                self.window.set_default_size(800, height);
+            }
+        }
+    }
+
+    /// Keep the configured gaps to the screen edges
+    fn apply_margins(&self, config: &BarConfig) {
+        if !self.window.is_layer_window() {
+            return;
+        }
+        for edge in [Edge::Top, Edge::Right, Edge::Bottom, Edge::Left] {
+            LayerShell::set_margin(&self.window, edge, margin(config, edge));
+        }
+    }
+
+    fn apply_layer(&self, layer: BarLayer) {
+        if !self.window.is_layer_window() {
+            return;
+        }
+        LayerShell::set_layer(&self.window, shell_layer(layer));
+        if let Some(trigger) = self.trigger.borrow().as_ref() {
+            LayerShell::set_layer(trigger, shell_layer(layer));
+        }
+    }
+
+    /// Give up the reserved space and slide out of view, revealed from an edge strip.
+    /// Needs layer shell, a plain window stays as it is.
+    fn apply_auto_hide(&self, config: &BarConfig) {
+        let enabled = config.autohide && self.window.is_layer_window();
+
+        if enabled {
+            LayerShell::set_exclusive_zone(&self.window, 0);
+            self.window.add_css_class("autohide");
+
+            // Follow the revealer instead of keeping the full thickness while hidden
+            let (width, height) = self.window.default_size();
+            match config.position.orientation() {
+                Orientation::Vertical => self.window.set_default_size(1, height),
+                _ => self.window.set_default_size(width, 1),
+            }
+
+            let mut trigger = self.trigger.borrow_mut();
+            let trigger = trigger.get_or_insert_with(|| self.create_trigger(config.layer));
+            set_anchors(trigger, config.position);
+            trigger.set_visible(self.window.is_visible());
+        } else {
+            self.window.remove_css_class("autohide");
+            if let Some(trigger) = self.trigger.borrow_mut().take() {
+                trigger.destroy();
+            }
+        }
+
+        self.auto_hide
+            .set_enabled(enabled, Duration::from_millis(config.autohide_delay));
+        self.update_exclusive_zone(config);
+    }
+
+    /// Reserve the space the bar actually takes, which is more than the configured
+    /// height when a module needs it
+    fn update_exclusive_zone(&self, config: &BarConfig) {
+        if !self.window.is_layer_window() {
+            return;
+        }
+        let zone = if self.auto_hide.enabled.get() {
+            0
+        } else {
+            let allocated = match config.position.orientation() {
+                Orientation::Vertical => self.window.width(),
+                _ => self.window.height(),
+            };
+            exclusive_zone(config, allocated.max(config.height))
+        };
+        if LayerShell::exclusive_zone(&self.window) != zone {
+            LayerShell::set_exclusive_zone(&self.window, zone);
+        }
+    }
+
+    fn create_trigger(&self, layer: BarLayer) -> ApplicationWindow {
+        let trigger = ApplicationWindow::builder()
+            .css_classes(["autohide-trigger"])
+            .default_width(TRIGGER_SIZE)
+            .default_height(TRIGGER_SIZE)
+            .build();
+        if let Some(app) = self.window.application() {
+            trigger.set_application(Some(&app));
+        }
+
+        LayerShell::init_layer_shell(&trigger);
+        LayerShell::set_monitor(&trigger, Some(&self.monitor));
+        // Same layer as the bar so it is reachable over fullscreen windows in overlay mode
+        LayerShell::set_layer(&trigger, shell_layer(layer));
+        LayerShell::set_keyboard_mode(&trigger, KeyboardMode::None);
+        // Touch the screen edge even when other surfaces reserve space there
+        LayerShell::set_exclusive_zone(&trigger, -1);
+
+        self.auto_hide.track_pointer(&trigger, false);
+        trigger.present();
+        trigger
+    }
+
+    fn create_config_warning() -> Button {
+        let button = Button::with_label("⚠");
+        button.add_css_class("config-warning");
+        button.set_visible(false);
+        button.connect_clicked(|_| {
+            if let Some(path) = Config::path() {
+                launch::open_uri(&gio::File::for_path(path).uri());
+            }
+        });
+        button
+    }
+
+    /// Point out problems in the configuration file, whose broken settings fell back
+    /// to their defaults. Clicking the badge opens the file.
+    fn show_problems(&self, problems: &[Diagnostic]) {
+        self.config_warning.set_visible(!problems.is_empty());
+        if problems.is_empty() {
+            return;
+        }
+
+        let mut tooltip = format!(
+            "{} problem(s) in the configuration, affected settings use their defaults",
+            problems.len()
+        );
+        for problem in problems.iter().take(LISTED_PROBLEMS) {
+            tooltip.push_str(&format!("\n{}", problem));
+        }
+        if problems.len() > LISTED_PROBLEMS {
+            tooltip.push_str(&format!("\n… and {} more", problems.len() - LISTED_PROBLEMS));
+        }
+        tooltip.push_str("\nRun `blade_bar check` for details");
+        self.config_warning.set_tooltip_text(Some(&tooltip));
+    }
+
+    pub fn set_alert(&self, alert: bool) {
+        if alert {
+            self.window.add_css_class("alert");
+        } else {
+            self.window.remove_css_class("alert");
+        }
+    }
+
+    /// Reveal an auto-hidden bar for `duration`
+    pub fn request_attention(&self, duration: Duration) {
+        if self.auto_hide.enabled.get() {
+            self.auto_hide.reveal();
+            self.auto_hide.hide_after(duration);
+        }
+    }
+
+    pub fn present(&self) {
+        self.window.present();
+        if let Some(trigger) = self.trigger.borrow().as_ref() {
+            trigger.present();
+        }
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        self.window.set_visible(visible);
+        if let Some(trigger) = self.trigger.borrow().as_ref() {
+            trigger.set_visible(visible);
+        }
+    }
+
+    /// Ask the modules placed as `name` to update immediately
+    pub fn refresh_module(&self, name: &str) {
+        for active in self.modules.borrow().iter() {
+            if active.name == name {
+                active.module.refresh();
+            }
+        }
+    }
+
+    /// Stop every module and close the window
+    pub fn shutdown(&self) {
+        for active in self.modules.borrow_mut().drain(..) {
+            active.task.abort();
+            active.module.shutdown();
+        }
+        if let Some(trigger) = self.trigger.borrow_mut().take() {
+            trigger.destroy();
+        }
+        self.window.destroy();
+    }
+}
+
+/// One bar per enabled output, following monitor hotplug and configuration changes
+pub struct Bars {
+    app: Application,
+    config: RefCell<Config>,
+    bars: RefCell<Vec<Bar>>,
+    visible: Cell<bool>,
+    alert: Cell<bool>,
+    /// Handler given to every bar while the layout editor is active
+    move_handler: RefCell<Option<MoveHandler>>,
+    monitor: RefCell<Option<FileMonitor>>,
+    pending_reload: RefCell<Option<SourceId>>,
+    // Keep running while all outputs are disconnected
+    _hold: ApplicationHoldGuard,
+}
+
+impl Bars {
+    pub fn new(app: &Application, config: Config) -> Rc<Self> {
+        let bars = Rc::new(Bars {
+            app: app.clone(),
+            config: RefCell::new(config),
+            bars: RefCell::new(Vec::new()),
+            // Bars hidden before a restart stay hidden
+            visible: Cell::new(!state::get().hidden),
+            alert: Cell::new(false),
+            move_handler: RefCell::new(None),
+            monitor: RefCell::new(None),
+            pending_reload: RefCell::new(None),
+            _hold: app.hold(),
+        });
+
+        BARS.with(|current| *current.borrow_mut() = Rc::downgrade(&bars));
+
+        bars.sync_outputs();
+        bars.watch_outputs();
+        bars.watch_config();
+
+        // Modules change size and add widgets as their content updates
+        let weak = Rc::downgrade(&bars);
+        scheduler::every(LAYOUT_INTERVAL, move || {
+            if let Some(bars) = weak.upgrade() {
+                bars.check_layout();
+            }
+        });
+        bars
+    }
+
+    fn check_layout(&self) {
+        let config = self.config.borrow();
+        for bar in self.bars.borrow().iter() {
+            bar.align_baselines();
+            bar.check_overflow(&config);
+        }
+    }
+
+    /// Create bars for newly enabled outputs and close those of removed or excluded ones
+    fn sync_outputs(&self) {
+        let Some(display) = gdk::Display::default() else {
+            return;
+        };
+
+        let config = self.config.borrow();
+        let monitors: Vec<gdk::Monitor> = display
+            .monitors()
+            .iter::<gdk::Monitor>()
+            .flatten()
+            .filter(|monitor| is_output_enabled(&config.bar, monitor))
+            .collect();
+
+        let mut bars = self.bars.borrow_mut();
+        bars.retain(|bar| {
+            let keep = monitors.contains(&bar.monitor);
+            if !keep {
+                bar.shutdown();
+            }
+            keep
+        });
+
+        for monitor in monitors {
+            if bars.iter().any(|bar| bar.monitor == monitor) {
+                continue;
+            }
+
+            info!(
+                "Creating bar on output {}",
+                monitor.connector().as_deref().unwrap_or("unknown")
+            );
+            let bar = Bar::new(&self.app, &monitor);
+            bar.set_editing(self.move_handler.borrow().clone());
+            bar.build(&config);
+            bar.present();
+            bar.set_visible(self.visible.get());
+            bar.set_alert(self.alert.get());
+            bars.push(bar);
+        }
+    }
+
+    /// Re-read the configuration and rebuild every bar
+    pub fn reload(&self) {
+        info!("Reloading configuration");
+        *self.config.borrow_mut() = Config::load();
+        notification_rules::configure(&self.config.borrow().notifications);
+        self.rebuild();
+    }
+
+    /// Rebuild every bar from the current configuration
+    fn rebuild(&self) {
+        let config = self.config.borrow();
+        color_scheme::apply(&config);
+        style::set_palette(&config);
+        style::set_font(&config.bar);
+        accessibility::configure(&config);
+        for bar in self.bars.borrow().iter() {
+            bar.build(&config);
+        }
+        drop(config);
+
+        // The output selection may have changed too
+        self.sync_outputs();
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible.get()
+    }
+
+    /// Show or hide the bars on every output
+    pub fn set_visible(&self, visible: bool) {
+        if self.visible.replace(visible) != visible {
+            state::update(|state| state.hidden = !visible);
+        }
+        for bar in self.bars.borrow().iter() {
+            bar.set_visible(visible);
+        }
+    }
+
+    pub fn refresh_module(&self, name: &str) {
+        for bar in self.bars.borrow().iter() {
+            bar.refresh_module(name);
+        }
+    }
+
+    /// Show or hide the modules placed as `name` on every bar, remembered across restarts
+    pub fn set_module_visible(&self, name: &str, visible: bool) {
+        if state::get().hidden_modules.contains(name) != visible {
+            return;
+        }
+        state::update(|state| {
+            if visible {
+                state.hidden_modules.remove(name);
+            } else {
+                state.hidden_modules.insert(name.to_string());
+            }
+        });
+
+        let config = self.config.borrow();
+        for bar in self.bars.borrow().iter() {
+            bar.overflow.clear();
+            bar.arrange();
+            bar.check_overflow(&config);
+        }
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.move_handler.borrow().is_some()
+    }
+
+    /// Toggle drag handles for rearranging modules, changes are saved to the config file
+    pub fn set_editing(self: &Rc<Self>, editing: bool) {
+        let handler = editing.then(|| {
+            let bars = Rc::downgrade(self);
+            Rc::new(move |from, to| {
+                // Rebuild after the drop has finished, it replaces the widgets involved
+                let bars = bars.clone();
+                glib::idle_add_local_once(move || {
+                    if let Some(bars) = bars.upgrade() {
+                        bars.move_module(from, to);
+                    }
+                });
+            }) as MoveHandler
+        });
+
+        *self.move_handler.borrow_mut() = handler.clone();
+        for bar in self.bars.borrow().iter() {
+            bar.set_editing(handler.clone());
+        }
+    }
+
+    fn move_module(&self, from: Placement, to: Placement) {
+        {
+            let mut config = self.config.borrow_mut();
+            config.layout.move_module(from, to);
+            if let Err(e) = config.layout.save() {
+                warn!("Failed to save the layout: {}", e);
+            }
+        }
+
+        // Apply right away, the file monitor reload that follows finds the same layout
+        self.rebuild();
+    }
+
+    fn set_alert(&self, alert: bool) {
+        if self.alert.replace(alert) == alert {
+            return;
+        }
+        for bar in self.bars.borrow().iter() {
+            bar.set_alert(alert);
+        }
+        if alert {
+            self.request_attention();
+        }
+    }
+
+    fn request_attention(&self) {
+        let duration = Duration::from_millis(self.config.borrow().bar.attention_duration);
+        for bar in self.bars.borrow().iter() {
+            bar.request_attention(duration);
+        }
+    }
+
+    /// Follow monitors being connected and disconnected
+    fn watch_outputs(self: &Rc<Self>) {
+        let Some(display) = gdk::Display::default() else {
+            return;
+        };
+
+        let bars = Rc::downgrade(self);
+        display.monitors().connect_items_changed(move |_, _, _, _| {
+            if let Some(bars) = bars.upgrade() {
+                bars.sync_outputs();
+            }
+        });
+    }
+
+    /// Reload when the configuration file changes or on SIGUSR2
+    fn watch_config(self: &Rc<Self>) {
+        let bars = Rc::downgrade(self);
+        glib::unix_signal_add_local(libc::SIGUSR2, move || {
+            if let Some(bars) = bars.upgrade() {
+                bars.reload();
+            }
+            glib::ControlFlow::Continue
+        });
+
+        let Some(path) = Config::path() else {
+            return;
+        };
+
+        // Watching a missing file is fine, the monitor reports it once it is created
+        let file = gio::File::for_path(&path);
+        match file.monitor_file(FileMonitorFlags::NONE, gio::Cancellable::NONE) {
+            Ok(monitor) => {
+                let bars = Rc::downgrade(self);
+                monitor.connect_changed(move |_, _, _, event| {
+                    if !matches!(
+                        event,
+                        FileMonitorEvent::ChangesDoneHint
+                            | FileMonitorEvent::Created
+                            | FileMonitorEvent::Deleted
+                    ) {
+                        return;
+                    }
+                    if let Some(bars) = bars.upgrade() {
+                        bars.schedule_reload();
+                    }
+                });
+                *self.monitor.borrow_mut() = Some(monitor);
+            }
+            Err(e) => warn!("Failed to watch '{}': {}", path.display(), e),
+        }
+    }
+
+    fn schedule_reload(self: &Rc<Self>) {
+        if let Some(source_id) = self.pending_reload.borrow_mut().take() {
+            source_id.remove();
+        }
+
+        let bars = Rc::downgrade(self);
+        let source_id = glib::timeout_add_local_once(RELOAD_DELAY, move || {
+            if let Some(bars) = bars.upgrade() {
+                bars.pending_reload.borrow_mut().take();
+                bars.reload();
+            }
+        });
+        *self.pending_reload.borrow_mut() = Some(source_id);
+    }
+
+    /// Stop every bar before the application exits
+    pub fn shutdown(&self) {
+        for bar in self.bars.borrow_mut().drain(..) {
+            bar.shutdown();
+        }
+    }
+}
+
+/// Anchor a layer surface to the edge of `position`, stretched along the adjacent edges
+fn set_anchors(window: &ApplicationWindow, position: Position) {
+    for edge in [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right] {
+        LayerShell::set_anchor(window, edge, false);
+    }
+    LayerShell::set_anchor(window, position.edge(), true);
+    for edge in position.span_edges() {
+        LayerShell::set_anchor(window, edge, true);
+    }
+}
+
+fn margin(config: &BarConfig, edge: Edge) -> i32 {
+    let margin = &config.margin;
+    match edge {
+        Edge::Top => margin.top,
+        Edge::Right => margin.right,
+        Edge::Bottom => margin.bottom,
+        Edge::Left => margin.left,
+        _ => 0,
+    }
+}
+
+fn shell_layer(layer: BarLayer) -> Layer {
+    match layer {
+        BarLayer::Top => Layer::Top,
+        BarLayer::Overlay => Layer::Overlay,
+        BarLayer::Bottom => Layer::Bottom,
+    }
+}
+
+/// Space reserved for a bar `thickness` pixels thick, unless configured. The
+/// compositor adds the margin on the anchored edge itself, the one facing the windows
+/// is added so they keep the gap too. An overlay bar floats over everything and
+/// reserves nothing.
+fn exclusive_zone(config: &BarConfig, thickness: i32) -> i32 {
+    if let Some(zone) = config.exclusive_zone {
+        return zone;
+    }
+    if config.layer == BarLayer::Overlay {
+        return 0;
+    }
+    thickness + margin(config, config.position.inner_edge()).max(0)
+}
+
+/// Whether a bar should be shown on `monitor` according to the output lists
+fn is_output_enabled(config: &BarConfig, monitor: &gdk::Monitor) -> bool {
+    let connector = monitor.connector();
+    let connector = connector.as_deref().unwrap_or_default();
+
+    if config.exclude_outputs.iter().any(|output| output == connector) {
+        return false;
+    }
+    config.outputs.is_empty() || config.outputs.iter().any(|output| output == connector)
+}
@@ -0,0 +1,42 @@
+// Baseline alignment for horizontal bars. Modules mix fonts, sizes, emoji and icon
+// glyphs, and GTK centers every widget in the bar's height on its own, so text of
+// different sizes ends up on different lines. This pass puts the labels of all
+// modules on one shared baseline and centers icons on it.
+//
+// The side zones are clipped by scrolled windows (see `BarLayout`), which do not pass
+// a baseline on, so the three zones are lined up with each other by centering.
+
+use gtk4::prelude::*;
+use gtk4::{Align, Box as GtkBox, Button, CenterBox, Image, Label, MenuButton, Popover, Widget};
+
+/// Align everything below `widget`. Widgets given an explicit alignment keep it, and
+/// popovers are left alone as they are laid out on their own.
+pub fn align(widget: &Widget) {
+    let mut child = widget.first_child();
+    while let Some(current) = child {
+        if !current.is::<Popover>() {
+            if current.valign() == Align::Fill {
+                if current.is::<Image>() {
+                    current.set_valign(Align::Center);
+                } else if passes_baseline(&current) {
+                    current.set_valign(Align::Baseline);
+                }
+            }
+            align(&current);
+        }
+        child = current.next_sibling();
+    }
+}
+
+/// Labels have a baseline, horizontal containers hand their children's up
+fn passes_baseline(widget: &Widget) -> bool {
+    if widget.is::<Label>() || widget.is::<Button>() || widget.is::<MenuButton>() {
+        return true;
+    }
+    if widget.is::<GtkBox>() || widget.is::<CenterBox>() {
+        return widget
+            .dynamic_cast_ref::<gtk4::Orientable>()
+            .is_some_and(|orientable| orientable.orientation() == gtk4::Orientation::Horizontal);
+    }
+    false
+}
@@ -0,0 +1,213 @@
+// `blade_bar check`: validate the configuration and stylesheets and report which
+// modules would load, without opening any windows.
+
+use gtk4::CssProvider;
+use gtk4::prelude::*;
+use std::cell::Cell;
+use std::fs;
+use std::rc::Rc;
+
+use crate::command::is_available;
+use crate::compositor::Compositor;
+use crate::config::{Config, validation};
+use crate::module::{create_module, is_module_name};
+use crate::style::{palette_css, user_css_path};
+use crate::themes;
+
+/// Well-known D-Bus names of services some modules talk to
+const DBUS_SERVICES: &[(&str, &str)] = &[
+    ("org.erikreider.swaync.cc", "swaync (notifications)"),
+    ("org.freedesktop.Notifications", "notification daemon"),
+    ("org.kde.StatusNotifierWatcher", "StatusNotifierWatcher (tray)"),
+];
+
+/// External programs used by modules when present
+const PROGRAMS: &[&str] = &[
+    "pactl",
+    "swaync-client",
+    "makoctl",
+    "fnottctl",
+    "kanshictl",
+    "wlsunset",
+    "hyprsunset",
+];
+
+/// Run every check, print a report and return the process exit code
+pub async fn run() -> i32 {
+    let mut errors = 0;
+
+    println!("Configuration");
+    let config = match Config::path() {
+        Some(path) => match fs::read_to_string(&path) {
+            Ok(contents) => {
+                let (config, diagnostics) = validation::parse(&contents);
+                if diagnostics.is_empty() {
+                    println!("  ok      {}", path.display());
+                }
+                for diagnostic in &diagnostics {
+                    println!("  error   {}: {}", path.display(), diagnostic);
+                    if let Some(context) = diagnostic.context(&contents) {
+                        for line in context.lines() {
+                            println!("          {}", line);
+                        }
+                    }
+                }
+                errors += diagnostics.len();
+                config
+            }
+            Err(_) => {
+                println!("  info    {} not found, using defaults", path.display());
+                Config::default()
+            }
+        },
+        None => {
+            println!("  info    no configuration directory, using defaults");
+            Config::default()
+        }
+    };
+
+    let configured_themes = [
+        ("theme", &config.theme),
+        ("light_theme", &config.light_theme),
+        ("dark_theme", &config.dark_theme),
+    ];
+    for (key, theme) in configured_themes {
+        let Some(theme) = theme else {
+            continue;
+        };
+        if themes::preset(theme).is_some() {
+            println!("  ok      {}: {}", key, theme);
+        } else {
+            println!(
+                "  error   unknown {} '{}', available: {}",
+                key,
+                theme,
+                themes::preset_names().join(", ")
+            );
+            errors += 1;
+        }
+    }
+
+    if let Some(palette) = &config.palette {
+        if themes::palette(palette).is_some() {
+            println!("  ok      palette: {}", palette);
+        } else {
+            println!(
+                "  error   unknown palette '{}', available: {}",
+                palette,
+                themes::palette_names().join(", ")
+            );
+            errors += 1;
+        }
+    }
+
+    // Stylesheets and module construction need GTK, but no window is created
+    let gtk_available = gtk4::init().is_ok();
+
+    println!("Stylesheets");
+    if gtk_available {
+        errors += check_css("built-in style.css", |provider| {
+            provider.load_from_data(include_str!("style.css"))
+        });
+        for (key, theme) in configured_themes {
+            if let Some(preset) = theme.as_deref().and_then(themes::preset) {
+                errors += check_css(key, |provider| provider.load_from_data(preset));
+            }
+        }
+        if let Some(css) = palette_css(&config) {
+            errors += check_css("palette", |provider| provider.load_from_data(&css));
+        }
+        match user_css_path().filter(|path| path.exists()) {
+            Some(path) => {
+                errors += check_css(&path.display().to_string(), |provider| {
+                    provider.load_from_path(&path)
+                });
+            }
+            None => println!("  info    no user style.css"),
+        }
+    } else {
+        println!("  skip    no display available");
+    }
+
+    println!("Services");
+    match Compositor::detect() {
+        Some(compositor) => println!("  ok      compositor: {}", compositor.display_name()),
+        None => println!("  warn    no supported compositor detected"),
+    }
+
+    match zbus::Connection::session().await {
+        Ok(connection) => {
+            let dbus = zbus::fdo::DBusProxy::new(&connection).await.ok();
+            for (name, description) in DBUS_SERVICES {
+                let owned = match &dbus {
+                    Some(dbus) => match name.to_string().try_into() {
+                        Ok(bus_name) => dbus.name_has_owner(bus_name).await.unwrap_or(false),
+                        Err(_) => false,
+                    },
+                    None => false,
+                };
+                let status = if owned { "ok    " } else { "warn  " };
+                println!("  {}  {}", status, description);
+            }
+        }
+        Err(e) => println!("  warn    session bus unavailable: {}", e),
+    }
+
+    for program in PROGRAMS {
+        let status = if is_available(program) { "ok    " } else { "missing" };
+        println!("  {} {}", status, program);
+    }
+
+    println!("Modules");
+    for (zone, names) in config.layout.zones() {
+        for name in names {
+            let label = format!("{:?}/{}", zone, name).to_lowercase();
+
+            // Counted with the configuration problems above
+            if !is_module_name(name) {
+                println!("  error   {}: unknown module", label);
+            } else if !gtk_available {
+                println!("  skip    {}: no display available", label);
+            } else if create_module(name, &config).is_some() {
+                println!("  ok      {}", label);
+            } else {
+                println!("  warn    {}: unavailable on this system, would be skipped", label);
+            }
+        }
+    }
+
+    if errors == 0 {
+        println!("No problems found");
+        0
+    } else {
+        println!("{} problem(s) found", errors);
+        1
+    }
+}
+
+/// Load a stylesheet into a scratch provider and report its parse errors
+fn check_css(name: &str, load: impl FnOnce(&CssProvider)) -> usize {
+    let provider = CssProvider::new();
+    let errors = Rc::new(Cell::new(0));
+
+    let errors_parse = errors.clone();
+    let name_parse = name.to_string();
+    provider.connect_parsing_error(move |_, section, error| {
+        let location = section.start_location();
+        println!(
+            "  error   {}:{}:{}: {}",
+            name_parse,
+            location.lines() + 1,
+            location.line_chars() + 1,
+            error
+        );
+        errors_parse.set(errors_parse.get() + 1);
+    });
+
+    load(&provider);
+
+    if errors.get() == 0 {
+        println!("  ok      {}", name);
+    }
+    errors.get()
+}
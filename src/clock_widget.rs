@@ -0,0 +1,155 @@
+use gtk4::glib::{DateTime, TimeZone};
+use gtk4::prelude::*;
+use gtk4::{Button, Calendar, Label, Orientation, Popover};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::config::ClockConfig;
+use crate::module::{BarModule, InitFuture};
+use crate::template::{Template, pick_icon};
+
+pub struct ClockWidget {
+    pub button: Button,
+    state: Rc<ClockState>,
+}
+
+/// Shared by the update loop and the calendar popover
+struct ClockState {
+    label: Label,
+    format: String,
+    timezone: TimeZone,
+    show_seconds: bool,
+    /// Label around the formatted time, from `label`
+    template: Option<Template>,
+    icons: Vec<String>,
+}
+
+impl ClockState {
+    fn now(&self) -> Option<DateTime> {
+        DateTime::now(&self.timezone).ok()
+    }
+
+    fn update(&self) {
+        let Some(now) = self.now() else {
+            return;
+        };
+
+        if let Ok(time) = now.format(&self.format) {
+            let text = match &self.template {
+                Some(template) => template.render(|key| match key {
+                    "time" => Some(time.as_str().into()),
+                    "icon" => {
+                        let hour = now.hour() as f64 + now.minute() as f64 / 60.0;
+                        Some(pick_icon(&self.icons, hour, 24.0).into())
+                    }
+                    _ => None,
+                }),
+                None => time.to_string(),
+            };
+            self.label.set_text(&text);
+        }
+        if let Ok(tooltip) = now.format("%A, %d %B %Y") {
+            if let Some(parent) = self.label.parent() {
+                parent.set_tooltip_text(Some(&tooltip));
+            }
+        }
+    }
+
+    /// Time left until the next second or minute boundary
+    fn delay_until_next_tick(&self) -> Duration {
+        let seconds = self.now().map(|now| now.seconds()).unwrap_or(0.0);
+        let remaining = if self.show_seconds {
+            1.0 - seconds.fract()
+        } else {
+            60.0 - seconds
+        };
+
+        // Land slightly after the boundary so the formatted time has already changed
+        Duration::from_secs_f64(remaining.max(0.0)) + Duration::from_millis(5)
+    }
+}
+
+impl ClockWidget {
+    pub fn new(config: &ClockConfig, orientation: Orientation) -> Self {
+        let button = Button::new();
+        button.add_css_class("clock-button");
+
+        let label = Label::new(None);
+        label.add_css_class("clock-label");
+        button.set_child(Some(&label));
+
+        let timezone = match &config.timezone {
+            Some(identifier) => TimeZone::new(Some(identifier.as_str())),
+            None => TimeZone::local(),
+        };
+
+        // Vertical bars stack hours, minutes and seconds on separate lines
+        let separator = match orientation {
+            Orientation::Vertical => "\n",
+            _ => ":",
+        };
+        label.set_justify(gtk4::Justification::Center);
+
+        let format = config.format.clone().unwrap_or_else(|| {
+            if config.show_seconds {
+                format!("%H{0}%M{0}%S", separator)
+            } else {
+                format!("%H{}%M", separator)
+            }
+        });
+
+        let state = Rc::new(ClockState {
+            label,
+            format,
+            timezone,
+            show_seconds: config.show_seconds,
+            template: config.label.as_deref().map(Template::parse),
+            icons: config.icons.clone(),
+        });
+
+        // Calendar popover, reset to today every time it is opened
+        let popover = Popover::new();
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+        popover.add_css_class("clock-popover");
+
+        let calendar = Calendar::new();
+        popover.set_child(Some(&calendar));
+
+        let state_show = state.clone();
+        popover.connect_show(move |_| {
+            if let Some(now) = state_show.now() {
+                calendar.select_day(&now);
+            }
+        });
+
+        button.connect_clicked(move |_| {
+            popover.popup();
+        });
+
+        state.update();
+
+        ClockWidget { button, state }
+    }
+}
+
+impl BarModule for ClockWidget {
+    fn name(&self) -> &'static str {
+        "clock"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    // Update on the next boundary instead of polling at a fixed interval. Runs as the
+    // init task so rebuilding the bar stops it.
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            loop {
+                glib::timeout_future(self.state.delay_until_next_tick()).await;
+                self.state.update();
+            }
+        })
+    }
+}
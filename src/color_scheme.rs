@@ -0,0 +1,120 @@
+// Light/dark switching: the desktop preference comes from the settings portal and
+// picks between the `light_theme` and `dark_theme` presets
+use futures_util::StreamExt;
+use std::cell::{Cell, RefCell};
+use tracing::{debug, info};
+use zbus::zvariant::OwnedValue;
+
+use crate::config::{ColorScheme, Config};
+use crate::style;
+
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const COLOR_SCHEME_KEY: &str = "color-scheme";
+
+/// Settings portal, exposing desktop preferences to sandboxed and regular apps alike
+#[zbus::proxy(
+    interface = "org.freedesktop.portal.Settings",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait PortalSettings {
+    /// Unlike the newer `ReadOne` every portal version has this. The value comes
+    /// wrapped in a second variant.
+    fn read(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+
+    #[zbus(signal)]
+    fn setting_changed(&self, namespace: &str, key: &str, value: OwnedValue) -> zbus::Result<()>;
+}
+
+/// The themes to pick from, kept so a change on the desktop can re-apply them
+#[derive(Default)]
+struct Themes {
+    theme: Option<String>,
+    light: Option<String>,
+    dark: Option<String>,
+    forced: ColorScheme,
+}
+
+thread_local! {
+    static THEMES: RefCell<Themes> = RefCell::new(Themes::default());
+    /// Last preference reported by the portal, `None` when it has none or is missing
+    static DESKTOP_PREFERS_DARK: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+/// Apply the theme for the configured or desktop color scheme, called at startup and
+/// on every reload
+pub fn apply(config: &Config) {
+    THEMES.with(|themes| {
+        *themes.borrow_mut() = Themes {
+            theme: config.theme.clone(),
+            light: config.light_theme.clone(),
+            dark: config.dark_theme.clone(),
+            forced: config.color_scheme,
+        }
+    });
+    refresh();
+}
+
+/// Follow the desktop color scheme for the lifetime of the bar
+pub fn watch() {
+    glib::spawn_future_local(async {
+        if let Err(e) = follow_portal().await {
+            // Without a portal the forced scheme or plain `theme` still applies
+            info!("Not following the desktop color scheme: {}", e);
+        }
+    });
+}
+
+async fn follow_portal() -> zbus::Result<()> {
+    let connection = zbus::Connection::session().await?;
+    let proxy = PortalSettingsProxy::new(&connection).await?;
+
+    // Subscribe first so a change while reading the initial value is not missed
+    let mut changes = proxy.receive_setting_changed().await?;
+    let value = proxy.read(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY).await?;
+    set_desktop_preference(&value);
+
+    while let Some(signal) = changes.next().await {
+        let args = signal.args()?;
+        if args.namespace == APPEARANCE_NAMESPACE && args.key == COLOR_SCHEME_KEY {
+            set_desktop_preference(&args.value);
+        }
+    }
+    Ok(())
+}
+
+/// The portal value is 0 for no preference, 1 for dark and 2 for light
+fn set_desktop_preference(value: &OwnedValue) {
+    let prefers_dark = match value.downcast_ref::<u32>() {
+        Ok(1) => Some(true),
+        Ok(2) => Some(false),
+        _ => None,
+    };
+    debug!("Desktop color scheme preference: {:?}", prefers_dark);
+    if DESKTOP_PREFERS_DARK.with(|stored| stored.replace(prefers_dark)) != prefers_dark {
+        refresh();
+    }
+}
+
+fn refresh() {
+    let dark = THEMES.with(|themes| match themes.borrow().forced {
+        ColorScheme::Auto => DESKTOP_PREFERS_DARK.with(Cell::get),
+        ColorScheme::Light => Some(false),
+        ColorScheme::Dark => Some(true),
+    });
+
+    // Popovers and other stock widgets follow along
+    if let (Some(dark), Some(settings)) = (dark, gtk4::Settings::default()) {
+        settings.set_gtk_application_prefer_dark_theme(dark);
+    }
+
+    THEMES.with(|themes| {
+        let themes = themes.borrow();
+        let variant = match dark {
+            Some(true) => themes.dark.as_ref(),
+            Some(false) => themes.light.as_ref(),
+            None => None,
+        };
+        style::set_theme(variant.or(themes.theme.as_ref()).map(String::as_str));
+    });
+}
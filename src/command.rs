@@ -0,0 +1,131 @@
+use gio::prelude::*;
+use gio::SubprocessFlags;
+use std::ffi::OsStr;
+use std::process::Command;
+use tracing::warn;
+
+use crate::launch;
+
+/// Check whether a program can be found in `$PATH`
+pub fn is_available(program: &str) -> bool {
+    Command::new("which")
+        .arg(program)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Run a shell command without blocking the GTK main loop and return its trimmed stdout
+pub async fn run_shell(command: &str) -> Option<String> {
+    let argv = [OsStr::new("sh"), OsStr::new("-c"), OsStr::new(command)];
+
+    let process = match gio::Subprocess::newv(&argv, SubprocessFlags::STDOUT_PIPE) {
+        Ok(process) => process,
+        Err(e) => {
+            warn!("Failed to spawn '{}': {}", command, e);
+            return None;
+        }
+    };
+
+    match process.communicate_utf8_future(None).await {
+        Ok((stdout, _)) => stdout.map(|stdout| stdout.trim().to_string()),
+        Err(e) => {
+            warn!("Failed to run '{}': {}", command, e);
+            None
+        }
+    }
+}
+
+/// Run a shell command in the background without waiting for it, e.g. for click actions.
+/// Applications it starts get an activation token so they open focused.
+pub fn spawn_shell(command: &str) {
+    let argv = [OsStr::new("sh"), OsStr::new("-c"), OsStr::new(command)];
+
+    let launcher = gio::SubprocessLauncher::new(SubprocessFlags::NONE);
+    launch::activation_env(&launcher);
+    if let Err(e) = launcher.spawn(&argv) {
+        warn!("Failed to spawn '{}': {}", command, e);
+    }
+}
+
+/// Start a command without waiting for it, e.g. a compositor dispatch. GLib reaps it
+/// once it exits.
+pub fn spawn_detached(argv: &[&str]) {
+    let argv: Vec<&OsStr> = argv.iter().map(OsStr::new).collect();
+
+    if let Err(e) = gio::Subprocess::newv(&argv, SubprocessFlags::NONE) {
+        warn!("Failed to spawn {:?}: {}", argv, e);
+    }
+}
+
+/// Spawn a command with its stdout piped, to be read with [`read_lines`]
+pub fn spawn_piped(argv: &[&str]) -> Option<gio::Subprocess> {
+    let argv: Vec<&OsStr> = argv.iter().map(OsStr::new).collect();
+
+    match gio::Subprocess::newv(&argv, SubprocessFlags::STDOUT_PIPE) {
+        Ok(process) => Some(process),
+        Err(e) => {
+            warn!("Failed to spawn {:?}: {}", argv, e);
+            None
+        }
+    }
+}
+
+/// Spawn a command with stdin and stdout piped, for input given to
+/// `communicate_utf8_future` instead of the command line, e.g. secrets
+pub fn spawn_piped_input(argv: &[&str]) -> Option<gio::Subprocess> {
+    let argv: Vec<&OsStr> = argv.iter().map(OsStr::new).collect();
+
+    match gio::Subprocess::newv(&argv, SubprocessFlags::STDIN_PIPE | SubprocessFlags::STDOUT_PIPE) {
+        Ok(process) => Some(process),
+        Err(e) => {
+            warn!("Failed to spawn {:?}: {}", argv, e);
+            None
+        }
+    }
+}
+
+/// `value` as a double-quoted string for a curl config read with `-K -`, the way to
+/// hand curl secrets and request bodies without putting them on its command line
+pub fn curl_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Call `on_line` for every line a process spawned by [`spawn_piped`] prints, until it exits
+pub async fn read_lines(process: &gio::Subprocess, mut on_line: impl FnMut(&str)) {
+    let Some(stdout) = process.stdout_pipe() else {
+        return;
+    };
+    let reader = gio::DataInputStream::new(&stdout);
+
+    loop {
+        match reader.read_line_utf8_future(gio::glib::Priority::DEFAULT).await {
+            Ok(Some(line)) => on_line(&line),
+            Ok(None) => break,
+            Err(e) => {
+                warn!("Failed to read command output: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// Spawn a long running command and call `on_line` for every line it prints, until it exits
+pub async fn watch_lines(argv: &[&str], on_line: impl FnMut(&str)) {
+    if let Some(process) = spawn_piped(argv) {
+        read_lines(&process, on_line).await;
+    }
+}
@@ -0,0 +1,125 @@
+pub mod hyprland;
+pub mod sway;
+
+use gio::prelude::*;
+use serde_json::Value;
+use std::process::Command;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::command::spawn_piped;
+use hyprland::HyprlandBackend;
+use sway::SwayBackend;
+
+/// A workspace as reported by the compositor
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub id: i64,
+    pub name: String,
+    pub focused: bool,
+    pub urgent: bool,
+}
+
+/// The currently focused window
+#[derive(Debug, Clone, Default)]
+pub struct ActiveWindow {
+    pub app_id: String,
+    pub title: String,
+}
+
+/// Compositor specific IPC, implemented once per supported compositor
+pub trait CompositorBackend {
+    /// Current workspaces in display order
+    fn workspaces(&self) -> Vec<Workspace>;
+
+    fn focus_workspace(&self, workspace: &Workspace);
+
+    /// Start listening for workspace changes on a background thread,
+    /// sending on `changed` whenever the workspace list needs a refresh
+    fn watch_workspaces(&self, changed: UnboundedSender<()>);
+
+    /// Focused window, `None` when nothing has focus
+    fn active_window(&self) -> Option<ActiveWindow>;
+
+    /// Start listening for focus and title changes on a background thread
+    fn watch_active_window(&self, changed: UnboundedSender<()>);
+}
+
+/// Wayland compositors the bar can talk to over IPC
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compositor {
+    Hyprland,
+    Sway,
+}
+
+impl Compositor {
+    /// Detect the running compositor from the IPC socket environment variables
+    pub fn detect() -> Option<Self> {
+        if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+            Some(Compositor::Hyprland)
+        } else if std::env::var_os("SWAYSOCK").is_some() {
+            Some(Compositor::Sway)
+        } else {
+            None
+        }
+    }
+
+    /// Workspace backend for this compositor
+    pub fn backend(self) -> Box<dyn CompositorBackend> {
+        match self {
+            Compositor::Hyprland => Box::new(HyprlandBackend),
+            Compositor::Sway => Box::new(SwayBackend),
+        }
+    }
+
+    /// Ask the compositor to reload its configuration, returning an error message on failure
+    pub fn reload_config(self) -> Result<(), String> {
+        let output = match self {
+            Compositor::Hyprland => Command::new("hyprctl").arg("reload").output(),
+            Compositor::Sway => Command::new("swaymsg").arg("reload").output(),
+        }
+        .map_err(|e| e.to_string())?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+
+        // hyprctl exits successfully even on failure and prints "ok" when it worked
+        let succeeded = match self {
+            Compositor::Hyprland => output.status.success() && stdout == "ok",
+            Compositor::Sway => output.status.success(),
+        };
+
+        if succeeded {
+            Ok(())
+        } else if !stderr.is_empty() {
+            Err(stderr)
+        } else {
+            Err(stdout)
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Compositor::Hyprland => "Hyprland",
+            Compositor::Sway => "Sway",
+        }
+    }
+}
+
+/// Run an IPC command (`hyprctl -j`, `swaymsg -r`) and parse its JSON output
+pub fn run_json(command: &mut Command) -> Option<Value> {
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+/// [`run_json`] without blocking the main loop while the compositor answers
+pub async fn query_json(argv: &[&str]) -> Option<Value> {
+    let process = spawn_piped(argv)?;
+    let (stdout, _) = process.communicate_future(None).await.ok()?;
+    if !process.is_successful() {
+        return None;
+    }
+    serde_json::from_slice(&stdout?).ok()
+}
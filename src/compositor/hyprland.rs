@@ -0,0 +1,132 @@
+// Hyprland backend using hyprctl for queries and socket2 for events
+
+use std::path::PathBuf;
+use std::process::Command;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::UnixStream;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::warn;
+
+use crate::compositor::{ActiveWindow, CompositorBackend, Workspace, run_json};
+
+pub struct HyprlandBackend;
+
+impl HyprlandBackend {
+    /// Path of the event socket for the running Hyprland instance
+    fn event_socket_path() -> Option<PathBuf> {
+        let signature = std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE")?;
+
+        // Newer Hyprland versions keep the sockets under $XDG_RUNTIME_DIR
+        let runtime_path = std::env::var_os("XDG_RUNTIME_DIR").map(|dir| {
+            PathBuf::from(dir)
+                .join("hypr")
+                .join(&signature)
+                .join(".socket2.sock")
+        });
+
+        runtime_path
+            .filter(|path| path.exists())
+            .or_else(|| Some(PathBuf::from("/tmp/hypr").join(&signature).join(".socket2.sock")))
+    }
+}
+
+impl CompositorBackend for HyprlandBackend {
+    fn workspaces(&self) -> Vec<Workspace> {
+        let Some(workspaces) = run_json(Command::new("hyprctl").args(["workspaces", "-j"])) else {
+            return Vec::new();
+        };
+        let active_id = run_json(Command::new("hyprctl").args(["activeworkspace", "-j"]))
+            .and_then(|active| active["id"].as_i64());
+
+        let mut workspaces: Vec<Workspace> = workspaces
+            .as_array()
+            .map(|workspaces| {
+                workspaces
+                    .iter()
+                    // Special (scratchpad) workspaces have negative ids
+                    .filter(|workspace| workspace["id"].as_i64().unwrap_or(-1) > 0)
+                    .map(|workspace| {
+                        let id = workspace["id"].as_i64().unwrap_or(-1);
+                        Workspace {
+                            id,
+                            name: workspace["name"].as_str().unwrap_or_default().to_string(),
+                            focused: Some(id) == active_id,
+                            urgent: false,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        workspaces.sort_by_key(|workspace| workspace.id);
+        workspaces
+    }
+
+    fn focus_workspace(&self, workspace: &Workspace) {
+        if let Err(e) = Command::new("hyprctl")
+            .args(["dispatch", "workspace", &workspace.id.to_string()])
+            .spawn()
+        {
+            warn!("Failed to switch to workspace '{}': {}", workspace.name, e);
+        }
+    }
+
+    fn watch_workspaces(&self, changed: UnboundedSender<()>) {
+        Self::watch_events(
+            &[
+                "workspace",
+                "workspacev2",
+                "focusedmon",
+                "createworkspace",
+                "destroyworkspace",
+                "moveworkspace",
+                "renameworkspace",
+                "urgent",
+            ],
+            changed,
+        );
+    }
+
+    fn active_window(&self) -> Option<ActiveWindow> {
+        let window = run_json(Command::new("hyprctl").args(["activewindow", "-j"]))?;
+
+        // hyprctl returns an empty object when no window is focused
+        let app_id = window["class"].as_str()?.to_string();
+        let title = window["title"].as_str().unwrap_or_default().to_string();
+        Some(ActiveWindow { app_id, title })
+    }
+
+    fn watch_active_window(&self, changed: UnboundedSender<()>) {
+        Self::watch_events(
+            &["activewindow", "windowtitle", "windowtitlev2", "closewindow"],
+            changed,
+        );
+    }
+}
+
+impl HyprlandBackend {
+    /// Forward the given socket2 events to `changed` from a task on the main runtime
+    fn watch_events(events: &'static [&'static str], changed: UnboundedSender<()>) {
+        let Some(path) = Self::event_socket_path() else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            let stream = match UnixStream::connect(&path).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("Failed to connect to Hyprland event socket: {}", e);
+                    return;
+                }
+            };
+
+            let mut lines = BufReader::new(stream).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let event = line.split(">>").next().unwrap_or_default();
+                if events.contains(&event) && changed.send(()).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
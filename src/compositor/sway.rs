@@ -0,0 +1,164 @@
+// Minimal client for the sway/i3 IPC protocol (swayipc)
+
+use serde_json::Value;
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::warn;
+
+use crate::compositor::{ActiveWindow, CompositorBackend, Workspace};
+
+const MAGIC: &[u8] = b"i3-ipc";
+
+const RUN_COMMAND: u32 = 0;
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const GET_TREE: u32 = 4;
+
+/// Connection to the sway IPC socket at `$SWAYSOCK`
+pub struct SwayIpc {
+    stream: UnixStream,
+}
+
+impl SwayIpc {
+    pub fn connect() -> io::Result<Self> {
+        let path = std::env::var_os("SWAYSOCK")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "SWAYSOCK is not set"))?;
+        Ok(SwayIpc {
+            stream: UnixStream::connect(path)?,
+        })
+    }
+
+    /// Send a message and wait for its reply
+    pub fn request(&mut self, message_type: u32, payload: &str) -> io::Result<Value> {
+        self.send(message_type, payload)?;
+        let (_, reply) = self.receive()?;
+        Ok(reply)
+    }
+
+    fn send(&mut self, message_type: u32, payload: &str) -> io::Result<()> {
+        let mut message = Vec::with_capacity(MAGIC.len() + 8 + payload.len());
+        message.extend_from_slice(MAGIC);
+        message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+        message.extend_from_slice(&message_type.to_ne_bytes());
+        message.extend_from_slice(payload.as_bytes());
+        self.stream.write_all(&message)
+    }
+
+    /// Read the next reply or event, returning its type and JSON payload
+    fn receive(&mut self) -> io::Result<(u32, Value)> {
+        let mut header = [0u8; 14];
+        self.stream.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid IPC magic"));
+        }
+
+        let length = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]) as usize;
+        let message_type = u32::from_ne_bytes([header[10], header[11], header[12], header[13]]);
+
+        let mut payload = vec![0u8; length];
+        self.stream.read_exact(&mut payload)?;
+
+        let value = serde_json::from_slice(&payload)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok((message_type, value))
+    }
+}
+
+/// Sway backend talking directly to the IPC socket
+pub struct SwayBackend;
+
+impl CompositorBackend for SwayBackend {
+    fn workspaces(&self) -> Vec<Workspace> {
+        let reply = SwayIpc::connect().and_then(|mut ipc| ipc.request(GET_WORKSPACES, ""));
+
+        let workspaces = match reply {
+            Ok(reply) => reply,
+            Err(e) => {
+                warn!("Failed to query sway workspaces: {}", e);
+                return Vec::new();
+            }
+        };
+
+        workspaces
+            .as_array()
+            .map(|workspaces| {
+                workspaces
+                    .iter()
+                    .map(|workspace| Workspace {
+                        id: workspace["num"].as_i64().unwrap_or(-1),
+                        name: workspace["name"].as_str().unwrap_or_default().to_string(),
+                        focused: workspace["focused"].as_bool().unwrap_or(false),
+                        urgent: workspace["urgent"].as_bool().unwrap_or(false),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn focus_workspace(&self, workspace: &Workspace) {
+        let command = format!("workspace \"{}\"", workspace.name.replace('"', "\\\""));
+        if let Err(e) = SwayIpc::connect().and_then(|mut ipc| ipc.request(RUN_COMMAND, &command)) {
+            warn!("Failed to switch to workspace '{}': {}", workspace.name, e);
+        }
+    }
+
+    fn watch_workspaces(&self, changed: UnboundedSender<()>) {
+        Self::watch_events("workspace", changed);
+    }
+
+    fn active_window(&self) -> Option<ActiveWindow> {
+        let tree = SwayIpc::connect()
+            .and_then(|mut ipc| ipc.request(GET_TREE, ""))
+            .ok()?;
+        find_focused(&tree).map(|node| ActiveWindow {
+            app_id: node["app_id"]
+                .as_str()
+                .or_else(|| node["window_properties"]["class"].as_str())
+                .unwrap_or_default()
+                .to_string(),
+            title: node["name"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+
+    fn watch_active_window(&self, changed: UnboundedSender<()>) {
+        Self::watch_events("window", changed);
+    }
+}
+
+impl SwayBackend {
+    /// Subscribe to one event type and forward every event to `changed`. The IPC
+    /// connection blocks, so it lives on the runtime's blocking pool.
+    fn watch_events(event: &'static str, changed: UnboundedSender<()>) {
+        tokio::task::spawn_blocking(move || {
+            let result = SwayIpc::connect().and_then(|mut ipc| {
+                ipc.request(SUBSCRIBE, &format!("[\"{}\"]", event))?;
+
+                // Every event on this connection is of the subscribed type
+                loop {
+                    ipc.receive()?;
+                    if changed.send(()).is_err() {
+                        return Ok(());
+                    }
+                }
+            });
+
+            if let Err(e) = result {
+                warn!("Sway {} subscription ended: {}", event, e);
+            }
+        });
+    }
+}
+
+/// Focused view in the sway tree
+fn find_focused(node: &Value) -> Option<&Value> {
+    if node["focused"].as_bool() == Some(true) && node["pid"].is_number() {
+        return Some(node);
+    }
+
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[*key].as_array())
+        .flatten()
+        .find_map(find_focused)
+}
@@ -0,0 +1,1320 @@
+pub mod validation;
+
+use crate::layout::{Position, Zone};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use toml_edit::{Array, DocumentMut};
+use tracing::warn;
+
+/// Top level bar configuration, loaded from `$XDG_CONFIG_HOME/blade_bar/config.toml`.
+/// New sections must also be registered in `validation::sections!`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Bundled theme preset layered under the user stylesheet, e.g. `catppuccin-mocha`
+    pub theme: Option<String>,
+    /// Preset used instead of `theme` while the color scheme is light
+    pub light_theme: Option<String>,
+    /// Preset used instead of `theme` while the color scheme is dark
+    pub dark_theme: Option<String>,
+    /// Follow the desktop's light/dark preference or force one
+    pub color_scheme: ColorScheme,
+    /// Built-in palette coloring the bar through CSS custom properties, e.g. `nord`
+    pub palette: Option<String>,
+    /// Named colors overriding the palette's, defined as `--<name>` custom properties
+    pub colors: HashMap<String, String>,
+    pub layout: LayoutConfig,
+    pub recent_files: RecentFilesConfig,
+    pub notes: NotesConfig,
+    pub clock: ClockConfig,
+    pub image: ImageConfig,
+    pub ticker: TickerConfig,
+    pub audio_profiles: AudioProfilesConfig,
+    pub audio: AudioConfig,
+    pub display_profiles: DisplayProfilesConfig,
+    pub window_title: WindowTitleConfig,
+    pub hypr_toggles: HyprTogglesConfig,
+    pub night_light: NightLightConfig,
+    pub power: PowerConfig,
+    pub notifications: NotificationsConfig,
+    pub bar: BarConfig,
+    pub tray: TrayConfig,
+    pub system_monitor: SystemMonitorConfig,
+    pub pipewire: PipewireConfig,
+    pub disk: DiskConfig,
+    pub magnifier: MagnifierConfig,
+    pub accessibility: AccessibilityConfig,
+    pub remote: RemoteConfig,
+    pub libvirt: LibvirtConfig,
+    pub torrent: TorrentConfig,
+    pub backlight: BacklightConfig,
+    pub downloads: DownloadsConfig,
+    pub time_tracker: TimeTrackerConfig,
+    pub matrix: MatrixConfig,
+    pub idle_inhibitor: IdleInhibitorConfig,
+    pub privacy: PrivacyConfig,
+    pub taskbar: TaskbarConfig,
+    pub launcher: LauncherConfig,
+    pub vpn: VpnConfig,
+    /// User defined modules, placed in the layout as `custom/<name>`
+    pub custom: HashMap<String, CustomModuleConfig>,
+    /// Drawers of modules, placed in the layout as `group/<name>`
+    pub group: HashMap<String, GroupConfig>,
+    /// Problems found while loading the file, shown as a badge on the bar
+    #[serde(skip)]
+    pub problems: Vec<validation::Diagnostic>,
+}
+
+/// Whether the bar is light or dark
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorScheme {
+    /// The `color-scheme` setting of the desktop, read through the settings portal
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+/// Ordered lists of module names for each zone of the bar
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    pub start: Vec<String>,
+    pub center: Vec<String>,
+    pub end: Vec<String>,
+    /// Modules collapse into an overflow menu when the bar is longer than its output,
+    /// lowest priority first. Unlisted modules have priority 0.
+    pub priority: HashMap<String, i32>,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            start: vec![
+                "title".to_string(),
+                "workspaces".to_string(),
+                "window_switcher".to_string(),
+            ],
+            center: vec!["clock".to_string()],
+            end: vec![
+                "system_monitor".to_string(),
+                "notifications".to_string(),
+                "tray".to_string(),
+            ],
+            priority: HashMap::new(),
+        }
+    }
+}
+
+impl LayoutConfig {
+    /// Module lists paired with the zone they are placed in
+    pub fn zones(&self) -> [(Zone, &[String]); 3] {
+        [
+            (Zone::Start, self.start.as_slice()),
+            (Zone::Center, self.center.as_slice()),
+            (Zone::End, self.end.as_slice()),
+        ]
+    }
+
+    pub fn zone_mut(&mut self, zone: Zone) -> &mut Vec<String> {
+        match zone {
+            Zone::Start => &mut self.start,
+            Zone::Center => &mut self.center,
+            Zone::End => &mut self.end,
+        }
+    }
+
+    /// Move the module at `from` so it is placed before the module at `to`,
+    /// given as zone and index into the zone's list
+    pub fn move_module(&mut self, from: (Zone, usize), to: (Zone, usize)) {
+        let (from_zone, from_index) = from;
+        let (to_zone, mut to_index) = to;
+
+        let source = self.zone_mut(from_zone);
+        if from_index >= source.len() {
+            return;
+        }
+        let name = source.remove(from_index);
+
+        // The target shifted when it came after the module in the same zone
+        if from_zone == to_zone && from_index < to_index {
+            to_index -= 1;
+        }
+        let target = self.zone_mut(to_zone);
+        target.insert(to_index.min(target.len()), name);
+    }
+
+    /// Write the module lists into the config file, leaving the rest of it untouched
+    pub fn save(&self) -> Result<(), String> {
+        let path = Config::path().ok_or("no configuration directory")?;
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+
+        let mut document: DocumentMut = contents
+            .parse()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let table = document
+            .entry("layout")
+            .or_insert(toml_edit::table())
+            .as_table_mut()
+            .ok_or_else(|| format!("{}: 'layout' is not a table", path.display()))?;
+
+        for (key, names) in [
+            ("start", &self.start),
+            ("center", &self.center),
+            ("end", &self.end),
+        ] {
+            let names: Array = names.iter().map(String::as_str).collect();
+            table[key] = toml_edit::value(names);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+        }
+        fs::write(&path, document.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// Settings for the recently used files module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RecentFilesConfig {
+    /// Maximum number of documents listed in the popover
+    pub limit: usize,
+}
+
+impl Default for RecentFilesConfig {
+    fn default() -> Self {
+        RecentFilesConfig { limit: 10 }
+    }
+}
+
+/// Settings for the notes scratchpad module
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NotesConfig {
+    /// File the notes are stored in, defaults to `$XDG_DATA_HOME/blade_bar/notes.txt`
+    pub path: Option<PathBuf>,
+}
+
+/// Settings for the clock module
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ClockConfig {
+    /// strftime style format, defaults to `%H:%M` (or `%H:%M:%S` with seconds)
+    pub format: Option<String>,
+    /// Update every second instead of every minute
+    pub show_seconds: bool,
+    /// IANA timezone identifier such as `Europe/Zurich`, defaults to local time
+    pub timezone: Option<String>,
+    /// Label template around the formatted time, with `{time}` and `{icon}`
+    pub label: Option<String>,
+    /// Icons for `{icon}`, spread evenly over the hours of the day
+    pub icons: Vec<String>,
+}
+
+/// Settings for the image module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ImageConfig {
+    /// Image file path or URI to display
+    pub path: Option<String>,
+    /// Shell command printing an image path or URI, takes precedence over `path`
+    pub command: Option<String>,
+    /// Refresh interval in seconds, 0 disables refreshing
+    pub interval: u64,
+    /// Height of the image in pixels
+    pub size: i32,
+    /// Command run for an image dropped on the module, `{file}` is replaced by its
+    /// quoted path, e.g. to set it as the wallpaper
+    pub on_drop: Option<String>,
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        ImageConfig {
+            path: None,
+            command: None,
+            interval: 0,
+            size: 20,
+            on_drop: None,
+        }
+    }
+}
+
+/// Settings for the scrolling ticker module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TickerConfig {
+    /// Static text to scroll
+    pub text: Option<String>,
+    /// Shell command whose output is scrolled, takes precedence over `text`
+    pub command: Option<String>,
+    /// Seconds between command runs
+    pub interval: u64,
+    /// Visible width of the ticker in pixels
+    pub width: i32,
+    /// Scroll speed in pixels per second
+    pub speed: f64,
+    /// Stop scrolling while the pointer is over the ticker
+    pub pause_on_hover: bool,
+}
+
+impl Default for TickerConfig {
+    fn default() -> Self {
+        TickerConfig {
+            text: None,
+            command: None,
+            interval: 60,
+            width: 250,
+            speed: 30.0,
+            pause_on_hover: true,
+        }
+    }
+}
+
+/// Settings for the volume module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// Volume change in percent per scroll step
+    pub step: u32,
+    /// Upper limit in percent when scrolling up
+    pub max_volume: u32,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig {
+            step: 5,
+            max_volume: 100,
+        }
+    }
+}
+
+/// Settings for the audio profile switcher module
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AudioProfilesConfig {
+    /// Switch to the first profile whose devices appear (e.g. when docking)
+    pub auto_switch: bool,
+    pub profiles: Vec<AudioProfile>,
+}
+
+/// A saved pair of default output and input devices
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AudioProfile {
+    pub name: String,
+    /// Substring matched against sink names
+    pub sink: Option<String>,
+    /// Substring matched against source names
+    pub source: Option<String>,
+}
+
+/// Settings for the display layout profile switcher module
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DisplayProfilesConfig {
+    /// Profiles to offer, when empty the profile names are read from the kanshi config
+    pub profiles: Vec<DisplayProfile>,
+}
+
+/// A saved monitor layout
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DisplayProfile {
+    pub name: String,
+    /// Shell command applying the layout (e.g. a `wlr-randr` invocation),
+    /// defaults to `kanshictl switch <name>`
+    pub command: Option<String>,
+}
+
+/// Settings for the focused window title module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WindowTitleConfig {
+    /// Maximum number of characters before the title is ellipsized
+    pub max_length: i32,
+    /// Prefix the title with the application id
+    pub show_app_id: bool,
+    /// Rewrite rules applied in order to the title
+    pub rewrite: Vec<TitleRewrite>,
+}
+
+impl Default for WindowTitleConfig {
+    fn default() -> Self {
+        WindowTitleConfig {
+            max_length: 60,
+            show_app_id: false,
+            rewrite: Vec::new(),
+        }
+    }
+}
+
+/// Regex replacement applied to window titles, optionally only for one application
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TitleRewrite {
+    /// Only apply to windows with this app_id / class
+    pub app_id: Option<String>,
+    /// Regular expression matched against the title
+    pub pattern: String,
+    /// Replacement, may reference capture groups as `$1`
+    pub replace: String,
+}
+
+/// Settings for the Hyprland keyword toggles module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HyprTogglesConfig {
+    pub toggles: Vec<HyprToggle>,
+}
+
+impl Default for HyprTogglesConfig {
+    fn default() -> Self {
+        HyprTogglesConfig {
+            toggles: vec![
+                HyprToggle::new("Blur", "decoration:blur:enabled"),
+                HyprToggle::new("Gaps", "general:gaps_out"),
+                HyprToggle::new("Anim", "animations:enabled"),
+            ],
+        }
+    }
+}
+
+/// A Hyprland option flipped between an "on" and an "off" value
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HyprToggle {
+    pub label: String,
+    /// Option name as used by `hyprctl keyword`, e.g. `decoration:blur:enabled`
+    pub option: String,
+    /// Value applied when enabled, defaults to the value found at startup
+    pub on: Option<String>,
+    /// Value applied when disabled
+    #[serde(default = "HyprToggle::default_off")]
+    pub off: String,
+}
+
+impl HyprToggle {
+    fn new(label: &str, option: &str) -> Self {
+        HyprToggle {
+            label: label.to_string(),
+            option: option.to_string(),
+            on: None,
+            off: Self::default_off(),
+        }
+    }
+
+    fn default_off() -> String {
+        "0".to_string()
+    }
+}
+
+/// Settings for the launcher module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LauncherConfig {
+    pub buttons: Vec<LauncherButton>,
+}
+
+impl Default for LauncherConfig {
+    fn default() -> Self {
+        LauncherConfig {
+            buttons: vec![LauncherButton {
+                icon: Some("view-app-grid-symbolic".to_string()),
+                command: Some("wofi --show drun".to_string()),
+                tooltip: Some("Applications".to_string()),
+                ..LauncherButton::default()
+            }],
+        }
+    }
+}
+
+/// One launcher button, running `command` or starting the pinned `app`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LauncherButton {
+    /// Icon name or image path, defaults to the icon of `app`
+    pub icon: Option<String>,
+    /// Text shown instead of an icon
+    pub label: Option<String>,
+    /// Shell command run on click
+    pub command: Option<String>,
+    /// Desktop file id of a pinned application, started on click when there is no
+    /// command and opening files dropped on the button
+    pub app: Option<String>,
+    pub tooltip: Option<String>,
+    /// Extra CSS class of the button
+    pub class: Option<String>,
+}
+
+/// Settings for the taskbar module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TaskbarConfig {
+    /// Icon size in pixels
+    pub icon_size: i32,
+    /// Show the window title next to the icon
+    pub show_titles: bool,
+    /// Maximum number of characters before a title is ellipsized
+    pub title_length: i32,
+}
+
+impl Default for TaskbarConfig {
+    fn default() -> Self {
+        TaskbarConfig {
+            icon_size: 20,
+            show_titles: false,
+            title_length: 20,
+        }
+    }
+}
+
+/// Settings for the idle inhibitor module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IdleInhibitorConfig {
+    /// Label while idle is inhibited
+    pub active_icon: String,
+    /// Label while the screen may idle
+    pub inactive_icon: String,
+}
+
+impl Default for IdleInhibitorConfig {
+    fn default() -> Self {
+        IdleInhibitorConfig {
+            active_icon: "☕".to_string(),
+            inactive_icon: "💤".to_string(),
+        }
+    }
+}
+
+/// Settings for the night light module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NightLightConfig {
+    /// `wlsunset` or `hyprsunset`, detected automatically when unset
+    pub backend: Option<String>,
+    /// Slider range in Kelvin
+    pub min_temperature: u32,
+    pub max_temperature: u32,
+    /// Temperature used until one has been picked with the slider
+    pub default_temperature: u32,
+}
+
+impl Default for NightLightConfig {
+    fn default() -> Self {
+        NightLightConfig {
+            backend: None,
+            min_temperature: 2500,
+            max_temperature: 6500,
+            default_temperature: 4000,
+        }
+    }
+}
+
+/// Settings for battery-aware throttling
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PowerConfig {
+    pub enabled: bool,
+    /// Throttle when discharging at or below this battery percentage
+    pub battery_threshold: u32,
+    /// Factor polling intervals are stretched by while throttled
+    pub interval_multiplier: u32,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        PowerConfig {
+            enabled: true,
+            battery_threshold: 100,
+            interval_multiplier: 3,
+        }
+    }
+}
+
+/// Settings for the VPN module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct VpnConfig {
+    /// Tunnel interface names, a trailing `*` matches any suffix
+    pub interfaces: Vec<String>,
+    /// Command bringing the VPN up on click
+    pub up: Option<String>,
+    /// Command bringing the VPN down on click
+    pub down: Option<String>,
+    /// NetworkManager connection toggled when no commands are configured
+    pub connection: Option<String>,
+    pub active_icon: String,
+    pub inactive_icon: String,
+    /// Seconds between checks of the interfaces
+    pub interval: u64,
+}
+
+impl Default for VpnConfig {
+    fn default() -> Self {
+        VpnConfig {
+            interfaces: ["wg*", "tun*", "tailscale*", "wg-mullvad", "proton*", "nordlynx"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            up: None,
+            down: None,
+            connection: None,
+            active_icon: "🔒".to_string(),
+            inactive_icon: "🔓".to_string(),
+            interval: 1,
+        }
+    }
+}
+
+/// Settings for the privacy indicators
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    /// Seconds between checks of the PipeWire graph and open video devices
+    pub interval: u64,
+    pub microphone_icon: String,
+    pub camera_icon: String,
+    pub screenshare_icon: String,
+    /// Applications whose capture is not shown, e.g. audio visualizers
+    pub ignore: Vec<String>,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        PrivacyConfig {
+            interval: 2,
+            microphone_icon: "🎤".to_string(),
+            camera_icon: "📷".to_string(),
+            screenshare_icon: "🖥".to_string(),
+            ignore: Vec::new(),
+        }
+    }
+}
+
+/// Settings for the bar windows
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BarConfig {
+    /// Screen edge: `top`, `bottom`, `left` or `right`
+    pub position: Position,
+    /// Connector names (e.g. `DP-1`) to show the bar on, empty for every output
+    pub outputs: Vec<String>,
+    /// Connector names to never show the bar on
+    pub exclude_outputs: Vec<String>,
+    /// Hide the bar off-screen until the pointer touches its edge
+    pub autohide: bool,
+    /// Milliseconds to wait after the pointer leaves before hiding again
+    pub autohide_delay: u64,
+    /// Milliseconds the bar stays revealed when a module requests attention
+    pub attention_duration: u64,
+    /// Thickness in pixels, the bar still grows when its modules need more
+    pub height: i32,
+    /// Space reserved for the bar, computed from its thickness and the margin facing
+    /// windows when not set
+    pub exclusive_zone: Option<i32>,
+    /// Gap between the bar and each screen edge, for a floating bar
+    pub margin: MarginConfig,
+    /// Layer-shell layer, `overlay` stays above fullscreen windows and `bottom`
+    /// below floating ones
+    pub layer: BarLayer,
+    /// Font family for everything on the bar, e.g. `Inter`
+    pub font_family: Option<String>,
+    /// Font size in points for text that does not set its own
+    pub font_size: Option<f64>,
+    /// Put the text of all modules on one baseline and center icons on it
+    pub align_baselines: bool,
+}
+
+impl Default for BarConfig {
+    fn default() -> Self {
+        BarConfig {
+            position: Position::default(),
+            outputs: Vec::new(),
+            exclude_outputs: Vec::new(),
+            autohide: false,
+            autohide_delay: 500,
+            attention_duration: 3000,
+            height: 30,
+            exclusive_zone: None,
+            margin: MarginConfig::default(),
+            layer: BarLayer::default(),
+            font_family: None,
+            font_size: None,
+            align_baselines: true,
+        }
+    }
+}
+
+/// Layer the bar is placed on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarLayer {
+    /// Above normal windows, below fullscreen ones, reserving space for the bar
+    #[default]
+    Top,
+    /// Above everything including fullscreen windows, without reserving space
+    Overlay,
+    /// Below normal windows, e.g. for a desktop-style bar that floating windows may
+    /// cover, still reserving space
+    Bottom,
+}
+
+/// Layer-shell margins in pixels
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MarginConfig {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
+/// Settings for a `custom/<name>` module driven by a shell command
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CustomModuleConfig {
+    /// Shell command producing the module output
+    pub exec: String,
+    /// Seconds between runs (or before restarting a continuous command), 0 runs it once
+    pub interval: u64,
+    /// Keep the command running and update on every line it prints
+    pub continuous: bool,
+    /// Parse output as JSON with `text`, `tooltip`, `class` and `percentage` keys,
+    /// otherwise the lines are text, tooltip and class
+    pub json: bool,
+    /// Label template, `{text}`, `{percentage}` and `{icon}` are substituted
+    pub format: Option<String>,
+    /// Icons for `{icon}`, spread evenly over the percentage range
+    pub icons: Vec<String>,
+    pub on_click: Option<String>,
+    pub on_middle_click: Option<String>,
+    pub on_right_click: Option<String>,
+    pub on_scroll_up: Option<String>,
+    pub on_scroll_down: Option<String>,
+    /// Command run for files dropped on the module, `{files}` is replaced by their
+    /// quoted paths
+    pub on_drop: Option<String>,
+}
+
+/// Settings for a `group/<name>` drawer
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GroupConfig {
+    /// Modules in the drawer, in order
+    pub modules: Vec<String>,
+    /// How the drawer opens besides clicking its button
+    pub reveal: GroupReveal,
+    /// Label of the toggle button
+    pub icon: String,
+    pub tooltip: Option<String>,
+    /// Start with the drawer open
+    pub expanded: bool,
+    /// Milliseconds of the slide animation
+    pub transition_duration: u32,
+}
+
+impl Default for GroupConfig {
+    fn default() -> Self {
+        GroupConfig {
+            modules: Vec::new(),
+            reveal: GroupReveal::default(),
+            icon: "⋯".to_string(),
+            tooltip: None,
+            expanded: false,
+            transition_duration: 250,
+        }
+    }
+}
+
+/// When a group drawer opens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupReveal {
+    /// Only on click of the button
+    #[default]
+    Click,
+    /// While the pointer is over the group
+    Hover,
+}
+
+/// Settings for the notifications module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Daemon the bar button controls, detected when not set
+    pub backend: Option<NotificationDaemon>,
+    /// Run the built-in notification daemon showing toasts, when no other daemon is running
+    pub daemon: bool,
+    /// Milliseconds a toast stays up when the application does not say
+    pub timeout: u64,
+    /// Rules for notifications that should not pop up, checked in order
+    pub mute: Vec<MuteRule>,
+    /// How the built-in daemon escalates notifications of each urgency
+    pub urgency: UrgencyConfig,
+    /// Icon name of the bar button without notifications
+    pub icon: String,
+    /// Icon name while notifications are waiting
+    pub unread_icon: String,
+    /// Icon name while do-not-disturb is on
+    pub dnd_icon: String,
+    /// Show the number of notifications next to the icon
+    pub show_count: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        NotificationsConfig {
+            backend: None,
+            daemon: false,
+            timeout: 5000,
+            mute: Vec::new(),
+            urgency: UrgencyConfig::default(),
+            icon: "preferences-system-notifications-symbolic".to_string(),
+            unread_icon: "preferences-system-notifications-symbolic".to_string(),
+            dnd_icon: "notifications-disabled-symbolic".to_string(),
+            show_count: true,
+        }
+    }
+}
+
+/// Notification daemons the notifications module can control
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationDaemon {
+    Swaync,
+    Mako,
+    Fnott,
+}
+
+/// Escalation settings for each notification urgency level
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UrgencyConfig {
+    pub low: UrgencyLevel,
+    pub normal: UrgencyLevel,
+    pub critical: UrgencyLevel,
+}
+
+impl Default for UrgencyConfig {
+    fn default() -> Self {
+        UrgencyConfig {
+            low: UrgencyLevel::default(),
+            normal: UrgencyLevel::default(),
+            critical: UrgencyLevel {
+                sticky: true,
+                tint_bar: true,
+                sound: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct UrgencyLevel {
+    /// Keep the toast up until it is dismissed, ignoring the timeout
+    pub sticky: bool,
+    /// Tint the bar border while the toast is up
+    pub tint_bar: bool,
+    /// Sound file played when the notification arrives
+    pub sound: Option<String>,
+}
+
+/// Mutes notifications from applications matching `app_name`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MuteRule {
+    /// Regular expression matched against the sending application's name
+    pub app_name: String,
+    #[serde(default)]
+    pub action: MuteAction,
+}
+
+/// What happens to a muted notification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MuteAction {
+    /// Close it right away and only count it on the bar
+    #[default]
+    Count,
+    /// Close it right away without a trace
+    Dismiss,
+}
+
+/// Settings for the tray module
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct TrayConfig {
+    /// Also show items that report themselves as passive (idle, nothing to act on)
+    pub show_passive: bool,
+    /// Items never shown, matched by id or title ignoring case
+    pub hidden: Vec<String>,
+    /// Items pinned first, in this order, the rest follow in order of appearance
+    pub order: Vec<String>,
+    /// Items shown on the bar, the rest collapse behind a chevron. 0 shows all.
+    pub max_visible: usize,
+}
+
+/// Settings for the system monitor module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SystemMonitorConfig {
+    /// How CPU load is shown on the bar, clicking always lists every core
+    pub cpu_display: CpuDisplay,
+    /// Draw the recent CPU and memory usage as small graphs next to the numbers
+    pub graphs: bool,
+    /// Number of samples in each graph, one is taken every two seconds
+    pub history: usize,
+    /// Memory label, with `{percent}`, `{used}`, `{total}`, `{available}`, `{swap_used}`,
+    /// `{swap_total}` and `{swap_percent}` replaced
+    pub memory_format: String,
+    /// CSS colors of the graphs
+    pub cpu_color: String,
+    pub memory_color: String,
+    /// Show the download and upload rate
+    pub network: bool,
+    /// Interfaces summed for the rates, all but loopback when empty
+    pub interfaces: Vec<String>,
+    /// List the traffic since boot of each interface in the tooltip
+    pub network_totals: bool,
+    /// Levels in percent (°C for the temperature) from which the labels get the
+    /// `warning` and `critical` classes, 0 disables a level
+    pub cpu_warning: f64,
+    pub cpu_critical: f64,
+    pub memory_warning: f64,
+    pub memory_critical: f64,
+    pub temperature_warning: f64,
+    pub temperature_critical: f64,
+    /// Send a desktop notification when a metric becomes critical
+    pub alerts: bool,
+    /// Minimum seconds between two notifications about the same metric
+    pub alert_interval: u64,
+    /// Shell commands run when a metric is clicked, instead of opening the core list
+    pub on_click: MetricActions,
+    /// Label templates replacing the built-in `CPU: 12.3%` style labels
+    pub labels: MetricLabels,
+    /// Icons for `{icon}` in the labels, spread evenly from 0 to 100% (to the
+    /// critical level for the temperature)
+    pub icons: Vec<String>,
+}
+
+/// A shell command per system monitor metric
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MetricActions {
+    pub cpu: Option<String>,
+    pub memory: Option<String>,
+    pub temperature: Option<String>,
+    pub network: Option<String>,
+}
+
+/// A label template per system monitor metric, the built-in label when unset
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MetricLabels {
+    /// `{usage}`, `{name}` (CPU or MAX) and `{icon}`
+    pub cpu: Option<String>,
+    /// `{usage}`, `{icon}` and the `memory_format` placeholders
+    pub memory: Option<String>,
+    /// `{temperature}` and `{icon}`
+    pub temperature: Option<String>,
+}
+
+impl Default for SystemMonitorConfig {
+    fn default() -> Self {
+        SystemMonitorConfig {
+            cpu_display: CpuDisplay::default(),
+            graphs: false,
+            history: 30,
+            memory_format: "{percent}".to_string(),
+            cpu_color: "#4CAF50".to_string(),
+            memory_color: "#2196F3".to_string(),
+            network: false,
+            interfaces: Vec::new(),
+            network_totals: true,
+            cpu_warning: 80.0,
+            cpu_critical: 95.0,
+            memory_warning: 80.0,
+            memory_critical: 95.0,
+            temperature_warning: 75.0,
+            temperature_critical: 90.0,
+            alerts: false,
+            alert_interval: 300,
+            on_click: MetricActions::default(),
+            labels: MetricLabels::default(),
+            icons: Vec::new(),
+        }
+    }
+}
+
+/// CPU load shown on the bar
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CpuDisplay {
+    /// Average over all cores
+    #[default]
+    Average,
+    /// The busiest core, shows single threaded load the average hides
+    MaxCore,
+    /// A small bar per core
+    PerCore,
+}
+
+/// Settings for the PipeWire quantum and sample rate module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PipewireConfig {
+    /// Quantum sizes (in samples) offered in the popover, besides automatic
+    pub quanta: Vec<u32>,
+    /// Sample rates offered in the popover, besides automatic
+    pub rates: Vec<u32>,
+}
+
+impl Default for PipewireConfig {
+    fn default() -> Self {
+        PipewireConfig {
+            quanta: vec![64, 128, 256, 512, 1024],
+            rates: vec![44100, 48000, 96000],
+        }
+    }
+}
+
+/// Settings for the disk usage module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DiskConfig {
+    /// Mount points whose free space is shown on the bar
+    pub mounts: Vec<String>,
+    /// Also show the read and write rate of all disks
+    pub io: bool,
+    /// Usage in percent from which a mount point is marked with the `warning` class
+    pub warning: u8,
+    /// Refresh interval in seconds
+    pub interval: u64,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        DiskConfig {
+            mounts: vec!["/".to_string()],
+            io: false,
+            warning: 90,
+            interval: 10,
+        }
+    }
+}
+
+/// Settings for the screen magnifier module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MagnifierConfig {
+    /// Zoom factor applied when zoom is toggled on, until it is changed by scrolling
+    pub zoom: f64,
+    /// Zoom change per scroll step
+    pub step: f64,
+    /// Upper limit when scrolling up
+    pub max: f64,
+}
+
+impl Default for MagnifierConfig {
+    fn default() -> Self {
+        MagnifierConfig {
+            zoom: 2.0,
+            step: 0.25,
+            max: 8.0,
+        }
+    }
+}
+
+/// Settings for the accessibility profile
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// Factor applied to font and icon sizes while the profile is on
+    pub scale: f64,
+}
+
+impl Default for AccessibilityConfig {
+    fn default() -> Self {
+        AccessibilityConfig { scale: 1.5 }
+    }
+}
+
+/// Settings for the remote machine monitor
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RemoteConfig {
+    pub hosts: Vec<RemoteHost>,
+    /// Seconds between polls of each host
+    pub interval: u64,
+    /// Command run on hosts that set none, printing `key=value` lines. `cpu` and `mem`
+    /// (percent) and `uptime` (seconds) are shown, `load` with `cores` stands in for `cpu`.
+    pub command: String,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        RemoteConfig {
+            hosts: Vec::new(),
+            interval: 60,
+            command: concat!(
+                "awk '{print \"load=\" $1}' /proc/loadavg; ",
+                "echo \"cores=$(nproc)\"; ",
+                "awk '/^MemTotal:/{t=$2} /^MemAvailable:/{a=$2} ",
+                "END{print \"mem=\" int((t-a)*100/t)}' /proc/meminfo; ",
+                "awk '{print \"uptime=\" int($1)}' /proc/uptime"
+            )
+            .to_string(),
+        }
+    }
+}
+
+/// A machine watched over SSH
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteHost {
+    /// Destination passed to ssh, a `Host` alias from ~/.ssh/config or `user@host`
+    pub host: String,
+    /// Label on the bar, defaults to the host
+    pub name: Option<String>,
+    /// Overrides the metrics command for this host
+    pub command: Option<String>,
+}
+
+/// Settings for the libvirt domain module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LibvirtConfig {
+    /// Connection URI passed to virsh
+    pub uri: String,
+    /// Refresh interval in seconds
+    pub interval: u64,
+}
+
+impl Default for LibvirtConfig {
+    fn default() -> Self {
+        LibvirtConfig {
+            uri: "qemu:///system".to_string(),
+            interval: 10,
+        }
+    }
+}
+
+/// Settings for the torrent client module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TorrentConfig {
+    pub client: TorrentClient,
+    /// Transmission RPC endpoint or qBittorrent Web UI address, the client's local
+    /// default when unset
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Refresh interval in seconds
+    pub interval: u64,
+}
+
+impl Default for TorrentConfig {
+    fn default() -> Self {
+        TorrentConfig {
+            client: TorrentClient::default(),
+            url: None,
+            username: None,
+            password: None,
+            interval: 5,
+        }
+    }
+}
+
+/// Settings for the backlight module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BacklightConfig {
+    /// Device under /sys/class/backlight, the first one when unset
+    pub device: Option<String>,
+    /// Brightness change in percent per scroll step
+    pub step: u32,
+    /// Lowest brightness in percent when scrolling down
+    pub min: u32,
+}
+
+impl Default for BacklightConfig {
+    fn default() -> Self {
+        BacklightConfig {
+            device: None,
+            step: 5,
+            min: 1,
+        }
+    }
+}
+
+/// Settings for the downloads module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DownloadsConfig {
+    /// Directory watched for downloads, the XDG download directory when unset
+    pub directory: Option<PathBuf>,
+    /// aria2 JSON-RPC endpoint such as `http://localhost:6800/jsonrpc`
+    pub aria2: Option<String>,
+    /// Secret of aria2's `--rpc-secret`
+    pub aria2_secret: Option<String>,
+    /// Completed files listed in the popover
+    pub recent: usize,
+    /// Seconds between progress updates
+    pub interval: u64,
+}
+
+impl Default for DownloadsConfig {
+    fn default() -> Self {
+        DownloadsConfig {
+            directory: None,
+            aria2: None,
+            aria2_secret: None,
+            recent: 5,
+            interval: 2,
+        }
+    }
+}
+
+/// Settings for the time tracker module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TimeTrackerConfig {
+    pub backend: TimeTrackerBackend,
+    /// Toggl API token, from the profile page
+    pub api_token: Option<String>,
+    /// Toggl workspace id for new timers, the account's default when unset
+    pub workspace: Option<u64>,
+    /// Toggl project id new timers are booked on
+    pub project: Option<u64>,
+    /// Description of new timers
+    pub description: String,
+    /// ActivityWatch server address, defaults to `http://localhost:5600`
+    pub url: Option<String>,
+    /// Refresh interval in seconds
+    pub interval: u64,
+}
+
+impl Default for TimeTrackerConfig {
+    fn default() -> Self {
+        TimeTrackerConfig {
+            backend: TimeTrackerBackend::default(),
+            api_token: None,
+            workspace: None,
+            project: None,
+            description: String::new(),
+            url: None,
+            interval: 30,
+        }
+    }
+}
+
+/// Service the time tracker module reads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeTrackerBackend {
+    /// The running timer, started and stopped from the bar
+    #[default]
+    Toggl,
+    /// Active (not AFK) time today, read only
+    Activitywatch,
+}
+
+/// Settings for the Matrix mentions module
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MatrixConfig {
+    /// Client-server API base URL, such as `https://matrix.org`
+    pub homeserver: String,
+    /// Access token of the account, from the client's settings
+    pub access_token: Option<String>,
+    /// Shell command opening a room, `{room}` is replaced by its shell-quoted id
+    pub open: String,
+    /// Seconds the homeserver may hold a sync request open
+    pub timeout: u64,
+}
+
+impl Default for MatrixConfig {
+    fn default() -> Self {
+        MatrixConfig {
+            homeserver: String::new(),
+            access_token: None,
+            open: "xdg-open https://matrix.to/#/{room}".to_string(),
+            timeout: 30,
+        }
+    }
+}
+
+/// Torrent client the module talks to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TorrentClient {
+    /// transmission-daemon's JSON RPC
+    #[default]
+    Transmission,
+    /// qBittorrent's Web API
+    Qbittorrent,
+}
+
+/// User configuration directory, `$XDG_CONFIG_HOME` or `~/.config`
+pub fn config_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+/// Directory for persistent data, `$XDG_DATA_HOME/blade_bar`
+pub fn data_dir() -> Option<PathBuf> {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })?;
+
+    Some(data_dir.join("blade_bar"))
+}
+
+/// Directory for logs and other state, `$XDG_STATE_HOME/blade_bar`
+pub fn state_dir() -> Option<PathBuf> {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+
+    Some(state_dir.join("blade_bar"))
+}
+
+impl Config {
+    /// Path of the user configuration file
+    pub fn path() -> Option<PathBuf> {
+        Some(config_home()?.join("blade_bar").join("config.toml"))
+    }
+
+    /// Load the configuration, falling back to defaults if the file is missing or invalid
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Config::default();
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => return Config::default(),
+        };
+
+        // Problems are reported but never fatal, broken sections fall back to defaults
+        let (mut config, diagnostics) = validation::parse(&contents);
+        for diagnostic in &diagnostics {
+            warn!("{}: {}", path.display(), diagnostic);
+        }
+        config.problems = diagnostics;
+        config
+    }
+}
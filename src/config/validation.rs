@@ -0,0 +1,454 @@
+// Soft validation of the configuration file.
+//
+// Every top level section is deserialized on its own so a mistake in one widget's
+// settings only resets that widget to its defaults. Unknown keys, type mismatches,
+// invalid values and unknown module names in the layout are reported with the line
+// and column they appear on and, where possible, a did-you-mean suggestion.
+
+use serde::Deserialize;
+use serde::de::{self, Deserializer, Visitor};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::config::*;
+use crate::module::{MODULE_NAMES, is_module_name};
+
+/// A problem found while loading the configuration
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// 1-based line in the config file, when it could be determined
+    pub line: Option<usize>,
+    /// 1-based character column within `line`
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// A problem at the first `needle` on `line`, or at the line as a whole
+    fn at(source: &str, line: Option<usize>, needle: &str, message: String) -> Self {
+        let column = line
+            .and_then(|line| source.lines().nth(line - 1))
+            .and_then(|text| Some(text[..text.find(needle)?].chars().count() + 1));
+        Diagnostic {
+            line,
+            column,
+            message,
+        }
+    }
+
+    /// The offending line of `source` with a caret under the column, for terminals
+    pub fn context(&self, source: &str) -> Option<String> {
+        let line = self.line?;
+        let text = source.lines().nth(line - 1)?;
+        let gutter = " ".repeat(line.to_string().len());
+        let mut context = format!("{} | {}", line, text);
+        if let Some(column) = self.column {
+            context.push_str(&format!("\n{} | {}^", gutter, " ".repeat(column - 1)));
+        }
+        Some(context)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "line {}, column {}: {}", line, column, self.message)
+            }
+            (Some(line), None) => write!(f, "line {}: {}", line, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Declares the top level sections of the config file and how to deserialize them
+macro_rules! sections {
+    ($($name:ident: $ty:ty),* $(,)?) => {
+        const KNOWN_SECTIONS: &[&str] = &[$(stringify!($name)),*];
+
+        fn deserialize_sections(
+            table: &toml::Table,
+            source: &str,
+            config: &mut Config,
+            diagnostics: &mut Vec<Diagnostic>,
+        ) {
+            $(
+                if let Some(value) = table.get(stringify!($name)) {
+                    if let Some(section) =
+                        deserialize_section::<$ty>(stringify!($name), value, source, diagnostics)
+                    {
+                        config.$name = section;
+                    }
+                }
+            )*
+        }
+    };
+}
+
+sections! {
+    theme: Option<String>,
+    light_theme: Option<String>,
+    dark_theme: Option<String>,
+    color_scheme: ColorScheme,
+    palette: Option<String>,
+    colors: HashMap<String, String>,
+    layout: LayoutConfig,
+    recent_files: RecentFilesConfig,
+    notes: NotesConfig,
+    clock: ClockConfig,
+    image: ImageConfig,
+    ticker: TickerConfig,
+    audio_profiles: AudioProfilesConfig,
+    audio: AudioConfig,
+    display_profiles: DisplayProfilesConfig,
+    window_title: WindowTitleConfig,
+    hypr_toggles: HyprTogglesConfig,
+    night_light: NightLightConfig,
+    power: PowerConfig,
+    notifications: NotificationsConfig,
+    bar: BarConfig,
+    tray: TrayConfig,
+    system_monitor: SystemMonitorConfig,
+    pipewire: PipewireConfig,
+    disk: DiskConfig,
+    magnifier: MagnifierConfig,
+    accessibility: AccessibilityConfig,
+    remote: RemoteConfig,
+    libvirt: LibvirtConfig,
+    torrent: TorrentConfig,
+    backlight: BacklightConfig,
+    downloads: DownloadsConfig,
+    time_tracker: TimeTrackerConfig,
+    matrix: MatrixConfig,
+    idle_inhibitor: IdleInhibitorConfig,
+    privacy: PrivacyConfig,
+    taskbar: TaskbarConfig,
+    launcher: LauncherConfig,
+    vpn: VpnConfig,
+    custom: HashMap<String, CustomModuleConfig>,
+    group: HashMap<String, GroupConfig>,
+}
+
+/// Parse and validate config file contents, returning the usable config and all problems found
+pub fn parse(source: &str) -> (Config, Vec<Diagnostic>) {
+    let mut config = Config::default();
+    let mut diagnostics = Vec::new();
+
+    let table = match source.parse::<toml::Table>() {
+        Ok(table) => table,
+        Err(e) => {
+            let start = e.span().map(|span| span.start.min(source.len()));
+            diagnostics.push(Diagnostic {
+                line: start.map(|start| line_of_offset(source, start)),
+                column: start.map(|start| column_of_offset(source, start)),
+                message: format!("syntax error: {}, using default configuration", e.message()),
+            });
+            return (config, diagnostics);
+        }
+    };
+
+    for key in table.keys() {
+        if !KNOWN_SECTIONS.contains(&key.as_str()) {
+            diagnostics.push(Diagnostic::at(
+                source,
+                find_section_line(source, key),
+                key,
+                format!("unknown section '{}'{}", key, suggestion(key, KNOWN_SECTIONS)),
+            ));
+        }
+    }
+
+    deserialize_sections(&table, source, &mut config, &mut diagnostics);
+    check_layout(&config, source, &mut diagnostics);
+
+    (config, diagnostics)
+}
+
+/// Deserialize one section, reporting unknown keys and every invalid value.
+///
+/// A table is deserialized one key at a time, so each mistake is reported and only
+/// that key falls back to its default; in `[custom]` or `[group]` a key is a whole
+/// entry, which is left out. Section structs do not use
+/// `#[serde(deny_unknown_fields)]`: it would fail the whole section on a typo, while
+/// serde_ignored lets the rest load and reports the key with a suggestion. Only the
+/// entries of lists (audio and display profiles, title rewrites, toggles, mute rules,
+/// remote hosts) deny unknown fields, where a typo drops the list it is in.
+fn deserialize_section<T: for<'de> Deserialize<'de>>(
+    section: &str,
+    value: &toml::Value,
+    source: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<T> {
+    let mut unknown = Vec::new();
+    let mut errors = Vec::new();
+
+    let result = match value.as_table() {
+        Some(table) => {
+            let mut valid = toml::Table::new();
+            for (key, entry) in table {
+                let single = toml::Table::from_iter([(key.clone(), entry.clone())]);
+                match deserialize_tracked::<T>(toml::Value::Table(single), &mut unknown) {
+                    Ok(_) => {
+                        valid.insert(key.clone(), entry.clone());
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
+            // Unknown keys were reported above, this only fails on keys that conflict
+            deserialize_tracked(toml::Value::Table(valid), &mut Vec::new())
+        }
+        None => deserialize_tracked(value.clone(), &mut unknown),
+    };
+
+    let known = known_fields::<T>();
+    for path in unknown {
+        let key = path.rsplit('.').next().unwrap_or(&path).to_string();
+        diagnostics.push(Diagnostic::at(
+            source,
+            find_key_line(source, section, &key),
+            &key,
+            format!(
+                "unknown key '{}' in [{}]{}",
+                path,
+                section,
+                suggestion(&key, &known)
+            ),
+        ));
+    }
+
+    for e in errors {
+        diagnostics.push(invalid_value(section, source, &e, "ignoring it"));
+    }
+
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            diagnostics.push(invalid_value(
+                section,
+                source,
+                &e,
+                "using defaults for this section",
+            ));
+            None
+        }
+    }
+}
+
+/// Deserialize `value`, collecting the paths of the keys `T` does not know in `unknown`
+fn deserialize_tracked<T: for<'de> Deserialize<'de>>(
+    value: toml::Value,
+    unknown: &mut Vec<String>,
+) -> Result<T, serde_path_to_error::Error<toml::de::Error>> {
+    // The callback is borrowed for the whole deserialization, keep it in one expression
+    serde_path_to_error::deserialize(serde_ignored::Deserializer::new(value, &mut |path| {
+        unknown.push(path.to_string());
+    }))
+}
+
+/// An invalid value in `section`, located by the path of the error
+fn invalid_value(
+    section: &str,
+    source: &str,
+    error: &serde_path_to_error::Error<toml::de::Error>,
+    fallback: &str,
+) -> Diagnostic {
+    let path = error.path().to_string();
+    let key = path.rsplit('.').next().unwrap_or(&path);
+    let line = find_key_line(source, section, key).or_else(|| find_section_line(source, section));
+
+    // An empty path means the section itself has the wrong type
+    let location = if path == "." {
+        format!("'{}'", section)
+    } else {
+        format!("'{}' in [{}]", path, section)
+    };
+
+    Diagnostic::at(
+        source,
+        line,
+        key,
+        format!(
+            "invalid value for {}: {}, {}",
+            location,
+            error.inner(),
+            fallback
+        ),
+    )
+}
+
+/// Layout entries no module answers to, which the bar would skip, and custom modules
+/// or groups without their section
+fn check_layout(config: &Config, source: &str, diagnostics: &mut Vec<Diagnostic>) {
+    for (zone, names) in config.layout.zones() {
+        let zone = format!("{:?}", zone).to_lowercase();
+        for name in names {
+            let message = if !is_module_name(name) {
+                format!(
+                    "unknown module '{}' in layout.{}{}",
+                    name,
+                    zone,
+                    suggestion(name, MODULE_NAMES)
+                )
+            } else if let Some(custom) = name
+                .strip_prefix("custom/")
+                .filter(|custom| !config.custom.contains_key(*custom))
+            {
+                format!("module '{}' has no [custom.{}] section", name, custom)
+            } else if let Some(group) = name
+                .strip_prefix("group/")
+                .filter(|group| !config.group.contains_key(*group))
+            {
+                format!("module '{}' has no [group.{}] section", name, group)
+            } else {
+                continue;
+            };
+
+            let quoted = format!("\"{}\"", name);
+            diagnostics.push(Diagnostic::at(
+                source,
+                find_in_section(source, "layout", &quoted),
+                &quoted,
+                message,
+            ));
+        }
+    }
+}
+
+/// Field names a struct accepts, captured from the `deserialize_struct` call serde makes
+fn known_fields<T: for<'de> Deserialize<'de>>() -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    let _ = T::deserialize(FieldCollector(&mut fields));
+    fields
+}
+
+struct FieldCollector<'a>(&'a mut Vec<&'static str>);
+
+impl<'de> Deserializer<'de> for FieldCollector<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.extend_from_slice(fields);
+        Err(de::Error::custom("fields collected"))
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// " (did you mean 'x'?)" for the closest candidate, or nothing if none is close
+fn suggestion(input: &str, candidates: &[&str]) -> String {
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(input, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| format!(" (did you mean '{}'?)", candidate))
+        .unwrap_or_default()
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+fn line_of_offset(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+fn column_of_offset(source: &str, offset: usize) -> usize {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    before[line_start..].chars().count() + 1
+}
+
+fn header_name(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let inner = line
+        .strip_prefix("[[")
+        .and_then(|rest| rest.strip_suffix("]]"))
+        .or_else(|| line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')))?;
+    Some(inner.trim())
+}
+
+/// Line of the `[section]` header (or a top level `section = ...` key)
+fn find_section_line(source: &str, section: &str) -> Option<usize> {
+    source
+        .lines()
+        .position(|line| {
+            header_name(line).is_some_and(|name| is_in_section(name, section))
+                || key_of(line) == Some(section)
+        })
+        .map(|index| index + 1)
+}
+
+/// Whether a table header belongs to `section`, including sub tables like `[section.sub]`
+fn is_in_section(header: &str, section: &str) -> bool {
+    header == section
+        || header
+            .strip_prefix(section)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Line where `key` is assigned inside `[section]` or one of its sub tables
+fn find_key_line(source: &str, section: &str, key: &str) -> Option<usize> {
+    let mut in_section = false;
+
+    for (index, line) in source.lines().enumerate() {
+        if let Some(name) = header_name(line) {
+            in_section = is_in_section(name, section);
+            continue;
+        }
+        if in_section && key_of(line) == Some(key) {
+            return Some(index + 1);
+        }
+    }
+
+    None
+}
+
+/// First line inside `[section]` containing `needle`, e.g. an entry of a multi-line
+/// array
+fn find_in_section(source: &str, section: &str, needle: &str) -> Option<usize> {
+    let mut in_section = false;
+
+    for (index, line) in source.lines().enumerate() {
+        if let Some(name) = header_name(line) {
+            in_section = is_in_section(name, section);
+            continue;
+        }
+        if in_section && !line.trim_start().starts_with('#') && line.contains(needle) {
+            return Some(index + 1);
+        }
+    }
+
+    None
+}
+
+fn key_of(line: &str) -> Option<&str> {
+    let (key, _) = line.split_once('=')?;
+    Some(key.trim().trim_matches('"'))
+}
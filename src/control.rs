@@ -0,0 +1,275 @@
+// Session bus interface for controlling the running bar from scripts and keybindings:
+//
+//   busctl --user call org.swordi.BladeBar /org/swordi/BladeBar org.swordi.BladeBar ToggleVisibility
+//
+// `blade_bar edit` calls `ToggleEditMode` the same way. The bus name doubles as the
+// single-instance guard and is claimed before the bar is built, `blade_bar --replace`
+// asks its owner to `Quit` first. Modules hidden with `SetModuleVisible` stay hidden
+// across restarts:
+//
+//   busctl --user call org.swordi.BladeBar /org/swordi/BladeBar org.swordi.BladeBar SetModuleVisible sb tray false
+//
+// Method calls arrive on zbus' executor and are forwarded to the GTK main loop over a
+// channel. Modules report updates through `module_updated`, which are emitted as the
+// `ModuleUpdated` signal.
+
+use gtk4::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use zbus::fdo::RequestNameFlags;
+use zbus::object_server::SignalEmitter;
+use tracing::warn;
+
+use crate::bar::Bars;
+
+const BUS_NAME: &str = "org.swordi.BladeBar";
+const OBJECT_PATH: &str = "/org/swordi/BladeBar";
+
+/// How long `--replace` waits for the running bar to let go of the bus name
+const REPLACE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Request received over D-Bus, handled on the main thread
+#[derive(Debug)]
+enum ControlCommand {
+    ToggleVisibility,
+    Reload,
+    SetVisible(bool),
+    RefreshModule(String),
+    SetModuleVisible(String, bool),
+    ToggleEditMode,
+    Quit,
+}
+
+thread_local! {
+    static UPDATES: RefCell<Option<UnboundedSender<String>>> = const { RefCell::new(None) };
+}
+
+/// Announce that module `name` has new content, emitted as `ModuleUpdated`
+pub fn module_updated(name: &str) {
+    UPDATES.with(|updates| {
+        if let Some(updates) = updates.borrow().as_ref() {
+            let _ = updates.send(name.to_string());
+        }
+    });
+}
+
+struct BarInterface {
+    commands: UnboundedSender<ControlCommand>,
+}
+
+#[zbus::interface(name = "org.swordi.BladeBar")]
+impl BarInterface {
+    fn toggle_visibility(&self) {
+        let _ = self.commands.send(ControlCommand::ToggleVisibility);
+    }
+
+    /// Re-read the configuration and rebuild the bar
+    fn reload(&self) {
+        let _ = self.commands.send(ControlCommand::Reload);
+    }
+
+    /// `hidden` or `normal`
+    fn set_mode(&self, mode: &str) -> zbus::fdo::Result<()> {
+        let visible = match mode {
+            "hidden" => false,
+            "normal" => true,
+            _ => {
+                return Err(zbus::fdo::Error::InvalidArgs(format!(
+                    "Unknown mode '{}', expected 'hidden' or 'normal'",
+                    mode
+                )));
+            }
+        };
+        let _ = self.commands.send(ControlCommand::SetVisible(visible));
+        Ok(())
+    }
+
+    /// Update a module now instead of waiting for its next refresh, e.g. `custom/updates`
+    fn refresh_module(&self, name: String) {
+        let _ = self.commands.send(ControlCommand::RefreshModule(name));
+    }
+
+    /// Show or hide a module by its name in the layout, remembered across restarts
+    fn set_module_visible(&self, name: String, visible: bool) {
+        let _ = self
+            .commands
+            .send(ControlCommand::SetModuleVisible(name, visible));
+    }
+
+    /// Show or hide drag handles for rearranging modules, saved to the config file
+    fn toggle_edit_mode(&self) {
+        let _ = self.commands.send(ControlCommand::ToggleEditMode);
+    }
+
+    /// Shut the bar down, e.g. for a new instance started with `--replace`
+    fn quit(&self) {
+        let _ = self.commands.send(ControlCommand::Quit);
+    }
+
+    #[zbus(signal)]
+    async fn module_updated(emitter: &SignalEmitter<'_>, name: &str) -> zbus::Result<()>;
+}
+
+/// Call a method without arguments on the running bar, returning the process exit code
+pub async fn call(method: &str) -> i32 {
+    let connection = match zbus::Connection::session().await {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!("Failed to connect to the session bus: {}", e);
+            return 1;
+        }
+    };
+
+    match connection
+        .call_method(Some(BUS_NAME), OBJECT_PATH, Some(BUS_NAME), method, &())
+        .await
+    {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Failed to call {} on the running bar: {}", method, e);
+            1
+        }
+    }
+}
+
+/// Session bus connection owning the bar's bus name, the control interface is served on it
+#[derive(Clone)]
+pub struct Instance {
+    /// `None` without a session bus
+    connection: Option<zbus::Connection>,
+}
+
+/// Claim the bus name for this process, first asking the bar owning it to quit with
+/// `replace`. `None` when another bar keeps running. Without a session bus there is
+/// nothing to check.
+pub async fn claim_instance(replace: bool) -> Option<Instance> {
+    let Ok(connection) = zbus::Connection::session().await else {
+        return Some(Instance { connection: None });
+    };
+
+    match request_name(&connection).await {
+        Ok(()) => return Some(Instance { connection: Some(connection) }),
+        Err(zbus::Error::NameTaken) if replace => {}
+        Err(zbus::Error::NameTaken) => {
+            eprintln!("BladeBar is already running, start with --replace to take over");
+            return None;
+        }
+        Err(e) => {
+            eprintln!("Failed to claim {} on the session bus: {}", BUS_NAME, e);
+            return None;
+        }
+    }
+
+    if let Err(e) = connection
+        .call_method(Some(BUS_NAME), OBJECT_PATH, Some(BUS_NAME), "Quit", &())
+        .await
+    {
+        eprintln!("Failed to ask the running bar to quit: {}", e);
+        return None;
+    }
+
+    // The name is released once the old bar has shut its modules down and exited
+    let deadline = Instant::now() + REPLACE_TIMEOUT;
+    loop {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        match request_name(&connection).await {
+            Ok(()) => return Some(Instance { connection: Some(connection) }),
+            Err(zbus::Error::NameTaken) if Instant::now() < deadline => {}
+            Err(zbus::Error::NameTaken) => {
+                eprintln!("The running bar did not quit in time");
+                return None;
+            }
+            Err(e) => {
+                eprintln!("Failed to claim {} on the session bus: {}", BUS_NAME, e);
+                return None;
+            }
+        }
+    }
+}
+
+/// Take the bus name only if it is free, so two bars started together cannot both get it
+async fn request_name(connection: &zbus::Connection) -> zbus::Result<()> {
+    connection
+        .request_name_with_flags(BUS_NAME, RequestNameFlags::DoNotQueue.into())
+        .await
+        .map(|_| ())
+}
+
+/// Export the control interface on the connection owning the bus name and handle its
+/// requests until the bar exits
+pub fn start(bars: &Rc<Bars>, instance: &Instance) {
+    let (commands_tx, mut commands_rx) = unbounded_channel::<ControlCommand>();
+    let (updates_tx, mut updates_rx) = unbounded_channel::<String>();
+    UPDATES.with(|updates| *updates.borrow_mut() = Some(updates_tx));
+
+    let bars = Rc::downgrade(bars);
+    glib::spawn_future_local(async move {
+        while let Some(command) = commands_rx.recv().await {
+            let Some(bars) = bars.upgrade() else {
+                break;
+            };
+            match command {
+                ControlCommand::ToggleVisibility => bars.set_visible(!bars.is_visible()),
+                ControlCommand::Reload => bars.reload(),
+                ControlCommand::SetVisible(visible) => bars.set_visible(visible),
+                ControlCommand::RefreshModule(name) => bars.refresh_module(&name),
+                ControlCommand::SetModuleVisible(name, visible) => {
+                    bars.set_module_visible(&name, visible)
+                }
+                ControlCommand::ToggleEditMode => bars.set_editing(!bars.is_editing()),
+                ControlCommand::Quit => {
+                    // Shutting down the application shuts down the bars too
+                    if let Some(app) = gio::Application::default() {
+                        app.quit();
+                    }
+                }
+            }
+        }
+    });
+
+    let connection = instance.connection.clone();
+    glib::spawn_future_local(async move {
+        let Some(connection) = connection else {
+            warn!("No session bus, not exporting {}", BUS_NAME);
+            return;
+        };
+        let interface = BarInterface {
+            commands: commands_tx,
+        };
+        if let Err(e) = connection.object_server().at(OBJECT_PATH, interface).await {
+            warn!("Failed to export {} on the session bus: {}", BUS_NAME, e);
+            return;
+        }
+
+        let interface = match connection
+            .object_server()
+            .interface::<_, BarInterface>(OBJECT_PATH)
+            .await
+        {
+            Ok(interface) => interface,
+            Err(e) => {
+                warn!("Failed to look up the control interface: {}", e);
+                return;
+            }
+        };
+
+        while let Some(name) = updates_rx.recv().await {
+            // Report every module of a burst once
+            let mut names = vec![name];
+            while let Ok(name) = updates_rx.try_recv() {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+
+            for name in names {
+                if let Err(e) = BarInterface::module_updated(interface.signal_emitter(), &name).await
+                {
+                    warn!("Failed to emit ModuleUpdated: {}", e);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,279 @@
+use gtk4::glib::Propagation;
+use gtk4::prelude::*;
+use gtk4::{Button, EventControllerScroll, EventControllerScrollFlags, GestureClick, Label};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::command::{read_lines, run_shell, spawn_piped, spawn_shell};
+use crate::config::CustomModuleConfig;
+use crate::control;
+use crate::drop_target::on_files_dropped;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+use crate::template::{Template, Value, pick_icon};
+
+/// One update produced by the user command
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CustomOutput {
+    text: String,
+    tooltip: Option<String>,
+    class: ClassList,
+    percentage: Option<u32>,
+}
+
+/// `class` may be given as a single name or a list of names
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ClassList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for ClassList {
+    fn default() -> Self {
+        ClassList::Many(Vec::new())
+    }
+}
+
+impl ClassList {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            ClassList::One(class) => vec![class],
+            ClassList::Many(classes) => classes,
+        }
+    }
+}
+
+impl CustomOutput {
+    /// Plain text output: first line is the text, then an optional tooltip and class
+    fn from_text(output: &str) -> Self {
+        let mut lines = output.lines();
+        CustomOutput {
+            text: lines.next().unwrap_or_default().to_string(),
+            tooltip: lines.next().map(str::to_string),
+            class: ClassList::One(lines.next().unwrap_or_default().to_string()),
+            percentage: None,
+        }
+    }
+}
+
+/// User defined module showing the output of a shell command, referenced as `custom/<name>`
+pub struct CustomWidget {
+    pub button: Button,
+    label: Label,
+    /// Layout name, `custom/<name>`
+    name: String,
+    config: CustomModuleConfig,
+    template: Option<Template>,
+    classes: RefCell<Vec<String>>,
+    // Streaming command, killed when the module is removed
+    process: RefCell<Option<gio::Subprocess>>,
+    refresh_requested: Notify,
+}
+
+impl CustomWidget {
+    pub fn new(name: &str, config: Option<&CustomModuleConfig>) -> Option<Self> {
+        let Some(config) = config else {
+            warn!("Module 'custom/{}' has no [custom.{}] section", name, name);
+            return None;
+        };
+        if config.exec.trim().is_empty() {
+            warn!("Module 'custom/{}' needs 'exec' to be configured", name);
+            return None;
+        }
+
+        let button = Button::new();
+        button.add_css_class("custom-module");
+        button.add_css_class(&format!("custom-{}", name));
+        button.set_visible(false);
+
+        let label = Label::new(None);
+        label.add_css_class("custom-label");
+        button.set_child(Some(&label));
+
+        if let Some(on_click) = config.on_click.clone() {
+            button.connect_clicked(move |_| spawn_shell(&on_click));
+        }
+
+        // Middle and right click
+        let on_middle_click = config.on_middle_click.clone();
+        let on_right_click = config.on_right_click.clone();
+        if on_middle_click.is_some() || on_right_click.is_some() {
+            let gesture = GestureClick::new();
+            gesture.set_button(0);
+            gesture.connect_pressed(move |gesture, _, _, _| {
+                let action = match gesture.current_button() {
+                    2 => &on_middle_click,
+                    3 => &on_right_click,
+                    _ => return,
+                };
+                if let Some(action) = action {
+                    spawn_shell(action);
+                }
+            });
+            button.add_controller(gesture);
+        }
+
+        let on_scroll_up = config.on_scroll_up.clone();
+        let on_scroll_down = config.on_scroll_down.clone();
+        if on_scroll_up.is_some() || on_scroll_down.is_some() {
+            let scroll = EventControllerScroll::new(
+                EventControllerScrollFlags::VERTICAL | EventControllerScrollFlags::DISCRETE,
+            );
+            scroll.connect_scroll(move |_, _dx, dy| {
+                let action = if dy < 0.0 { &on_scroll_up } else { &on_scroll_down };
+                if let Some(action) = action {
+                    spawn_shell(action);
+                }
+                Propagation::Stop
+            });
+            button.add_controller(scroll);
+        }
+
+        if let Some(on_drop) = config.on_drop.clone() {
+            on_files_dropped(&button, move |files| {
+                let paths: Vec<String> = files
+                    .iter()
+                    .filter_map(|file| file.path())
+                    .map(|path| gtk4::glib::shell_quote(path).to_string_lossy().into_owned())
+                    .collect();
+                if !paths.is_empty() {
+                    spawn_shell(&on_drop.replace("{files}", &paths.join(" ")));
+                }
+            });
+        }
+
+        Some(CustomWidget {
+            button,
+            label,
+            name: format!("custom/{}", name),
+            config: config.clone(),
+            template: config.format.as_deref().map(Template::parse),
+            classes: RefCell::new(Vec::new()),
+            process: RefCell::new(None),
+            refresh_requested: Notify::new(),
+        })
+    }
+
+    fn parse(&self, output: &str) -> CustomOutput {
+        if !self.config.json {
+            return CustomOutput::from_text(output);
+        }
+
+        match serde_json::from_str(output) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                warn!("Invalid JSON from '{}': {}", self.config.exec, e);
+                CustomOutput::default()
+            }
+        }
+    }
+
+    fn update(&self, output: &str) {
+        let output = self.parse(output);
+
+        let text = match &self.template {
+            Some(template) => template.render(|key| match key {
+                "text" => Some(output.text.as_str().into()),
+                "percentage" => Some(match output.percentage {
+                    Some(percentage) => Value::Number(percentage as f64),
+                    None => "".into(),
+                }),
+                "icon" => Some(match output.percentage {
+                    Some(percentage) => {
+                        pick_icon(&self.config.icons, percentage as f64, 100.0).into()
+                    }
+                    None => "".into(),
+                }),
+                _ => None,
+            }),
+            None => output.text.clone(),
+        };
+
+        self.label.set_text(&text);
+        self.button.set_tooltip_text(output.tooltip.as_deref());
+
+        // Swap the classes set by the previous output for the new ones
+        let mut classes = self.classes.borrow_mut();
+        for class in classes.drain(..) {
+            self.button.remove_css_class(&class);
+        }
+        for class in output.class.into_vec() {
+            if !class.is_empty() {
+                self.button.add_css_class(&class);
+                classes.push(class);
+            }
+        }
+
+        // An empty text hides the module, like waybar
+        self.button.set_visible(!output.text.is_empty());
+
+        control::module_updated(&self.name);
+    }
+
+    async fn run_continuous(&self) {
+        let Some(process) = spawn_piped(&["sh", "-c", &self.config.exec]) else {
+            return;
+        };
+        *self.process.borrow_mut() = Some(process.clone());
+
+        read_lines(&process, |line| self.update(line)).await;
+
+        self.process.borrow_mut().take();
+    }
+}
+
+impl BarModule for CustomWidget {
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            let interval = Duration::from_secs(self.config.interval);
+
+            loop {
+                if self.config.continuous {
+                    self.run_continuous().await;
+                } else if let Some(output) = run_shell(&self.config.exec).await {
+                    self.update(&output);
+                }
+
+                // With an interval, commands are re-run and streams restarted after exiting.
+                // A refresh request re-runs them right away.
+                let tick = async {
+                    if interval.is_zero() {
+                        std::future::pending::<()>().await;
+                    }
+                    next_tick(interval).await;
+                };
+                tokio::select! {
+                    _ = tick => {}
+                    _ = self.refresh_requested.notified() => {}
+                }
+            }
+        })
+    }
+
+    fn refresh(&self) {
+        // Restart a running stream, the loop notices it exiting
+        if let Some(process) = self.process.borrow().as_ref() {
+            process.force_exit();
+        }
+        self.refresh_requested.notify_one();
+    }
+
+    fn shutdown(&self) {
+        if let Some(process) = self.process.borrow_mut().take() {
+            process.force_exit();
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Grid, Label, LevelBar, Orientation, Popover};
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use sysinfo::Disks;
+
+use crate::config::DiskConfig;
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+use crate::system_monitor::format_bytes;
+
+/// Size of the sectors counted in /proc/diskstats, whatever the device uses
+const SECTOR_SIZE: u64 = 512;
+
+/// Space on one mounted filesystem
+struct Filesystem {
+    mount_point: String,
+    file_system: String,
+    total: u64,
+    available: u64,
+}
+
+impl Filesystem {
+    fn used_percentage(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.total - self.available) as f64 / self.total as f64 * 100.0
+    }
+}
+
+/// Free space of the configured mount points and disk throughput, with a popover
+/// listing every mounted filesystem
+pub struct DiskWidget {
+    pub button: Button,
+    state: Rc<DiskState>,
+    interval: Duration,
+}
+
+struct DiskState {
+    button: Button,
+    mounts: Vec<(String, Label)>,
+    io_label: Option<Label>,
+    list: Grid,
+    warning: u8,
+    disks: RefCell<Disks>,
+    /// Sectors read and written at the previous sample
+    last_io: Cell<Option<(u64, u64, Instant)>>,
+}
+
+impl DiskWidget {
+    pub fn new(config: &DiskConfig, orientation: Orientation) -> Self {
+        let button = Button::new();
+        button.add_css_class("disk-button");
+
+        let container = GtkBox::new(orientation, 8);
+        let mounts = config
+            .mounts
+            .iter()
+            .map(|mount| {
+                let label = Label::new(Some(&format!("{} ---", mount)));
+                label.add_css_class("disk-label");
+                container.append(&label);
+                (mount.clone(), label)
+            })
+            .collect();
+        let io_label = config.io.then(|| {
+            let label = Label::new(Some("R --- W ---"));
+            label.add_css_class("disk-io-label");
+            container.append(&label);
+            label
+        });
+        button.set_child(Some(&container));
+
+        let list = Grid::new();
+        list.add_css_class("disk-list");
+        list.set_row_spacing(4);
+        list.set_column_spacing(8);
+        let popover = Popover::new();
+        popover.set_child(Some(&list));
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let state = Rc::new(DiskState {
+            button: button.clone(),
+            mounts,
+            io_label,
+            list,
+            warning: config.warning,
+            disks: RefCell::new(Disks::new_with_refreshed_list()),
+            last_io: Cell::new(None),
+        });
+
+        let state_click = state.clone();
+        button.connect_clicked(move |_| {
+            state_click.update_list();
+            popover.popup();
+        });
+
+        DiskWidget {
+            button,
+            state,
+            interval: Duration::from_secs(config.interval.max(1)),
+        }
+    }
+}
+
+impl DiskState {
+    /// Mounted filesystems with a size, virtual ones like proc report none
+    fn filesystems(&self) -> Vec<Filesystem> {
+        let mut disks = self.disks.borrow_mut();
+        // Refreshing the list also picks up filesystems mounted since the last update
+        disks.refresh_list();
+        let mut filesystems: Vec<Filesystem> = disks
+            .list()
+            .iter()
+            .filter(|disk| disk.total_space() > 0)
+            .map(|disk| Filesystem {
+                mount_point: disk.mount_point().to_string_lossy().to_string(),
+                file_system: disk.file_system().to_string_lossy().to_string(),
+                total: disk.total_space(),
+                available: disk.available_space(),
+            })
+            .collect();
+        filesystems.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+        filesystems.dedup_by(|a, b| a.mount_point == b.mount_point);
+        filesystems
+    }
+
+    fn update(&self) {
+        let filesystems = self.filesystems();
+        let mut warn = false;
+
+        for (mount, label) in &self.mounts {
+            match filesystems.iter().find(|fs| &fs.mount_point == mount) {
+                Some(fs) => {
+                    let used = fs.used_percentage();
+                    label.set_text(&format!("{} {}", mount, format_bytes(fs.available as f64, true)));
+                    label.set_tooltip_text(Some(&format!(
+                        "{}: {} free of {} ({:.0}% used)",
+                        mount,
+                        format_bytes(fs.available as f64, false),
+                        format_bytes(fs.total as f64, false),
+                        used
+                    )));
+                    if used >= self.warning as f64 {
+                        label.add_css_class("warning");
+                        warn = true;
+                    } else {
+                        label.remove_css_class("warning");
+                    }
+                }
+                None => {
+                    label.set_text(&format!("{} N/A", mount));
+                    label.set_tooltip_text(Some(&format!("{} is not mounted", mount)));
+                    label.remove_css_class("warning");
+                }
+            }
+        }
+
+        if warn {
+            self.button.add_css_class("warning");
+        } else {
+            self.button.remove_css_class("warning");
+        }
+
+        if let Some(io_label) = &self.io_label {
+            self.update_io(io_label);
+        }
+
+        control::module_updated("disk");
+    }
+
+    /// Read and write rate of all block devices since the previous sample
+    fn update_io(&self, label: &Label) {
+        let Some((read, written)) = read_diskstats() else {
+            label.set_text("R N/A W N/A");
+            return;
+        };
+        let now = Instant::now();
+
+        if let Some((last_read, last_written, last_time)) = self.last_io.get() {
+            let elapsed = now.duration_since(last_time).as_secs_f64().max(0.001);
+            let rate = |current: u64, last: u64| {
+                current.saturating_sub(last) as f64 * SECTOR_SIZE as f64 / elapsed
+            };
+            label.set_text(&format!(
+                "R {}/s W {}/s",
+                format_bytes(rate(read, last_read), false),
+                format_bytes(rate(written, last_written), false)
+            ));
+        }
+        self.last_io.set(Some((read, written, now)));
+    }
+
+    /// Rebuild the popover breakdown of all mounted filesystems
+    fn update_list(&self) {
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+
+        for (row, fs) in self.filesystems().iter().enumerate() {
+            let row = row as i32;
+            let used = fs.used_percentage();
+
+            let mount = Label::new(Some(&fs.mount_point));
+            mount.set_halign(gtk4::Align::Start);
+            let file_system = Label::new(Some(&fs.file_system));
+            file_system.add_css_class("disk-fs-type");
+            file_system.set_halign(gtk4::Align::Start);
+            let level = LevelBar::for_interval(0.0, 100.0);
+            level.set_value(used);
+            level.set_size_request(100, -1);
+            level.set_valign(gtk4::Align::Center);
+            let space = Label::new(Some(&format!(
+                "{} / {}",
+                format_bytes((fs.total - fs.available) as f64, false),
+                format_bytes(fs.total as f64, false)
+            )));
+            space.set_xalign(1.0);
+            if used >= self.warning as f64 {
+                space.add_css_class("warning");
+            }
+
+            self.list.attach(&mount, 0, row, 1, 1);
+            self.list.attach(&file_system, 1, row, 1, 1);
+            self.list.attach(&level, 2, row, 1, 1);
+            self.list.attach(&space, 3, row, 1, 1);
+        }
+    }
+}
+
+/// Sectors read and written by all whole block devices since boot
+fn read_diskstats() -> Option<(u64, u64)> {
+    let stats = fs::read_to_string("/proc/diskstats").ok()?;
+    let mut read = 0;
+    let mut written = 0;
+
+    for line in stats.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(name) = fields.get(2) else {
+            continue;
+        };
+        // Partitions are counted in their disk already, loop and ram devices are not disks
+        if name.starts_with("loop")
+            || name.starts_with("ram")
+            || !fs::exists(format!("/sys/block/{}", name)).unwrap_or(false)
+        {
+            continue;
+        }
+        read += fields.get(5).and_then(|value| value.parse::<u64>().ok()).unwrap_or(0);
+        written += fields.get(9).and_then(|value| value.parse::<u64>().ok()).unwrap_or(0);
+    }
+
+    Some((read, written))
+}
+
+impl BarModule for DiskWidget {
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn refresh(&self) {
+        self.state.update();
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            loop {
+                self.state.update();
+                next_tick(self.interval).await;
+            }
+        })
+    }
+}
@@ -0,0 +1,158 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, Orientation, Popover};
+use std::cell::RefCell;
+use std::fs;
+use std::process::Command;
+use std::rc::Rc;
+use tracing::warn;
+
+use crate::config::{DisplayProfile, DisplayProfilesConfig, config_home};
+use crate::module::BarModule;
+
+/// Shows the active monitor layout and switches between kanshi / wlr-randr profiles
+pub struct DisplayProfilesWidget {
+    pub button: Button,
+}
+
+struct DisplayProfilesState {
+    label: Label,
+    popover: Popover,
+    list: GtkBox,
+    profiles: Vec<DisplayProfile>,
+    /// Last profile applied from the bar, kanshi has no query for the active one
+    current: RefCell<Option<String>>,
+}
+
+impl DisplayProfilesWidget {
+    pub fn new(config: &DisplayProfilesConfig) -> Option<Self> {
+        let profiles = if config.profiles.is_empty() {
+            read_kanshi_profiles()
+        } else {
+            config.profiles.clone()
+        };
+
+        if profiles.is_empty() {
+            warn!("Display profiles module found no profiles in config or kanshi config");
+            return None;
+        }
+
+        let button = Button::new();
+        button.add_css_class("display-profiles-button");
+
+        let label = Label::new(Some("🖥"));
+        label.add_css_class("display-profiles-label");
+        button.set_child(Some(&label));
+
+        let popover = Popover::new();
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let list = GtkBox::new(Orientation::Vertical, 2);
+        list.add_css_class("menu");
+        popover.set_child(Some(&list));
+
+        let state = Rc::new(DisplayProfilesState {
+            label,
+            popover: popover.clone(),
+            list,
+            profiles,
+            current: RefCell::new(None),
+        });
+        state.rebuild_list();
+
+        button.connect_clicked(move |_| {
+            popover.popup();
+        });
+
+        Some(DisplayProfilesWidget { button })
+    }
+}
+
+impl DisplayProfilesState {
+    fn rebuild_list(self: &Rc<Self>) {
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+
+        let current = self.current.borrow().clone();
+        for profile in &self.profiles {
+            let item = Button::with_label(&profile.name);
+            item.add_css_class("flat");
+            item.add_css_class("menu-item");
+            if current.as_deref() == Some(profile.name.as_str()) {
+                item.add_css_class("active");
+            }
+
+            let state = self.clone();
+            let profile = profile.clone();
+            item.connect_clicked(move |_| {
+                state.popover.popdown();
+                if state.apply(&profile) {
+                    *state.current.borrow_mut() = Some(profile.name.clone());
+                    state.label.set_text(&format!("🖥 {}", profile.name));
+                    state.rebuild_list();
+                }
+            });
+
+            self.list.append(&item);
+        }
+    }
+
+    fn apply(&self, profile: &DisplayProfile) -> bool {
+        let result = match &profile.command {
+            Some(command) => Command::new("sh").args(["-c", command]).status(),
+            None => Command::new("kanshictl")
+                .args(["switch", &profile.name])
+                .status(),
+        };
+
+        match result {
+            Ok(status) if status.success() => true,
+            Ok(status) => {
+                warn!(
+                    "Switching to display profile '{}' failed: {}",
+                    profile.name, status
+                );
+                false
+            }
+            Err(e) => {
+                warn!("Failed to switch to display profile '{}': {}", profile.name, e);
+                false
+            }
+        }
+    }
+}
+
+impl BarModule for DisplayProfilesWidget {
+    fn name(&self) -> &'static str {
+        "display_profiles"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+}
+
+/// Named profiles (`profile <name> { ... }`) declared in the kanshi config
+fn read_kanshi_profiles() -> Vec<DisplayProfile> {
+    let Some(contents) =
+        config_home().and_then(|dir| fs::read_to_string(dir.join("kanshi").join("config")).ok())
+    else {
+        return Vec::new();
+    };
+
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            if words.next() != Some("profile") {
+                return None;
+            }
+            words.next().filter(|name| *name != "{")
+        })
+        .map(|name| DisplayProfile {
+            name: name.to_string(),
+            command: None,
+        })
+        .collect()
+}
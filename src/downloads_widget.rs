@@ -0,0 +1,412 @@
+use gio::{FileMonitor, FileMonitorEvent, FileMonitorFlags};
+use gtk4::prelude::*;
+use gtk4::{
+    Box as GtkBox, Button, Label, ListBox, ListBoxRow, Orientation, Popover, ProgressBar,
+    SelectionMode,
+};
+use serde_json::{Value, json};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::command::{is_available, spawn_piped_input};
+use crate::config::DownloadsConfig;
+use crate::control;
+use crate::launch::open_uri;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+use crate::system_monitor::format_bytes;
+
+/// Suffixes browsers give a file while it is being downloaded
+const PARTIAL_SUFFIXES: [&str; 4] = [".part", ".crdownload", ".partial", ".download"];
+
+/// Control file aria2 keeps next to an unfinished download
+const ARIA2_SUFFIX: &str = ".aria2";
+
+/// A download still being written
+#[derive(Debug, Clone)]
+struct ActiveDownload {
+    name: String,
+    /// Bytes written so far
+    done: u64,
+    /// Full size, when the source knows it
+    total: Option<u64>,
+}
+
+/// Downloads in progress in the download directory and aria2, and the files
+/// completed since the bar started
+pub struct DownloadsWidget {
+    pub button: Button,
+    state: Rc<DownloadsState>,
+}
+
+struct DownloadsState {
+    directory: PathBuf,
+    aria2: Option<String>,
+    aria2_secret: Option<String>,
+    recent_limit: usize,
+    interval: Duration,
+    label: Label,
+    active_list: GtkBox,
+    recent_list: ListBox,
+    /// Completed files, newest first
+    recent: RefCell<VecDeque<PathBuf>>,
+    // Keeps the download directory watched while the module exists
+    monitor: RefCell<Option<FileMonitor>>,
+}
+
+impl DownloadsWidget {
+    pub fn new(config: &DownloadsConfig) -> Option<Self> {
+        let directory = config
+            .directory
+            .clone()
+            .or_else(|| glib::user_special_dir(glib::UserDirectory::Downloads))?;
+        if !directory.is_dir() {
+            debug!("Download directory '{}' does not exist", directory.display());
+            return None;
+        }
+        let aria2 = config.aria2.clone().filter(|_| is_available("curl"));
+
+        let button = Button::new();
+        button.add_css_class("downloads-button");
+
+        let label = Label::new(Some("⬇"));
+        label.add_css_class("downloads-label");
+        button.set_child(Some(&label));
+
+        let content = GtkBox::new(Orientation::Vertical, 6);
+        content.add_css_class("downloads-popover");
+
+        let active_title = Label::new(Some("Downloading"));
+        active_title.add_css_class("downloads-title");
+        active_title.set_halign(gtk4::Align::Start);
+        let active_list = GtkBox::new(Orientation::Vertical, 4);
+        active_list.add_css_class("downloads-active");
+
+        let recent_title = Label::new(Some("Recently completed"));
+        recent_title.add_css_class("downloads-title");
+        recent_title.set_halign(gtk4::Align::Start);
+        let recent_list = ListBox::new();
+        recent_list.set_selection_mode(SelectionMode::None);
+        recent_list.add_css_class("downloads-recent");
+        let placeholder = Label::new(Some("Nothing completed yet"));
+        placeholder.add_css_class("dim-label");
+        recent_list.set_placeholder(Some(&placeholder));
+
+        let open_directory = Button::with_label("Open download folder");
+        let directory_uri = gio::File::for_path(&directory).uri();
+        open_directory.connect_clicked(move |_| open_uri(&directory_uri));
+
+        content.append(&active_title);
+        content.append(&active_list);
+        content.append(&recent_title);
+        content.append(&recent_list);
+        content.append(&open_directory);
+
+        let popover = Popover::new();
+        popover.set_child(Some(&content));
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let state = Rc::new(DownloadsState {
+            directory,
+            aria2,
+            aria2_secret: config.aria2_secret.clone(),
+            recent_limit: config.recent.max(1),
+            interval: Duration::from_secs(config.interval.max(1)),
+            label,
+            active_list,
+            recent_list,
+            recent: RefCell::new(VecDeque::new()),
+            monitor: RefCell::new(None),
+        });
+        state.watch();
+        state.rebuild_recent();
+
+        let state_click = state.clone();
+        button.connect_clicked(move |_| {
+            popover.popup();
+            let state = state_click.clone();
+            glib::spawn_future_local(async move {
+                state.update().await;
+            });
+        });
+
+        Some(DownloadsWidget { button, state })
+    }
+}
+
+impl DownloadsState {
+    async fn update(&self) {
+        let mut downloads = self.read_partial_files();
+        downloads.extend(self.read_aria2().await);
+
+        // Overall progress over the downloads whose size is known
+        let (done, total) = downloads
+            .iter()
+            .filter_map(|download| Some((download.done, download.total?)))
+            .fold((0, 0), |(done, total), (d, t)| (done + d, total + t));
+        let progress = (total > 0).then(|| done as f64 / total as f64 * 100.0);
+
+        self.label.set_text(&match (downloads.len(), progress) {
+            (0, _) => "⬇".to_string(),
+            (_, Some(progress)) => format!("⬇ {:.0}%", progress),
+            (count, None) => format!("⬇ {}", count),
+        });
+        if let Some(button) = self.label.parent() {
+            let tooltip = match downloads.len() {
+                0 => "No downloads in progress".to_string(),
+                1 => "1 download in progress".to_string(),
+                count => format!("{} downloads in progress", count),
+            };
+            button.set_tooltip_text(Some(&tooltip));
+            if downloads.is_empty() {
+                button.remove_css_class("active");
+            } else {
+                button.add_css_class("active");
+            }
+        }
+
+        self.rebuild_active(&downloads);
+        control::module_updated("downloads");
+    }
+
+    fn rebuild_active(&self, downloads: &[ActiveDownload]) {
+        while let Some(child) = self.active_list.first_child() {
+            self.active_list.remove(&child);
+        }
+        if downloads.is_empty() {
+            let idle = Label::new(Some("No downloads in progress"));
+            idle.add_css_class("dim-label");
+            self.active_list.append(&idle);
+            return;
+        }
+
+        for download in downloads {
+            let row = GtkBox::new(Orientation::Vertical, 2);
+            let name = Label::new(Some(&download.name));
+            name.set_halign(gtk4::Align::Start);
+            name.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+            name.set_max_width_chars(40);
+            row.append(&name);
+
+            let progress = ProgressBar::new();
+            progress.set_show_text(true);
+            match download.total {
+                Some(total) if total > 0 => {
+                    progress.set_fraction((download.done as f64 / total as f64).min(1.0));
+                    progress.set_text(Some(&format!(
+                        "{} of {}",
+                        format_bytes(download.done as f64, false),
+                        format_bytes(total as f64, false)
+                    )));
+                }
+                _ => progress.set_text(Some(&format_bytes(download.done as f64, false))),
+            }
+            row.append(&progress);
+            self.active_list.append(&row);
+        }
+    }
+
+    fn rebuild_recent(&self) {
+        while let Some(row) = self.recent_list.row_at_index(0) {
+            self.recent_list.remove(&row);
+        }
+
+        for path in self.recent.borrow().iter() {
+            let row_box = GtkBox::new(Orientation::Horizontal, 4);
+
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let open = Button::with_label(&name);
+            open.add_css_class("flat");
+            open.set_hexpand(true);
+            if let Some(label) = open.child().and_downcast::<Label>() {
+                label.set_halign(gtk4::Align::Start);
+                label.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+                label.set_max_width_chars(40);
+            }
+            let file_uri = gio::File::for_path(path).uri();
+            open.connect_clicked(move |_| open_uri(&file_uri));
+
+            let folder = Button::from_icon_name("folder-open-symbolic");
+            folder.add_css_class("flat");
+            folder.set_tooltip_text(Some("Open containing folder"));
+            let folder_uri = path
+                .parent()
+                .map(|parent| gio::File::for_path(parent).uri());
+            folder.connect_clicked(move |_| {
+                if let Some(folder_uri) = &folder_uri {
+                    open_uri(folder_uri);
+                }
+            });
+
+            row_box.append(&open);
+            row_box.append(&folder);
+            let row = ListBoxRow::new();
+            row.set_child(Some(&row_box));
+            row.set_tooltip_text(Some(&path.display().to_string()));
+            self.recent_list.append(&row);
+        }
+    }
+
+    fn completed(&self, path: PathBuf) {
+        if is_partial(&path) || !path.is_file() {
+            return;
+        }
+        {
+            let mut recent = self.recent.borrow_mut();
+            recent.retain(|existing| *existing != path);
+            recent.push_front(path);
+            recent.truncate(self.recent_limit);
+        }
+        self.rebuild_recent();
+    }
+
+    /// Record finished downloads: browsers rename the partial file, aria2 deletes its
+    /// control file and other tools often move the file in when done
+    fn watch(self: &Rc<Self>) {
+        let directory = gio::File::for_path(&self.directory);
+        match directory.monitor_directory(FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
+        {
+            Ok(monitor) => {
+                let state = Rc::downgrade(self);
+                monitor.connect_changed(move |_, file, other_file, event| {
+                    let Some(state) = state.upgrade() else {
+                        return;
+                    };
+                    let finished = match event {
+                        FileMonitorEvent::Renamed
+                            if file.path().is_some_and(|path| is_partial(&path)) =>
+                        {
+                            other_file.and_then(|file| file.path())
+                        }
+                        FileMonitorEvent::MovedIn => file.path(),
+                        FileMonitorEvent::Deleted => file.path().and_then(|path| {
+                            let path = path.to_str()?.strip_suffix(ARIA2_SUFFIX)?;
+                            Some(PathBuf::from(path))
+                        }),
+                        _ => None,
+                    };
+                    if let Some(path) = finished {
+                        state.completed(path);
+                    }
+                });
+                *self.monitor.borrow_mut() = Some(monitor);
+            }
+            Err(e) => warn!(
+                "Failed to watch download directory '{}': {}",
+                self.directory.display(),
+                e
+            ),
+        }
+    }
+
+    /// Partial files of browser downloads, their final size is unknown
+    fn read_partial_files(&self) -> Vec<ActiveDownload> {
+        let Ok(entries) = fs::read_dir(&self.directory) else {
+            return Vec::new();
+        };
+        let mut downloads: Vec<ActiveDownload> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                let name = PARTIAL_SUFFIXES
+                    .iter()
+                    .find_map(|suffix| file_name.strip_suffix(suffix))?;
+                Some(ActiveDownload {
+                    name: name.to_string(),
+                    done: entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+                    total: None,
+                })
+            })
+            .collect();
+        downloads.sort_by(|a, b| a.name.cmp(&b.name));
+        downloads
+    }
+
+    /// Active downloads of an aria2 daemon through its JSON-RPC interface
+    async fn read_aria2(&self) -> Vec<ActiveDownload> {
+        let Some(url) = &self.aria2 else {
+            return Vec::new();
+        };
+
+        let mut params = Vec::new();
+        if let Some(secret) = &self.aria2_secret {
+            params.push(json!(format!("token:{}", secret)));
+        }
+        params.push(json!(["totalLength", "completedLength", "files"]));
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": "blade_bar",
+            "method": "aria2.tellActive",
+            "params": params,
+        })
+        .to_string();
+
+        // The request goes through stdin to keep the secret out of the process list
+        let Some(process) =
+            spawn_piped_input(&["curl", "-s", "--max-time", "3", "--data-binary", "@-", url])
+        else {
+            return Vec::new();
+        };
+        let Ok((stdout, _)) = process.communicate_utf8_future(Some(request)).await else {
+            return Vec::new();
+        };
+        let Ok(reply) = serde_json::from_str::<Value>(stdout.as_deref().unwrap_or_default())
+        else {
+            debug!("No answer from aria2 at {}", url);
+            return Vec::new();
+        };
+
+        // Lengths are decimal strings
+        let length = |value: &Value| value.as_str().and_then(|value| value.parse::<u64>().ok());
+        reply["result"]
+            .as_array()
+            .map(|downloads| {
+                downloads
+                    .iter()
+                    .map(|download| ActiveDownload {
+                        name: download["files"][0]["path"]
+                            .as_str()
+                            .and_then(|path| Path::new(path).file_name())
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| "aria2 download".to_string()),
+                        done: length(&download["completedLength"]).unwrap_or(0),
+                        total: length(&download["totalLength"]).filter(|total| *total > 0),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn is_partial(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    name.ends_with(ARIA2_SUFFIX) || PARTIAL_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+impl BarModule for DownloadsWidget {
+    fn name(&self) -> &'static str {
+        "downloads"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            loop {
+                self.state.update().await;
+                next_tick(self.state.interval).await;
+            }
+        })
+    }
+}
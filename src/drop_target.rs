@@ -0,0 +1,57 @@
+// Drag-and-drop onto bar widgets. GTK puts the widget in the `:drop(active)` state
+// while something it accepts hovers over it, which the stylesheet highlights.
+
+use gtk4::gdk::{DragAction, FileList};
+use gtk4::glib::Type;
+use gtk4::prelude::*;
+use gtk4::DropTarget;
+
+/// Call `on_drop` with the files dropped on `widget`
+pub fn on_files_dropped(
+    widget: &impl IsA<gtk4::Widget>,
+    on_drop: impl Fn(Vec<gio::File>) + 'static,
+) {
+    let target = DropTarget::new(FileList::static_type(), DragAction::COPY);
+    target.connect_drop(move |_, value, _, _| {
+        let Ok(files) = value.get::<FileList>() else {
+            return false;
+        };
+        let files = files.files();
+        if files.is_empty() {
+            return false;
+        }
+        on_drop(files);
+        true
+    });
+    widget.add_controller(target);
+}
+
+/// Call `on_drop` with text dropped on `widget`, dropped files arrive as their
+/// paths, one per line
+pub fn on_text_dropped(widget: &impl IsA<gtk4::Widget>, on_drop: impl Fn(&str) + 'static) {
+    let target = DropTarget::new(Type::INVALID, DragAction::COPY);
+    target.set_types(&[FileList::static_type(), String::static_type()]);
+    target.connect_drop(move |_, value, _, _| {
+        let text = if let Ok(files) = value.get::<FileList>() {
+            files
+                .files()
+                .iter()
+                .map(|file| match file.path() {
+                    Some(path) => path.display().to_string(),
+                    None => file.uri().to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else if let Ok(text) = value.get::<String>() {
+            text
+        } else {
+            return false;
+        };
+        if text.is_empty() {
+            return false;
+        }
+        on_drop(&text);
+        true
+    });
+    widget.add_controller(target);
+}
@@ -0,0 +1,319 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, Orientation, Popover, Switch};
+use std::cell::{Cell, RefCell};
+use std::process::Command;
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::command::is_available;
+use crate::compositor::{Compositor, run_json};
+use crate::control;
+use crate::hypr_toggles_widget::{get_option, is_off};
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+
+/// How often the output state is read again, it rarely changes behind our back
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Where output settings are read from and written to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    Hyprland,
+    Sway,
+    /// Any compositor with wlr-output-management, only the first enabled output
+    WlrRandr,
+}
+
+impl Backend {
+    fn detect() -> Option<Self> {
+        match Compositor::detect() {
+            Some(Compositor::Hyprland) => Some(Backend::Hyprland),
+            Some(Compositor::Sway) => Some(Backend::Sway),
+            None if is_available("wlr-randr") => Some(Backend::WlrRandr),
+            None => None,
+        }
+    }
+
+    /// State of the focused output
+    fn read(self) -> Option<OutputState> {
+        match self {
+            Backend::Hyprland => {
+                let monitors = run_json(Command::new("hyprctl").args(["monitors", "-j"]))?;
+                let monitor = monitors
+                    .as_array()?
+                    .iter()
+                    .find(|monitor| monitor["focused"].as_bool() == Some(true))?;
+                Some(OutputState {
+                    name: monitor["name"].as_str()?.to_string(),
+                    width: monitor["width"].as_u64()?,
+                    height: monitor["height"].as_u64()?,
+                    refresh: monitor["refreshRate"].as_f64().unwrap_or_default(),
+                    vrr: monitor["vrr"].as_bool().unwrap_or(false),
+                    tearing: get_option("general:allow_tearing").map(|value| !is_off(&value, "0")),
+                    // Only configurable through monitor rules, not at runtime
+                    hdr: None,
+                })
+            }
+            Backend::Sway => {
+                let outputs = run_json(Command::new("swaymsg").args(["-r", "-t", "get_outputs"]))?;
+                let output = outputs
+                    .as_array()?
+                    .iter()
+                    .find(|output| output["focused"].as_bool() == Some(true))?;
+                let mode = &output["current_mode"];
+                Some(OutputState {
+                    name: output["name"].as_str()?.to_string(),
+                    width: mode["width"].as_u64()?,
+                    height: mode["height"].as_u64()?,
+                    // In mHz
+                    refresh: mode["refresh"].as_f64().unwrap_or_default() / 1000.0,
+                    vrr: output["adaptive_sync_status"].as_str() == Some("enabled"),
+                    // Tearing is allowed per window in sway
+                    tearing: None,
+                    // Only reported by sway versions that support it
+                    hdr: output["hdr"].as_bool(),
+                })
+            }
+            Backend::WlrRandr => {
+                let outputs = run_json(Command::new("wlr-randr").arg("--json"))?;
+                let output = outputs
+                    .as_array()?
+                    .iter()
+                    .find(|output| output["enabled"].as_bool() == Some(true))?;
+                let mode = output["modes"]
+                    .as_array()?
+                    .iter()
+                    .find(|mode| mode["current"].as_bool() == Some(true))?;
+                Some(OutputState {
+                    name: output["name"].as_str()?.to_string(),
+                    width: mode["width"].as_u64()?,
+                    height: mode["height"].as_u64()?,
+                    refresh: mode["refresh"].as_f64().unwrap_or_default(),
+                    vrr: output["adaptive_sync"].as_bool().unwrap_or(false),
+                    tearing: None,
+                    hdr: None,
+                })
+            }
+        }
+    }
+
+    fn set(self, output: &str, setting: Setting, enabled: bool) {
+        let on_off = if enabled { "on" } else { "off" };
+        let flag = if enabled { "1" } else { "0" };
+        let command: Vec<&str> = match (self, setting) {
+            (Backend::Hyprland, Setting::Vrr) => vec!["hyprctl", "keyword", "misc:vrr", flag],
+            (Backend::Hyprland, Setting::Tearing) => {
+                vec!["hyprctl", "keyword", "general:allow_tearing", flag]
+            }
+            (Backend::Sway, Setting::Vrr) => {
+                vec!["swaymsg", "output", output, "adaptive_sync", on_off]
+            }
+            (Backend::Sway, Setting::Hdr) => vec!["swaymsg", "output", output, "hdr", on_off],
+            (Backend::WlrRandr, Setting::Vrr) => vec![
+                "wlr-randr",
+                "--output",
+                output,
+                "--adaptive-sync",
+                if enabled { "enabled" } else { "disabled" },
+            ],
+            _ => return,
+        };
+
+        match Command::new(command[0]).args(&command[1..]).output() {
+            Ok(result) if result.status.success() => {}
+            Ok(result) => warn!(
+                "{} failed: {}",
+                command.join(" "),
+                String::from_utf8_lossy(&result.stdout).trim()
+            ),
+            Err(e) => warn!("Failed to run {}: {}", command[0], e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Setting {
+    Vrr,
+    Tearing,
+    Hdr,
+}
+
+/// Mode and gaming related settings of one output, `None` where not supported
+#[derive(Debug, Clone, PartialEq)]
+struct OutputState {
+    name: String,
+    width: u64,
+    height: u64,
+    refresh: f64,
+    vrr: bool,
+    tearing: Option<bool>,
+    hdr: Option<bool>,
+}
+
+/// Resolution of the focused output with VRR, tearing and HDR toggles
+pub struct GamingWidget {
+    pub button: Button,
+    state: Rc<GamingState>,
+}
+
+struct GamingState {
+    backend: Backend,
+    label: Label,
+    switches: Vec<(Setting, GtkBox, Switch)>,
+    /// Set while the switches are moved to match the output
+    updating: Cell<bool>,
+    output: RefCell<Option<OutputState>>,
+}
+
+impl GamingWidget {
+    pub fn new() -> Option<Self> {
+        let backend = Backend::detect()?;
+
+        let button = Button::new();
+        button.add_css_class("gaming-button");
+
+        let label = Label::new(Some("🎮"));
+        label.add_css_class("gaming-label");
+        button.set_child(Some(&label));
+
+        let popover = Popover::new();
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let content = GtkBox::new(Orientation::Vertical, 6);
+        content.add_css_class("gaming");
+
+        let mut switches = Vec::new();
+        for (setting, title) in [
+            (Setting::Vrr, "Variable refresh rate"),
+            (Setting::Tearing, "Allow tearing"),
+            (Setting::Hdr, "HDR"),
+        ] {
+            let row = GtkBox::new(Orientation::Horizontal, 8);
+            let row_label = Label::new(Some(title));
+            row_label.set_hexpand(true);
+            row_label.set_halign(gtk4::Align::Start);
+            let switch = Switch::new();
+            row.append(&row_label);
+            row.append(&switch);
+            content.append(&row);
+            switches.push((setting, row, switch));
+        }
+        popover.set_child(Some(&content));
+
+        let state = Rc::new(GamingState {
+            backend,
+            label,
+            switches,
+            updating: Cell::new(false),
+            output: RefCell::new(None),
+        });
+
+        for (setting, _, switch) in &state.switches {
+            let setting = *setting;
+            let state_switch = Rc::downgrade(&state);
+            switch.connect_state_set(move |_, enabled| {
+                if let Some(state) = state_switch.upgrade() {
+                    if !state.updating.get() {
+                        state.set(setting, enabled);
+                    }
+                }
+                gtk4::glib::Propagation::Proceed
+            });
+        }
+
+        let state_click = state.clone();
+        button.connect_clicked(move |_| {
+            state_click.update();
+            popover.popup();
+        });
+
+        Some(GamingWidget { button, state })
+    }
+}
+
+impl GamingState {
+    fn set(&self, setting: Setting, enabled: bool) {
+        let Some(name) = self.output.borrow().as_ref().map(|output| output.name.clone()) else {
+            return;
+        };
+        self.backend.set(&name, setting, enabled);
+        self.update();
+    }
+
+    fn update(&self) {
+        let output = self.backend.read();
+        if *self.output.borrow() == output {
+            return;
+        }
+
+        match &output {
+            Some(output) => {
+                self.label.set_text(&format!(
+                    "🎮 {}x{}@{:.0}",
+                    output.width, output.height, output.refresh
+                ));
+                if let Some(parent) = self.label.parent() {
+                    parent.set_tooltip_text(Some(&format!(
+                        "{}: {}x{} at {:.2} Hz, VRR {}",
+                        output.name,
+                        output.width,
+                        output.height,
+                        output.refresh,
+                        if output.vrr { "on" } else { "off" }
+                    )));
+                    if output.vrr {
+                        parent.add_css_class("vrr");
+                    } else {
+                        parent.remove_css_class("vrr");
+                    }
+                }
+
+                self.updating.set(true);
+                for (setting, row, switch) in &self.switches {
+                    let value = match setting {
+                        Setting::Vrr => Some(output.vrr),
+                        Setting::Tearing => output.tearing,
+                        Setting::Hdr => output.hdr,
+                    };
+                    row.set_visible(value.is_some());
+                    switch.set_active(value.unwrap_or(false));
+                }
+                self.updating.set(false);
+            }
+            None => {
+                self.label.set_text("🎮");
+                if let Some(parent) = self.label.parent() {
+                    parent.set_tooltip_text(Some("No focused output"));
+                }
+            }
+        }
+
+        *self.output.borrow_mut() = output;
+        control::module_updated("gaming");
+    }
+}
+
+impl BarModule for GamingWidget {
+    fn name(&self) -> &'static str {
+        "gaming"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn refresh(&self) {
+        self.state.update();
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            loop {
+                self.state.update();
+                next_tick(REFRESH_INTERVAL).await;
+            }
+        })
+    }
+}
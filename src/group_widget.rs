@@ -0,0 +1,179 @@
+use futures_util::future::join_all;
+use glib::SourceId;
+use gtk4::prelude::*;
+use gtk4::{
+    Box as GtkBox, Button, EventControllerMotion, Label, Orientation, Revealer,
+    RevealerTransitionType,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::{Config, GroupConfig, GroupReveal};
+use crate::module::{BarModule, InitFuture, create_module};
+use crate::state;
+
+/// Delay after the pointer leaves a hover drawer before it closes, so it survives
+/// crossing a gap or a popover
+const HIDE_DELAY: Duration = Duration::from_millis(500);
+
+/// Drawer holding several modules behind a toggle button, referenced in the layout
+/// as `group/<name>`
+pub struct GroupWidget {
+    pub container: GtkBox,
+    children: Vec<Rc<dyn BarModule>>,
+}
+
+impl GroupWidget {
+    pub fn new(name: &str, config: &Config, orientation: Orientation) -> Option<Self> {
+        let Some(group) = config.group.get(name) else {
+            warn!("Module 'group/{}' has no [group.{}] section", name, name);
+            return None;
+        };
+
+        let children: Vec<Rc<dyn BarModule>> = group
+            .modules
+            .iter()
+            .filter(|child| {
+                // Groups inside groups could include themselves
+                let nested = child.starts_with("group/");
+                if nested {
+                    warn!("Group '{}' cannot contain another group ('{}')", name, child);
+                }
+                !nested
+            })
+            .filter_map(|child| create_module(child, config))
+            .collect();
+        if children.is_empty() {
+            warn!("Group '{}' has no available modules", name);
+            return None;
+        }
+
+        let container = GtkBox::new(orientation, 0);
+        container.add_css_class("group");
+        container.add_css_class(&format!("group-{}", name));
+
+        let drawer = GtkBox::new(orientation, 0);
+        drawer.add_css_class("group-drawer");
+        for child in &children {
+            drawer.append(child.widget());
+        }
+
+        let revealer = Revealer::new();
+        revealer.set_transition_type(match orientation {
+            Orientation::Horizontal => RevealerTransitionType::SlideRight,
+            _ => RevealerTransitionType::SlideDown,
+        });
+        revealer.set_transition_duration(group.transition_duration);
+        revealer.set_child(Some(&drawer));
+
+        let button = Button::new();
+        button.add_css_class("group-button");
+        let label = Label::new(Some(&group.icon));
+        label.add_css_class("group-label");
+        button.set_child(Some(&label));
+        if let Some(tooltip) = &group.tooltip {
+            button.set_tooltip_text(Some(tooltip));
+        }
+
+        container.append(&button);
+        container.append(&revealer);
+        // A drawer opened or closed by clicking stays that way across restarts
+        let persist = group.reveal == GroupReveal::Click;
+        let expanded = match state::get().expanded_groups.get(name) {
+            Some(expanded) if persist => *expanded,
+            _ => group.expanded,
+        };
+        Self::set_expanded(&button, &revealer, expanded);
+
+        let revealer_click = revealer.clone();
+        let name_click = name.to_string();
+        button.connect_clicked(move |button| {
+            let expanded = !revealer_click.reveals_child();
+            Self::set_expanded(button, &revealer_click, expanded);
+            if persist {
+                let name = name_click.clone();
+                state::update(|state| {
+                    state.expanded_groups.insert(name, expanded);
+                });
+            }
+        });
+
+        if group.reveal == GroupReveal::Hover {
+            Self::reveal_on_hover(&container, &button, &revealer);
+        }
+
+        Some(GroupWidget {
+            container,
+            children,
+        })
+    }
+
+    fn set_expanded(button: &Button, revealer: &Revealer, expanded: bool) {
+        revealer.set_reveal_child(expanded);
+        if expanded {
+            button.add_css_class("expanded");
+        } else {
+            button.remove_css_class("expanded");
+        }
+    }
+
+    fn reveal_on_hover(container: &GtkBox, button: &Button, revealer: &Revealer) {
+        let pending_hide: Rc<RefCell<Option<SourceId>>> = Rc::new(RefCell::new(None));
+        let motion = EventControllerMotion::new();
+
+        let pending_enter = pending_hide.clone();
+        let (button_enter, revealer_enter) = (button.clone(), revealer.clone());
+        motion.connect_enter(move |_, _, _| {
+            if let Some(source_id) = pending_enter.borrow_mut().take() {
+                source_id.remove();
+            }
+            Self::set_expanded(&button_enter, &revealer_enter, true);
+        });
+
+        let (button_leave, revealer_leave) = (button.clone(), revealer.clone());
+        motion.connect_leave(move |_| {
+            if let Some(source_id) = pending_hide.borrow_mut().take() {
+                source_id.remove();
+            }
+            let (button, revealer) = (button_leave.clone(), revealer_leave.clone());
+            let pending_inner = pending_hide.clone();
+            let source_id = glib::timeout_add_local_once(HIDE_DELAY, move || {
+                pending_inner.borrow_mut().take();
+                Self::set_expanded(&button, &revealer, false);
+            });
+            *pending_hide.borrow_mut() = Some(source_id);
+        });
+
+        container.add_controller(motion);
+    }
+}
+
+impl BarModule for GroupWidget {
+    fn name(&self) -> &'static str {
+        "group"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.container.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            join_all(self.children.iter().map(|child| child.init())).await;
+        })
+    }
+
+    fn shutdown(&self) {
+        for child in &self.children {
+            child.shutdown();
+        }
+    }
+
+    fn refresh(&self) {
+        for child in &self.children {
+            child.refresh();
+        }
+    }
+}
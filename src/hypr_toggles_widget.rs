@@ -0,0 +1,117 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Orientation, ToggleButton};
+use serde_json::Value;
+use std::process::Command;
+use tracing::warn;
+
+use crate::compositor::{Compositor, run_json};
+use crate::config::{HyprToggle, HyprTogglesConfig};
+use crate::module::BarModule;
+
+/// Toggle buttons flipping Hyprland options with `hyprctl keyword`
+pub struct HyprTogglesWidget {
+    pub container: GtkBox,
+}
+
+impl HyprTogglesWidget {
+    pub fn new(config: &HyprTogglesConfig, orientation: Orientation) -> Option<Self> {
+        if Compositor::detect()? != Compositor::Hyprland || config.toggles.is_empty() {
+            return None;
+        }
+
+        let container = GtkBox::new(orientation, 2);
+        container.add_css_class("hypr-toggles");
+
+        for toggle in &config.toggles {
+            container.append(&Self::create_toggle(toggle));
+        }
+
+        Some(HyprTogglesWidget { container })
+    }
+
+    fn create_toggle(toggle: &HyprToggle) -> ToggleButton {
+        let button = ToggleButton::with_label(&toggle.label);
+        button.add_css_class("hypr-toggle");
+
+        // Reflect the current state and remember the "on" value if none was configured
+        let current = get_option(&toggle.option);
+        let is_on = current.as_deref().is_some_and(|value| !is_off(value, &toggle.off));
+        let on_value = toggle
+            .on
+            .clone()
+            .or_else(|| current.clone().filter(|_| is_on))
+            .unwrap_or_else(|| "1".to_string());
+
+        button.set_active(is_on);
+        button.set_tooltip_text(Some(&format!(
+            "{} = {}",
+            toggle.option,
+            current.as_deref().unwrap_or("unknown")
+        )));
+
+        let option = toggle.option.clone();
+        let off_value = toggle.off.clone();
+        button.connect_toggled(move |button| {
+            let value = if button.is_active() {
+                &on_value
+            } else {
+                &off_value
+            };
+
+            match Command::new("hyprctl")
+                .args(["keyword", &option, value])
+                .output()
+            {
+                Ok(output) if output.status.success() => {
+                    button.set_tooltip_text(Some(&format!("{} = {}", option, value)));
+                }
+                Ok(output) => warn!(
+                    "hyprctl keyword {} {} failed: {}",
+                    option,
+                    value,
+                    String::from_utf8_lossy(&output.stdout).trim()
+                ),
+                Err(e) => warn!("Failed to run hyprctl keyword {}: {}", option, e),
+            }
+        });
+
+        button
+    }
+}
+
+impl BarModule for HyprTogglesWidget {
+    fn name(&self) -> &'static str {
+        "hypr_toggles"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.container.upcast_ref()
+    }
+}
+
+/// Current value of a Hyprland option as a string
+pub fn get_option(option: &str) -> Option<String> {
+    let value = run_json(Command::new("hyprctl").args(["getoption", option, "-j"]))?;
+
+    // The value lives in a field named after its type
+    ["int", "float", "str", "custom"]
+        .iter()
+        .find_map(|key| match &value[*key] {
+            Value::Number(number) => Some(number.to_string()),
+            Value::String(string) => Some(string.trim().to_string()),
+            _ => None,
+        })
+}
+
+/// Compare values numerically where possible, "0" and "0.000000" are both off
+pub fn is_off(value: &str, off: &str) -> bool {
+    let numeric = |s: &str| -> Option<Vec<f64>> {
+        s.split_whitespace().map(|part| part.parse().ok()).collect()
+    };
+
+    match (numeric(value), numeric(off)) {
+        (Some(value), Some(off)) if off.len() == 1 => value.iter().all(|v| *v == off[0]),
+        (Some(value), Some(off)) => value == off,
+        _ => value == off,
+    }
+}
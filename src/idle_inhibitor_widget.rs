@@ -0,0 +1,180 @@
+use gtk4::prelude::*;
+use gtk4::{ApplicationInhibitFlags, Button, Label};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tracing::{info, warn};
+use zbus::zvariant::OwnedFd;
+
+use crate::config::IdleInhibitorConfig;
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::state;
+
+const REASON: &str = "Idle inhibitor enabled from the bar";
+
+/// logind's manager, for inhibitor locks
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LoginManager {
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+}
+
+/// A held inhibitor, released when dropped or replaced
+enum Inhibitor {
+    /// GTK's inhibitor, a `zwp_idle_inhibit_manager_v1` inhibitor on the bar surface
+    /// on Wayland
+    Surface(gtk4::Application, u32),
+    /// A logind idle lock for compositors without the protocol, held until the
+    /// descriptor is closed
+    Logind { _lock: OwnedFd },
+}
+
+impl Drop for Inhibitor {
+    fn drop(&mut self) {
+        if let Inhibitor::Surface(application, cookie) = self {
+            application.uninhibit(*cookie);
+        }
+    }
+}
+
+/// Caffeine toggle keeping the screen from idling, locking or blanking while on
+pub struct IdleInhibitorWidget {
+    pub button: Button,
+    state: Rc<IdleInhibitorState>,
+}
+
+struct IdleInhibitorState {
+    label: Label,
+    active_icon: String,
+    inactive_icon: String,
+    inhibitor: RefCell<Option<Inhibitor>>,
+}
+
+impl IdleInhibitorWidget {
+    pub fn new(config: &IdleInhibitorConfig) -> Self {
+        let button = Button::new();
+        button.add_css_class("idle-inhibitor-button");
+
+        let label = Label::new(None);
+        label.add_css_class("idle-inhibitor-label");
+        button.set_child(Some(&label));
+
+        let state = Rc::new(IdleInhibitorState {
+            label,
+            active_icon: config.active_icon.clone(),
+            inactive_icon: config.inactive_icon.clone(),
+            inhibitor: RefCell::new(None),
+        });
+        state.show();
+
+        let state_click = state.clone();
+        button.connect_clicked(move |button| {
+            if state_click.inhibitor.borrow_mut().take().is_some() {
+                info!("Idle inhibitor released");
+                state::update(|state| state.idle_inhibited = false);
+                state_click.show();
+                return;
+            }
+            state_click.inhibit(button);
+        });
+
+        IdleInhibitorWidget { button, state }
+    }
+}
+
+impl IdleInhibitorState {
+    /// Take an inhibitor on the bar surface, or from logind when the compositor does
+    /// not support it
+    fn inhibit(self: &Rc<Self>, button: &Button) {
+        if let Some(inhibitor) = inhibit_surface(button) {
+            info!("Idle inhibited on the bar surface");
+            self.hold(inhibitor);
+            return;
+        }
+
+        let state = self.clone();
+        glib::spawn_future_local(async move {
+            match inhibit_logind().await {
+                Some(inhibitor) => {
+                    info!("Idle inhibited through logind");
+                    state.hold(inhibitor);
+                }
+                None => state.show(),
+            }
+        });
+    }
+
+    fn hold(&self, inhibitor: Inhibitor) {
+        *self.inhibitor.borrow_mut() = Some(inhibitor);
+        state::update(|state| state.idle_inhibited = true);
+        self.show();
+    }
+
+    fn show(&self) {
+        let active = self.inhibitor.borrow().is_some();
+        if active {
+            self.label.set_text(&self.active_icon);
+        } else {
+            self.label.set_text(&self.inactive_icon);
+        }
+        if let Some(button) = self.label.parent() {
+            if active {
+                button.add_css_class("active");
+                button.set_tooltip_text(Some("Idle inhibited, the screen stays on"));
+            } else {
+                button.remove_css_class("active");
+                button.set_tooltip_text(Some("Idle allowed"));
+            }
+        }
+        control::module_updated("idle_inhibitor");
+    }
+}
+
+fn inhibit_surface(button: &Button) -> Option<Inhibitor> {
+    let window = button.root().and_downcast::<gtk4::Window>()?;
+    let application = window.application()?;
+    let cookie = application.inhibit(Some(&window), ApplicationInhibitFlags::IDLE, Some(REASON));
+    // 0 when no inhibitor could be taken
+    (cookie != 0).then(|| Inhibitor::Surface(application, cookie))
+}
+
+async fn inhibit_logind() -> Option<Inhibitor> {
+    let connection = match zbus::Connection::system().await {
+        Ok(connection) => connection,
+        Err(e) => {
+            warn!("Failed to connect to the system bus: {}", e);
+            return None;
+        }
+    };
+    let manager = LoginManagerProxy::new(&connection).await.ok()?;
+    match manager.inhibit("idle", "blade_bar", REASON, "block").await {
+        Ok(lock) => Some(Inhibitor::Logind { _lock: lock }),
+        Err(e) => {
+            warn!("Failed to take a logind idle inhibitor: {}", e);
+            None
+        }
+    }
+}
+
+impl BarModule for IdleInhibitorWidget {
+    fn name(&self) -> &'static str {
+        "idle_inhibitor"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    // Inhibiting on the surface needs the button to be on the bar window
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            if state::get().idle_inhibited && self.state.inhibitor.borrow().is_none() {
+                info!("Restoring the idle inhibitor");
+                self.state.inhibit(&self.button);
+            }
+        })
+    }
+}
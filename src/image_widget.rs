@@ -0,0 +1,116 @@
+use gtk4::gdk::Texture;
+use gtk4::gdk_pixbuf::Pixbuf;
+use gtk4::prelude::*;
+use gtk4::Picture;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::command::{run_shell, spawn_shell};
+use crate::config::ImageConfig;
+use crate::drop_target::on_files_dropped;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+
+/// Displays a static image or the image produced by a command, refreshed on an interval
+pub struct ImageWidget {
+    pub picture: Picture,
+    path: Option<String>,
+    command: Option<String>,
+    interval: u64,
+}
+
+impl ImageWidget {
+    pub fn new(config: &ImageConfig) -> Option<Self> {
+        if config.path.is_none() && config.command.is_none() {
+            warn!("Image module needs either 'path' or 'command' to be configured");
+            return None;
+        }
+
+        let picture = Picture::new();
+        picture.add_css_class("image-widget");
+        picture.set_can_shrink(true);
+        picture.set_size_request(-1, config.size);
+
+        // Show a dropped image right away, the command can e.g. make it the wallpaper
+        let on_drop = config.on_drop.clone();
+        let picture_drop = picture.clone();
+        on_files_dropped(&picture, move |files| {
+            let Some(path) = files.first().and_then(|file| file.path()) else {
+                return;
+            };
+            if let Some(on_drop) = &on_drop {
+                let quoted = gtk4::glib::shell_quote(&path).to_string_lossy().into_owned();
+                spawn_shell(&on_drop.replace("{file}", &quoted));
+            }
+
+            let picture = picture_drop.clone();
+            glib::spawn_future_local(async move {
+                show(&picture, &path.display().to_string()).await;
+            });
+        });
+
+        Some(ImageWidget {
+            picture,
+            path: config.path.clone(),
+            command: config.command.clone(),
+            interval: config.interval,
+        })
+    }
+
+    /// Resolve the current image location, running the command if one is configured
+    async fn resolve_source(&self) -> Option<String> {
+        match &self.command {
+            Some(command) => run_shell(command).await.filter(|output| !output.is_empty()),
+            None => self.path.clone(),
+        }
+    }
+
+    async fn refresh(&self) {
+        if let Some(source) = self.resolve_source().await {
+            show(&self.picture, &source).await;
+        }
+    }
+}
+
+/// Load the image at `source` into `picture`, keeping the previous one on failure
+async fn show(picture: &Picture, source: &str) {
+    // Accepts both local paths and URIs (file://, http(s):// through GVfs)
+    let file = gio::File::for_commandline_arg(source);
+
+    let pixbuf = match file.read_future(gio::glib::Priority::DEFAULT).await {
+        Ok(stream) => Pixbuf::from_stream_future(&stream).await,
+        Err(e) => Err(e),
+    };
+
+    match pixbuf {
+        Ok(pixbuf) => {
+            let texture = Texture::for_pixbuf(&pixbuf);
+            picture.set_paintable(Some(&texture));
+            picture.set_tooltip_text(Some(source));
+        }
+        Err(e) => warn!("Failed to load image '{}': {}", source, e),
+    }
+}
+
+impl BarModule for ImageWidget {
+    fn name(&self) -> &'static str {
+        "image"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.picture.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            loop {
+                self.refresh().await;
+
+                if self.interval == 0 {
+                    break;
+                }
+                next_tick(Duration::from_secs(self.interval)).await;
+            }
+        })
+    }
+}
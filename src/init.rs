@@ -0,0 +1,102 @@
+// `blade_bar init`: write a commented default config.toml and a starter style.css
+// into the config directory.
+
+use std::fs;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::style::user_css_path;
+use crate::themes;
+
+const USAGE: &str = "Usage: blade_bar init [--theme <name>] [--force]";
+
+/// Contents of the generated style.css
+const STYLE_TEMPLATE: &str = "\
+/* BladeBar user stylesheet
+ *
+ * Rules here are applied on top of the built-in style and the theme selected
+ * in config.toml, and reloaded as soon as this file is saved. Widgets use CSS
+ * classes such as .main-container, .workspace-button, .clock-label or .tray-button.
+ */
+";
+
+/// Write the scaffolding and return the process exit code
+pub fn run(args: &[String]) -> i32 {
+    let mut theme = "default".to_string();
+    let mut force = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--theme" => match args.next() {
+                Some(name) => theme = name.clone(),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return 2;
+                }
+            },
+            "--force" => force = true,
+            _ => {
+                eprintln!("Unknown argument '{}'\n{}", arg, USAGE);
+                return 2;
+            }
+        }
+    }
+
+    if themes::preset(&theme).is_none() {
+        eprintln!(
+            "Unknown theme '{}', available: {}",
+            theme,
+            themes::preset_names().join(", ")
+        );
+        return 2;
+    }
+
+    let (Some(config_path), Some(css_path)) = (Config::path(), user_css_path()) else {
+        eprintln!("Could not determine the configuration directory, set $XDG_CONFIG_HOME or $HOME");
+        return 1;
+    };
+
+    // Select the preset in the config so it keeps following updates of the bundled theme
+    let mut config = include_str!("default_config.toml").to_string();
+    if theme != "default" {
+        config = config.replacen(
+            "# theme = \"catppuccin-mocha\"",
+            &format!("theme = \"{}\"", theme),
+            1,
+        );
+    }
+
+    let results = [
+        write_file(&config_path, &config, force),
+        write_file(&css_path, STYLE_TEMPLATE, force),
+    ];
+
+    if results.iter().all(|written| *written) { 0 } else { 1 }
+}
+
+/// Write `contents` to `path` unless it already exists, returning whether it succeeded
+fn write_file(path: &Path, contents: &str, force: bool) -> bool {
+    if path.exists() && !force {
+        eprintln!("{} already exists, use --force to overwrite", path.display());
+        return false;
+    }
+
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            eprintln!("Failed to create '{}': {}", parent.display(), e);
+            return false;
+        }
+    }
+
+    match fs::write(path, contents) {
+        Ok(()) => {
+            println!("Wrote {}", path.display());
+            true
+        }
+        Err(e) => {
+            eprintln!("Failed to write '{}': {}", path.display(), e);
+            false
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, Orientation};
+use jack::{AsyncClient, Client, ClientOptions, ClientStatus, NotificationHandler, TransportState};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+
+/// How often the transport is queried while connected
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Wait between attempts to reach a JACK (or pipewire-jack) server
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+const CLIENT_NAME: &str = "blade_bar";
+
+/// Transport state and tempo as last shown
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TransportStatus {
+    state: TransportState,
+    bpm: Option<f64>,
+    /// Bar and beat, when the timebase master provides them
+    position: Option<(usize, usize)>,
+}
+
+/// Sets the flag when the server goes away, JACK calls this from its own thread
+struct ShutdownFlag(Arc<AtomicBool>);
+
+impl NotificationHandler for ShutdownFlag {
+    unsafe fn shutdown(&mut self, _status: ClientStatus, _reason: &str) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// JACK transport state and tempo, with play/pause and return-to-start buttons
+pub struct JackTransportWidget {
+    pub container: GtkBox,
+    state: Rc<TransportWidgetState>,
+}
+
+struct TransportWidgetState {
+    label: Label,
+    play: Button,
+    rewind: Button,
+    client: RefCell<Option<AsyncClient<ShutdownFlag, ()>>>,
+    server_gone: Arc<AtomicBool>,
+    last_attempt: Cell<Option<Instant>>,
+    status: Cell<Option<TransportStatus>>,
+}
+
+impl JackTransportWidget {
+    pub fn new(orientation: Orientation) -> Self {
+        let container = GtkBox::new(orientation, 2);
+        container.add_css_class("jack-transport");
+
+        let rewind = Button::from_icon_name("media-skip-backward-symbolic");
+        rewind.add_css_class("jack-transport-button");
+        rewind.set_tooltip_text(Some("Return to start"));
+
+        let play = Button::from_icon_name("media-playback-start-symbolic");
+        play.add_css_class("jack-transport-button");
+
+        let label = Label::new(Some("JACK: off"));
+        label.add_css_class("jack-transport-label");
+
+        container.append(&rewind);
+        container.append(&play);
+        container.append(&label);
+
+        let state = Rc::new(TransportWidgetState {
+            label,
+            play: play.clone(),
+            rewind: rewind.clone(),
+            client: RefCell::new(None),
+            server_gone: Arc::new(AtomicBool::new(false)),
+            last_attempt: Cell::new(None),
+            status: Cell::new(None),
+        });
+        state.render(None);
+
+        let state_play = state.clone();
+        play.connect_clicked(move |_| {
+            state_play.toggle();
+        });
+
+        let state_rewind = state.clone();
+        rewind.connect_clicked(move |_| {
+            state_rewind.rewind();
+        });
+
+        JackTransportWidget { container, state }
+    }
+}
+
+impl TransportWidgetState {
+    /// Connect if needed and show the current transport
+    fn poll(&self) {
+        if self.server_gone.swap(false, Ordering::Relaxed) {
+            info!("JACK server went away");
+            // Closing is still required to free the client after a server shutdown
+            self.client.borrow_mut().take();
+        }
+
+        if self.client.borrow().is_none() {
+            let due = self
+                .last_attempt
+                .get()
+                .is_none_or(|last| last.elapsed() >= RECONNECT_INTERVAL);
+            if !due {
+                return;
+            }
+            self.last_attempt.set(Some(Instant::now()));
+            self.connect();
+        }
+
+        let status = self.client.borrow().as_ref().and_then(|client| {
+            let transport = client.as_client().transport();
+            let query = transport.query().ok()?;
+            let bbt = query.pos.bbt();
+            Some(TransportStatus {
+                state: query.state,
+                bpm: bbt.map(|bbt| bbt.bpm),
+                position: bbt.map(|bbt| (bbt.bar, bbt.beat)),
+            })
+        });
+        self.show(status);
+    }
+
+    fn connect(&self) {
+        // Never start a server just to show its transport
+        let client = match Client::new(CLIENT_NAME, ClientOptions::NO_START_SERVER) {
+            Ok((client, _status)) => client,
+            Err(_) => return,
+        };
+        match client.activate_async(ShutdownFlag(self.server_gone.clone()), ()) {
+            Ok(client) => {
+                info!("Connected to the JACK transport");
+                *self.client.borrow_mut() = Some(client);
+            }
+            Err(e) => warn!("Failed to activate the JACK client: {}", e),
+        }
+    }
+
+    fn toggle(&self) {
+        let client = self.client.borrow();
+        let Some(client) = client.as_ref() else {
+            return;
+        };
+        let transport = client.as_client().transport();
+        let result = match transport.query_state() {
+            Ok(TransportState::Stopped) => transport.start(),
+            Ok(_) => transport.stop(),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("Failed to change the JACK transport: {}", e);
+        }
+    }
+
+    fn rewind(&self) {
+        if let Some(client) = self.client.borrow().as_ref() {
+            if let Err(e) = client.as_client().transport().locate(0) {
+                warn!("Failed to move the JACK transport: {}", e);
+            }
+        }
+    }
+
+    fn show(&self, status: Option<TransportStatus>) {
+        if self.status.get() != status {
+            self.status.set(status);
+            self.render(status);
+            control::module_updated("jack_transport");
+        }
+    }
+
+    fn render(&self, status: Option<TransportStatus>) {
+        let connected = status.is_some();
+        self.play.set_sensitive(connected);
+        self.rewind.set_sensitive(connected);
+
+        let Some(status) = status else {
+            self.label.set_text("JACK: off");
+            self.label.set_tooltip_text(Some("No JACK server running"));
+            self.play.set_icon_name("media-playback-start-symbolic");
+            self.play.set_tooltip_text(None);
+            return;
+        };
+
+        let rolling = status.state != TransportState::Stopped;
+        self.play.set_icon_name(if rolling {
+            "media-playback-pause-symbolic"
+        } else {
+            "media-playback-start-symbolic"
+        });
+        self.play.set_tooltip_text(Some(if rolling { "Stop" } else { "Play" }));
+
+        let state = match status.state {
+            TransportState::Stopped => "Stopped",
+            TransportState::Starting => "Starting",
+            TransportState::Rolling => "Rolling",
+        };
+        self.label.set_text(&match status.bpm {
+            Some(bpm) => format!("{:.0} BPM", bpm),
+            None => state.to_string(),
+        });
+        self.label.set_tooltip_text(Some(&match status.position {
+            Some((bar, beat)) => format!("{}, bar {} beat {}", state, bar, beat),
+            None => state.to_string(),
+        }));
+
+        if let Some(parent) = self.label.parent() {
+            if rolling {
+                parent.add_css_class("rolling");
+            } else {
+                parent.remove_css_class("rolling");
+            }
+        }
+    }
+}
+
+impl BarModule for JackTransportWidget {
+    fn name(&self) -> &'static str {
+        "jack_transport"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.container.upcast_ref()
+    }
+
+    fn refresh(&self) {
+        self.state.poll();
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            loop {
+                self.state.poll();
+                next_tick(POLL_INTERVAL).await;
+            }
+        })
+    }
+
+    fn shutdown(&self) {
+        if let Some(client) = self.state.client.borrow_mut().take() {
+            if let Err(e) = client.deactivate() {
+                warn!("Failed to close the JACK client: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+// Launching applications from the bar with an XDG activation token. Wayland
+// compositors refuse focus to windows that appear without one, so anything opened
+// from a click would otherwise end up behind the current window.
+
+use gtk4::prelude::*;
+use tracing::warn;
+
+/// Launch context of the bar's display, GTK attaches an activation token to every
+/// launch made through it
+pub fn context() -> Option<gio::AppLaunchContext> {
+    if !gtk4::is_initialized_main_thread() {
+        return None;
+    }
+    let display = gtk4::gdk::Display::default()?;
+    Some(display.app_launch_context().upcast())
+}
+
+/// A fresh token for a launch happening outside of GIO, tied to the latest input
+/// event so it should be taken right after the click that triggers the launch
+pub fn activation_token() -> Option<String> {
+    context()?
+        .startup_notify_id(None::<&gio::AppInfo>, &[])
+        .map(|token| token.to_string())
+}
+
+/// Environment handing the token to a spawned process, `DESKTOP_STARTUP_ID` for
+/// toolkits that only know the X11 startup notification
+pub fn activation_env(launcher: &gio::SubprocessLauncher) {
+    if let Some(token) = activation_token() {
+        launcher.setenv("XDG_ACTIVATION_TOKEN", &token, true);
+        launcher.setenv("DESKTOP_STARTUP_ID", &token, true);
+    }
+}
+
+/// Open a file or URI with its default application
+pub fn open_uri(uri: &str) {
+    if let Err(e) = gio::AppInfo::launch_default_for_uri(uri, context().as_ref()) {
+        warn!("Failed to open '{}': {}", uri, e);
+    }
+}
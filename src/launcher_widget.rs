@@ -0,0 +1,116 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Image, Label, Orientation};
+use std::path::Path;
+use tracing::warn;
+
+use crate::command::spawn_shell;
+use crate::config::{LauncherButton, LauncherConfig};
+use crate::drop_target::on_files_dropped;
+use crate::launch;
+use crate::module::BarModule;
+
+/// Buttons running a command or starting a pinned application, files dropped on a
+/// pinned application are opened with it
+pub struct LauncherWidget {
+    pub container: GtkBox,
+}
+
+impl LauncherWidget {
+    pub fn new(config: &LauncherConfig, orientation: Orientation) -> Option<Self> {
+        let container = GtkBox::new(orientation, 2);
+        container.add_css_class("launcher");
+
+        for launcher in &config.buttons {
+            if let Some(button) = Self::create_button(launcher) {
+                container.append(&button);
+            }
+        }
+        if container.first_child().is_none() {
+            warn!("Module 'launcher' has no usable buttons");
+            return None;
+        }
+
+        Some(LauncherWidget { container })
+    }
+
+    fn create_button(launcher: &LauncherButton) -> Option<Button> {
+        let app = match &launcher.app {
+            Some(id) => {
+                let id = if id.ends_with(".desktop") {
+                    id.clone()
+                } else {
+                    format!("{}.desktop", id)
+                };
+                let Some(app) = gio::DesktopAppInfo::new(&id) else {
+                    warn!("Launcher application '{}' not found", id);
+                    return None;
+                };
+                Some(app)
+            }
+            None => None,
+        };
+        if app.is_none() && launcher.command.is_none() {
+            warn!("Launcher buttons need 'command' or 'app' to be configured");
+            return None;
+        }
+
+        let button = Button::new();
+        button.add_css_class("launcher-button");
+        if let Some(class) = &launcher.class {
+            button.add_css_class(class);
+        }
+
+        // An explicit icon or text, otherwise the application's own icon
+        if let Some(text) = &launcher.label {
+            button.set_child(Some(&Label::new(Some(text))));
+        } else if let Some(icon) = &launcher.icon {
+            let image = if icon.contains('/') {
+                Image::from_file(Path::new(icon))
+            } else {
+                Image::from_icon_name(icon)
+            };
+            button.set_child(Some(&image));
+        } else if let Some(gicon) = app.as_ref().and_then(|app| app.icon()) {
+            button.set_child(Some(&Image::from_gicon(&gicon)));
+        } else {
+            button.set_child(Some(&Image::from_icon_name("application-x-executable")));
+        }
+
+        let tooltip = launcher
+            .tooltip
+            .clone()
+            .or_else(|| app.as_ref().map(|app| app.display_name().to_string()))
+            .or_else(|| launcher.command.clone());
+        button.set_tooltip_text(tooltip.as_deref());
+
+        let command = launcher.command.clone();
+        let app_click = app.clone();
+        button.connect_clicked(move |_| match (&command, &app_click) {
+            (Some(command), _) => spawn_shell(command),
+            (None, Some(app)) => launch_app(app, &[]),
+            (None, None) => {}
+        });
+
+        if let Some(app) = app {
+            on_files_dropped(&button, move |files| launch_app(&app, &files));
+        }
+
+        Some(button)
+    }
+}
+
+fn launch_app(app: &gio::DesktopAppInfo, files: &[gio::File]) {
+    if let Err(e) = app.launch(files, launch::context().as_ref()) {
+        warn!("Failed to launch '{}': {}", app.display_name(), e);
+    }
+}
+
+impl BarModule for LauncherWidget {
+    fn name(&self) -> &'static str {
+        "launcher"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.container.upcast_ref()
+    }
+}
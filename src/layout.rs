@@ -0,0 +1,177 @@
+use gtk4::prelude::*;
+use gtk4::{
+    BaselinePosition, Box as GtkBox, CenterBox, Orientation, PolicyType, RevealerTransitionType,
+    ScrolledWindow, Widget,
+};
+use gtk4_layer_shell::Edge;
+use serde::Deserialize;
+
+/// Space between the modules of a zone
+pub const ZONE_SPACING: i32 = 10;
+
+/// Zone of the bar a module is placed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Start,
+    Center,
+    End,
+}
+
+/// Screen edge the bar is attached to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Position {
+    #[default]
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Position {
+    /// Direction modules are laid out in
+    pub fn orientation(self) -> Orientation {
+        match self {
+            Position::Top | Position::Bottom => Orientation::Horizontal,
+            Position::Left | Position::Right => Orientation::Vertical,
+        }
+    }
+
+    pub fn edge(self) -> Edge {
+        match self {
+            Position::Top => Edge::Top,
+            Position::Bottom => Edge::Bottom,
+            Position::Left => Edge::Left,
+            Position::Right => Edge::Right,
+        }
+    }
+
+    /// Edge facing the rest of the screen
+    pub fn inner_edge(self) -> Edge {
+        match self {
+            Position::Top => Edge::Bottom,
+            Position::Bottom => Edge::Top,
+            Position::Left => Edge::Right,
+            Position::Right => Edge::Left,
+        }
+    }
+
+    /// Edges the bar is stretched between
+    pub fn span_edges(self) -> [Edge; 2] {
+        match self.orientation() {
+            Orientation::Vertical => [Edge::Top, Edge::Bottom],
+            _ => [Edge::Left, Edge::Right],
+        }
+    }
+
+    /// Slide animation moving the bar in from its edge
+    pub fn reveal_transition(self) -> RevealerTransitionType {
+        match self {
+            Position::Top => RevealerTransitionType::SlideDown,
+            Position::Bottom => RevealerTransitionType::SlideUp,
+            Position::Left => RevealerTransitionType::SlideRight,
+            Position::Right => RevealerTransitionType::SlideLeft,
+        }
+    }
+
+    /// CSS class added to the bar window, e.g. `left`
+    pub fn css_class(self) -> &'static str {
+        match self {
+            Position::Top => "top",
+            Position::Bottom => "bottom",
+            Position::Left => "left",
+            Position::Right => "right",
+        }
+    }
+}
+
+/// Three-zone bar layout: modules are packed left, centered, or right.
+///
+/// The center zone stays centered on the output however wide the sides are. When they
+/// run out of room the sides are clipped at their inner end, the center keeps its place.
+pub struct BarLayout {
+    pub root: CenterBox,
+    start: GtkBox,
+    center: GtkBox,
+    end: GtkBox,
+}
+
+impl BarLayout {
+    pub fn new(orientation: Orientation) -> Self {
+        let root = CenterBox::new();
+        root.set_orientation(orientation);
+        match orientation {
+            Orientation::Vertical => root.set_vexpand(true),
+            _ => root.set_hexpand(true),
+        }
+        root.add_css_class("main-container");
+
+        let start = Self::create_zone("left-section", orientation);
+        let center = Self::create_zone("center-section", orientation);
+        let end = Self::create_zone("right-section", orientation);
+
+        root.set_start_widget(Some(&Self::clip_zone(&start, orientation, false)));
+        root.set_center_widget(Some(&center));
+        root.set_end_widget(Some(&Self::clip_zone(&end, orientation, true)));
+
+        BarLayout {
+            root,
+            start,
+            center,
+            end,
+        }
+    }
+
+    fn create_zone(css_class: &str, orientation: Orientation) -> GtkBox {
+        let zone = GtkBox::new(orientation, ZONE_SPACING);
+        zone.add_css_class(css_class);
+        // Shares the baseline of the modules' text, see `baseline`
+        zone.set_baseline_position(BaselinePosition::Center);
+        zone
+    }
+
+    /// Let a side zone shrink below its minimum size by clipping it, showing its outer
+    /// end: the start for the start zone, the end for the end zone
+    fn clip_zone(zone: &GtkBox, orientation: Orientation, keep_end: bool) -> ScrolledWindow {
+        let clip = ScrolledWindow::new();
+        clip.add_css_class("section-clip");
+        clip.set_propagate_natural_width(true);
+        clip.set_propagate_natural_height(true);
+        clip.set_child(Some(zone));
+
+        let adjustment = match orientation {
+            Orientation::Vertical => {
+                clip.set_policy(PolicyType::Never, PolicyType::External);
+                clip.vadjustment()
+            }
+            _ => {
+                clip.set_policy(PolicyType::External, PolicyType::Never);
+                clip.hadjustment()
+            }
+        };
+        if keep_end {
+            adjustment.connect_changed(|adjustment| {
+                adjustment.set_value(adjustment.upper() - adjustment.page_size());
+            });
+        }
+        clip
+    }
+
+    /// Container backing the given zone
+    pub fn zone(&self, zone: Zone) -> &GtkBox {
+        match zone {
+            Zone::Start => &self.start,
+            Zone::Center => &self.center,
+            Zone::End => &self.end,
+        }
+    }
+
+    /// Append a widget to the end of the given zone
+    pub fn append(&self, zone: Zone, widget: &impl IsA<Widget>) {
+        self.zone(zone).append(widget);
+    }
+
+    pub fn widget(&self) -> &CenterBox {
+        &self.root
+    }
+}
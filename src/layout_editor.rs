@@ -0,0 +1,114 @@
+// Interactive layout editing: while edit mode is on, every module sits next to a
+// drag handle and can be dropped anywhere in the three zones. Modules are identified
+// by their zone and index in the configured layout lists, see
+// `LayoutConfig::move_module`.
+
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, DragSource, DropTarget, Image, Orientation, Widget, gdk};
+use std::rc::Rc;
+
+use crate::layout::Zone;
+
+/// Place in the configured layout: zone and index into its module list
+pub type Placement = (Zone, usize);
+
+/// Called with the dragged module and the placement it was dropped before
+pub type MoveHandler = Rc<dyn Fn(Placement, Placement)>;
+
+/// Wrap a module widget in a box with a drag handle carrying its placement
+pub fn handle(widget: &impl IsA<Widget>, placement: Placement, orientation: Orientation) -> GtkBox {
+    let container = GtkBox::new(orientation, 2);
+    container.add_css_class("edit-handle");
+
+    let grip = Image::from_icon_name("list-drag-handle-symbolic");
+    grip.add_css_class("edit-grip");
+    grip.set_tooltip_text(Some("Drag to move"));
+
+    let payload = encode(placement);
+    let drag = DragSource::new();
+    drag.set_actions(gdk::DragAction::MOVE);
+    drag.connect_prepare(move |_, _, _| Some(gdk::ContentProvider::for_value(&payload.to_value())));
+    let container_drag = container.clone();
+    drag.connect_drag_begin(move |drag, _| {
+        let icon = gtk4::WidgetPaintable::new(Some(&container_drag));
+        drag.set_icon(Some(&icon), 0, 0);
+    });
+    grip.add_controller(drag);
+
+    container.append(&grip);
+    container.append(widget);
+    container
+}
+
+/// Accept module drops on a zone container
+///
+/// `indices` maps the zone's children, in order, to their index in the configured list.
+pub fn accept_drops(
+    zone_box: &GtkBox,
+    zone: Zone,
+    indices: Vec<usize>,
+    orientation: Orientation,
+    on_move: MoveHandler,
+) {
+    let drop = DropTarget::new(gtk4::glib::Type::STRING, gdk::DragAction::MOVE);
+
+    let zone_box_drop = zone_box.clone();
+    drop.connect_drop(move |_, value, x, y| {
+        let Some(from) = value.get::<String>().ok().as_deref().and_then(decode) else {
+            return false;
+        };
+
+        // Insert before the first child whose middle lies past the pointer
+        let pointer = match orientation {
+            Orientation::Vertical => y as f32,
+            _ => x as f32,
+        };
+        let mut position = indices.len();
+        let mut child = zone_box_drop.first_child();
+        let mut current = 0;
+        while let Some(widget) = child {
+            if let Some(bounds) = widget.compute_bounds(&zone_box_drop) {
+                let middle = match orientation {
+                    Orientation::Vertical => bounds.y() + bounds.height() / 2.0,
+                    _ => bounds.x() + bounds.width() / 2.0,
+                };
+                if pointer < middle {
+                    position = current;
+                    break;
+                }
+            }
+            current += 1;
+            child = widget.next_sibling();
+        }
+
+        // Past the last shown module, after it (modules unavailable here keep their place)
+        let index = match indices.get(position) {
+            Some(index) => *index,
+            None => indices.last().map_or(0, |last| last + 1),
+        };
+        on_move(from, (zone, index));
+        true
+    });
+
+    zone_box.add_controller(drop);
+}
+
+fn encode((zone, index): Placement) -> String {
+    let zone = match zone {
+        Zone::Start => "start",
+        Zone::Center => "center",
+        Zone::End => "end",
+    };
+    format!("{}:{}", zone, index)
+}
+
+fn decode(payload: &str) -> Option<Placement> {
+    let (zone, index) = payload.split_once(':')?;
+    let zone = match zone {
+        "start" => Zone::Start,
+        "center" => Zone::Center,
+        "end" => Zone::End,
+        _ => return None,
+    };
+    Some((zone, index.parse().ok()?))
+}
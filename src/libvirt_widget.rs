@@ -0,0 +1,290 @@
+use gtk4::prelude::*;
+use gtk4::{Button, Grid, Label, Popover};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::command::{is_available, spawn_piped};
+use crate::config::LibvirtConfig;
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+use crate::system_monitor::format_bytes;
+
+/// A defined domain and what it uses while running
+#[derive(Debug, Clone, PartialEq)]
+struct Domain {
+    name: String,
+    running: bool,
+    vcpus: u64,
+    /// Current memory in bytes
+    memory: u64,
+}
+
+/// Defined libvirt domains with start/stop actions and the resources of running ones
+pub struct LibvirtWidget {
+    pub button: Button,
+    state: Rc<LibvirtState>,
+}
+
+struct LibvirtState {
+    uri: String,
+    interval: Duration,
+    label: Label,
+    list: Grid,
+    domains: RefCell<Vec<Domain>>,
+    /// CPU time in nanoseconds of each running domain at the previous poll
+    last_cpu: RefCell<(HashMap<String, u64>, Option<Instant>)>,
+}
+
+impl LibvirtWidget {
+    pub fn new(config: &LibvirtConfig) -> Option<Self> {
+        if !is_available("virsh") {
+            return None;
+        }
+
+        let button = Button::new();
+        button.add_css_class("libvirt-button");
+
+        let label = Label::new(Some("VM --"));
+        label.add_css_class("libvirt-label");
+        button.set_child(Some(&label));
+
+        let list = Grid::new();
+        list.add_css_class("libvirt-list");
+        list.set_row_spacing(4);
+        list.set_column_spacing(8);
+        let popover = Popover::new();
+        popover.set_child(Some(&list));
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let state = Rc::new(LibvirtState {
+            uri: config.uri.clone(),
+            interval: Duration::from_secs(config.interval.max(1)),
+            label,
+            list,
+            domains: RefCell::new(Vec::new()),
+            last_cpu: RefCell::new((HashMap::new(), None)),
+        });
+
+        let state_click = state.clone();
+        button.connect_clicked(move |_| {
+            popover.popup();
+            let state = state_click.clone();
+            glib::spawn_future_local(async move {
+                state.update().await;
+            });
+        });
+
+        Some(LibvirtWidget { button, state })
+    }
+}
+
+impl LibvirtState {
+    async fn update(self: &Rc<Self>) {
+        let Some((domains, cpu_times)) = self.read_domains().await else {
+            self.label.set_text("VM N/A");
+            if let Some(button) = self.label.parent() {
+                button.set_tooltip_text(Some(&format!("Cannot connect to {}", self.uri)));
+            }
+            return;
+        };
+
+        let running: Vec<&Domain> = domains.iter().filter(|domain| domain.running).collect();
+        let vcpus: u64 = running.iter().map(|domain| domain.vcpus).sum();
+        let memory: u64 = running.iter().map(|domain| domain.memory).sum();
+        let usage = self.cpu_usage(cpu_times);
+
+        self.label.set_text(&format!("VM {}/{}", running.len(), domains.len()));
+        if let Some(button) = self.label.parent() {
+            let mut tooltip = format!(
+                "{} of {} domains running\n{} vCPUs, {} memory",
+                running.len(),
+                domains.len(),
+                vcpus,
+                format_bytes(memory as f64, false)
+            );
+            if let Some(usage) = usage {
+                tooltip.push_str(&format!("\nCPU {:.0}% of one host core", usage));
+            }
+            button.set_tooltip_text(Some(&tooltip));
+            if running.is_empty() {
+                button.remove_css_class("running");
+            } else {
+                button.add_css_class("running");
+            }
+        }
+
+        if *self.domains.borrow() != domains {
+            self.rebuild_list(&domains);
+            *self.domains.borrow_mut() = domains;
+        }
+        control::module_updated("libvirt");
+    }
+
+    /// CPU time used by the running domains since the previous poll, in percent
+    fn cpu_usage(&self, cpu_times: HashMap<String, u64>) -> Option<f64> {
+        let mut last_cpu = self.last_cpu.borrow_mut();
+        let (last_times, sampled) = &mut *last_cpu;
+        let now = Instant::now();
+
+        let usage = sampled.map(|sampled| {
+            let elapsed = now.duration_since(sampled).as_nanos().max(1) as f64;
+            // Domains started since the previous poll have nothing to compare against
+            let used: u64 = cpu_times
+                .iter()
+                .filter_map(|(name, time)| Some(time.saturating_sub(*last_times.get(name)?)))
+                .sum();
+            used as f64 / elapsed * 100.0
+        });
+
+        *last_times = cpu_times;
+        *sampled = Some(now);
+        usage
+    }
+
+    fn rebuild_list(self: &Rc<Self>, domains: &[Domain]) {
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+
+        if domains.is_empty() {
+            self.list.attach(&Label::new(Some("No domains defined")), 0, 0, 1, 1);
+            return;
+        }
+
+        for (row, domain) in domains.iter().enumerate() {
+            let row = row as i32;
+
+            let name = Label::new(Some(&domain.name));
+            name.set_halign(gtk4::Align::Start);
+            name.set_hexpand(true);
+
+            let details = Label::new(Some(&if domain.running {
+                format!(
+                    "{} vCPU, {}",
+                    domain.vcpus,
+                    format_bytes(domain.memory as f64, false)
+                )
+            } else {
+                "stopped".to_string()
+            }));
+            details.add_css_class("libvirt-details");
+            details.set_xalign(1.0);
+
+            let action = Button::with_label(if domain.running { "Shut down" } else { "Start" });
+            action.add_css_class("libvirt-action");
+            let command = if domain.running { "shutdown" } else { "start" };
+            let domain_name = domain.name.clone();
+            let state = Rc::downgrade(self);
+            action.connect_clicked(move |action| {
+                let Some(state) = state.upgrade() else {
+                    return;
+                };
+                action.set_sensitive(false);
+                let domain_name = domain_name.clone();
+                glib::spawn_future_local(async move {
+                    state.run(command, &domain_name).await;
+                    state.update().await;
+                });
+            });
+
+            self.list.attach(&name, 0, row, 1, 1);
+            self.list.attach(&details, 1, row, 1, 1);
+            self.list.attach(&action, 2, row, 1, 1);
+        }
+    }
+
+    async fn run(&self, command: &str, domain: &str) {
+        if self.virsh(&[command, domain]).await.is_none() {
+            warn!("virsh {} {} failed", command, domain);
+        }
+    }
+
+    /// All defined domains sorted by name and the CPU time of the running ones,
+    /// `None` if the hypervisor is not reachable
+    async fn read_domains(&self) -> Option<(Vec<Domain>, HashMap<String, u64>)> {
+        let all = self.virsh(&["list", "--all", "--name"]).await?;
+        let mut domains: Vec<Domain> = all
+            .lines()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| Domain {
+                name: name.to_string(),
+                running: false,
+                vcpus: 0,
+                memory: 0,
+            })
+            .collect();
+        domains.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // Only running domains are listed, each as "Domain: 'name'" followed by its stats
+        let stats = self
+            .virsh(&["domstats", "--list-running", "--vcpu", "--balloon", "--cpu-total"])
+            .await
+            .unwrap_or_default();
+        let mut cpu_times = HashMap::new();
+        let mut current: Option<usize> = None;
+        for line in stats.lines().map(str::trim) {
+            if let Some(name) = line
+                .strip_prefix("Domain: '")
+                .and_then(|rest| rest.strip_suffix('\''))
+            {
+                current = domains.iter().position(|domain| domain.name == name);
+                if let Some(index) = current {
+                    domains[index].running = true;
+                }
+                continue;
+            }
+            let (Some(index), Some((key, value))) = (current, line.split_once('=')) else {
+                continue;
+            };
+            let value = value.parse::<u64>().unwrap_or(0);
+            let domain = &mut domains[index];
+            match key {
+                "vcpu.current" => domain.vcpus = value,
+                // In KiB
+                "balloon.current" => domain.memory = value * 1024,
+                "cpu.time" => {
+                    cpu_times.insert(domain.name.clone(), value);
+                }
+                _ => {}
+            }
+        }
+
+        Some((domains, cpu_times))
+    }
+
+    /// Run virsh against the configured connection and return its stdout
+    async fn virsh(&self, args: &[&str]) -> Option<String> {
+        let mut argv = vec!["virsh", "-q", "-c", &self.uri];
+        argv.extend_from_slice(args);
+        let process = spawn_piped(&argv)?;
+        let (stdout, _) = process.communicate_utf8_future(None).await.ok()?;
+        process
+            .is_successful()
+            .then(|| stdout.map(|stdout| stdout.to_string()).unwrap_or_default())
+    }
+}
+
+impl BarModule for LibvirtWidget {
+    fn name(&self) -> &'static str {
+        "libvirt"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            loop {
+                self.state.update().await;
+                next_tick(self.state.interval).await;
+            }
+        })
+    }
+}
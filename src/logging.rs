@@ -0,0 +1,89 @@
+// Diagnostics go through `tracing`. The level comes from `--log-level`, falling back to
+// `RUST_LOG` and then to info for the bar and warnings for the libraries it uses.
+// `--log-file` writes to `$XDG_STATE_HOME/blade_bar/blade_bar.log` instead of stderr.
+
+use std::fs::{self, OpenOptions};
+use std::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::state_dir;
+
+/// Filter used when neither `--log-level` nor `RUST_LOG` is given
+const DEFAULT_FILTER: &str = "warn,blade_bar=info";
+
+const LOG_FILE: &str = "blade_bar.log";
+
+#[derive(Debug, Default)]
+pub struct LogOptions {
+    /// A bare level for the bar itself, or a full `RUST_LOG` style filter
+    level: Option<String>,
+    file: bool,
+}
+
+/// Take the logging flags out of the arguments, leaving the subcommand and its arguments
+pub fn parse_args(args: &mut Vec<String>) -> Result<LogOptions, String> {
+    let mut options = LogOptions::default();
+    let mut index = 0;
+    while index < args.len() {
+        match args[index].as_str() {
+            "--log-level" => {
+                args.remove(index);
+                if index >= args.len() {
+                    return Err("--log-level needs a level, e.g. debug".to_string());
+                }
+                options.level = Some(args.remove(index));
+            }
+            "--log-file" => {
+                args.remove(index);
+                options.file = true;
+            }
+            arg => match arg.strip_prefix("--log-level=") {
+                Some(level) => {
+                    options.level = Some(level.to_string());
+                    args.remove(index);
+                }
+                None => index += 1,
+            },
+        }
+    }
+    Ok(options)
+}
+
+/// Install the global subscriber
+pub fn init(options: &LogOptions) {
+    let filter = match &options.level {
+        // A plain level only raises or lowers the bar's own messages
+        Some(level) if !level.contains(['=', ',']) => {
+            EnvFilter::try_new(format!("warn,blade_bar={}", level))
+        }
+        Some(filter) => EnvFilter::try_new(filter),
+        None => EnvFilter::try_from_default_env(),
+    };
+    let filter = filter.unwrap_or_else(|e| {
+        if options.level.is_some() {
+            eprintln!("Invalid log level, using the default: {}", e);
+        }
+        EnvFilter::new(DEFAULT_FILTER)
+    });
+
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    if options.file {
+        match open_log_file() {
+            Ok(file) => {
+                subscriber.with_ansi(false).with_writer(Mutex::new(file)).init();
+                return;
+            }
+            Err(e) => eprintln!("Failed to open the log file, logging to stderr: {}", e),
+        }
+    }
+    subscriber.with_writer(std::io::stderr).init();
+}
+
+fn open_log_file() -> std::io::Result<fs::File> {
+    let dir = state_dir().ok_or_else(|| std::io::Error::other("no state directory"))?;
+    fs::create_dir_all(&dir)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(LOG_FILE))
+}
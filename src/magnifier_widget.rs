@@ -0,0 +1,166 @@
+use gtk4::glib::Propagation;
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, EventControllerScroll, EventControllerScrollFlags, Image, Label};
+use std::cell::Cell;
+use std::process::Command;
+use std::rc::Rc;
+use tracing::warn;
+
+use crate::compositor::Compositor;
+use crate::config::MagnifierConfig;
+use crate::control;
+use crate::hypr_toggles_widget::get_option;
+use crate::module::BarModule;
+
+/// Names of the zoom option, it moved from `misc` to `cursor` in Hyprland 0.37
+const ZOOM_OPTIONS: [&str; 2] = ["cursor:zoom_factor", "misc:cursor_zoom_factor"];
+
+/// Toggle Hyprland's cursor zoom, scrolling on the widget changes the zoom level
+pub struct MagnifierWidget {
+    pub button: Button,
+    state: Rc<MagnifierState>,
+}
+
+struct MagnifierState {
+    option: &'static str,
+    label: Label,
+    step: f64,
+    max: f64,
+    /// Current zoom factor, 1.0 is off
+    zoom: Cell<f64>,
+    /// Factor restored when zoom is toggled back on
+    last_zoom: Cell<f64>,
+}
+
+impl MagnifierWidget {
+    pub fn new(config: &MagnifierConfig) -> Option<Self> {
+        if Compositor::detect()? != Compositor::Hyprland {
+            return None;
+        }
+        let (option, current) = ZOOM_OPTIONS
+            .iter()
+            .find_map(|option| Some((*option, get_option(option)?.parse::<f64>().ok()?)))?;
+
+        let button = Button::new();
+        button.add_css_class("magnifier-button");
+
+        let content = GtkBox::new(gtk4::Orientation::Horizontal, 4);
+        content.append(&Image::from_icon_name("zoom-in-symbolic"));
+        let label = Label::new(None);
+        label.add_css_class("magnifier-label");
+        content.append(&label);
+        button.set_child(Some(&content));
+
+        let max = config.max.max(1.0);
+        let state = Rc::new(MagnifierState {
+            option,
+            label,
+            step: config.step,
+            max,
+            zoom: Cell::new(current),
+            last_zoom: Cell::new(config.zoom.clamp(1.0, max)),
+        });
+        state.show();
+
+        let state_click = state.clone();
+        button.connect_clicked(move |_| {
+            state_click.toggle();
+        });
+
+        let scroll = EventControllerScroll::new(
+            EventControllerScrollFlags::VERTICAL | EventControllerScrollFlags::DISCRETE,
+        );
+        let state_scroll = state.clone();
+        scroll.connect_scroll(move |_, _dx, dy| {
+            let step = if dy < 0.0 { state_scroll.step } else { -state_scroll.step };
+            state_scroll.set_zoom(state_scroll.zoom.get() + step);
+            Propagation::Stop
+        });
+        button.add_controller(scroll);
+
+        Some(MagnifierWidget { button, state })
+    }
+}
+
+impl MagnifierState {
+    fn toggle(&self) {
+        if self.zoom.get() > 1.0 {
+            self.set_zoom(1.0);
+        } else {
+            self.set_zoom(self.last_zoom.get());
+        }
+    }
+
+    fn set_zoom(&self, zoom: f64) {
+        let zoom = zoom.clamp(1.0, self.max);
+        if zoom == self.zoom.get() {
+            return;
+        }
+
+        match Command::new("hyprctl")
+            .args(["keyword", self.option, &format!("{:.2}", zoom)])
+            .output()
+        {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => {
+                warn!(
+                    "hyprctl keyword {} failed: {}",
+                    self.option,
+                    String::from_utf8_lossy(&output.stdout).trim()
+                );
+                return;
+            }
+            Err(e) => {
+                warn!("Failed to run hyprctl keyword {}: {}", self.option, e);
+                return;
+            }
+        }
+
+        self.zoom.set(zoom);
+        if zoom > 1.0 {
+            self.last_zoom.set(zoom);
+        }
+        self.show();
+    }
+
+    fn show(&self) {
+        let zoom = self.zoom.get();
+        let zoomed = zoom > 1.0;
+        self.label.set_text(&format!("{:.2}×", zoom));
+        self.label.set_visible(zoomed);
+
+        if let Some(button) = self.label.parent().and_then(|content| content.parent()) {
+            button.set_tooltip_text(Some(if zoomed {
+                "Click to stop zooming, scroll to change the zoom"
+            } else {
+                "Click to zoom, scroll to change the zoom"
+            }));
+            if zoomed {
+                button.add_css_class("zoomed");
+            } else {
+                button.remove_css_class("zoomed");
+            }
+        }
+
+        control::module_updated("magnifier");
+    }
+}
+
+impl BarModule for MagnifierWidget {
+    fn name(&self) -> &'static str {
+        "magnifier"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn refresh(&self) {
+        // Zoom may have been changed with a keybinding
+        let current = get_option(self.state.option).and_then(|value| value.parse().ok());
+        if let Some(current) = current {
+            self.state.zoom.set(current);
+            self.state.show();
+        }
+    }
+}
@@ -1,113 +1,172 @@
 use gtk::prelude::*;
-use gtk::{Application, ApplicationWindow, Box, CssProvider, Label, Orientation, gdk::Display};
+use gtk::Application;
 use gtk4 as gtk;
-use gtk4_layer_shell::{Edge, Layer, LayerShell};
-
-mod system_monitor;
-use system_monitor::SystemMonitor;
-
+use std::cell::RefCell;
+use std::rc::Rc;
+
+mod accessibility;
+mod accessibility_widget;
+mod audio_profiles_widget;
+mod audio_widget;
+mod backlight_widget;
+mod bar;
+mod baseline;
+use bar::Bars;
+
+mod check;
+mod clock_widget;
+mod color_scheme;
+mod command;
+mod compositor;
+mod config;
+use config::Config;
+
+mod control;
+mod custom_widget;
+mod disk_widget;
+mod display_profiles_widget;
+mod downloads_widget;
+mod drop_target;
+mod gaming_widget;
+mod group_widget;
+mod hypr_toggles_widget;
+mod idle_inhibitor_widget;
+mod image_widget;
+mod init;
+mod jack_transport_widget;
+mod launch;
+mod launcher_widget;
+mod layout;
+mod layout_editor;
+mod libvirt_widget;
+mod logging;
+mod magnifier_widget;
+mod matrix_widget;
+mod module;
+mod night_light_widget;
+mod notes_widget;
+mod notification_backend;
+mod notification_daemon;
+mod notification_rules;
 mod notification_widget;
-use notification_widget::NotificationWidget;
-
+mod overflow;
+mod pipewire_widget;
+mod popover_state;
+mod power;
+mod preview;
+mod privacy_widget;
+mod recent_files_widget;
+mod reload_widget;
+mod remote_widget;
+mod scheduler;
+mod slider_popover;
+mod sources;
+mod sparkline;
+mod state;
+mod style;
+mod system_monitor;
+mod taskbar_widget;
+mod template;
+mod theme_switcher_widget;
+mod themes;
+mod ticker_widget;
+mod time_tracker_widget;
+mod toasts;
+mod torrent_widget;
 mod tray_widget;
-use tray_widget::TrayWidget;
+mod vpn_widget;
+mod window_rules_widget;
+mod window_switcher;
+mod window_title_widget;
+mod workspaces_widget;
 
-fn load_css() {
-    let css_provider = CssProvider::new();
-
-    // Load CSS from file
-    css_provider.load_from_data(include_str!("style.css"));
+#[tokio::main]
+async fn main() {
+    // Subcommands run without creating the bar
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let log_options = match logging::parse_args(&mut args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    logging::init(&log_options);
 
-    // Apply CSS to the default display
-    if let Some(display) = Display::default() {
-        gtk::style_context_add_provider_for_display(
-            &display,
-            &css_provider,
-            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
-        );
+    let replace = match args.iter().position(|arg| arg == "--replace") {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    };
+
+    if let Some(command) = args.first() {
+        match command.as_str() {
+            "check" => std::process::exit(check::run().await),
+            "init" => std::process::exit(init::run(&args[1..])),
+            "edit" => std::process::exit(control::call("ToggleEditMode").await),
+            "--preview" => std::process::exit(preview::run(args.get(1).map(String::as_str))),
+            _ => {
+                eprintln!(
+                    "Unknown command '{}', expected: check, init, edit, --preview, --replace, --log-level, --log-file",
+                    command
+                );
+                std::process::exit(2);
+            }
+        }
     }
-}
 
-#[tokio::main]
-async fn main() {
+    // Two bars would stack their layer surfaces on the same outputs
+    let Some(instance) = control::claim_instance(replace).await else {
+        std::process::exit(1);
+    };
+
+    // The bus name claimed above is the single-instance guard, not GApplication
     let app = Application::builder()
         .application_id("org.swordi.BladeBar")
+        .flags(gio::ApplicationFlags::NON_UNIQUE)
         .build();
 
-    app.connect_activate(move |app| {
-        load_css();
-
-        let window = ApplicationWindow::builder()
-            .application(app)
-            .title("Wayland Bar")
-            .css_classes(["main-window"])
-            .build();
-
-        // Initialize layer shell for this window
-        LayerShell::init_layer_shell(&window);
+    // Bars on every output, kept so modules can be rebuilt on reload and shut down on exit
+    let bars: Rc<RefCell<Option<Rc<Bars>>>> = Rc::new(RefCell::new(None));
 
-        // Enable transparency
-        if let Some(surface) = window.surface() {
-            surface.set_opaque_region(None);
+    let bars_activate = bars.clone();
+    app.connect_activate(move |app| {
+        style::load_css();
+
+        // Power and daemon settings only apply at startup, the rest of the config is reloaded live
+        let config = Config::load();
+        power::start(&config.power);
+        notification_rules::configure(&config.notifications);
+        notification_daemon::start(app, &config.notifications);
+        color_scheme::apply(&config);
+        color_scheme::watch();
+        style::set_palette(&config);
+        style::set_font(&config.bar);
+        accessibility::configure(&config);
+
+        let bars = Bars::new(app, config);
+        control::start(&bars, &instance);
+        *bars_activate.borrow_mut() = Some(bars);
+
+        // Quit cleanly instead of being killed mid-write, quitting runs the shutdown
+        // handler below, which tears the bars down
+        for signal in [libc::SIGINT, libc::SIGTERM] {
+            let app = app.clone();
+            glib::unix_signal_add_local(signal, move || {
+                app.quit();
+                glib::ControlFlow::Break
+            });
         }
+    });
 
-        // Set the desired layer
-        LayerShell::set_layer(&window, Layer::Top);
-
-        // Reserve space so your bar is not covered
-        LayerShell::set_exclusive_zone(&window, 30); // height in pixels
-
-        // Anchor to the top, left, right edges
-        LayerShell::set_anchor(&window, Edge::Top, true);
-        LayerShell::set_anchor(&window, Edge::Left, true);
-        LayerShell::set_anchor(&window, Edge::Right, true);
-
-        // Optional: set a fixed height
-        window.set_default_size(800, 30); // width x height
-
-        // Create main container
-        let main_box = Box::new(Orientation::Horizontal, 10);
-        main_box.set_hexpand(true);
-        main_box.add_css_class("main-container");
-
-        // Create system monitor widget
-        let system_monitor = SystemMonitor::new();
-
-        // Create notification widget (if swaync is available)
-        let notification_widget = NotificationWidget::new();
-
-        // Add some spacing and the widgets to the right side
-        let spacer = Label::new(None);
-        spacer.set_hexpand(true);
-
-        let title_label = Label::new(Some("BladeBar"));
-        title_label.add_css_class("title-label");
-
-        main_box.append(&title_label);
-        main_box.append(&spacer);
-
-        main_box.append(system_monitor.widget());
-
-        // Add notification widget if available
-        if let Some(notification) = notification_widget {
-            main_box.append(notification.widget());
+    app.connect_shutdown(move |_| {
+        if let Some(bars) = bars.borrow_mut().take() {
+            bars.shutdown();
         }
-
-        window.set_child(Some(&main_box));
-        window.present();
-
-        // Create tray widget AFTER the window is presented and GTK is fully running
-        let main_box_weak = main_box.downgrade();
-        glib::timeout_add_local_once(std::time::Duration::from_millis(500), move || {
-            glib::spawn_future_local(async move {
-                if let Ok(tray_widget) = TrayWidget::new().await {
-                    if let Some(main_box) = main_box_weak.upgrade() {
-                        main_box.append(tray_widget.widget());
-                    }
-                }
-            });
-        });
     });
 
-    app.run();
+    // Our own flags were handled above, GTK would reject them
+    app.run_with_args::<&str>(&[]);
 }
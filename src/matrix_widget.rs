@@ -0,0 +1,283 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, ListBox, ListBoxRow, Orientation, Popover, SelectionMode};
+use serde_json::{Value, json};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::command::{is_available, spawn_piped_input, spawn_shell};
+use crate::config::MatrixConfig;
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+
+/// Wait before syncing again after a failed request
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Unread counts of a joined room
+#[derive(Debug, Clone, Default)]
+struct Room {
+    name: Option<String>,
+    /// Mentions and other highlighted messages
+    highlights: u64,
+    notifications: u64,
+}
+
+/// Matrix rooms with unread mentions, kept current through a filtered sync that
+/// only carries unread counts and room names
+pub struct MatrixWidget {
+    pub button: Button,
+    state: Rc<MatrixState>,
+}
+
+struct MatrixState {
+    homeserver: String,
+    access_token: String,
+    open: String,
+    timeout: Duration,
+    label: Label,
+    list: ListBox,
+    rooms: RefCell<HashMap<String, Room>>,
+    /// Room ids in list order
+    listed: RefCell<Vec<String>>,
+}
+
+impl MatrixWidget {
+    pub fn new(config: &MatrixConfig) -> Option<Self> {
+        let Some(access_token) = config.access_token.clone() else {
+            warn!("Module 'matrix' needs 'access_token' to be configured");
+            return None;
+        };
+        if config.homeserver.is_empty() || !is_available("curl") {
+            return None;
+        }
+
+        let button = Button::new();
+        button.add_css_class("matrix-button");
+
+        let label = Label::new(Some("💬 -"));
+        label.add_css_class("matrix-label");
+        button.set_child(Some(&label));
+
+        let content = GtkBox::new(Orientation::Vertical, 6);
+        content.add_css_class("matrix-popover");
+        let list = ListBox::new();
+        list.set_selection_mode(SelectionMode::None);
+        list.add_css_class("matrix-rooms");
+        let placeholder = Label::new(Some("No unread mentions"));
+        placeholder.add_css_class("dim-label");
+        list.set_placeholder(Some(&placeholder));
+        content.append(&list);
+
+        let popover = Popover::new();
+        popover.set_child(Some(&content));
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let state = Rc::new(MatrixState {
+            homeserver: config.homeserver.trim_end_matches('/').to_string(),
+            access_token,
+            open: config.open.clone(),
+            timeout: Duration::from_secs(config.timeout.max(1)),
+            label,
+            list,
+            rooms: RefCell::new(HashMap::new()),
+            listed: RefCell::new(Vec::new()),
+        });
+
+        // Open the clicked room in the configured client
+        let state_activate = state.clone();
+        let popover_weak = popover.downgrade();
+        state.list.connect_row_activated(move |_, row| {
+            if let Some(room) = state_activate.listed.borrow().get(row.index() as usize) {
+                // Room ids are chosen by the homeserver, never let them reach the shell as is
+                let quoted = gtk4::glib::shell_quote(room).to_string_lossy().into_owned();
+                spawn_shell(&state_activate.open.replace("{room}", &quoted));
+            }
+            if let Some(popover) = popover_weak.upgrade() {
+                popover.popdown();
+            }
+        });
+
+        button.connect_clicked(move |_| {
+            popover.popup();
+        });
+
+        Some(MatrixWidget { button, state })
+    }
+}
+
+impl MatrixState {
+    /// Long-poll the homeserver, `None` when the request failed
+    async fn sync(&self, since: Option<&str>) -> Option<String> {
+        let filter = json!({
+            "presence": { "not_types": ["*"] },
+            "account_data": { "not_types": ["*"] },
+            "room": {
+                "timeline": { "limit": 1 },
+                "ephemeral": { "not_types": ["*"] },
+                "account_data": { "not_types": ["*"] },
+                "state": {
+                    "lazy_load_members": true,
+                    "types": ["m.room.name", "m.room.canonical_alias"],
+                },
+            },
+        });
+        let url = format!("{}/_matrix/client/v3/sync", self.homeserver);
+        let filter = format!("filter={}", filter);
+        let timeout = format!("timeout={}", self.timeout.as_millis());
+        let since = since.map(|since| format!("since={}", since));
+        let max_time = (self.timeout.as_secs() + 30).to_string();
+
+        let mut argv = vec![
+            "curl",
+            "-s",
+            "--fail",
+            "--max-time",
+            max_time.as_str(),
+            // Reads the authorization header from stdin, out of the process list
+            "-K",
+            "-",
+            "-G",
+            "--data-urlencode",
+            filter.as_str(),
+            "--data-urlencode",
+            timeout.as_str(),
+        ];
+        if let Some(since) = &since {
+            argv.extend(["--data-urlencode", since.as_str()]);
+        }
+        argv.push(&url);
+
+        let process = spawn_piped_input(&argv)?;
+        let header = format!("header = \"Authorization: Bearer {}\"\n", self.access_token);
+        let (stdout, _) = process.communicate_utf8_future(Some(header)).await.ok()?;
+        if !process.is_successful() {
+            debug!("Matrix sync failed with status {}", process.exit_status());
+            return None;
+        }
+        let response: Value = serde_json::from_str(stdout.as_deref().unwrap_or_default()).ok()?;
+
+        self.apply(&response);
+        response["next_batch"].as_str().map(str::to_string)
+    }
+
+    /// Merge a sync response, incremental ones only carry rooms that changed
+    fn apply(&self, response: &Value) {
+        let mut rooms = self.rooms.borrow_mut();
+
+        if let Some(joined) = response["rooms"]["join"].as_object() {
+            for (id, update) in joined {
+                let room = rooms.entry(id.clone()).or_default();
+                let unread = &update["unread_notifications"];
+                room.highlights = unread["highlight_count"].as_u64().unwrap_or(0);
+                room.notifications = unread["notification_count"].as_u64().unwrap_or(0);
+
+                let state = update["state"]["events"].as_array().into_iter().flatten();
+                let timeline = update["timeline"]["events"].as_array().into_iter().flatten();
+                for event in state.chain(timeline) {
+                    match event["type"].as_str() {
+                        Some("m.room.name") => {
+                            room.name = event["content"]["name"].as_str().map(str::to_string)
+                        }
+                        Some("m.room.canonical_alias") if room.name.is_none() => {
+                            room.name = event["content"]["alias"].as_str().map(str::to_string)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        if let Some(left) = response["rooms"]["leave"].as_object() {
+            for id in left.keys() {
+                rooms.remove(id);
+            }
+        }
+    }
+
+    fn show(&self) {
+        let rooms = self.rooms.borrow();
+        let mut mentioned: Vec<(&String, &Room)> =
+            rooms.iter().filter(|(_, room)| room.highlights > 0).collect();
+        mentioned.sort_by(|(a_id, a), (b_id, b)| {
+            b.highlights.cmp(&a.highlights).then_with(|| a_id.cmp(b_id))
+        });
+        let unread = rooms.values().filter(|room| room.notifications > 0).count();
+
+        self.label.set_text(&format!("💬 {}", mentioned.len()));
+        if let Some(button) = self.label.parent() {
+            let mut tooltip = format!(
+                "{} rooms with mentions, {} with unread messages",
+                mentioned.len(),
+                unread
+            );
+            for (id, room) in &mentioned {
+                tooltip.push_str(&format!(
+                    "\n{}: {}",
+                    room.name.as_deref().unwrap_or(id),
+                    room.highlights
+                ));
+            }
+            button.set_tooltip_text(Some(&tooltip));
+            if mentioned.is_empty() {
+                button.remove_css_class("mentioned");
+            } else {
+                button.add_css_class("mentioned");
+            }
+        }
+
+        while let Some(row) = self.list.row_at_index(0) {
+            self.list.remove(&row);
+        }
+        for (id, room) in &mentioned {
+            let row_box = GtkBox::new(Orientation::Horizontal, 8);
+            let name = Label::new(Some(room.name.as_deref().unwrap_or(id)));
+            name.set_halign(gtk4::Align::Start);
+            name.set_hexpand(true);
+            name.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+            name.set_max_width_chars(32);
+            let count = Label::new(Some(&room.highlights.to_string()));
+            count.add_css_class("matrix-count");
+            row_box.append(&name);
+            row_box.append(&count);
+
+            let row = ListBoxRow::new();
+            row.set_child(Some(&row_box));
+            row.set_tooltip_text(Some(id));
+            self.list.append(&row);
+        }
+        *self.listed.borrow_mut() = mentioned.iter().map(|(id, _)| (*id).clone()).collect();
+
+        control::module_updated("matrix");
+    }
+}
+
+impl BarModule for MatrixWidget {
+    fn name(&self) -> &'static str {
+        "matrix"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            let mut since: Option<String> = None;
+            loop {
+                match self.state.sync(since.as_deref()).await {
+                    Some(next_batch) => {
+                        since = Some(next_batch);
+                        self.state.show();
+                    }
+                    None => {
+                        self.state.label.set_text("💬 N/A");
+                        next_tick(RETRY_DELAY).await;
+                    }
+                }
+            }
+        })
+    }
+}
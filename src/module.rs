@@ -0,0 +1,281 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Label, Orientation, Separator, Widget};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use tracing::warn;
+
+use crate::accessibility_widget::AccessibilityWidget;
+use crate::audio_profiles_widget::AudioProfilesWidget;
+use crate::audio_widget::AudioWidget;
+use crate::backlight_widget::BacklightWidget;
+use crate::clock_widget::ClockWidget;
+use crate::config::Config;
+use crate::custom_widget::CustomWidget;
+use crate::disk_widget::DiskWidget;
+use crate::display_profiles_widget::DisplayProfilesWidget;
+use crate::downloads_widget::DownloadsWidget;
+use crate::gaming_widget::GamingWidget;
+use crate::group_widget::GroupWidget;
+use crate::hypr_toggles_widget::HyprTogglesWidget;
+use crate::idle_inhibitor_widget::IdleInhibitorWidget;
+use crate::image_widget::ImageWidget;
+use crate::jack_transport_widget::JackTransportWidget;
+use crate::launcher_widget::LauncherWidget;
+use crate::libvirt_widget::LibvirtWidget;
+use crate::magnifier_widget::MagnifierWidget;
+use crate::matrix_widget::MatrixWidget;
+use crate::night_light_widget::NightLightWidget;
+use crate::notes_widget::NotesWidget;
+use crate::notification_widget::NotificationWidget;
+use crate::pipewire_widget::PipewireWidget;
+use crate::privacy_widget::PrivacyWidget;
+use crate::recent_files_widget::RecentFilesWidget;
+use crate::reload_widget::ReloadWidget;
+use crate::remote_widget::RemoteWidget;
+use crate::system_monitor::SystemMonitor;
+use crate::taskbar_widget::TaskbarWidget;
+use crate::theme_switcher_widget::ThemeSwitcherWidget;
+use crate::ticker_widget::TickerWidget;
+use crate::time_tracker_widget::TimeTrackerWidget;
+use crate::torrent_widget::TorrentWidget;
+use crate::tray_widget::TrayModule;
+use crate::vpn_widget::VpnWidget;
+use crate::window_rules_widget::WindowRulesWidget;
+use crate::window_switcher::WindowSwitcher;
+use crate::window_title_widget::WindowTitleWidget;
+use crate::workspaces_widget::WorkspacesWidget;
+
+/// Future returned by [`BarModule::init`]
+pub type InitFuture<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+/// Common interface implemented by every component that can be placed on the bar
+pub trait BarModule {
+    /// Name used to reference the module from the layout configuration
+    fn name(&self) -> &'static str;
+
+    /// Root widget that is added to the bar
+    fn widget(&self) -> &Widget;
+
+    /// Asynchronous setup that runs once the widget has been added to the bar
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async {})
+    }
+
+    /// Release external resources (threads, subscriptions) before the bar exits
+    /// or the module is dropped by a configuration reload
+    fn shutdown(&self) {}
+
+    /// Update immediately, when requested over the control interface
+    fn refresh(&self) {}
+
+    /// Keep this instance when the configuration is reloaded to `config` instead of
+    /// recreating it
+    fn keep_on_reload(&self, _config: &Config) -> bool {
+        false
+    }
+}
+
+/// Every name accepted by [`create_module`]
+pub const MODULE_NAMES: &[&str] = &[
+    "launcher",
+    "title",
+    "workspaces",
+    "window_title",
+    "window_switcher",
+    "taskbar",
+    "recent_files",
+    "downloads",
+    "night_light",
+    "idle_inhibitor",
+    "backlight",
+    "magnifier",
+    "accessibility",
+    "notes",
+    "time_tracker",
+    "clock",
+    "image",
+    "ticker",
+    "audio",
+    "pipewire",
+    "jack_transport",
+    "audio_profiles",
+    "display_profiles",
+    "theme_switcher",
+    "hypr_toggles",
+    "window_rules",
+    "gaming",
+    "reload",
+    "system_monitor",
+    "disk",
+    "vpn",
+    "remote",
+    "libvirt",
+    "torrent",
+    "matrix",
+    "privacy",
+    "notifications",
+    "tray",
+    "separator",
+    "spacer",
+];
+
+/// Whether `name` refers to a built-in module, a `custom/<name>` module, a
+/// `group/<name>` drawer or a fixed `spacer/<pixels>` gap
+pub fn is_module_name(name: &str) -> bool {
+    MODULE_NAMES.contains(&name)
+        || name.starts_with("custom/")
+        || name.starts_with("group/")
+        || name
+            .strip_prefix("spacer/")
+            .is_some_and(|size| size.parse::<i32>().is_ok())
+}
+
+/// Create the module registered under `name`, if it exists and is available on this system
+pub fn create_module(name: &str, config: &Config) -> Option<Rc<dyn BarModule>> {
+    if let Some(custom) = name.strip_prefix("custom/") {
+        return Some(Rc::new(CustomWidget::new(custom, config.custom.get(custom))?));
+    }
+
+    // Direction of the bar, for modules that lay out several items
+    let orientation = config.bar.position.orientation();
+
+    if let Some(group) = name.strip_prefix("group/") {
+        return Some(Rc::new(GroupWidget::new(group, config, orientation)?));
+    }
+    if let Some(size) = name.strip_prefix("spacer/") {
+        let Ok(size) = size.parse() else {
+            warn!("Invalid spacer size in '{}', expected pixels", name);
+            return None;
+        };
+        return Some(Rc::new(Spacer::new(orientation, Some(size))));
+    }
+
+    let module: Rc<dyn BarModule> = match name {
+        "launcher" => Rc::new(LauncherWidget::new(&config.launcher, orientation)?),
+        "title" => Rc::new(TitleModule::new()),
+        "workspaces" => Rc::new(WorkspacesWidget::new(orientation)?),
+        "window_title" => Rc::new(WindowTitleWidget::new(&config.window_title, orientation)?),
+        "window_switcher" => Rc::new(WindowSwitcher::new()?),
+        "taskbar" => Rc::new(TaskbarWidget::new(&config.taskbar, orientation)),
+        "recent_files" => Rc::new(RecentFilesWidget::new(&config.recent_files)),
+        "downloads" => Rc::new(DownloadsWidget::new(&config.downloads)?),
+        "night_light" => Rc::new(NightLightWidget::new(&config.night_light)?),
+        "idle_inhibitor" => Rc::new(IdleInhibitorWidget::new(&config.idle_inhibitor)),
+        "backlight" => Rc::new(BacklightWidget::new(&config.backlight)?),
+        "magnifier" => Rc::new(MagnifierWidget::new(&config.magnifier)?),
+        "accessibility" => Rc::new(AccessibilityWidget::new()),
+        "notes" => Rc::new(NotesWidget::new(&config.notes)?),
+        "time_tracker" => Rc::new(TimeTrackerWidget::new(&config.time_tracker)?),
+        "clock" => Rc::new(ClockWidget::new(&config.clock, orientation)),
+        "image" => Rc::new(ImageWidget::new(&config.image)?),
+        "ticker" => Rc::new(TickerWidget::new(&config.ticker, orientation)?),
+        "audio" => Rc::new(AudioWidget::new(&config.audio)),
+        "pipewire" => Rc::new(PipewireWidget::new(&config.pipewire)?),
+        "jack_transport" => Rc::new(JackTransportWidget::new(orientation)),
+        "audio_profiles" => Rc::new(AudioProfilesWidget::new(&config.audio_profiles)?),
+        "display_profiles" => Rc::new(DisplayProfilesWidget::new(&config.display_profiles)?),
+        "theme_switcher" => Rc::new(ThemeSwitcherWidget::new()?),
+        "hypr_toggles" => Rc::new(HyprTogglesWidget::new(&config.hypr_toggles, orientation)?),
+        "window_rules" => Rc::new(WindowRulesWidget::new()?),
+        "gaming" => Rc::new(GamingWidget::new()?),
+        "reload" => Rc::new(ReloadWidget::new()?),
+        "system_monitor" => Rc::new(SystemMonitor::new(&config.system_monitor, orientation)),
+        "disk" => Rc::new(DiskWidget::new(&config.disk, orientation)),
+        "vpn" => Rc::new(VpnWidget::new(&config.vpn)),
+        "remote" => Rc::new(RemoteWidget::new(&config.remote, orientation)?),
+        "libvirt" => Rc::new(LibvirtWidget::new(&config.libvirt)?),
+        "torrent" => Rc::new(TorrentWidget::new(&config.torrent)?),
+        "matrix" => Rc::new(MatrixWidget::new(&config.matrix)?),
+        "privacy" => Rc::new(PrivacyWidget::new(&config.privacy, orientation)),
+        "notifications" => Rc::new(NotificationWidget::new(&config.notifications)?),
+        "tray" => Rc::new(TrayModule::new(&config.tray, orientation)),
+        "separator" => Rc::new(SeparatorModule::new(orientation)),
+        "spacer" => Rc::new(Spacer::new(orientation, None)),
+        _ => {
+            warn!("Unknown module '{}' in layout configuration", name);
+            return None;
+        }
+    };
+
+    Some(module)
+}
+
+/// Static bar title
+struct TitleModule {
+    label: Label,
+}
+
+impl TitleModule {
+    fn new() -> Self {
+        let label = Label::new(Some("BladeBar"));
+        label.add_css_class("title-label");
+        TitleModule { label }
+    }
+}
+
+impl BarModule for TitleModule {
+    fn name(&self) -> &'static str {
+        "title"
+    }
+
+    fn widget(&self) -> &Widget {
+        self.label.upcast_ref()
+    }
+}
+
+/// Rule across the bar between groups of modules
+struct SeparatorModule {
+    separator: Separator,
+}
+
+impl SeparatorModule {
+    fn new(orientation: Orientation) -> Self {
+        // Perpendicular to the bar, a vertical rule on a horizontal bar
+        let separator = Separator::new(match orientation {
+            Orientation::Horizontal => Orientation::Vertical,
+            _ => Orientation::Horizontal,
+        });
+        separator.add_css_class("bar-separator");
+        SeparatorModule { separator }
+    }
+}
+
+impl BarModule for SeparatorModule {
+    fn name(&self) -> &'static str {
+        "separator"
+    }
+
+    fn widget(&self) -> &Widget {
+        self.separator.upcast_ref()
+    }
+}
+
+/// Empty gap, `size` pixels long or taking all the room left in its zone
+struct Spacer {
+    gap: GtkBox,
+}
+
+impl Spacer {
+    fn new(orientation: Orientation, size: Option<i32>) -> Self {
+        let gap = GtkBox::new(orientation, 0);
+        gap.add_css_class("bar-spacer");
+        match (size, orientation) {
+            (Some(size), Orientation::Horizontal) => gap.set_size_request(size, -1),
+            (Some(size), _) => gap.set_size_request(-1, size),
+            (None, Orientation::Horizontal) => gap.set_hexpand(true),
+            (None, _) => gap.set_vexpand(true),
+        }
+        Spacer { gap }
+    }
+}
+
+impl BarModule for Spacer {
+    fn name(&self) -> &'static str {
+        "spacer"
+    }
+
+    fn widget(&self) -> &Widget {
+        self.gap.upcast_ref()
+    }
+}
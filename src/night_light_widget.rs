@@ -0,0 +1,256 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, Orientation, Popover, Scale, Switch};
+use std::cell::{Cell, RefCell};
+use std::process::{Child, Command};
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::command::is_available;
+use crate::compositor::Compositor;
+use crate::config::NightLightConfig;
+use crate::module::BarModule;
+use crate::state;
+
+/// Delay after the slider stops moving before the temperature is applied
+const APPLY_DELAY: Duration = Duration::from_millis(300);
+
+/// Tool used to change the screen color temperature
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    /// Restarted with new `-t`/`-T` arguments on every change
+    Wlsunset,
+    /// Adjusted live over its hyprctl IPC
+    Hyprsunset,
+}
+
+impl Backend {
+    fn detect(configured: Option<&str>) -> Option<Self> {
+        match configured {
+            Some("wlsunset") => Some(Backend::Wlsunset),
+            Some("hyprsunset") => Some(Backend::Hyprsunset),
+            Some(other) => {
+                warn!("Unknown night light backend '{}'", other);
+                None
+            }
+            None if Compositor::detect() == Some(Compositor::Hyprland)
+                && is_available("hyprsunset") =>
+            {
+                Some(Backend::Hyprsunset)
+            }
+            None if is_available("wlsunset") => Some(Backend::Wlsunset),
+            None => None,
+        }
+    }
+}
+
+/// Night light toggle with a popover slider for the color temperature
+pub struct NightLightWidget {
+    pub button: Button,
+}
+
+struct NightLightState {
+    backend: Backend,
+    label: Label,
+    enabled: Cell<bool>,
+    temperature: Cell<u32>,
+    /// Process we started, killed before restarting or disabling
+    child: RefCell<Option<Child>>,
+    pending_apply: RefCell<Option<glib::SourceId>>,
+}
+
+impl NightLightWidget {
+    pub fn new(config: &NightLightConfig) -> Option<Self> {
+        let backend = Backend::detect(config.backend.as_deref())?;
+
+        // Last temperature picked with the slider
+        let temperature = state::get()
+            .night_light_temperature
+            .unwrap_or(config.default_temperature)
+            .clamp(config.min_temperature, config.max_temperature);
+
+        let button = Button::new();
+        button.add_css_class("night-light-button");
+
+        let label = Label::new(None);
+        label.add_css_class("night-light-label");
+        button.set_child(Some(&label));
+
+        let state = Rc::new(NightLightState {
+            backend,
+            label,
+            enabled: Cell::new(false),
+            temperature: Cell::new(temperature),
+            child: RefCell::new(None),
+            pending_apply: RefCell::new(None),
+        });
+        state.update_label();
+
+        // Popover with an on/off switch and the temperature slider
+        let popover = Popover::new();
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let content = GtkBox::new(Orientation::Vertical, 6);
+        content.add_css_class("night-light");
+
+        let switch_row = GtkBox::new(Orientation::Horizontal, 8);
+        let switch_label = Label::new(Some("Night light"));
+        switch_label.set_hexpand(true);
+        switch_label.set_halign(gtk4::Align::Start);
+        let switch = Switch::new();
+        switch_row.append(&switch_label);
+        switch_row.append(&switch);
+        content.append(&switch_row);
+
+        let scale = Scale::with_range(
+            Orientation::Horizontal,
+            config.min_temperature as f64,
+            config.max_temperature as f64,
+            100.0,
+        );
+        scale.set_value(temperature as f64);
+        scale.set_draw_value(true);
+        scale.set_size_request(220, -1);
+        scale.set_format_value_func(|_, value| format!("{:.0} K", value));
+        content.append(&scale);
+
+        popover.set_child(Some(&content));
+
+        let state_switch = state.clone();
+        switch.connect_state_set(move |_, enabled| {
+            state_switch.set_enabled(enabled);
+            gtk4::glib::Propagation::Proceed
+        });
+
+        let state_scale = state.clone();
+        scale.connect_value_changed(move |scale| {
+            let temperature = (scale.value() / 100.0).round() as u32 * 100;
+            state_scale.temperature.set(temperature);
+            state_scale.update_label();
+            state::update(|state| state.night_light_temperature = Some(temperature));
+            state_scale.schedule_apply();
+        });
+
+        button.connect_clicked(move |_| {
+            popover.popup();
+        });
+
+        Some(NightLightWidget { button })
+    }
+}
+
+impl NightLightState {
+    fn update_label(&self) {
+        let icon = if self.enabled.get() { "🌙" } else { "☀" };
+        self.label.set_text(icon);
+        if let Some(parent) = self.label.parent() {
+            parent.set_tooltip_text(Some(&if self.enabled.get() {
+                format!("Night light: {} K", self.temperature.get())
+            } else {
+                "Night light off".to_string()
+            }));
+        }
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        self.enabled.set(enabled);
+        self.update_label();
+
+        if enabled {
+            self.apply();
+        } else {
+            self.stop();
+        }
+    }
+
+    /// Apply once the slider settles, restarting wlsunset for every step would flicker
+    fn schedule_apply(self: &Rc<Self>) {
+        if !self.enabled.get() {
+            return;
+        }
+
+        if let Some(source_id) = self.pending_apply.borrow_mut().take() {
+            source_id.remove();
+        }
+
+        let state = self.clone();
+        let source_id = glib::timeout_add_local_once(APPLY_DELAY, move || {
+            state.pending_apply.borrow_mut().take();
+            state.apply();
+        });
+        *self.pending_apply.borrow_mut() = Some(source_id);
+    }
+
+    fn apply(&self) {
+        let temperature = self.temperature.get();
+
+        match self.backend {
+            Backend::Wlsunset => {
+                self.stop();
+                // A one Kelvin wide day/night range keeps the temperature constant
+                let child = Command::new("wlsunset")
+                    .args([
+                        "-t",
+                        &temperature.to_string(),
+                        "-T",
+                        &(temperature + 1).to_string(),
+                    ])
+                    .spawn();
+                match child {
+                    Ok(child) => *self.child.borrow_mut() = Some(child),
+                    Err(e) => warn!("Failed to start wlsunset: {}", e),
+                }
+            }
+            Backend::Hyprsunset => {
+                let ipc = Command::new("hyprctl")
+                    .args(["hyprsunset", "temperature", &temperature.to_string()])
+                    .output();
+
+                // Fall back to starting hyprsunset when it isn't running yet
+                let applied = ipc.is_ok_and(|output| {
+                    output.status.success()
+                        && !String::from_utf8_lossy(&output.stdout).contains("error")
+                });
+                if !applied && self.child.borrow().is_none() {
+                    match Command::new("hyprsunset")
+                        .args(["-t", &temperature.to_string()])
+                        .spawn()
+                    {
+                        Ok(child) => *self.child.borrow_mut() = Some(child),
+                        Err(e) => warn!("Failed to start hyprsunset: {}", e),
+                    }
+                }
+            }
+        }
+    }
+
+    fn stop(&self) {
+        if let Some(mut child) = self.child.borrow_mut().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        if self.backend == Backend::Hyprsunset {
+            let _ = Command::new("hyprctl")
+                .args(["hyprsunset", "identity"])
+                .output();
+        }
+    }
+}
+
+impl Drop for NightLightState {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl BarModule for NightLightWidget {
+    fn name(&self) -> &'static str {
+        "night_light"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+}
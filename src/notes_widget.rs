@@ -0,0 +1,142 @@
+use glib::SourceId;
+use gtk4::prelude::*;
+use gtk4::{Button, Image, Popover, ScrolledWindow, TextBuffer, TextView, WrapMode};
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::{NotesConfig, data_dir};
+use crate::drop_target::on_text_dropped;
+use crate::module::BarModule;
+
+/// Delay after the last keystroke before the notes are written to disk
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+pub struct NotesWidget {
+    pub button: Button,
+    buffer: TextBuffer,
+    path: PathBuf,
+    /// Debounced save not written yet
+    pending_save: Rc<RefCell<Option<SourceId>>>,
+}
+
+impl NotesWidget {
+    pub fn new(config: &NotesConfig) -> Option<Self> {
+        let path = config.path.clone().or_else(Self::default_path)?;
+
+        let button = Button::new();
+        button.add_css_class("notes-button");
+        button.set_child(Some(&Image::from_icon_name("accessories-text-editor-symbolic")));
+        button.set_tooltip_text(Some("Notes"));
+
+        let popover = Popover::new();
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+        popover.add_css_class("notes");
+
+        let text_view = TextView::new();
+        text_view.set_wrap_mode(WrapMode::WordChar);
+        text_view.add_css_class("notes-text");
+        text_view.set_left_margin(6);
+        text_view.set_right_margin(6);
+        text_view.set_top_margin(6);
+        text_view.set_bottom_margin(6);
+
+        let buffer = text_view.buffer();
+        buffer.set_text(&fs::read_to_string(&path).unwrap_or_default());
+
+        let scrolled = ScrolledWindow::new();
+        scrolled.set_min_content_width(320);
+        scrolled.set_min_content_height(240);
+        scrolled.set_child(Some(&text_view));
+        popover.set_child(Some(&scrolled));
+
+        // Save on change, debounced so typing doesn't hit the disk on every keystroke
+        let pending_save: Rc<RefCell<Option<SourceId>>> = Rc::new(RefCell::new(None));
+        let pending_save_changed = pending_save.clone();
+        let path_changed = path.clone();
+        buffer.connect_changed(move |buffer| {
+            if let Some(source_id) = pending_save_changed.borrow_mut().take() {
+                source_id.remove();
+            }
+
+            let buffer = buffer.clone();
+            let path = path_changed.clone();
+            let pending_save_inner = pending_save_changed.clone();
+            let source_id = glib::timeout_add_local_once(SAVE_DELAY, move || {
+                pending_save_inner.borrow_mut().take();
+                let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+                Self::save(&path, &text);
+            });
+            *pending_save_changed.borrow_mut() = Some(source_id);
+        });
+
+        // Text or files dropped on the button are appended as a new line
+        let buffer_drop = buffer.clone();
+        on_text_dropped(&button, move |text| {
+            let mut end = buffer_drop.end_iter();
+            if end.offset() > 0 && end.backward_char() && end.char() != '\n' {
+                buffer_drop.insert(&mut buffer_drop.end_iter(), "\n");
+            }
+            buffer_drop.insert(&mut buffer_drop.end_iter(), &format!("{}\n", text.trim_end()));
+        });
+
+        let text_view_focus = text_view.clone();
+        popover.connect_show(move |_| {
+            text_view_focus.grab_focus();
+        });
+
+        button.connect_clicked(move |_| {
+            popover.popup();
+        });
+
+        Some(NotesWidget {
+            button,
+            buffer,
+            path,
+            pending_save,
+        })
+    }
+
+    fn save(path: &Path, text: &str) {
+        if let Some(parent) = path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                warn!("Failed to create notes directory '{}': {}", parent.display(), e);
+                return;
+            }
+        }
+
+        if let Err(e) = fs::write(path, text) {
+            warn!("Failed to save notes to '{}': {}", path.display(), e);
+        }
+    }
+
+    /// Path the notes are stored at when no override is configured
+    pub fn default_path() -> Option<PathBuf> {
+        data_dir().map(|dir| dir.join("notes.txt"))
+    }
+}
+
+impl BarModule for NotesWidget {
+    fn name(&self) -> &'static str {
+        "notes"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    /// Write notes typed within the save delay before they are lost
+    fn shutdown(&self) {
+        if let Some(source_id) = self.pending_save.borrow_mut().take() {
+            source_id.remove();
+            let text = self
+                .buffer
+                .text(&self.buffer.start_iter(), &self.buffer.end_iter(), false);
+            Self::save(&self.path, &text);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+pub mod fnott;
+pub mod mako;
+pub mod swaync;
+
+use std::fs;
+use std::rc::Rc;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::command::is_available;
+use crate::config::NotificationDaemon;
+use crate::notification_widget::NotificationStatus;
+
+use fnott::FnottBackend;
+use mako::MakoBackend;
+use swaync::SwayncBackend;
+
+/// Control over the running notification daemon, implemented once per supported
+/// daemon
+pub trait NotificationBackend {
+    /// Start following the daemon in the background, sending the status whenever
+    /// it changes. `changed` is dropped when the daemon goes away.
+    fn watch(&self, changed: UnboundedSender<NotificationStatus>);
+
+    /// Show or hide the daemon's notification panel, or its closest equivalent
+    fn toggle_panel(&self);
+
+    fn toggle_dnd(&self);
+
+    fn dismiss_all(&self);
+}
+
+impl NotificationDaemon {
+    const ALL: [NotificationDaemon; 3] = [
+        NotificationDaemon::Swaync,
+        NotificationDaemon::Mako,
+        NotificationDaemon::Fnott,
+    ];
+
+    /// The daemon that is running, or else the first one installed
+    pub fn detect() -> Option<Self> {
+        let running: Vec<String> = fs::read_dir("/proc")
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|entry| fs::read_to_string(entry.path().join("comm")).ok())
+                    .map(|comm| comm.trim().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self::ALL
+            .into_iter()
+            .find(|daemon| running.iter().any(|comm| comm == daemon.process()))
+            .or_else(|| Self::ALL.into_iter().find(|daemon| is_available(daemon.client())))
+    }
+
+    fn process(self) -> &'static str {
+        match self {
+            NotificationDaemon::Swaync => "swaync",
+            NotificationDaemon::Mako => "mako",
+            NotificationDaemon::Fnott => "fnott",
+        }
+    }
+
+    /// Control program shipped with the daemon
+    pub fn client(self) -> &'static str {
+        match self {
+            NotificationDaemon::Swaync => "swaync-client",
+            NotificationDaemon::Mako => "makoctl",
+            NotificationDaemon::Fnott => "fnottctl",
+        }
+    }
+
+    pub fn backend(self) -> Rc<dyn NotificationBackend> {
+        match self {
+            NotificationDaemon::Swaync => Rc::new(SwayncBackend::default()),
+            NotificationDaemon::Mako => Rc::new(MakoBackend::default()),
+            NotificationDaemon::Fnott => Rc::new(FnottBackend::default()),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::NotificationBackend;
+use crate::command::{run_shell, spawn_piped};
+use crate::notification_widget::NotificationStatus;
+use crate::scheduler::next_tick;
+
+/// fnott has no change notifications, so `fnottctl list` is polled
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// fnott through `fnottctl`. It cannot report whether it is paused, so
+/// do-not-disturb is tracked here, starting off.
+#[derive(Default)]
+pub struct FnottBackend {
+    paused: Rc<Cell<bool>>,
+    /// Wakes the poll after an action, so it shows without waiting a full interval
+    changed: Rc<Notify>,
+}
+
+/// Ids of the notifications on screen, `fnottctl list` prints one `<id>: ...` line
+/// for each
+async fn list() -> Option<Vec<String>> {
+    let process = spawn_piped(&["fnottctl", "list"])?;
+    let (stdout, _) = process.communicate_utf8_future(None).await.ok()?;
+    if !process.is_successful() {
+        return None;
+    }
+    Some(
+        stdout
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(id, _)| id.trim())
+            .filter(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+impl FnottBackend {
+    /// Run a `fnottctl` command, then update the status right away
+    fn run(&self, command: &'static str) {
+        let changed = self.changed.clone();
+        glib::spawn_future_local(async move {
+            run_shell(command).await;
+            changed.notify_one();
+        });
+    }
+}
+
+impl NotificationBackend for FnottBackend {
+    fn watch(&self, changed: UnboundedSender<NotificationStatus>) {
+        let paused = self.paused.clone();
+        let wake = self.changed.clone();
+        glib::spawn_future_local(async move {
+            let mut last = None;
+            // fnottctl fails once fnott is gone, which ends the watch
+            while let Some(ids) = list().await {
+                let status = NotificationStatus {
+                    count: ids.len() as u32,
+                    dnd: paused.get(),
+                };
+                if last != Some((status.count, status.dnd)) {
+                    last = Some((status.count, status.dnd));
+                    if changed.send(status).is_err() {
+                        return;
+                    }
+                }
+
+                tokio::select! {
+                    _ = next_tick(POLL_INTERVAL) => {}
+                    _ = wake.notified() => {}
+                }
+            }
+        });
+    }
+
+    fn toggle_panel(&self) {
+        // No panel, dismiss the notification on top instead
+        self.run("fnottctl dismiss");
+    }
+
+    fn toggle_dnd(&self) {
+        let paused = !self.paused.get();
+        self.paused.set(paused);
+        self.run(if paused { "fnottctl pause" } else { "fnottctl unpause" });
+    }
+
+    fn dismiss_all(&self) {
+        let changed = self.changed.clone();
+        glib::spawn_future_local(async move {
+            for id in list().await.unwrap_or_default() {
+                run_shell(&format!("fnottctl dismiss {}", id)).await;
+            }
+            changed.notify_one();
+        });
+    }
+}
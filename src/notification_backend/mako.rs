@@ -0,0 +1,133 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::warn;
+use zbus::zvariant::OwnedValue;
+
+use super::NotificationBackend;
+use crate::notification_widget::NotificationStatus;
+use crate::scheduler::next_tick;
+
+/// mako has no change signal, so it is polled
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The mode mako configurations conventionally hide notifications in
+const DND_MODE: &str = "do-not-disturb";
+
+/// mako's own D-Bus interface, next to the standard notifications one
+#[zbus::proxy(
+    interface = "fr.emersion.Mako",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/fr/emersion/Mako"
+)]
+trait Mako {
+    fn list_notifications(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    fn dismiss_all_notifications(&self) -> zbus::Result<()>;
+
+    /// Bring back the most recently dismissed notification
+    fn restore_notification(&self) -> zbus::Result<()>;
+
+    fn list_modes(&self) -> zbus::Result<Vec<String>>;
+
+    fn set_modes(&self, modes: &[&str]) -> zbus::Result<()>;
+}
+
+/// mako, polled over D-Bus. Do-not-disturb is the `do-not-disturb` mode and the
+/// panel toggle restores the last dismissed notification.
+#[derive(Default)]
+pub struct MakoBackend {
+    proxy: Rc<RefCell<Option<MakoProxy<'static>>>>,
+    /// Wakes the poll after an action, so it shows without waiting a full interval
+    changed: Rc<Notify>,
+}
+
+impl MakoBackend {
+    async fn poll(
+        slot: &RefCell<Option<MakoProxy<'static>>>,
+        wake: &Notify,
+        changed: &UnboundedSender<NotificationStatus>,
+    ) -> zbus::Result<()> {
+        let connection = zbus::Connection::session().await?;
+        let proxy = MakoProxy::new(&connection).await?;
+        *slot.borrow_mut() = Some(proxy.clone());
+
+        let mut last = None;
+        loop {
+            let status = NotificationStatus {
+                count: proxy.list_notifications().await?.len() as u32,
+                dnd: proxy.list_modes().await?.iter().any(|mode| mode == DND_MODE),
+            };
+            if last != Some((status.count, status.dnd)) {
+                last = Some((status.count, status.dnd));
+                if changed.send(status).is_err() {
+                    return Ok(());
+                }
+            }
+
+            tokio::select! {
+                _ = next_tick(POLL_INTERVAL) => {}
+                _ = wake.notified() => {}
+            }
+        }
+    }
+
+    /// Run `call` on the connected proxy, then update the status right away
+    fn call<F, Fut>(&self, action: &'static str, call: F)
+    where
+        F: FnOnce(MakoProxy<'static>) -> Fut + 'static,
+        Fut: Future<Output = zbus::Result<()>>,
+    {
+        let Some(proxy) = self.proxy.borrow().clone() else {
+            return;
+        };
+        let changed = self.changed.clone();
+        glib::spawn_future_local(async move {
+            if let Err(e) = call(proxy).await {
+                warn!("Failed to {}: {}", action, e);
+            }
+            changed.notify_one();
+        });
+    }
+}
+
+impl NotificationBackend for MakoBackend {
+    fn watch(&self, changed: UnboundedSender<NotificationStatus>) {
+        let slot = self.proxy.clone();
+        let wake = self.changed.clone();
+        glib::spawn_future_local(async move {
+            if let Err(e) = Self::poll(&slot, &wake, &changed).await {
+                warn!("Lost connection to mako: {}", e);
+            }
+            slot.borrow_mut().take();
+        });
+    }
+
+    fn toggle_panel(&self) {
+        self.call("restore a notification", |proxy| async move {
+            proxy.restore_notification().await
+        });
+    }
+
+    fn toggle_dnd(&self) {
+        self.call("toggle do-not-disturb", |proxy| async move {
+            let mut modes = proxy.list_modes().await?;
+            if modes.iter().any(|mode| mode == DND_MODE) {
+                modes.retain(|mode| mode != DND_MODE);
+            } else {
+                modes.push(DND_MODE.to_string());
+            }
+            let modes: Vec<&str> = modes.iter().map(String::as_str).collect();
+            proxy.set_modes(&modes).await
+        });
+    }
+
+    fn dismiss_all(&self) {
+        self.call("dismiss notifications", |proxy| async move {
+            proxy.dismiss_all_notifications().await
+        });
+    }
+}
@@ -0,0 +1,117 @@
+use futures_util::StreamExt;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::warn;
+
+use super::NotificationBackend;
+use crate::notification_widget::NotificationStatus;
+
+/// D-Bus interface of the swaync control center
+#[zbus::proxy(
+    interface = "org.erikreider.swaync.cc",
+    default_service = "org.erikreider.swaync.cc",
+    default_path = "/org/erikreider/swaync/cc"
+)]
+trait SwayncControlCenter {
+    fn notification_count(&self) -> zbus::Result<u32>;
+
+    fn get_dnd(&self) -> zbus::Result<bool>;
+
+    /// Flip do-not-disturb, returning the new state
+    fn toggle_dnd(&self) -> zbus::Result<bool>;
+
+    fn toggle_visibility(&self) -> zbus::Result<()>;
+
+    fn close_all_notifications(&self) -> zbus::Result<()>;
+
+    /// Emitted whenever the count, DND state or panel visibility changes
+    #[zbus(signal)]
+    fn subscribe(&self, count: u32, dnd: bool, cc_open: bool) -> zbus::Result<()>;
+}
+
+/// swaync, followed through its `Subscribe` signal
+#[derive(Default)]
+pub struct SwayncBackend {
+    proxy: Rc<RefCell<Option<SwayncControlCenterProxy<'static>>>>,
+}
+
+impl SwayncBackend {
+    /// Connect to swaync and send the status on every `Subscribe` signal
+    async fn subscribe(
+        slot: &RefCell<Option<SwayncControlCenterProxy<'static>>>,
+        changed: &UnboundedSender<NotificationStatus>,
+    ) -> zbus::Result<()> {
+        let connection = zbus::Connection::session().await?;
+        let proxy = SwayncControlCenterProxy::new(&connection).await?;
+        *slot.borrow_mut() = Some(proxy.clone());
+
+        // Subscribe before reading the initial state so no change is missed in between
+        let mut signals = proxy.receive_subscribe().await?;
+
+        let _ = changed.send(NotificationStatus {
+            count: proxy.notification_count().await?,
+            dnd: proxy.get_dnd().await?,
+        });
+
+        while let Some(signal) = signals.next().await {
+            let args = signal.args()?;
+            let status = NotificationStatus {
+                count: args.count,
+                dnd: args.dnd,
+            };
+            if changed.send(status).is_err() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl NotificationBackend for SwayncBackend {
+    fn watch(&self, changed: UnboundedSender<NotificationStatus>) {
+        let slot = self.proxy.clone();
+        glib::spawn_future_local(async move {
+            if let Err(e) = Self::subscribe(&slot, &changed).await {
+                warn!("swaync D-Bus subscription failed: {}", e);
+            }
+            // The signal stream only ends when swaync goes away
+            slot.borrow_mut().take();
+        });
+    }
+
+    fn toggle_panel(&self) {
+        let Some(proxy) = self.proxy.borrow().clone() else {
+            return;
+        };
+        glib::spawn_future_local(async move {
+            if let Err(e) = proxy.toggle_visibility().await {
+                warn!("Failed to toggle notification panel: {}", e);
+            }
+        });
+    }
+
+    fn toggle_dnd(&self) {
+        let Some(proxy) = self.proxy.borrow().clone() else {
+            return;
+        };
+        // The Subscribe signal that follows updates the icon
+        glib::spawn_future_local(async move {
+            if let Err(e) = proxy.toggle_dnd().await {
+                warn!("Failed to toggle do-not-disturb: {}", e);
+            }
+        });
+    }
+
+    fn dismiss_all(&self) {
+        let Some(proxy) = self.proxy.borrow().clone() else {
+            return;
+        };
+        glib::spawn_future_local(async move {
+            if let Err(e) = proxy.close_all_notifications().await {
+                warn!("Failed to dismiss notifications: {}", e);
+            }
+        });
+    }
+}
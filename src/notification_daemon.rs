@@ -0,0 +1,286 @@
+// Built-in org.freedesktop.Notifications server, enabled with `daemon = true` in
+// `[notifications]`. Notifications are shown as toasts; clicking an action or closing
+// a toast is reported back to the application with `ActionInvoked` and
+// `NotificationClosed`.
+//
+// Each urgency level can keep its toasts up until dismissed, tint the bar and play a
+// sound, by default critical notifications stay up and tint the bar.
+//
+// Like the control interface, method calls arrive on zbus' executor and are handed to
+// the GTK main loop over a channel.
+
+use gtk4::prelude::*;
+use gtk4::{Application, MediaFile};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use zbus::fdo::{RequestNameFlags, RequestNameReply};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::OwnedValue;
+use tracing::{info, warn};
+
+use crate::config::{MuteAction, NotificationsConfig, UrgencyConfig, UrgencyLevel};
+use crate::notification_rules;
+use crate::toasts::{CloseReason, Notification, NotificationImage, ToastEvent, Toasts, Urgency};
+
+const BUS_NAME: &str = "org.freedesktop.Notifications";
+const OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+
+thread_local! {
+    /// Sound being played, dropping it would stop playback
+    static SOUND: RefCell<Option<MediaFile>> = const { RefCell::new(None) };
+}
+
+/// Request received over D-Bus, handled on the main thread
+#[derive(Debug)]
+enum DaemonCommand {
+    Show(Notification),
+    Close(u32),
+}
+
+struct NotificationServer {
+    commands: UnboundedSender<DaemonCommand>,
+    next_id: u32,
+    default_timeout: Duration,
+}
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl NotificationServer {
+    fn get_capabilities(&self) -> Vec<&str> {
+        vec!["actions", "body", "body-markup", "icon-static"]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &mut self,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> u32 {
+        let id = if replaces_id != 0 {
+            replaces_id
+        } else {
+            self.next_id += 1;
+            self.next_id
+        };
+
+        let urgency = hints
+            .get("urgency")
+            .and_then(|value| u8::try_from(value).ok())
+            .map(Urgency::from_hint)
+            .unwrap_or_default();
+
+        // -1 leaves it to the server, 0 means never
+        let timeout = match expire_timeout {
+            0 => None,
+            timeout if timeout < 0 => Some(self.default_timeout),
+            timeout => Some(Duration::from_millis(timeout as u64)),
+        };
+
+        let image = image_hint(&hints);
+
+        // Actions come as a flat list of key, label pairs
+        let actions = actions
+            .chunks_exact(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect();
+
+        let _ = self.commands.send(DaemonCommand::Show(Notification {
+            id,
+            app_name,
+            app_icon,
+            summary,
+            body,
+            image,
+            actions,
+            urgency,
+            timeout,
+        }));
+        id
+    }
+
+    fn close_notification(&self, id: u32) {
+        let _ = self.commands.send(DaemonCommand::Close(id));
+    }
+
+    fn get_server_information(&self) -> (&str, &str, &str, &str) {
+        ("BladeBar", "swordi", env!("CARGO_PKG_VERSION"), "1.2")
+    }
+
+    #[zbus(signal)]
+    async fn notification_closed(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        reason: u32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn action_invoked(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        action_key: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Take over notifications if enabled and no other daemon owns the name
+pub fn start(app: &Application, config: &NotificationsConfig) {
+    if !config.daemon {
+        return;
+    }
+
+    let (commands_tx, mut commands_rx) = unbounded_channel::<DaemonCommand>();
+    let (events_tx, mut events_rx) = unbounded_channel::<ToastEvent>();
+    let toasts = Toasts::new(app, events_tx.clone());
+    let urgency = config.urgency.clone();
+
+    glib::spawn_future_local(async move {
+        while let Some(command) = commands_rx.recv().await {
+            match command {
+                DaemonCommand::Show(mut notification) => {
+                    match notification_rules::action_for(&notification.app_name) {
+                        Some(action) => {
+                            if action == MuteAction::Count {
+                                notification_rules::count_muted();
+                            }
+                            let _ = events_tx
+                                .send(ToastEvent::Closed(notification.id, CloseReason::Dismissed));
+                        }
+                        None => {
+                            let level = urgency_level(&urgency, notification.urgency);
+                            if level.sticky {
+                                notification.timeout = None;
+                            }
+                            if let Some(sound) = &level.sound {
+                                play_sound(sound);
+                            }
+                            toasts.show(notification, level.tint_bar);
+                        }
+                    }
+                }
+                DaemonCommand::Close(id) => toasts.close(id, CloseReason::Closed),
+            }
+        }
+    });
+
+    let server = NotificationServer {
+        commands: commands_tx,
+        next_id: 0,
+        default_timeout: Duration::from_millis(config.timeout),
+    };
+
+    glib::spawn_future_local(async move {
+        let connection = match zbus::connection::Builder::session()
+            .and_then(|builder| builder.serve_at(OBJECT_PATH, server))
+        {
+            Ok(builder) => builder.build().await,
+            Err(e) => Err(e),
+        };
+        let connection = match connection {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("Failed to start the notification daemon: {}", e);
+                return;
+            }
+        };
+
+        // Never take the name from a running daemon
+        match connection
+            .request_name_with_flags(BUS_NAME, RequestNameFlags::DoNotQueue.into())
+            .await
+        {
+            Ok(RequestNameReply::PrimaryOwner) => {}
+            Ok(_) => {
+                info!(
+                    "Not starting the notification daemon, {} is owned by another one",
+                    BUS_NAME
+                );
+                return;
+            }
+            Err(e) => {
+                warn!("Failed to request {}: {}", BUS_NAME, e);
+                return;
+            }
+        }
+
+        let interface = match connection
+            .object_server()
+            .interface::<_, NotificationServer>(OBJECT_PATH)
+            .await
+        {
+            Ok(interface) => interface,
+            Err(e) => {
+                warn!("Failed to look up the notification interface: {}", e);
+                return;
+            }
+        };
+
+        while let Some(event) = events_rx.recv().await {
+            let emitter = interface.signal_emitter();
+            let result = match event {
+                ToastEvent::Closed(id, reason) => {
+                    NotificationServer::notification_closed(emitter, id, reason as u32).await
+                }
+                ToastEvent::ActionInvoked(id, action) => {
+                    NotificationServer::action_invoked(emitter, id, &action).await
+                }
+            };
+            if let Err(e) = result {
+                warn!("Failed to emit notification signal: {}", e);
+            }
+        }
+    });
+}
+
+/// Image from the hints, raw data winning over a path. Older clients use the names
+/// from earlier versions of the specification.
+fn image_hint(hints: &HashMap<String, OwnedValue>) -> Option<NotificationImage> {
+    let data = ["image-data", "image_data", "icon_data"]
+        .iter()
+        .find_map(|key| hints.get(*key))
+        .and_then(|value| value.try_clone().ok())
+        .and_then(|value| <(i32, i32, i32, bool, i32, i32, Vec<u8>)>::try_from(value).ok());
+    if let Some((width, height, rowstride, has_alpha, bits_per_sample, _channels, pixels)) = data {
+        if bits_per_sample == 8 {
+            return Some(NotificationImage::Data {
+                width,
+                height,
+                rowstride,
+                has_alpha,
+                pixels,
+            });
+        }
+    }
+
+    ["image-path", "image_path"]
+        .iter()
+        .find_map(|key| hints.get(*key))
+        .and_then(|value| <&str>::try_from(value).ok())
+        .filter(|path| !path.is_empty())
+        .map(|path| NotificationImage::Path(path.to_string()))
+}
+
+fn urgency_level(config: &UrgencyConfig, urgency: Urgency) -> &UrgencyLevel {
+    match urgency {
+        Urgency::Low => &config.low,
+        Urgency::Normal => &config.normal,
+        Urgency::Critical => &config.critical,
+    }
+}
+
+/// Play a sound file through GStreamer, replacing the one still playing
+fn play_sound(path: &str) {
+    let media = MediaFile::for_filename(path);
+    media.connect_error_notify(|media| {
+        if let Some(e) = media.error() {
+            warn!("Failed to play notification sound: {}", e);
+        }
+    });
+    media.play();
+    SOUND.with(|sound| *sound.borrow_mut() = Some(media));
+}
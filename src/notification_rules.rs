@@ -0,0 +1,309 @@
+// Per-application mute rules for notifications. The built-in daemon applies them
+// directly. Other daemons have no per-application API, so a monitoring connection
+// watches `Notify` calls on the session bus, pairs them with their replies to learn
+// the notification id and closes matching notifications through the standard
+// `CloseNotification` method. The daemon has usually shown the popup by then, so a
+// muted notification flashes briefly before it is closed. swaync additionally gets an
+// inhibitor while muted notifications are on their way, which keeps the popups of
+// those arriving in the meantime, e.g. the rest of a burst, from showing at all.
+
+use futures_util::StreamExt;
+use regex::Regex;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::time::Duration;
+use zbus::message::Type as MessageType;
+use zbus::zvariant::OwnedValue;
+use tracing::{debug, warn};
+
+use crate::config::{MuteAction, MuteRule, NotificationsConfig};
+
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+
+/// swaync's control center, which takes inhibitors that hold back popups
+const SWAYNC_NAME: &str = "org.erikreider.swaync.cc";
+const SWAYNC_PATH: &str = "/org/erikreider/swaync/cc";
+/// Id our inhibitor is registered under
+const INHIBITOR_ID: &str = "blade_bar";
+/// Longest a muted notification keeps the inhibitor, in case its reply is never seen
+const INHIBIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Arguments of `org.freedesktop.Notifications.Notify`
+type NotifyArgs = (
+    String,
+    u32,
+    String,
+    String,
+    String,
+    Vec<String>,
+    HashMap<String, OwnedValue>,
+    i32,
+);
+
+struct CompiledRule {
+    app_name: Regex,
+    action: MuteAction,
+}
+
+thread_local! {
+    static RULES: RefCell<Vec<CompiledRule>> = const { RefCell::new(Vec::new()) };
+    static MUTED: Cell<u32> = const { Cell::new(0) };
+    static STARTED: Cell<bool> = const { Cell::new(false) };
+    /// Why the bus monitor stopped, shown in the notification module's tooltip
+    static MONITOR_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Notifications closed by a `count` rule since the count was last reset
+pub fn muted_count() -> u32 {
+    MUTED.with(|muted| muted.get())
+}
+
+pub fn count_muted() {
+    MUTED.with(|muted| muted.set(muted.get() + 1));
+}
+
+/// Why mute rules are not applied to the running daemon, if they are not
+pub fn monitor_error() -> Option<String> {
+    MONITOR_ERROR.with(|error| error.borrow().clone())
+}
+
+/// Forget the muted notifications, e.g. once the user looked at the notification panel
+pub fn reset_muted_count() {
+    MUTED.with(|muted| muted.set(0));
+}
+
+/// Apply the configured rules, starting the bus monitor the first time any are set.
+/// The built-in daemon checks the rules itself and needs no monitor.
+pub fn configure(config: &NotificationsConfig) {
+    let rules: Vec<CompiledRule> = config.mute.iter().filter_map(compile).collect();
+    let has_rules = !rules.is_empty();
+    RULES.with(|current| *current.borrow_mut() = rules);
+
+    if has_rules && !config.daemon && !STARTED.with(|started| started.replace(true)) {
+        MONITOR_ERROR.with(|error| *error.borrow_mut() = None);
+        glib::spawn_future_local(async {
+            if let Err(e) = monitor().await {
+                warn!("Notification mute rules disabled: {}", e);
+                MONITOR_ERROR.with(|error| *error.borrow_mut() = Some(e.to_string()));
+            }
+            STARTED.with(|started| started.set(false));
+        });
+    }
+}
+
+fn compile(rule: &MuteRule) -> Option<CompiledRule> {
+    match Regex::new(&rule.app_name) {
+        Ok(app_name) => Some(CompiledRule {
+            app_name,
+            action: rule.action,
+        }),
+        Err(e) => {
+            warn!(
+                "Invalid notification mute pattern '{}': {}",
+                rule.app_name, e
+            );
+            None
+        }
+    }
+}
+
+/// What to do with a notification from `app_name`, `None` if it is not muted
+pub fn action_for(app_name: &str) -> Option<MuteAction> {
+    RULES.with(|rules| {
+        rules
+            .borrow()
+            .iter()
+            .find(|rule| rule.app_name.is_match(app_name))
+            .map(|rule| rule.action)
+    })
+}
+
+/// Watch notifications being sent and close those matching a rule
+async fn monitor() -> zbus::Result<()> {
+    // A monitoring connection can not send anything, closing goes through a second one
+    let monitor = zbus::Connection::session().await?;
+    let sender = zbus::Connection::session().await?;
+    let inhibitor = Inhibitor::new(&sender).await?;
+
+    monitor
+        .call_method(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            Some("org.freedesktop.DBus.Monitoring"),
+            "BecomeMonitor",
+            &(
+                vec![
+                    "type='method_call',interface='org.freedesktop.Notifications',member='Notify'",
+                    "type='method_return'",
+                    "type='error'",
+                ],
+                0u32,
+            ),
+        )
+        .await?;
+
+    // Muted Notify calls waiting for their reply, by caller and serial
+    let mut pending: HashMap<(String, u32), MuteAction> = HashMap::new();
+
+    let mut messages = zbus::MessageStream::from(&monitor);
+    while let Some(message) = messages.next().await {
+        let Ok(message) = message else {
+            continue;
+        };
+        let header = message.header();
+        let caller = header.sender().map(|sender| sender.to_string());
+
+        match header.message_type() {
+            MessageType::MethodCall => {
+                if header.member().map(|member| member.as_str()) != Some("Notify") {
+                    continue;
+                }
+                let Ok(args) = message.body().deserialize::<NotifyArgs>() else {
+                    continue;
+                };
+                if let (Some(caller), Some(action)) = (caller, action_for(&args.0)) {
+                    let serial = header.primary().serial_num().get();
+                    inhibitor.hold((caller.clone(), serial));
+                    pending.insert((caller, serial), action);
+                }
+            }
+            MessageType::MethodReturn => {
+                let (Some(destination), Some(serial)) =
+                    (header.destination(), header.reply_serial())
+                else {
+                    continue;
+                };
+                let key = (destination.to_string(), serial.get());
+                let Some(action) = pending.remove(&key) else {
+                    continue;
+                };
+                let Ok(id) = message.body().deserialize::<u32>() else {
+                    continue;
+                };
+
+                if action == MuteAction::Count {
+                    count_muted();
+                }
+
+                let sender = sender.clone();
+                let inhibitor = inhibitor.clone();
+                glib::spawn_future_local(async move {
+                    if let Err(e) = close(&sender, id).await {
+                        warn!("Failed to close muted notification {}: {}", id, e);
+                    }
+                    inhibitor.release(&key);
+                });
+            }
+            // The daemon refused a muted notification, nothing to close
+            MessageType::Error => {
+                let (Some(destination), Some(serial)) =
+                    (header.destination(), header.reply_serial())
+                else {
+                    continue;
+                };
+                let key = (destination.to_string(), serial.get());
+                if pending.remove(&key).is_some() {
+                    inhibitor.release(&key);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    inhibitor.release_all();
+    Ok(())
+}
+
+async fn close(connection: &zbus::Connection, id: u32) -> zbus::Result<()> {
+    connection
+        .call_method(
+            Some(NOTIFICATIONS_NAME),
+            NOTIFICATIONS_PATH,
+            Some(NOTIFICATIONS_NAME),
+            "CloseNotification",
+            &id,
+        )
+        .await?;
+    Ok(())
+}
+
+/// swaync's inhibitor, held as long as any muted notification is on its way
+struct Inhibitor {
+    connection: zbus::Connection,
+    /// Whether swaync is the running daemon
+    available: bool,
+    /// Muted `Notify` calls not closed yet, by caller and serial
+    held: RefCell<HashSet<(String, u32)>>,
+}
+
+impl Inhibitor {
+    async fn new(connection: &zbus::Connection) -> zbus::Result<Rc<Self>> {
+        let available = zbus::fdo::DBusProxy::new(connection)
+            .await?
+            .name_has_owner(SWAYNC_NAME.try_into()?)
+            .await?;
+        Ok(Rc::new(Inhibitor {
+            connection: connection.clone(),
+            available,
+            held: RefCell::new(HashSet::new()),
+        }))
+    }
+
+    fn hold(self: &Rc<Self>, key: (String, u32)) {
+        if !self.available {
+            return;
+        }
+        let first = {
+            let mut held = self.held.borrow_mut();
+            held.insert(key.clone());
+            held.len() == 1
+        };
+        if first {
+            self.call("AddInhibitor");
+        }
+
+        let inhibitor = self.clone();
+        glib::spawn_future_local(async move {
+            glib::timeout_future(INHIBIT_TIMEOUT).await;
+            inhibitor.release(&key);
+        });
+    }
+
+    fn release(self: &Rc<Self>, key: &(String, u32)) {
+        let last = {
+            let mut held = self.held.borrow_mut();
+            held.remove(key) && held.is_empty()
+        };
+        if last {
+            self.call("RemoveInhibitor");
+        }
+    }
+
+    fn release_all(self: &Rc<Self>) {
+        let held = std::mem::take(&mut *self.held.borrow_mut());
+        if !held.is_empty() {
+            self.call("RemoveInhibitor");
+        }
+    }
+
+    fn call(self: &Rc<Self>, method: &'static str) {
+        let inhibitor = self.clone();
+        glib::spawn_future_local(async move {
+            let result = inhibitor
+                .connection
+                .call_method(
+                    Some(SWAYNC_NAME),
+                    SWAYNC_PATH,
+                    Some(SWAYNC_NAME),
+                    method,
+                    &(INHIBITOR_ID,),
+                )
+                .await;
+            if let Err(e) = result {
+                debug!("swaync {} failed: {}", method, e);
+            }
+        });
+    }
+}
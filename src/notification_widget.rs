@@ -1,126 +1,152 @@
-use glib::ControlFlow;
-use glib::timeout_add_local;
 use gtk4::prelude::*;
-use gtk4::{Button, Label};
-use std::process::Command;
-use std::time::Duration;
-
+use gtk4::{Box as GtkBox, Button, Image, Label, Orientation, Popover};
+use std::rc::Rc;
+use tokio::sync::mpsc::unbounded_channel;
+use tracing::info;
+
+use crate::bar;
+use crate::config::{NotificationDaemon, NotificationsConfig};
+use crate::module::{BarModule, InitFuture};
+use crate::notification_backend::NotificationBackend;
+use crate::notification_rules;
+
+/// Notification count and do-not-disturb state of the running daemon (swaync,
+/// mako or fnott), with its panel on click
 pub struct NotificationWidget {
     pub button: Button,
-    label: Label,
+    indicator: NotificationIndicator,
+    /// Do-not-disturb entry of the right-click menu, labelled after the current state
+    dnd_button: Button,
+    backend: Rc<dyn NotificationBackend>,
 }
 
 impl NotificationWidget {
-    pub fn new() -> Option<Self> {
-        // Check if swaync-client is available
-        if !Self::is_swaync_available() {
-            return None;
-        }
+    pub fn new(config: &NotificationsConfig) -> Option<Self> {
+        let daemon = config.backend.or_else(NotificationDaemon::detect)?;
+        info!("Notification widget controls {:?}", daemon);
 
         let button = Button::new();
-        button.add_css_class("notification-button");
+        let indicator = NotificationIndicator::new(&button, config);
 
-        let label = Label::new(None);
-        label.add_css_class("notification-label");
-        button.set_child(Some(&label));
+        let dnd_button = Button::with_label("Do not disturb");
+        dnd_button.add_css_class("flat");
 
-        let widget = NotificationWidget { button, label };
+        let widget = NotificationWidget {
+            button,
+            indicator,
+            dnd_button,
+            backend: daemon.backend(),
+        };
 
         widget.setup_click_handlers();
-        widget.start_monitoring();
 
         Some(widget)
     }
 
-    fn is_swaync_available() -> bool {
-        Command::new("which")
-            .arg("swaync-client")
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false)
-    }
-
     fn setup_click_handlers(&self) {
         let button = self.button.clone();
 
         // Left click: toggle notification panel
-        button.connect_clicked(|_| {
-            let _ = Command::new("swaync-client").args(["-t", "-sw"]).spawn();
+        let backend = self.backend.clone();
+        button.connect_clicked(move |_| {
+            notification_rules::reset_muted_count();
+            backend.toggle_panel();
+        });
+
+        // Right click: menu to toggle do-not-disturb or dismiss all notifications
+        let menu = GtkBox::new(Orientation::Vertical, 2);
+        menu.add_css_class("notification-menu");
+        let dismiss_button = Button::with_label("Dismiss all");
+        dismiss_button.add_css_class("flat");
+        menu.append(&self.dnd_button);
+        menu.append(&dismiss_button);
+
+        let popover = Popover::new();
+        popover.set_child(Some(&menu));
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let backend = self.backend.clone();
+        let popover_dnd = popover.clone();
+        self.dnd_button.connect_clicked(move |_| {
+            popover_dnd.popdown();
+            backend.toggle_dnd();
+        });
+
+        let backend = self.backend.clone();
+        let popover_dismiss = popover.clone();
+        dismiss_button.connect_clicked(move |_| {
+            popover_dismiss.popdown();
+            notification_rules::reset_muted_count();
+            backend.dismiss_all();
         });
 
-        // Right click: dismiss all notifications
         let gesture = gtk4::GestureClick::new();
         gesture.set_button(3); // Right mouse button
-
-        gesture.connect_pressed(|_, _, _, _| {
-            let _ = Command::new("swaync-client").args(["-d", "-sw"]).spawn();
+        gesture.connect_pressed(move |_, _, _, _| {
+            popover.popup();
         });
 
         button.add_controller(gesture);
     }
 
-    fn start_monitoring(&self) {
-        let label = self.label.clone();
-
-        // Update every 2 seconds with a timeout to prevent hanging
-        timeout_add_local(Duration::from_secs(2), move || {
-            // Use a simple approach: try to get status with a short timeout
-            if let Some(status) = Self::get_notification_status() {
-                Self::update_display(&label, &status);
-            } else {
-                // If swaync is not responding, show a default state
-                label.set_text("🔔");
-                if let Some(parent) = label.parent() {
-                    parent.set_tooltip_text(Some("Notifications unavailable"));
-                }
-            }
-            ControlFlow::Continue
-        });
-
-        // Initial update
-        if let Some(status) = Self::get_notification_status() {
-            Self::update_display(&self.label, &status);
+    fn show_status(&self, status: &NotificationStatus) {
+        self.indicator.show(status);
+        self.dnd_button.set_label(if status.dnd {
+            "Turn off do not disturb"
         } else {
-            self.label.set_text("🔔");
-            if let Some(parent) = self.label.parent() {
-                parent.set_tooltip_text(Some("Notifications unavailable"));
-            }
-        }
+            "Turn on do not disturb"
+        });
     }
+}
 
-    fn get_notification_status() -> Option<NotificationStatus> {
-        // Get notification count
-        let count_output = Command::new("swaync-client").arg("--count").output().ok()?;
-
-        if !count_output.status.success() {
-            return None;
-        }
-
-        let count_str = String::from_utf8_lossy(&count_output.stdout);
-        let count = count_str.trim().parse::<u32>().unwrap_or(0);
+/// Bell icon with a count badge, the button gets `empty`, `unread` and `dnd`
+/// classes for the stylesheet
+pub struct NotificationIndicator {
+    button: Button,
+    icon: Image,
+    count: Label,
+    config: NotificationsConfig,
+}
 
-        // Get DND status
-        let dnd_output = Command::new("swaync-client")
-            .arg("--get-dnd")
-            .output()
-            .ok()?;
+impl NotificationIndicator {
+    pub fn new(button: &Button, config: &NotificationsConfig) -> Self {
+        button.add_css_class("notification-button");
 
-        if !dnd_output.status.success() {
-            return None;
+        let content = GtkBox::new(Orientation::Horizontal, 2);
+        let icon = Image::from_icon_name(&config.icon);
+        icon.add_css_class("notification-icon");
+        let count = Label::new(None);
+        count.add_css_class("notification-count");
+        count.set_visible(false);
+        content.append(&icon);
+        content.append(&count);
+        button.set_child(Some(&content));
+
+        NotificationIndicator {
+            button: button.clone(),
+            icon,
+            count,
+            config: config.clone(),
         }
-
-        let dnd_str = String::from_utf8_lossy(&dnd_output.stdout);
-        let dnd = dnd_str.trim().to_lowercase() == "true";
-
-        Some(NotificationStatus { count, dnd })
     }
 
-    fn update_display(label: &Label, status: &NotificationStatus) {
-        let icon = Self::get_icon_for_status(status);
-        label.set_markup(&icon);
+    pub fn show(&self, status: &NotificationStatus) {
+        let (icon, class) = if status.dnd {
+            (&self.config.dnd_icon, "dnd")
+        } else if status.count > 0 {
+            (&self.config.unread_icon, "unread")
+        } else {
+            (&self.config.icon, "empty")
+        };
+        self.icon.set_icon_name(Some(icon));
+        self.set_state_class(Some(class));
+
+        self.count.set_text(&status.count.to_string());
+        self.count.set_visible(self.config.show_count && status.count > 0);
 
         // Set tooltip
-        let tooltip = if status.count > 0 {
+        let mut tooltip = if status.count > 0 {
             format!(
                 "{} notification{}",
                 status.count,
@@ -130,39 +156,75 @@ impl NotificationWidget {
             "No notifications".to_string()
         };
 
-        if let Some(parent) = label.parent() {
-            parent.set_tooltip_text(Some(&tooltip));
+        // Notifications closed by a mute rule
+        let muted = notification_rules::muted_count();
+        if muted > 0 {
+            tooltip.push_str(&format!("\n{} muted", muted));
         }
+        if status.dnd {
+            tooltip.push_str("\nDo not disturb");
+        }
+        if let Some(error) = notification_rules::monitor_error() {
+            tooltip.push_str(&format!("\nMute rules not applied: {}", error));
+        }
+        self.button.set_tooltip_text(Some(&tooltip));
     }
 
-    fn get_icon_for_status(status: &NotificationStatus) -> String {
-        // Show notification indicator if there are notifications
-        if status.count > 0 {
-            if status.dnd {
-                // DND with notifications
-                "<span foreground='red'><sup>●</sup></span>".to_string()
-            } else {
-                // Normal notifications
-                "<span foreground='red'><sup>●</sup></span>".to_string()
-            }
-        } else {
-            if status.dnd {
-                // DND without notifications
-                "".to_string()
+    fn show_unavailable(&self) {
+        // If the daemon is not responding, show a default state
+        self.icon.set_icon_name(Some(&self.config.icon));
+        self.count.set_visible(false);
+        self.set_state_class(None);
+        self.button.set_tooltip_text(Some("Notifications unavailable"));
+    }
+
+    fn set_state_class(&self, class: Option<&str>) {
+        for state in ["empty", "unread", "dnd"] {
+            if Some(state) == class {
+                self.button.add_css_class(state);
             } else {
-                // No notifications
-                "".to_string()
+                self.button.remove_css_class(state);
             }
         }
     }
+}
+
+impl BarModule for NotificationWidget {
+    fn name(&self) -> &'static str {
+        "notifications"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            self.indicator.show_unavailable();
+
+            let (changed_tx, mut changed_rx) = unbounded_channel::<NotificationStatus>();
+            self.backend.watch(changed_tx);
+
+            let mut count = None;
+            while let Some(status) = changed_rx.recv().await {
+                self.show_status(&status);
+
+                // Reveal an auto-hidden bar for new notifications
+                if count.is_some_and(|count| status.count > count) && !status.dnd {
+                    bar::request_attention();
+                }
+                count = Some(status.count);
+            }
 
-    pub fn widget(&self) -> &Button {
-        &self.button
+            // The backend drops the sender when the daemon goes away
+            self.indicator.show_unavailable();
+        })
     }
 }
 
+/// What the daemon reports, sent by its [`NotificationBackend`]
 #[derive(Debug)]
-struct NotificationStatus {
-    count: u32,
-    dnd: bool,
+pub struct NotificationStatus {
+    pub count: u32,
+    pub dnd: bool,
 }
@@ -0,0 +1,112 @@
+// Overflow for bars longer than their output: when the modules do not fit at their
+// natural size, the lowest priority ones (`[layout] priority`) move into a popover
+// behind a chevron at the end of the bar instead of the sides being clipped.
+
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, MenuButton, Orientation, Popover, Widget};
+use std::cell::RefCell;
+use std::cmp::Reverse;
+
+use crate::layout::ZONE_SPACING;
+use crate::layout_editor::Placement;
+
+/// A module as considered for collapsing
+pub struct Candidate {
+    pub placement: Placement,
+    pub priority: i32,
+    pub widget: Widget,
+}
+
+pub struct Overflow {
+    pub button: MenuButton,
+    content: GtkBox,
+    /// Modules currently in the popover
+    collapsed: RefCell<Vec<Placement>>,
+}
+
+impl Overflow {
+    pub fn new() -> Self {
+        let content = GtkBox::new(Orientation::Vertical, ZONE_SPACING);
+        content.add_css_class("bar-overflow-content");
+
+        let popover = Popover::new();
+        popover.set_child(Some(&content));
+
+        let button = MenuButton::new();
+        button.add_css_class("bar-overflow");
+        button.set_icon_name("view-more-symbolic");
+        button.set_tooltip_text(Some("More modules"));
+        button.set_popover(Some(&popover));
+
+        Overflow {
+            button,
+            content,
+            collapsed: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn content(&self) -> &GtkBox {
+        &self.content
+    }
+
+    pub fn is_collapsed(&self, placement: Placement) -> bool {
+        self.collapsed.borrow().contains(&placement)
+    }
+
+    pub fn has_collapsed(&self) -> bool {
+        !self.collapsed.borrow().is_empty()
+    }
+
+    /// Forget the collapsed modules, e.g. when the placements change. Returns whether
+    /// any were collapsed.
+    pub fn clear(&self) -> bool {
+        !std::mem::take(&mut *self.collapsed.borrow_mut()).is_empty()
+    }
+
+    /// Work out which modules to collapse for the bar `root` to fit into `available`
+    /// pixels. Returns whether that changed, the bar then needs to be rearranged.
+    pub fn update(
+        &self,
+        root: &Widget,
+        orientation: Orientation,
+        available: i32,
+        modules: &[Candidate],
+    ) -> bool {
+        let natural = |widget: &Widget| widget.measure(orientation, -1).1 + ZONE_SPACING;
+        let chevron = natural(self.button.upcast_ref());
+
+        // Padding and the like, everything in the bar that is not a module
+        let placed: i32 = modules
+            .iter()
+            .filter(|module| !self.is_collapsed(module.placement))
+            .map(|module| natural(&module.widget))
+            .sum::<i32>()
+            + if self.has_collapsed() { chevron } else { 0 };
+        let overhead = natural(root) - ZONE_SPACING - placed;
+
+        // Lowest priority first, among equals from the end of the bar
+        let mut candidates: Vec<(usize, &Candidate)> = modules.iter().enumerate().collect();
+        candidates.sort_by_key(|(index, module)| (module.priority, Reverse(*index)));
+
+        let mut total = overhead + modules.iter().map(|module| natural(&module.widget)).sum::<i32>();
+        let mut wanted = Vec::new();
+        for (_, module) in candidates {
+            if total <= available {
+                break;
+            }
+            if wanted.is_empty() {
+                total += chevron;
+            }
+            total -= natural(&module.widget);
+            wanted.push(module.placement);
+        }
+
+        let mut collapsed = self.collapsed.borrow_mut();
+        let changed = wanted.len() != collapsed.len()
+            || wanted.iter().any(|placement| !collapsed.contains(placement));
+        if changed {
+            *collapsed = wanted;
+        }
+        changed
+    }
+}
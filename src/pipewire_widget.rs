@@ -0,0 +1,266 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, Orientation, Popover, ToggleButton};
+use std::cell::{Cell, RefCell};
+use std::process::Command;
+use std::rc::Rc;
+use tracing::warn;
+
+use crate::command::{is_available, run_shell, watch_lines};
+use crate::config::PipewireConfig;
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+
+/// Graph timing of the running PipeWire driver, as reported by `pw-top`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct GraphStatus {
+    quantum: u32,
+    rate: u32,
+    /// Errors (mostly xruns) of all drivers since they started
+    xruns: u64,
+}
+
+/// Current PipeWire quantum, sample rate and xrun count, with a popover to force them
+pub struct PipewireWidget {
+    pub button: Button,
+    state: Rc<PipewireState>,
+}
+
+struct PipewireState {
+    label: Label,
+    quantum_buttons: Vec<(u32, ToggleButton)>,
+    rate_buttons: Vec<(u32, ToggleButton)>,
+    /// Set while the buttons are moved to match the forced settings
+    updating: Cell<bool>,
+    status: RefCell<Option<GraphStatus>>,
+}
+
+impl PipewireWidget {
+    pub fn new(config: &PipewireConfig) -> Option<Self> {
+        if !is_available("pw-top") || !is_available("pw-metadata") {
+            return None;
+        }
+
+        let button = Button::new();
+        button.add_css_class("pipewire-button");
+
+        let label = Label::new(Some("PW: idle"));
+        label.add_css_class("pipewire-label");
+        button.set_child(Some(&label));
+
+        let popover = Popover::new();
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let content = GtkBox::new(Orientation::Vertical, 6);
+        content.add_css_class("pipewire");
+        let (quantum_row, quantum_buttons) = choice_row("Quantum", &config.quanta);
+        let (rate_row, rate_buttons) = choice_row("Rate", &config.rates);
+        content.append(&quantum_row);
+        content.append(&rate_row);
+        popover.set_child(Some(&content));
+
+        let state = Rc::new(PipewireState {
+            label,
+            quantum_buttons,
+            rate_buttons,
+            updating: Cell::new(false),
+            status: RefCell::new(None),
+        });
+
+        for (key, buttons) in [
+            ("clock.force-quantum", &state.quantum_buttons),
+            ("clock.force-rate", &state.rate_buttons),
+        ] {
+            for (value, toggle) in buttons {
+                let value = *value;
+                let state_toggle = Rc::downgrade(&state);
+                toggle.connect_toggled(move |toggle| {
+                    let Some(state) = state_toggle.upgrade() else {
+                        return;
+                    };
+                    if toggle.is_active() && !state.updating.get() {
+                        force_setting(key, value);
+                    }
+                });
+            }
+        }
+
+        let state_click = state.clone();
+        button.connect_clicked(move |_| {
+            // Another client may have changed the forced values in the meantime
+            let state = state_click.clone();
+            glib::spawn_future_local(async move {
+                state.read_forced().await;
+            });
+            popover.popup();
+        });
+
+        Some(PipewireWidget { button, state })
+    }
+}
+
+/// A row of grouped toggle buttons, "Auto" (0) followed by the configured values
+fn choice_row(title: &str, values: &[u32]) -> (GtkBox, Vec<(u32, ToggleButton)>) {
+    let row = GtkBox::new(Orientation::Horizontal, 2);
+    let title_label = Label::new(Some(title));
+    title_label.set_width_chars(8);
+    title_label.set_xalign(0.0);
+    row.append(&title_label);
+
+    let mut buttons: Vec<(u32, ToggleButton)> = Vec::new();
+    for value in std::iter::once(0).chain(values.iter().copied()) {
+        let label = if value == 0 {
+            "Auto".to_string()
+        } else {
+            value.to_string()
+        };
+        let toggle = ToggleButton::with_label(&label);
+        toggle.add_css_class("pipewire-choice");
+        if let Some((_, first)) = buttons.first() {
+            toggle.set_group(Some(first));
+        }
+        row.append(&toggle);
+        buttons.push((value, toggle));
+    }
+    (row, buttons)
+}
+
+impl PipewireState {
+    fn show(&self, status: Option<GraphStatus>) {
+        if *self.status.borrow() == status {
+            return;
+        }
+        *self.status.borrow_mut() = status;
+
+        let parent = self.label.parent();
+        match status {
+            Some(status) if status.rate > 0 => {
+                self.label
+                    .set_text(&format!("{}/{}k", status.quantum, format_rate(status.rate)));
+                let latency = status.quantum as f64 / status.rate as f64 * 1000.0;
+                if let Some(parent) = &parent {
+                    parent.set_tooltip_text(Some(&format!(
+                        "Quantum {} at {} Hz ({:.1} ms)\n{} xruns",
+                        status.quantum, status.rate, latency, status.xruns
+                    )));
+                }
+            }
+            _ => {
+                self.label.set_text("PW: idle");
+                if let Some(parent) = &parent {
+                    parent.set_tooltip_text(Some("No PipeWire driver running"));
+                }
+            }
+        }
+
+        if let Some(parent) = &parent {
+            if status.is_some_and(|status| status.xruns > 0) {
+                parent.add_css_class("xruns");
+            } else {
+                parent.remove_css_class("xruns");
+            }
+        }
+
+        control::module_updated("pipewire");
+    }
+
+    /// Select the buttons of the currently forced quantum and rate
+    async fn read_forced(&self) {
+        let Some(output) = run_shell("pw-metadata -n settings").await else {
+            return;
+        };
+
+        self.updating.set(true);
+        for (key, buttons) in [
+            ("clock.force-quantum", &self.quantum_buttons),
+            ("clock.force-rate", &self.rate_buttons),
+        ] {
+            let forced = metadata_value(&output, key).unwrap_or(0);
+            // Values forced from elsewhere that have no button show no selection
+            for (value, toggle) in buttons {
+                toggle.set_active(*value == forced);
+            }
+        }
+        self.updating.set(false);
+    }
+}
+
+impl BarModule for PipewireWidget {
+    fn name(&self) -> &'static str {
+        "pipewire"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            self.state.read_forced().await;
+
+            // pw-top prints a table per second, drivers first with followers marked "+"
+            let mut drivers: Vec<GraphStatus> = Vec::new();
+            watch_lines(&["pw-top", "-b"], |line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.first() == Some(&"S") {
+                    // Header of the next table, the previous one is complete
+                    self.state.show(summarize(&drivers));
+                    drivers.clear();
+                } else if fields.len() > 8 && !line.contains(" + ") {
+                    let running = fields[0] == "R";
+                    let parse = |index: usize| fields[index].parse::<u64>().unwrap_or(0);
+                    drivers.push(GraphStatus {
+                        quantum: if running { parse(2) as u32 } else { 0 },
+                        rate: if running { parse(3) as u32 } else { 0 },
+                        xruns: parse(8),
+                    });
+                }
+            })
+            .await;
+
+            self.state.show(None);
+        })
+    }
+}
+
+/// The running driver with the xruns of all drivers
+fn summarize(drivers: &[GraphStatus]) -> Option<GraphStatus> {
+    if drivers.is_empty() {
+        return None;
+    }
+    let running = drivers.iter().find(|driver| driver.rate > 0).copied();
+    Some(GraphStatus {
+        xruns: drivers.iter().map(|driver| driver.xruns).sum(),
+        ..running.unwrap_or_default()
+    })
+}
+
+/// 48000 as "48", 44100 as "44.1"
+fn format_rate(rate: u32) -> String {
+    if rate % 1000 == 0 {
+        (rate / 1000).to_string()
+    } else {
+        format!("{:.1}", rate as f64 / 1000.0)
+    }
+}
+
+/// Read `key` from `pw-metadata` output, whose lines look like
+/// `update: id:0 key:'clock.rate' value:'48000' type:''`
+fn metadata_value(output: &str, key: &str) -> Option<u32> {
+    let marker = format!("key:'{}' value:'", key);
+    output.lines().find_map(|line| {
+        let start = line.find(&marker)? + marker.len();
+        let end = line[start..].find('\'')?;
+        line[start..start + end].parse().ok()
+    })
+}
+
+/// Force a clock setting for the whole graph, 0 returns it to automatic
+fn force_setting(key: &str, value: u32) {
+    if let Err(e) = Command::new("pw-metadata")
+        .args(["-n", "settings", "0", key, &value.to_string()])
+        .spawn()
+    {
+        warn!("Failed to set {}: {}", key, e);
+    }
+}
@@ -0,0 +1,84 @@
+// Open popovers and their scroll positions, captured before the modules are rebuilt
+// on a configuration reload and restored afterwards so the reload is not noticed.
+
+use gtk4::prelude::*;
+use gtk4::{Popover, ScrolledWindow, Widget};
+
+/// A popover that was open, identified by its module and position in the widget tree
+struct OpenPopover {
+    module: String,
+    index: usize,
+    /// Vertical scroll offset of each scrolled window inside, in tree order
+    scroll: Vec<f64>,
+}
+
+#[derive(Default)]
+pub struct PopoverState {
+    open: Vec<OpenPopover>,
+}
+
+impl PopoverState {
+    /// Record the open popovers of the given modules, keyed by layout name
+    pub fn capture<'a>(modules: impl Iterator<Item = (&'a str, &'a Widget)>) -> Self {
+        let mut open = Vec::new();
+        for (module, widget) in modules {
+            for (index, popover) in descendants::<Popover>(widget).into_iter().enumerate() {
+                if !popover.is_visible() {
+                    continue;
+                }
+                let scroll = descendants::<ScrolledWindow>(popover.upcast_ref())
+                    .iter()
+                    .map(|scrolled| scrolled.vadjustment().value())
+                    .collect();
+                open.push(OpenPopover {
+                    module: module.to_string(),
+                    index,
+                    scroll,
+                });
+            }
+        }
+        PopoverState { open }
+    }
+
+    /// Reopen the recorded popovers in the rebuilt modules
+    pub fn restore<'a>(self, modules: impl Iterator<Item = (&'a str, &'a Widget)>) {
+        if self.open.is_empty() {
+            return;
+        }
+
+        for (module, widget) in modules {
+            let popovers = descendants::<Popover>(widget);
+            for open in self.open.iter().filter(|open| open.module == module) {
+                let Some(popover) = popovers.get(open.index) else {
+                    continue;
+                };
+                popover.popup();
+
+                // Scroll once the content has been measured
+                let scroll = open.scroll.clone();
+                let popover = popover.clone();
+                glib::idle_add_local_once(move || {
+                    let scrolled = descendants::<ScrolledWindow>(popover.upcast_ref());
+                    for (scrolled, value) in scrolled.iter().zip(scroll) {
+                        scrolled.vadjustment().set_value(value);
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Widgets of type `T` anywhere below `widget`, in tree order. Popovers are children
+/// of the widget they are attached to, so they are found too.
+fn descendants<T: IsA<Widget>>(widget: &Widget) -> Vec<T> {
+    let mut found = Vec::new();
+    let mut child = widget.first_child();
+    while let Some(current) = child {
+        if let Some(matching) = current.downcast_ref::<T>() {
+            found.push(matching.clone());
+        }
+        found.extend(descendants::<T>(&current));
+        child = current.next_sibling();
+    }
+    found
+}
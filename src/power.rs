@@ -0,0 +1,108 @@
+use std::cell::Cell;
+use std::fs;
+use std::time::Duration;
+use tracing::info;
+
+use crate::accessibility;
+use crate::config::PowerConfig;
+use crate::scheduler;
+use crate::sources::{self, PowerSource, PowerSupply};
+
+/// How often the power supply state is checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+thread_local! {
+    static POWER_SAVING: Cell<bool> = const { Cell::new(false) };
+    static INTERVAL_MULTIPLIER: Cell<u32> = const { Cell::new(1) };
+}
+
+/// Whether the bar is currently throttled because it runs on a low battery
+pub fn is_power_saving() -> bool {
+    POWER_SAVING.with(|saving| saving.get())
+}
+
+/// Polling interval to use, stretched while power saving is active
+pub fn scaled_interval(base: Duration) -> Duration {
+    if is_power_saving() {
+        base * INTERVAL_MULTIPLIER.with(|multiplier| multiplier.get())
+    } else {
+        base
+    }
+}
+
+/// Start watching the power supply and toggle power saving on battery
+pub fn start(config: &PowerConfig) {
+    if !config.enabled {
+        return;
+    }
+
+    INTERVAL_MULTIPLIER.with(|multiplier| multiplier.set(config.interval_multiplier.max(1)));
+
+    let threshold = config.battery_threshold;
+    let source = sources::power_source();
+    update(source.as_ref(), threshold);
+    scheduler::every(CHECK_INTERVAL, move || update(source.as_ref(), threshold));
+}
+
+fn update(source: &dyn PowerSource, threshold: u32) {
+    let saving = match source.read() {
+        Some(supply) => !supply.on_ac && supply.battery_percentage <= threshold,
+        None => false,
+    };
+
+    if saving == is_power_saving() {
+        return;
+    }
+    POWER_SAVING.with(|power_saving| power_saving.set(saving));
+
+    info!(
+        "Power saving {}",
+        if saving { "enabled (on battery)" } else { "disabled" }
+    );
+
+    // Animations are purely decorative, drop them while throttled
+    if let Some(settings) = gtk4::Settings::default() {
+        settings.set_gtk_enable_animations(!saving && !accessibility::is_enabled());
+    }
+}
+
+/// Reads AC and battery state from /sys/class/power_supply
+pub struct SysfsPowerSource;
+
+impl PowerSource for SysfsPowerSource {
+    fn read(&self) -> Option<PowerSupply> {
+        read_power_supply()
+    }
+}
+
+fn read_power_supply() -> Option<PowerSupply> {
+    let mut on_ac = false;
+    let mut battery_percentage = None;
+
+    for entry in fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let path = entry.path();
+        let supply_type = fs::read_to_string(path.join("type")).unwrap_or_default();
+
+        match supply_type.trim() {
+            "Mains" | "USB" => {
+                if fs::read_to_string(path.join("online")).is_ok_and(|online| online.trim() == "1") {
+                    on_ac = true;
+                }
+            }
+            "Battery" => {
+                if let Some(capacity) = fs::read_to_string(path.join("capacity"))
+                    .ok()
+                    .and_then(|capacity| capacity.trim().parse::<u32>().ok())
+                {
+                    battery_percentage = Some(capacity);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some(PowerSupply {
+        on_ac,
+        battery_percentage: battery_percentage?,
+    })
+}
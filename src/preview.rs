@@ -0,0 +1,276 @@
+// `blade_bar --preview <module>`: show a single module in a regular window, fed
+// with fake data that cycles through its visual states, for theme development.
+
+use gtk4::prelude::*;
+use gtk4::{
+    Application, ApplicationWindow, Box as GtkBox, Button, Image, Label, Orientation, Widget,
+};
+use std::time::Duration;
+
+use crate::color_scheme;
+use crate::config::{Config, NotificationsConfig};
+use crate::layout::{BarLayout, Zone};
+use crate::module::create_module;
+use crate::notification_widget::{NotificationIndicator, NotificationStatus};
+use crate::style;
+
+/// Time each fake state is shown for
+const STEP_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Advances a demo widget to the given state
+type Step = Box<dyn Fn(u32)>;
+
+/// Modules that depend on external services and are previewed with fake data instead
+const DEMOS: &[&str] = &[
+    "workspaces",
+    "window_title",
+    "system_monitor",
+    "notifications",
+    "tray",
+    "audio",
+    "reload",
+];
+
+/// Open the preview window and return the process exit code
+pub fn run(name: Option<&str>) -> i32 {
+    let Some(name) = name.map(str::to_string) else {
+        eprintln!("Usage: blade_bar --preview <module>");
+        eprintln!("Modules with fake data: {}", DEMOS.join(", "));
+        return 2;
+    };
+
+    // Separate id so the preview can run next to the real bar
+    let app = Application::builder()
+        .application_id("org.swordi.BladeBar.Preview")
+        .build();
+
+    app.connect_activate(move |app| {
+        style::load_css();
+        let config = Config::load();
+        color_scheme::apply(&config);
+        style::set_palette(&config);
+        style::set_font(&config.bar);
+
+        let window = ApplicationWindow::builder()
+            .application(app)
+            .title(format!("BladeBar preview: {}", name))
+            .css_classes(["main-window", "top", "preview"])
+            .default_width(600)
+            .build();
+
+        // Same container hierarchy as the bar so theme selectors match
+        let layout = BarLayout::new(Orientation::Horizontal);
+
+        if let Some((widget, step)) = demo(&name) {
+            layout.append(Zone::Center, &widget);
+
+            let mut state = 0;
+            step(state);
+            glib::timeout_add_local(STEP_INTERVAL, move || {
+                state += 1;
+                step(state);
+                glib::ControlFlow::Continue
+            });
+        } else if let Some(module) = create_module(&name, &config) {
+            // Modules without external dependencies show real data
+            layout.append(Zone::Center, module.widget());
+            glib::spawn_future_local(async move {
+                module.init().await;
+            });
+        } else {
+            eprintln!("Module '{}' can not be previewed on this system", name);
+            app.quit();
+            return;
+        }
+
+        window.set_child(Some(layout.widget()));
+        window.present();
+    });
+
+    // Keep GTK from interpreting the bar's own arguments
+    app.run_with_args::<&str>(&[]).value()
+}
+
+/// Build a stand-in for `name` using the module's CSS classes, with a step function
+fn demo(name: &str) -> Option<(Widget, Step)> {
+    let demo = match name {
+        "workspaces" => demo_workspaces(),
+        "window_title" => demo_window_title(),
+        "system_monitor" => demo_system_monitor(),
+        "notifications" => demo_notifications(),
+        "tray" => demo_tray(),
+        "audio" => demo_audio(),
+        "reload" => demo_reload(),
+        _ => return None,
+    };
+    Some(demo)
+}
+
+fn demo_workspaces() -> (Widget, Step) {
+    let container = GtkBox::new(Orientation::Horizontal, 0);
+    container.add_css_class("workspaces");
+
+    let buttons: Vec<Button> = (1..=5)
+        .map(|index| {
+            let button = Button::with_label(&index.to_string());
+            button.add_css_class("workspace-button");
+            container.append(&button);
+            button
+        })
+        .collect();
+
+    let step = Box::new(move |state: u32| {
+        let active = state as usize % buttons.len();
+        let urgent = (active + 2) % buttons.len();
+        for (index, button) in buttons.iter().enumerate() {
+            set_class(button, "active", index == active);
+            set_class(button, "urgent", index == urgent && state % 2 == 0);
+        }
+    });
+
+    (container.upcast(), step)
+}
+
+fn demo_window_title() -> (Widget, Step) {
+    const TITLES: &[&str] = &[
+        "README.md - Visual Studio Code",
+        "Mozilla Firefox",
+        "A very long window title that should end up being ellipsized by the bar",
+        "",
+    ];
+
+    let label = Label::new(None);
+    label.add_css_class("window-title");
+    label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+    label.set_max_width_chars(60);
+
+    let label_step = label.clone();
+    let step = Box::new(move |state: u32| {
+        let title = TITLES[state as usize % TITLES.len()];
+        label_step.set_text(title);
+        label_step.set_visible(!title.is_empty());
+    });
+
+    (label.upcast(), step)
+}
+
+fn demo_system_monitor() -> (Widget, Step) {
+    let container = GtkBox::new(Orientation::Horizontal, 10);
+    container.add_css_class("system-monitor");
+
+    let cpu_label = Label::new(None);
+    cpu_label.add_css_class("cpu-label");
+    let memory_label = Label::new(None);
+    memory_label.add_css_class("memory-label");
+    let temp_label = Label::new(None);
+    temp_label.add_css_class("temp-label");
+
+    container.append(&cpu_label);
+    container.append(&memory_label);
+    container.append(&temp_label);
+
+    // Sweep from idle to fully loaded
+    let step = Box::new(move |state: u32| {
+        let load = (state % 5) as f32 / 4.0;
+        cpu_label.set_text(&format!("CPU: {:.1}%", 3.0 + load * 96.0));
+        memory_label.set_text(&format!("MEM: {:.1}%", 20.0 + load * 78.0));
+        temp_label.set_text(&format!("TEMP: {:.0}°C", 40.0 + load * 55.0));
+    });
+
+    (container.upcast(), step)
+}
+
+fn demo_notifications() -> (Widget, Step) {
+    let button = Button::new();
+    let indicator = NotificationIndicator::new(&button, &NotificationsConfig::default());
+
+    let step = Box::new(move |state: u32| {
+        let status = NotificationStatus {
+            count: [0, 1, 5, 0][state as usize % 4],
+            dnd: state % 4 >= 2,
+        };
+        indicator.show(&status);
+    });
+
+    (button.upcast(), step)
+}
+
+fn demo_tray() -> (Widget, Step) {
+    let container = GtkBox::new(Orientation::Horizontal, 5);
+    container.add_css_class("tray-widget");
+
+    let buttons: Vec<Button> = [
+        "network-wireless-symbolic",
+        "audio-volume-high-symbolic",
+        "mail-unread-symbolic",
+    ]
+    .iter()
+    .map(|icon_name| {
+        let button = Button::new();
+        button.add_css_class("tray-button");
+        button.set_child(Some(&Image::from_icon_name(icon_name)));
+        container.append(&button);
+        button
+    })
+    .collect();
+
+    // Items requesting attention
+    let step = Box::new(move |state: u32| {
+        for (index, button) in buttons.iter().enumerate() {
+            set_class(button, "needs-attention", state % 2 == 1 && index == 2);
+        }
+    });
+
+    (container.upcast(), step)
+}
+
+fn demo_audio() -> (Widget, Step) {
+    let button = Button::new();
+    button.add_css_class("audio-button");
+
+    let label = Label::new(None);
+    label.add_css_class("audio-label");
+    button.set_child(Some(&label));
+
+    let step = Box::new(move |state: u32| {
+        let text = match state % 4 {
+            0 => "🔊 80%",
+            1 => "🔉 30%",
+            2 => "🔇 30%",
+            _ => "VOL: N/A",
+        };
+        label.set_text(text);
+    });
+
+    (button.upcast(), step)
+}
+
+fn demo_reload() -> (Widget, Step) {
+    let button = Button::new();
+    button.add_css_class("reload-button");
+
+    let icon = Image::from_icon_name("view-refresh-symbolic");
+    button.set_child(Some(&icon));
+
+    let button_step = button.clone();
+    let step = Box::new(move |state: u32| {
+        let (class, icon_name) = match state % 3 {
+            0 => (None, "view-refresh-symbolic"),
+            1 => (Some("success"), "emblem-ok-symbolic"),
+            _ => (Some("failure"), "dialog-error-symbolic"),
+        };
+        set_class(&button_step, "success", class == Some("success"));
+        set_class(&button_step, "failure", class == Some("failure"));
+        icon.set_icon_name(Some(icon_name));
+    });
+
+    (button.upcast(), step)
+}
+
+fn set_class(widget: &impl IsA<Widget>, class: &str, enabled: bool) {
+    if enabled {
+        widget.add_css_class(class);
+    } else {
+        widget.remove_css_class(class);
+    }
+}
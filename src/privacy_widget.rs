@@ -0,0 +1,234 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Label, Orientation};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::command::{is_available, spawn_piped};
+use crate::config::PrivacyConfig;
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+
+/// Processes that open cameras on behalf of PipeWire clients, which are named
+/// through the PipeWire graph instead
+const MEDIA_SERVERS: &[&str] = &["pipewire", "wireplumber", "pipewire-media-session"];
+
+/// Applications capturing each kind of device
+#[derive(Debug, Default)]
+struct Capture {
+    microphone: BTreeSet<String>,
+    camera: BTreeSet<String>,
+    screenshare: BTreeSet<String>,
+}
+
+/// Icons lit while the microphone, a camera or the screen is being captured, with
+/// the applications responsible in the tooltip
+pub struct PrivacyWidget {
+    pub container: GtkBox,
+    config: PrivacyConfig,
+    microphone: Label,
+    camera: Label,
+    screenshare: Label,
+}
+
+impl PrivacyWidget {
+    pub fn new(config: &PrivacyConfig, orientation: Orientation) -> Self {
+        let container = GtkBox::new(orientation, 4);
+        container.add_css_class("privacy");
+        container.set_visible(false);
+
+        let indicator = |icon: &str, class: &str| {
+            let label = Label::new(Some(icon));
+            label.add_css_class("privacy-indicator");
+            label.add_css_class(class);
+            label.set_visible(false);
+            container.append(&label);
+            label
+        };
+        let microphone = indicator(&config.microphone_icon, "privacy-microphone");
+        let camera = indicator(&config.camera_icon, "privacy-camera");
+        let screenshare = indicator(&config.screenshare_icon, "privacy-screenshare");
+
+        PrivacyWidget {
+            container,
+            config: config.clone(),
+            microphone,
+            camera,
+            screenshare,
+        }
+    }
+
+    async fn update(&self, pipewire: bool) {
+        let mut capture = if pipewire {
+            pipewire_capture().await.unwrap_or_default()
+        } else {
+            Capture::default()
+        };
+        if let Ok(openers) = tokio::task::spawn_blocking(camera_openers).await {
+            capture.camera.extend(openers);
+        }
+        for apps in [&mut capture.microphone, &mut capture.camera, &mut capture.screenshare] {
+            apps.retain(|app| !self.config.ignore.iter().any(|ignored| ignored == app));
+        }
+
+        let mut tooltip = Vec::new();
+        for (label, apps, title) in [
+            (&self.microphone, &capture.microphone, "Microphone"),
+            (&self.camera, &capture.camera, "Camera"),
+            (&self.screenshare, &capture.screenshare, "Screen sharing"),
+        ] {
+            label.set_visible(!apps.is_empty());
+            if !apps.is_empty() {
+                tooltip.push(format!(
+                    "{}: {}",
+                    title,
+                    apps.iter().cloned().collect::<Vec<_>>().join(", ")
+                ));
+            }
+        }
+
+        self.container.set_visible(!tooltip.is_empty());
+        self.container.set_tooltip_text(Some(&tooltip.join("\n")));
+        control::module_updated("privacy");
+    }
+}
+
+/// Streams capturing from microphones, cameras and screencasts, from a snapshot of
+/// the PipeWire graph
+async fn pipewire_capture() -> Option<Capture> {
+    let process = spawn_piped(&["pw-dump"])?;
+    let (stdout, _) = process.communicate_utf8_future(None).await.ok()?;
+    let objects: Vec<Value> = match serde_json::from_str(stdout.as_deref().unwrap_or_default()) {
+        Ok(objects) => objects,
+        Err(e) => {
+            debug!("Unexpected pw-dump output: {}", e);
+            return None;
+        }
+    };
+
+    let mut nodes: HashMap<u64, &Value> = HashMap::new();
+    let mut clients: HashMap<u64, &Value> = HashMap::new();
+    for object in &objects {
+        let Some(id) = object["id"].as_u64() else {
+            continue;
+        };
+        match object["type"].as_str() {
+            Some("PipeWire:Interface:Node") => {
+                nodes.insert(id, object);
+            }
+            Some("PipeWire:Interface:Client") => {
+                clients.insert(id, object);
+            }
+            _ => {}
+        }
+    }
+
+    let mut capture = Capture::default();
+    let links = objects
+        .iter()
+        .filter(|object| object["type"] == "PipeWire:Interface:Link");
+    for link in links {
+        let info = &link["info"];
+        let (Some(source), Some(stream)) = (
+            info["output-node-id"].as_u64().and_then(|id| nodes.get(&id)),
+            info["input-node-id"].as_u64().and_then(|id| nodes.get(&id)),
+        ) else {
+            continue;
+        };
+        if stream["info"]["state"] != "running" {
+            continue;
+        }
+
+        let source_props = &source["info"]["props"];
+        let stream_class = stream["info"]["props"]["media.class"].as_str().unwrap_or_default();
+        let source_class = source_props["media.class"].as_str().unwrap_or_default();
+        let apps = match (stream_class, source_class) {
+            // Sink monitors are output being recorded, not the microphone
+            ("Stream/Input/Audio", class) if class.starts_with("Audio/Source") => {
+                &mut capture.microphone
+            }
+            ("Stream/Input/Video", "Video/Source") if source_props["device.api"].is_string() => {
+                &mut capture.camera
+            }
+            // Screencasts are streams the portal backend or compositor produces
+            ("Stream/Input/Video", "Video/Source" | "Stream/Output/Video") => {
+                &mut capture.screenshare
+            }
+            _ => continue,
+        };
+        apps.insert(application_name(stream, &clients));
+    }
+    Some(capture)
+}
+
+/// Name of the application owning a stream node
+fn application_name(node: &Value, clients: &HashMap<u64, &Value>) -> String {
+    let props = &node["info"]["props"];
+    let client = props["client.id"]
+        .as_u64()
+        .and_then(|id| clients.get(&id))
+        .map(|client| &client["info"]["props"]);
+    ["application.name", "application.process.binary"]
+        .iter()
+        .flat_map(|key| [Some(&props[*key]), client.map(|client| &client[*key])])
+        .flatten()
+        .find_map(|value| value.as_str())
+        .or_else(|| props["node.name"].as_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Processes holding a video device open, for applications using V4L2 directly
+fn camera_openers() -> BTreeSet<String> {
+    let mut openers = BTreeSet::new();
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return openers;
+    };
+    let pids = processes
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().bytes().all(|b| b.is_ascii_digit()));
+    for process in pids {
+        // Only processes of this user are readable, which are the ones of interest
+        let Ok(fds) = fs::read_dir(process.path().join("fd")) else {
+            continue;
+        };
+        let has_camera = fds.flatten().any(|fd| {
+            fs::read_link(fd.path())
+                .is_ok_and(|target| target.to_string_lossy().starts_with("/dev/video"))
+        });
+        if !has_camera {
+            continue;
+        }
+        let Ok(comm) = fs::read_to_string(process.path().join("comm")) else {
+            continue;
+        };
+        let comm = comm.trim();
+        if !MEDIA_SERVERS.contains(&comm) {
+            openers.insert(comm.to_string());
+        }
+    }
+    openers
+}
+
+impl BarModule for PrivacyWidget {
+    fn name(&self) -> &'static str {
+        "privacy"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.container.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            let pipewire = is_available("pw-dump");
+            loop {
+                self.update(pipewire).await;
+                next_tick(Duration::from_secs(self.config.interval.max(1))).await;
+            }
+        })
+    }
+}
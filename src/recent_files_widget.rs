@@ -0,0 +1,232 @@
+use gtk4::prelude::*;
+use gtk4::{
+    Box as GtkBox, Button, Image, Label, ListBox, ListBoxRow, Orientation, Popover, RecentInfo,
+    RecentManager, SelectionMode, ToggleButton,
+};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use tracing::warn;
+
+use crate::config::RecentFilesConfig;
+use crate::launch;
+use crate::module::BarModule;
+
+/// Document categories the popover can be filtered by
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FileFilter {
+    All,
+    Documents,
+    Images,
+    Media,
+}
+
+impl FileFilter {
+    const ALL: [FileFilter; 4] = [
+        FileFilter::All,
+        FileFilter::Documents,
+        FileFilter::Images,
+        FileFilter::Media,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            FileFilter::All => "All",
+            FileFilter::Documents => "Docs",
+            FileFilter::Images => "Images",
+            FileFilter::Media => "Media",
+        }
+    }
+
+    fn matches(self, mime_type: &str) -> bool {
+        match self {
+            FileFilter::All => true,
+            FileFilter::Documents => {
+                mime_type.starts_with("text/")
+                    || mime_type == "application/pdf"
+                    || mime_type.starts_with("application/vnd.oasis.opendocument")
+                    || mime_type.starts_with("application/vnd.openxmlformats")
+                    || mime_type == "application/msword"
+            }
+            FileFilter::Images => mime_type.starts_with("image/"),
+            FileFilter::Media => {
+                mime_type.starts_with("audio/") || mime_type.starts_with("video/")
+            }
+        }
+    }
+}
+
+pub struct RecentFilesWidget {
+    pub button: Button,
+}
+
+impl RecentFilesWidget {
+    pub fn new(config: &RecentFilesConfig) -> Self {
+        let button = Button::new();
+        button.add_css_class("recent-files-button");
+        button.set_child(Some(&Image::from_icon_name("document-open-recent-symbolic")));
+        button.set_tooltip_text(Some("Recent files"));
+
+        let popover = Popover::new();
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+        popover.add_css_class("recent-files");
+
+        let content = GtkBox::new(Orientation::Vertical, 6);
+
+        let list_box = ListBox::new();
+        list_box.set_selection_mode(SelectionMode::None);
+        list_box.add_css_class("recent-files-list");
+
+        let placeholder = Label::new(Some("No recent files"));
+        placeholder.add_css_class("dim-label");
+        list_box.set_placeholder(Some(&placeholder));
+
+        let manager = RecentManager::default();
+        let limit = config.limit;
+        let filter = Rc::new(Cell::new(FileFilter::All));
+        let items: Rc<RefCell<Vec<RecentInfo>>> = Rc::new(RefCell::new(Vec::new()));
+
+        // Filter buttons: only one category is active at a time
+        let filter_box = GtkBox::new(Orientation::Horizontal, 4);
+        filter_box.add_css_class("recent-files-filters");
+        let mut group: Option<ToggleButton> = None;
+
+        for file_filter in FileFilter::ALL {
+            let toggle = ToggleButton::with_label(file_filter.label());
+            toggle.add_css_class("recent-files-filter");
+            if let Some(group) = &group {
+                toggle.set_group(Some(group));
+            } else {
+                toggle.set_active(true);
+                group = Some(toggle.clone());
+            }
+
+            let filter = filter.clone();
+            let manager = manager.clone();
+            let list_box = list_box.clone();
+            let items = items.clone();
+            toggle.connect_toggled(move |toggle| {
+                if toggle.is_active() {
+                    filter.set(file_filter);
+                    Self::populate(&list_box, &manager, &items, file_filter, limit);
+                }
+            });
+
+            filter_box.append(&toggle);
+        }
+        content.append(&filter_box);
+        content.append(&list_box);
+
+        // Clear the whole recent files history
+        let clear_button = Button::with_label("Clear history");
+        clear_button.add_css_class("recent-files-clear");
+        let manager_clear = manager.clone();
+        clear_button.connect_clicked(move |_| {
+            if let Err(e) = manager_clear.purge_items() {
+                warn!("Failed to clear recent files: {}", e);
+            }
+        });
+        content.append(&clear_button);
+
+        popover.set_child(Some(&content));
+
+        // Open the clicked document with its default application
+        let items_activate = items.clone();
+        let popover_weak = popover.downgrade();
+        list_box.connect_row_activated(move |_, row| {
+            if let Some(info) = items_activate.borrow().get(row.index() as usize) {
+                if let Err(e) =
+                    gio::AppInfo::launch_default_for_uri(&info.uri(), launch::context().as_ref())
+                {
+                    warn!("Failed to open '{}': {}", info.uri(), e);
+                }
+            }
+            if let Some(popover) = popover_weak.upgrade() {
+                popover.popdown();
+            }
+        });
+
+        // Keep the list in sync with the recent files store
+        let list_box_changed = list_box.clone();
+        let items_changed = items.clone();
+        let filter_changed = filter.clone();
+        manager.connect_changed(move |manager| {
+            Self::populate(
+                &list_box_changed,
+                manager,
+                &items_changed,
+                filter_changed.get(),
+                limit,
+            );
+        });
+
+        Self::populate(&list_box, &manager, &items, filter.get(), limit);
+
+        button.connect_clicked(move |_| {
+            popover.popup();
+        });
+
+        RecentFilesWidget { button }
+    }
+
+    fn populate(
+        list_box: &ListBox,
+        manager: &RecentManager,
+        items: &Rc<RefCell<Vec<RecentInfo>>>,
+        filter: FileFilter,
+        limit: usize,
+    ) {
+        while let Some(row) = list_box.row_at_index(0) {
+            list_box.remove(&row);
+        }
+
+        let mut recent: Vec<RecentInfo> = manager
+            .items()
+            .into_iter()
+            .filter(|info| info.exists() && filter.matches(&info.mime_type()))
+            .collect();
+
+        // Most recently modified first
+        recent.sort_by(|a, b| b.modified().cmp(&a.modified()));
+        recent.truncate(limit);
+
+        for info in &recent {
+            list_box.append(&Self::create_row(info));
+        }
+
+        *items.borrow_mut() = recent;
+    }
+
+    fn create_row(info: &RecentInfo) -> ListBoxRow {
+        let row_box = GtkBox::new(Orientation::Horizontal, 8);
+        row_box.add_css_class("recent-files-row");
+
+        let icon = match info.gicon() {
+            Some(gicon) => Image::from_gicon(&gicon),
+            None => Image::from_icon_name("text-x-generic"),
+        };
+        icon.set_pixel_size(16);
+        row_box.append(&icon);
+
+        let name_label = Label::new(Some(&info.display_name()));
+        name_label.set_halign(gtk4::Align::Start);
+        name_label.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+        name_label.set_max_width_chars(40);
+        row_box.append(&name_label);
+
+        let row = ListBoxRow::new();
+        row.set_child(Some(&row_box));
+        row.set_tooltip_text(info.uri_display().as_deref());
+        row
+    }
+}
+
+impl BarModule for RecentFilesWidget {
+    fn name(&self) -> &'static str {
+        "recent_files"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+}
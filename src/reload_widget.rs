@@ -0,0 +1,88 @@
+use gtk4::prelude::*;
+use gtk4::{Button, Image};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::compositor::Compositor;
+use crate::module::BarModule;
+
+/// How long the success/failure state stays visible after a reload
+const RESULT_DURATION: Duration = Duration::from_secs(3);
+
+/// Button reloading the compositor configuration, briefly showing whether it worked
+pub struct ReloadWidget {
+    pub button: Button,
+}
+
+impl ReloadWidget {
+    pub fn new() -> Option<Self> {
+        let compositor = Compositor::detect()?;
+
+        let button = Button::new();
+        button.add_css_class("reload-button");
+
+        let icon = Image::from_icon_name("view-refresh-symbolic");
+        button.set_child(Some(&icon));
+
+        let default_tooltip = format!("Reload {} config", compositor.display_name());
+        button.set_tooltip_text(Some(&default_tooltip));
+
+        let pending_reset: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+
+        button.connect_clicked(move |button| {
+            if let Some(source_id) = pending_reset.borrow_mut().take() {
+                source_id.remove();
+            }
+
+            let (css_class, icon_name, tooltip) = match compositor.reload_config() {
+                Ok(()) => ("success", "emblem-ok-symbolic", "Config reloaded".to_string()),
+                Err(message) => {
+                    warn!(
+                        "Failed to reload {} config: {}",
+                        compositor.display_name(),
+                        message
+                    );
+                    (
+                        "failure",
+                        "dialog-error-symbolic",
+                        format!("Reload failed: {}", message),
+                    )
+                }
+            };
+
+            button.remove_css_class("success");
+            button.remove_css_class("failure");
+            button.add_css_class(css_class);
+            icon.set_icon_name(Some(icon_name));
+            button.set_tooltip_text(Some(&tooltip));
+
+            // Return to the idle state after a moment
+            let button = button.clone();
+            let icon = icon.clone();
+            let default_tooltip = default_tooltip.clone();
+            let pending_reset_inner = pending_reset.clone();
+            let source_id = glib::timeout_add_local_once(RESULT_DURATION, move || {
+                pending_reset_inner.borrow_mut().take();
+                button.remove_css_class("success");
+                button.remove_css_class("failure");
+                icon.set_icon_name(Some("view-refresh-symbolic"));
+                button.set_tooltip_text(Some(&default_tooltip));
+            });
+            *pending_reset.borrow_mut() = Some(source_id);
+        });
+
+        Some(ReloadWidget { button })
+    }
+}
+
+impl BarModule for ReloadWidget {
+    fn name(&self) -> &'static str {
+        "reload"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+}
@@ -0,0 +1,240 @@
+use futures_util::future::join_all;
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, Orientation};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::command::{is_available, spawn_piped};
+use crate::config::{RemoteConfig, RemoteHost};
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+
+/// Idle time before the shared SSH connection closes, longer than the usual poll
+/// interval so each host keeps a single connection open
+const CONTROL_PERSIST: &str = "10m";
+
+/// Metrics reported by one host
+#[derive(Debug, Clone, Default)]
+struct HostMetrics {
+    cpu: Option<f64>,
+    mem: Option<f64>,
+    uptime: Option<u64>,
+    /// Other `key=value` lines, listed in the tooltip
+    extra: Vec<(String, String)>,
+}
+
+/// CPU, memory and uptime of remote machines, polled over SSH
+pub struct RemoteWidget {
+    pub container: GtkBox,
+    state: Rc<RemoteState>,
+}
+
+struct RemoteState {
+    hosts: Vec<HostView>,
+    interval: Duration,
+}
+
+struct HostView {
+    host: RemoteHost,
+    name: String,
+    command: String,
+    button: Button,
+    label: Label,
+}
+
+impl RemoteWidget {
+    pub fn new(config: &RemoteConfig, orientation: Orientation) -> Option<Self> {
+        if config.hosts.is_empty() || !is_available("ssh") {
+            return None;
+        }
+
+        let container = GtkBox::new(orientation, 4);
+        container.add_css_class("remote");
+
+        let hosts: Vec<HostView> = config
+            .hosts
+            .iter()
+            .map(|host| {
+                let name = host.name.clone().unwrap_or_else(|| host.host.clone());
+                let label = Label::new(Some(&format!("{} …", name)));
+                label.add_css_class("remote-label");
+                let button = Button::new();
+                button.add_css_class("remote-host");
+                button.set_child(Some(&label));
+                container.append(&button);
+                HostView {
+                    host: host.clone(),
+                    name,
+                    command: host.command.clone().unwrap_or_else(|| config.command.clone()),
+                    button,
+                    label,
+                }
+            })
+            .collect();
+
+        let state = Rc::new(RemoteState {
+            hosts,
+            interval: Duration::from_secs(config.interval.max(1)),
+        });
+
+        for index in 0..state.hosts.len() {
+            let state_click = state.clone();
+            state.hosts[index].button.connect_clicked(move |_| {
+                let state = state_click.clone();
+                glib::spawn_future_local(async move {
+                    state.hosts[index].poll().await;
+                });
+            });
+        }
+
+        Some(RemoteWidget { container, state })
+    }
+}
+
+impl RemoteState {
+    async fn poll(&self) {
+        join_all(self.hosts.iter().map(HostView::poll)).await;
+    }
+}
+
+impl HostView {
+    async fn poll(&self) {
+        let metrics = run_remote(&self.host.host, &self.command).await;
+        self.show(metrics.as_ref());
+        control::module_updated("remote");
+    }
+
+    fn show(&self, metrics: Option<&HostMetrics>) {
+        let Some(metrics) = metrics else {
+            self.label.set_text(&format!("{} offline", self.name));
+            self.button
+                .set_tooltip_text(Some(&format!("{} is not reachable", self.host.host)));
+            self.button.add_css_class("offline");
+            return;
+        };
+        self.button.remove_css_class("offline");
+
+        let percent = |value: Option<f64>| match value {
+            Some(value) => format!("{:.0}%", value),
+            None => "--".to_string(),
+        };
+        self.label.set_text(&format!(
+            "{} {} {}",
+            self.name,
+            percent(metrics.cpu),
+            percent(metrics.mem)
+        ));
+
+        let mut tooltip = format!(
+            "{}\nCPU: {}\nMemory: {}",
+            self.host.host,
+            percent(metrics.cpu),
+            percent(metrics.mem)
+        );
+        if let Some(uptime) = metrics.uptime {
+            tooltip.push_str(&format!("\nUp {}", format_uptime(uptime)));
+        }
+        for (key, value) in &metrics.extra {
+            tooltip.push_str(&format!("\n{}: {}", key, value));
+        }
+        self.button.set_tooltip_text(Some(&tooltip));
+    }
+}
+
+/// Run `command` on `host`, sharing one master connection per host between polls
+async fn run_remote(host: &str, command: &str) -> Option<HostMetrics> {
+    let control_path = control_dir().join("blade_bar-ssh-%C");
+    let control_path = format!("ControlPath={}", control_path.display());
+    let persist = format!("ControlPersist={}", CONTROL_PERSIST);
+    let process = spawn_piped(&[
+        "ssh",
+        "-q",
+        "-o",
+        "BatchMode=yes",
+        "-o",
+        "ConnectTimeout=5",
+        "-o",
+        "ControlMaster=auto",
+        "-o",
+        &control_path,
+        "-o",
+        &persist,
+        host,
+        command,
+    ])?;
+
+    let (stdout, _) = process.communicate_utf8_future(None).await.ok()?;
+    if !process.is_successful() {
+        debug!("ssh {} exited with status {}", host, process.exit_status());
+        return None;
+    }
+    Some(parse_metrics(stdout.as_deref().unwrap_or_default()))
+}
+
+/// Directory for the SSH control sockets, the user's runtime directory if there is one
+fn control_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+fn parse_metrics(output: &str) -> HostMetrics {
+    let mut metrics = HostMetrics::default();
+    let mut load = None;
+    let mut cores = None;
+
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "cpu" => metrics.cpu = value.trim_end_matches('%').parse().ok(),
+            "mem" => metrics.mem = value.trim_end_matches('%').parse().ok(),
+            "uptime" => metrics.uptime = value.parse::<f64>().ok().map(|uptime| uptime as u64),
+            "load" => load = value.parse::<f64>().ok(),
+            "cores" => cores = value.parse::<f64>().ok().filter(|cores| *cores > 0.0),
+            _ => metrics.extra.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    // The 1 minute load average relative to the core count approximates CPU usage
+    if let (None, Some(load), Some(cores)) = (metrics.cpu, load, cores) {
+        metrics.cpu = Some((load / cores * 100.0).min(100.0));
+    }
+    metrics
+}
+
+/// 93784 seconds as "1d 2h 3m"
+fn format_uptime(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+impl BarModule for RemoteWidget {
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.container.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            loop {
+                self.state.poll().await;
+                next_tick(self.state.interval).await;
+            }
+        })
+    }
+}
@@ -0,0 +1,67 @@
+// Central scheduler for periodic widget refreshes.
+//
+// Every periodic task is aligned to wall-clock multiples of its interval, and tasks
+// sharing an interval are driven by a single timer. All 2s pollers therefore fire
+// together, and they also coincide with every other tick of the 4s pollers, which
+// keeps the number of wakeups low.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::power::scaled_interval;
+
+type Task = Rc<dyn Fn()>;
+
+thread_local! {
+    static GROUPS: RefCell<HashMap<Duration, Rc<RefCell<Vec<Task>>>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Run `func` every `interval`, batched with all other tasks of the same interval.
+/// The interval is stretched while power saving is active.
+pub fn every<F: Fn() + 'static>(interval: Duration, func: F) {
+    GROUPS.with(|groups| {
+        let mut groups = groups.borrow_mut();
+
+        if let Some(group) = groups.get(&interval) {
+            group.borrow_mut().push(Rc::new(func));
+            return;
+        }
+
+        let group = Rc::new(RefCell::new(vec![Rc::new(func) as Task]));
+        groups.insert(interval, group.clone());
+        schedule_group(interval, group);
+    });
+}
+
+/// Wait until the next aligned deadline for `interval`, for async refresh loops
+pub async fn next_tick(interval: Duration) {
+    glib::timeout_future(delay_until_aligned(scaled_interval(interval))).await;
+}
+
+fn schedule_group(interval: Duration, group: Rc<RefCell<Vec<Task>>>) {
+    let delay = delay_until_aligned(scaled_interval(interval));
+
+    glib::timeout_add_local_once(delay, move || {
+        // Clone the list so tasks may register new tasks while running
+        let tasks: Vec<Task> = group.borrow().clone();
+        for task in tasks {
+            task();
+        }
+        schedule_group(interval, group);
+    });
+}
+
+/// Time until the next wall-clock multiple of `period`
+fn delay_until_aligned(period: Duration) -> Duration {
+    let period_ms = period.as_millis().max(1);
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_millis())
+        .unwrap_or(0);
+
+    let remaining = period_ms - now_ms % period_ms;
+    Duration::from_millis(remaining as u64)
+}
@@ -0,0 +1,109 @@
+// Popover with a labelled slider for modules that control a level (volume,
+// brightness). The slider drives the backend through a callback, and backend changes
+// are pushed back with `set_value` without echoing them to the callback.
+
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Label, Orientation, Popover, Scale, Widget};
+use std::cell::Cell;
+use std::rc::Rc;
+
+pub struct SliderPopover {
+    pub popover: Popover,
+    /// Rows above the slider, for extra controls such as a mute toggle
+    pub content: GtkBox,
+    scale: Scale,
+    value_label: Label,
+    /// Set while the slider is moved from `set_value`
+    updating: Rc<Cell<bool>>,
+}
+
+impl SliderPopover {
+    /// Popover attached to `parent` with a slider from 0 to `max` moving by `step`.
+    /// `on_change` gets every value the user picks.
+    pub fn new(
+        parent: &impl IsA<Widget>,
+        title: &str,
+        max: u32,
+        step: u32,
+        on_change: impl Fn(u32) + 'static,
+    ) -> Self {
+        let popover = Popover::new();
+        popover.set_parent(parent);
+        popover.set_has_arrow(true);
+
+        let content = GtkBox::new(Orientation::Vertical, 6);
+        content.add_css_class("slider-popover");
+
+        let header = GtkBox::new(Orientation::Horizontal, 8);
+        let title_label = Label::new(Some(title));
+        title_label.set_hexpand(true);
+        title_label.set_halign(gtk4::Align::Start);
+        let value_label = Label::new(None);
+        value_label.add_css_class("slider-value");
+        header.append(&title_label);
+        header.append(&value_label);
+        content.append(&header);
+
+        let scale = Scale::with_range(Orientation::Horizontal, 0.0, max as f64, 1.0);
+        scale.set_increments(step as f64, (step * 2) as f64);
+        scale.set_size_request(220, -1);
+        scale.set_focusable(true);
+        content.append(&scale);
+
+        popover.set_child(Some(&content));
+
+        let updating = Rc::new(Cell::new(false));
+        let label = value_label.clone();
+        let updating_scale = updating.clone();
+        scale.connect_value_changed(move |scale| {
+            let value = scale.value().round() as u32;
+            label.set_text(&format!("{}%", value));
+            if !updating_scale.get() {
+                on_change(value);
+            }
+        });
+
+        // Arrow keys adjust the level as soon as the popover opens
+        let scale_focus = scale.clone();
+        popover.connect_show(move |_| {
+            scale_focus.grab_focus();
+        });
+
+        SliderPopover {
+            popover,
+            content,
+            scale,
+            value_label,
+            updating,
+        }
+    }
+
+    pub fn popup(&self) {
+        self.popover.popup();
+    }
+
+    /// Show a value read from the backend
+    pub fn set_value(&self, value: u32) {
+        // Never fight the user over the slider while it is being dragged
+        if self.popover.is_visible() && self.scale_grabbed() {
+            return;
+        }
+        self.updating.set(true);
+        self.scale.set_value(value as f64);
+        self.updating.set(false);
+        self.value_label.set_text(&format!("{}%", value));
+    }
+
+    /// Raise the upper end when the backend reports a level past it
+    pub fn set_max(&self, max: u32) {
+        let adjustment = self.scale.adjustment();
+        if adjustment.upper() != max as f64 {
+            adjustment.set_upper(max as f64);
+        }
+    }
+
+    /// Whether a pointer button is held on the slider
+    fn scale_grabbed(&self) -> bool {
+        self.scale.state_flags().contains(gtk4::StateFlags::ACTIVE)
+    }
+}
@@ -0,0 +1,182 @@
+// Abstractions over the external data sources modules read from (sysfs, sysinfo,
+// compositor IPC, the StatusNotifierWatcher). Each has a real implementation next to the code that uses it and
+// a scripted fake in `sources::mock`, selected at runtime with `BLADE_BAR_MOCK=1`
+// when built with `--features mock`. Combined with `GDK_BACKEND=broadway` this runs
+// the whole bar without hardware, a compositor or a desktop session, see
+// `scripts/headless.sh`.
+
+#[cfg(feature = "mock")]
+pub mod mock;
+#[cfg(feature = "mock")]
+pub mod mock_tray;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use system_tray::client::{ActivateRequest, Event as TrayEvent};
+use system_tray::item::StatusNotifierItem;
+use system_tray::menu::TrayMenu;
+use tokio::sync::broadcast;
+
+use crate::compositor::{Compositor, CompositorBackend};
+use crate::power::SysfsPowerSource;
+use crate::system_monitor::SysinfoSource;
+use crate::tray_widget::WatcherTraySource;
+
+/// One sample of CPU, memory and temperature
+#[derive(Debug, Clone)]
+pub struct SystemReadings {
+    pub cpu_usage: Option<f32>,
+    /// Every logical core in order, empty when unknown
+    pub cores: Vec<CoreReading>,
+    pub memory_percentage: Option<f64>,
+    /// Memory and swap breakdown, `None` when unknown
+    pub memory: Option<MemoryReading>,
+    pub temperature: f32,
+    /// Traffic of every network interface
+    pub interfaces: Vec<InterfaceReading>,
+    /// Busiest processes, by CPU and by memory
+    pub top_cpu: Vec<ProcessReading>,
+    pub top_memory: Vec<ProcessReading>,
+}
+
+/// A process in the top lists
+#[derive(Debug, Clone)]
+pub struct ProcessReading {
+    pub pid: u32,
+    pub name: String,
+    /// Percent of one core, like top, so busy processes can exceed 100
+    pub cpu: f32,
+    /// Resident memory in bytes
+    pub memory: u64,
+}
+
+/// Traffic of one network interface
+#[derive(Debug, Clone)]
+pub struct InterfaceReading {
+    pub name: String,
+    /// Bytes per second since the previous sample
+    pub rx_rate: f64,
+    pub tx_rate: f64,
+    /// Bytes since boot
+    pub rx_total: u64,
+    pub tx_total: u64,
+}
+
+/// Memory and swap in bytes
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryReading {
+    pub total: u64,
+    pub used: u64,
+    pub available: u64,
+    /// Page cache and kernel buffers, reclaimed when needed
+    pub buffers_cache: u64,
+    pub swap_total: u64,
+    pub swap_used: u64,
+}
+
+/// Load and clock of one logical core
+#[derive(Debug, Clone, Copy)]
+pub struct CoreReading {
+    pub usage: f32,
+    /// Current frequency in MHz, 0 when unknown
+    pub frequency: u64,
+}
+
+/// Provides system load readings
+pub trait SystemSource {
+    fn sample(&mut self) -> SystemReadings;
+}
+
+/// AC and battery state
+#[derive(Debug, Clone, Copy)]
+pub struct PowerSupply {
+    pub on_ac: bool,
+    pub battery_percentage: u32,
+}
+
+/// Provides the power supply state, `None` on machines without a battery
+pub trait PowerSource {
+    fn read(&self) -> Option<PowerSupply>;
+}
+
+/// Future returned by the asynchronous source methods
+pub type SourceFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Provides the registered tray items, their menus and changes to them
+pub trait TraySource: Send + Sync {
+    /// Items registered so far, keyed by service
+    fn items(&self) -> Vec<(String, StatusNotifierItem)>;
+
+    /// Latest menu layout of an item, `None` until it published one
+    fn menu(&self, service_key: &str) -> Option<TrayMenu>;
+
+    /// Items added, updated and removed from now on. Closes when the source stops working.
+    fn subscribe(&self) -> broadcast::Receiver<TrayEvent>;
+
+    /// Resolves when the source has to be replaced even though it still delivers events
+    fn lost(&self) -> SourceFuture<'static, ()>;
+
+    /// Forward a click or a menu entry activation to an item, returning an error message
+    /// on failure
+    fn activate(&self, request: ActivateRequest) -> SourceFuture<'_, Result<(), String>>;
+
+    /// Scroll an item by `delta` steps, `orientation` is "vertical" or "horizontal"
+    fn scroll<'a>(
+        &'a self,
+        service_key: &'a str,
+        delta: i32,
+        orientation: &'a str,
+    ) -> SourceFuture<'a, Result<(), String>>;
+
+    /// Hand an item the token for focusing a window it opens on the next activation
+    fn provide_xdg_activation_token<'a>(
+        &'a self,
+        service_key: &'a str,
+        token: String,
+    ) -> SourceFuture<'a, Result<(), String>>;
+}
+
+/// Whether fake data sources were requested
+pub fn use_mocks() -> bool {
+    cfg!(feature = "mock") && std::env::var_os("BLADE_BAR_MOCK").is_some()
+}
+
+pub fn system_source() -> Box<dyn SystemSource> {
+    #[cfg(feature = "mock")]
+    if use_mocks() {
+        return Box::new(mock::MockSystem::default());
+    }
+    Box::new(SysinfoSource::new())
+}
+
+pub fn power_source() -> Box<dyn PowerSource> {
+    #[cfg(feature = "mock")]
+    if use_mocks() {
+        return Box::new(mock::MockPower::new());
+    }
+    Box::new(SysfsPowerSource)
+}
+
+/// Workspace and window backend of the running compositor, if any
+pub fn compositor_backend() -> Option<Box<dyn CompositorBackend>> {
+    #[cfg(feature = "mock")]
+    if use_mocks() {
+        return Some(Box::new(mock::MockCompositor));
+    }
+    Some(Compositor::detect()?.backend())
+}
+
+/// Register a tray host with the StatusNotifierWatcher. The fakes are regular items
+/// published next to it, so the real source is used either way.
+pub async fn tray_source() -> Result<Arc<dyn TraySource>, String> {
+    Ok(Arc::new(WatcherTraySource::connect().await?))
+}
+
+/// Publish the fake tray items once the tray host is running
+pub fn tray_fixtures() {
+    #[cfg(feature = "mock")]
+    if use_mocks() {
+        mock_tray::publish();
+    }
+}
@@ -0,0 +1,198 @@
+// Scripted fakes for the data sources, cycling through their interesting states
+// on a fixed schedule so every visual state shows up within a few seconds.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::debug;
+
+use crate::compositor::{ActiveWindow, CompositorBackend, Workspace};
+use crate::sources::{
+    CoreReading, InterfaceReading, MemoryReading, PowerSource, PowerSupply, ProcessReading,
+    SystemReadings, SystemSource,
+};
+
+/// How long each fake state lasts
+const STEP: Duration = Duration::from_secs(2);
+
+/// Number of steps since the epoch, shared by all fakes so they change together
+fn current_step() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs() / STEP.as_secs())
+        .unwrap_or(0)
+}
+
+/// Load sweeping from idle to saturated and back
+#[derive(Default)]
+pub struct MockSystem {
+    samples: u32,
+}
+
+/// Processes whose load follows the sweep, in order of their peak
+const PROCESSES: &[(&str, f32, u64)] = &[
+    ("cargo", 380.0, 900),
+    ("firefox", 120.0, 2600),
+    ("code", 60.0, 1400),
+    ("pipewire", 8.0, 40),
+    ("blade_bar", 3.0, 80),
+    ("foot", 1.0, 30),
+];
+
+impl SystemSource for MockSystem {
+    fn sample(&mut self) -> SystemReadings {
+        self.samples += 1;
+        let load = (self.samples as f32 * 0.4).sin().abs();
+
+        // Eight cores out of phase with each other
+        let cores = (0..8)
+            .map(|core| {
+                let usage = ((self.samples + core * 3) as f32 * 0.4).sin().abs() * 97.0 + 2.0;
+                CoreReading {
+                    usage,
+                    frequency: 800 + (usage * 40.0) as u64,
+                }
+            })
+            .collect();
+
+        let processes: Vec<ProcessReading> = PROCESSES
+            .iter()
+            .enumerate()
+            .map(|(index, (name, cpu, memory))| ProcessReading {
+                pid: 1000 + index as u32 * 111,
+                name: name.to_string(),
+                cpu: cpu * load,
+                memory: memory << 20,
+            })
+            .collect();
+        let mut top_memory = processes.clone();
+        top_memory.sort_by_key(|process| std::cmp::Reverse(process.memory));
+
+        SystemReadings {
+            cpu_usage: Some(2.0 + load * 97.0),
+            cores,
+            memory_percentage: Some(30.0 + load as f64 * 68.0),
+            // 16 GiB with swap only used near the top of the sweep
+            memory: Some(MemoryReading {
+                total: 16 << 30,
+                used: ((0.3 + load as f64 * 0.68) * (16u64 << 30) as f64) as u64,
+                available: ((0.7 - load as f64 * 0.68) * (16u64 << 30) as f64) as u64,
+                buffers_cache: 3 << 30,
+                swap_total: 8 << 30,
+                swap_used: if load > 0.8 { 1 << 30 } else { 0 },
+            }),
+            // Sensor missing every tenth sample
+            temperature: if self.samples % 10 == 0 { 0.0 } else { 38.0 + load * 60.0 },
+            // A download ramping between idle and 50 MB/s, plus an idle loopback
+            interfaces: vec![
+                InterfaceReading {
+                    name: "wlan0".to_string(),
+                    rx_rate: load as f64 * 50_000_000.0,
+                    tx_rate: load as f64 * 120_000.0,
+                    rx_total: self.samples as u64 * 40_000_000,
+                    tx_total: self.samples as u64 * 100_000,
+                },
+                InterfaceReading {
+                    name: "lo".to_string(),
+                    rx_rate: 0.0,
+                    tx_rate: 0.0,
+                    rx_total: 0,
+                    tx_total: 0,
+                },
+            ],
+            top_cpu: processes,
+            top_memory,
+        }
+    }
+}
+
+/// Battery discharging from full, then charging again
+pub struct MockPower {
+    started: Instant,
+}
+
+impl MockPower {
+    pub fn new() -> Self {
+        MockPower {
+            started: Instant::now(),
+        }
+    }
+}
+
+impl PowerSource for MockPower {
+    fn read(&self) -> Option<PowerSupply> {
+        // One percent per second, 200 second cycle
+        let position = self.started.elapsed().as_secs() % 200;
+        let (on_ac, battery_percentage) = if position < 100 {
+            (false, 100 - position as u32)
+        } else {
+            (true, position as u32 - 100)
+        };
+
+        Some(PowerSupply {
+            on_ac,
+            battery_percentage,
+        })
+    }
+}
+
+/// Five workspaces with focus moving along and one becoming urgent
+pub struct MockCompositor;
+
+const TITLES: &[(&str, &str)] = &[
+    ("firefox", "Mozilla Firefox"),
+    ("code", "main.rs - blade_bar - Visual Studio Code"),
+    ("foot", "~/src/blade_bar"),
+    ("", ""),
+];
+
+impl MockCompositor {
+    /// Send on `changed` every step, from a task on the main runtime like the real backends
+    fn tick(changed: UnboundedSender<()>) {
+        tokio::spawn(async move {
+            while changed.send(()).is_ok() {
+                tokio::time::sleep(STEP).await;
+            }
+        });
+    }
+}
+
+impl CompositorBackend for MockCompositor {
+    fn workspaces(&self) -> Vec<Workspace> {
+        let step = current_step();
+        let focused = (step % 5) as i64 + 1;
+        let urgent = ((step + 2) % 5) as i64 + 1;
+
+        (1..=5)
+            .map(|id| Workspace {
+                id,
+                name: id.to_string(),
+                focused: id == focused,
+                urgent: id == urgent && step % 3 == 0,
+            })
+            .collect()
+    }
+
+    fn focus_workspace(&self, workspace: &Workspace) {
+        debug!("Mock compositor: focus workspace {}", workspace.name);
+    }
+
+    fn watch_workspaces(&self, changed: UnboundedSender<()>) {
+        Self::tick(changed);
+    }
+
+    fn active_window(&self) -> Option<ActiveWindow> {
+        let (app_id, title) = TITLES[current_step() as usize % TITLES.len()];
+        if app_id.is_empty() {
+            return None;
+        }
+
+        Some(ActiveWindow {
+            app_id: app_id.to_string(),
+            title: title.to_string(),
+        })
+    }
+
+    fn watch_active_window(&self, changed: UnboundedSender<()>) {
+        Self::tick(changed);
+    }
+}
@@ -0,0 +1,502 @@
+// Fake StatusNotifierItems covering the behaviors the tray has to render: icons by
+// name, pixmaps of several sizes, attention states, tooltips and deeply nested menus.
+// Each item gets its own connection and well-known name, like a separate application.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, Type, Value};
+use tracing::{debug, warn};
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+
+/// How often the attention item switches between `Active` and `NeedsAttention`
+const ATTENTION_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Icon sizes published by the pixmap item, including ones that need scaling
+const PIXMAP_SIZES: &[i32] = &[16, 22, 24, 32, 48, 64, 128];
+
+/// ARGB32 image in network byte order: width, height, pixels
+type Pixmap = (i32, i32, Vec<u8>);
+
+/// Icon name, icon pixmaps, title and description
+type ToolTip = (String, Vec<Pixmap>, String, String);
+
+#[zbus::proxy(
+    interface = "org.kde.StatusNotifierWatcher",
+    default_service = "org.kde.StatusNotifierWatcher",
+    default_path = "/StatusNotifierWatcher"
+)]
+trait StatusNotifierWatcher {
+    fn register_status_notifier_item(&self, service: &str) -> zbus::Result<()>;
+}
+
+/// One fake tray item
+struct MockItem {
+    id: &'static str,
+    title: &'static str,
+    status: &'static str,
+    icon_name: &'static str,
+    icon_pixmap: Vec<Pixmap>,
+    attention_icon_name: &'static str,
+    tool_tip: Option<(&'static str, &'static str)>,
+    /// Activation opens the menu instead of calling `Activate`
+    item_is_menu: bool,
+}
+
+impl MockItem {
+    fn new(id: &'static str, title: &'static str) -> Self {
+        MockItem {
+            id,
+            title,
+            status: "Active",
+            icon_name: "",
+            icon_pixmap: Vec::new(),
+            attention_icon_name: "",
+            tool_tip: None,
+            item_is_menu: false,
+        }
+    }
+}
+
+#[zbus::interface(name = "org.kde.StatusNotifierItem")]
+impl MockItem {
+    #[zbus(property)]
+    fn category(&self) -> &str {
+        "ApplicationStatus"
+    }
+
+    #[zbus(property)]
+    fn id(&self) -> &str {
+        self.id
+    }
+
+    #[zbus(property)]
+    fn title(&self) -> &str {
+        self.title
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        self.status
+    }
+
+    #[zbus(property)]
+    fn window_id(&self) -> i32 {
+        0
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> &str {
+        self.icon_name
+    }
+
+    #[zbus(property)]
+    fn icon_pixmap(&self) -> Vec<Pixmap> {
+        self.icon_pixmap.clone()
+    }
+
+    #[zbus(property)]
+    fn overlay_icon_name(&self) -> &str {
+        ""
+    }
+
+    #[zbus(property)]
+    fn attention_icon_name(&self) -> &str {
+        self.attention_icon_name
+    }
+
+    #[zbus(property)]
+    fn attention_movie_name(&self) -> &str {
+        ""
+    }
+
+    #[zbus(property)]
+    fn tool_tip(&self) -> ToolTip {
+        let (title, description) = self.tool_tip.unwrap_or_default();
+        (
+            self.icon_name.to_string(),
+            Vec::new(),
+            title.to_string(),
+            description.to_string(),
+        )
+    }
+
+    #[zbus(property)]
+    fn item_is_menu(&self) -> bool {
+        self.item_is_menu
+    }
+
+    #[zbus(property)]
+    fn menu(&self) -> OwnedObjectPath {
+        ObjectPath::from_static_str_unchecked(MENU_PATH).into()
+    }
+
+    fn activate(&self, x: i32, y: i32) {
+        debug!("Mock tray: {} activated at {},{}", self.id, x, y);
+    }
+
+    fn secondary_activate(&self, x: i32, y: i32) {
+        debug!("Mock tray: {} secondary activated at {},{}", self.id, x, y);
+    }
+
+    fn context_menu(&self, x: i32, y: i32) {
+        debug!("Mock tray: {} context menu at {},{}", self.id, x, y);
+    }
+
+    fn scroll(&self, delta: i32, orientation: &str) {
+        debug!("Mock tray: {} scrolled {} {}", self.id, delta, orientation);
+    }
+
+    #[zbus(signal)]
+    async fn new_status(emitter: &SignalEmitter<'_>, status: &str) -> zbus::Result<()>;
+}
+
+/// Menu entry, `children` makes it a submenu
+struct MenuNode {
+    id: i32,
+    properties: Vec<(&'static str, Value<'static>)>,
+    children: Vec<MenuNode>,
+}
+
+impl MenuNode {
+    fn new(id: i32, label: &'static str) -> Self {
+        MenuNode {
+            id,
+            properties: vec![("label", Value::from(label))],
+            children: Vec::new(),
+        }
+    }
+
+    fn separator(id: i32) -> Self {
+        MenuNode {
+            id,
+            properties: vec![("type", Value::from("separator"))],
+            children: Vec::new(),
+        }
+    }
+
+    fn with(mut self, name: &'static str, value: impl Into<Value<'static>>) -> Self {
+        self.properties.push((name, value.into()));
+        self
+    }
+
+    fn submenu(mut self, children: Vec<MenuNode>) -> Self {
+        self.properties
+            .push(("children-display", Value::from("submenu")));
+        self.children = children;
+        self
+    }
+
+    fn find(&self, id: i32) -> Option<&MenuNode> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(id))
+    }
+
+    fn find_mut(&mut self, id: i32) -> Option<&mut MenuNode> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children
+            .iter_mut()
+            .find_map(|child| child.find_mut(id))
+    }
+
+    fn property(&self, name: &str) -> Option<&Value<'static>> {
+        self.properties
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    fn label(&self) -> String {
+        match self.property("label") {
+            Some(Value::Str(label)) => label.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Layout of this node, `depth` levels of children deep (-1 for all)
+    fn layout(&self, depth: i32) -> MenuLayout {
+        let children = if depth == 0 {
+            Vec::new()
+        } else {
+            self.children
+                .iter()
+                .map(|child| Value::from(child.layout(depth - 1)))
+                .collect()
+        };
+
+        MenuLayout {
+            id: self.id,
+            properties: self.property_map(),
+            children,
+        }
+    }
+
+    fn property_map(&self) -> HashMap<String, Value<'static>> {
+        self.properties
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    value.try_clone().unwrap_or(Value::from("")),
+                )
+            })
+            .collect()
+    }
+}
+
+/// `(ia{sv}av)` node of the com.canonical.dbusmenu layout
+#[derive(serde::Serialize, Type, Value)]
+struct MenuLayout {
+    id: i32,
+    properties: HashMap<String, Value<'static>>,
+    children: Vec<Value<'static>>,
+}
+
+/// Menu with every kind of entry, nested several levels deep
+fn deep_menu() -> MenuNode {
+    let mut level = MenuNode::new(40, "Level 4").with("icon-name", "folder-symbolic");
+    for depth in (1..=3).rev() {
+        level = MenuNode::new(
+            40 + depth,
+            ["", "Level 1", "Level 2", "Level 3"][depth as usize],
+        )
+        .submenu(vec![MenuNode::new(50 + depth, "Sibling entry"), level]);
+    }
+
+    MenuNode::new(0, "").submenu(vec![
+        MenuNode::new(1, "Plain entry"),
+        MenuNode::new(2, "Entry with icon").with("icon-name", "document-open-symbolic"),
+        MenuNode::new(3, "Disabled entry").with("enabled", false),
+        MenuNode::new(4, "Hidden entry").with("visible", false),
+        MenuNode::separator(5),
+        MenuNode::new(6, "Checkbox")
+            .with("toggle-type", "checkmark")
+            .with("toggle-state", 1),
+        MenuNode::new(7, "Radio A")
+            .with("toggle-type", "radio")
+            .with("toggle-state", 1),
+        MenuNode::new(8, "Radio B")
+            .with("toggle-type", "radio")
+            .with("toggle-state", 0),
+        MenuNode::separator(9),
+        MenuNode::new(
+            10,
+            "_Mnemonic and a very long label that should be ellipsized or wrapped",
+        ),
+        level,
+    ])
+}
+
+/// com.canonical.dbusmenu server for a static tree, toggles update on click
+struct MockMenu {
+    root: MenuNode,
+    revision: u32,
+}
+
+#[zbus::interface(name = "com.canonical.dbusmenu")]
+impl MockMenu {
+    #[zbus(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[zbus(property)]
+    fn text_direction(&self) -> &str {
+        "ltr"
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> &str {
+        "normal"
+    }
+
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn get_layout(
+        &self,
+        parent_id: i32,
+        recursion_depth: i32,
+        _property_names: Vec<String>,
+    ) -> zbus::fdo::Result<(u32, MenuLayout)> {
+        let node = self
+            .root
+            .find(parent_id)
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("No menu entry {}", parent_id)))?;
+        Ok((self.revision, node.layout(recursion_depth)))
+    }
+
+    fn get_group_properties(
+        &self,
+        ids: Vec<i32>,
+        _property_names: Vec<String>,
+    ) -> Vec<(i32, HashMap<String, Value<'static>>)> {
+        ids.into_iter()
+            .filter_map(|id| self.root.find(id))
+            .map(|node| (node.id, node.property_map()))
+            .collect()
+    }
+
+    fn get_property(&self, id: i32, name: &str) -> zbus::fdo::Result<Value<'static>> {
+        self.root
+            .find(id)
+            .and_then(|node| node.property(name))
+            .and_then(|value| value.try_clone().ok())
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("No property {} on {}", name, id)))
+    }
+
+    async fn event(
+        &mut self,
+        id: i32,
+        event_id: &str,
+        _data: Value<'_>,
+        _timestamp: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) {
+        if event_id != "clicked" {
+            return;
+        }
+
+        let Some(node) = self.root.find_mut(id) else {
+            return;
+        };
+        debug!("Mock tray: menu entry '{}' clicked", node.label());
+
+        // Flip checkboxes so state changes can be checked too
+        if matches!(node.property("toggle-type"), Some(Value::Str(kind)) if kind.as_str() == "checkmark")
+        {
+            let checked = matches!(node.property("toggle-state"), Some(Value::I32(1)));
+            node.properties.retain(|(name, _)| *name != "toggle-state");
+            node.properties
+                .push(("toggle-state", Value::from(if checked { 0 } else { 1 })));
+
+            self.revision += 1;
+            if let Err(e) = Self::layout_updated(&emitter, self.revision, 0).await {
+                warn!("Mock tray: failed to emit LayoutUpdated: {}", e);
+            }
+        }
+    }
+
+    fn about_to_show(&self, _id: i32) -> bool {
+        false
+    }
+
+    #[zbus(signal)]
+    async fn layout_updated(
+        emitter: &SignalEmitter<'_>,
+        revision: u32,
+        parent: i32,
+    ) -> zbus::Result<()>;
+}
+
+/// Solid square with a border so scaling artifacts are visible
+fn pixmap(size: i32) -> Pixmap {
+    let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let border = x == 0 || y == 0 || x == size - 1 || y == size - 1;
+            let [r, g, b] = if border {
+                [255, 255, 255]
+            } else {
+                [size as u8 * 2, 120, 200]
+            };
+            pixels.extend_from_slice(&[255, r, g, b]);
+        }
+    }
+    (size, size, pixels)
+}
+
+/// The matrix of items published by the fixture
+fn items() -> Vec<MockItem> {
+    let mut by_name = MockItem::new("mock-icon-name", "Icon by name");
+    by_name.icon_name = "network-wireless-symbolic";
+
+    let mut pixmaps = MockItem::new("mock-pixmaps", "Pixmaps only");
+    pixmaps.icon_pixmap = PIXMAP_SIZES.iter().map(|size| pixmap(*size)).collect();
+
+    let mut attention = MockItem::new("mock-attention", "Attention");
+    attention.icon_name = "mail-read-symbolic";
+    attention.attention_icon_name = "mail-unread-symbolic";
+
+    let mut tool_tip = MockItem::new("mock-tooltip", "Tooltip");
+    tool_tip.icon_name = "dialog-information-symbolic";
+    tool_tip.tool_tip = Some((
+        "Tooltip title",
+        "Description with <b>markup</b>\nand a second line",
+    ));
+
+    let mut menu = MockItem::new("mock-menu", "Deep menu");
+    menu.icon_name = "open-menu-symbolic";
+    menu.item_is_menu = true;
+
+    let mut passive = MockItem::new("mock-passive", "Passive");
+    passive.icon_name = "weather-clear-night-symbolic";
+    passive.status = "Passive";
+
+    vec![by_name, pixmaps, attention, tool_tip, menu, passive]
+}
+
+/// Publish the fake items and register them with the StatusNotifierWatcher
+pub fn publish() {
+    let pid = std::process::id();
+    for (index, item) in items().into_iter().enumerate() {
+        let bus_name = format!("org.kde.StatusNotifierItem-{}-{}", pid, index + 1);
+        glib::spawn_future_local(async move {
+            if let Err(e) = serve(&bus_name, item).await {
+                warn!("Mock tray: failed to publish {}: {}", bus_name, e);
+            }
+        });
+    }
+}
+
+async fn serve(bus_name: &str, item: MockItem) -> zbus::Result<()> {
+    let cycles_attention = !item.attention_icon_name.is_empty();
+    let menu = MockMenu {
+        root: deep_menu(),
+        revision: 1,
+    };
+
+    let connection = zbus::connection::Builder::session()?
+        .name(bus_name)?
+        .serve_at(ITEM_PATH, item)?
+        .serve_at(MENU_PATH, menu)?
+        .build()
+        .await?;
+
+    StatusNotifierWatcherProxy::new(&connection)
+        .await?
+        .register_status_notifier_item(bus_name)
+        .await?;
+
+    if !cycles_attention {
+        // Keep the connection, and with it the item, alive
+        std::future::pending::<()>().await;
+    }
+
+    let interface = connection
+        .object_server()
+        .interface::<_, MockItem>(ITEM_PATH)
+        .await?;
+    loop {
+        tokio::time::sleep(ATTENTION_INTERVAL).await;
+
+        let status = {
+            let mut item = interface.get_mut().await;
+            item.status = if item.status == "Active" {
+                "NeedsAttention"
+            } else {
+                "Active"
+            };
+            item.status
+        };
+        MockItem::new_status(interface.signal_emitter(), status).await?;
+    }
+}
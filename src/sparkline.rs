@@ -0,0 +1,102 @@
+// Small line graph of the most recent values of a metric, drawn with cairo.
+
+use gtk4::gdk::RGBA;
+use gtk4::prelude::*;
+use gtk4::DrawingArea;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use tracing::warn;
+
+/// Logical size of the graph
+const WIDTH: i32 = 48;
+const HEIGHT: i32 = 16;
+
+/// Opacity of the area under the line
+const FILL_ALPHA: f32 = 0.3;
+
+pub struct Sparkline {
+    pub area: DrawingArea,
+    history: Rc<RefCell<VecDeque<f64>>>,
+    length: usize,
+}
+
+impl Sparkline {
+    /// Graph of the last `length` values between 0 and `max`
+    pub fn new(length: usize, max: f64, color: RGBA) -> Self {
+        let length = length.max(2);
+        let history: Rc<RefCell<VecDeque<f64>>> =
+            Rc::new(RefCell::new(VecDeque::with_capacity(length)));
+
+        let area = DrawingArea::new();
+        area.add_css_class("sparkline");
+        area.set_content_width(WIDTH);
+        area.set_content_height(HEIGHT);
+        area.set_valign(gtk4::Align::Center);
+
+        let history_draw = history.clone();
+        area.set_draw_func(move |_, cr, width, height| {
+            let history = history_draw.borrow();
+            if history.len() < 2 {
+                return;
+            }
+
+            // Newest value on the right edge, older ones scroll off to the left
+            let (width, height) = (width as f64, height as f64);
+            let step = width / (length - 1) as f64;
+            let offset = (length - history.len()) as f64 * step;
+            let y = |value: f64| height - (value / max).clamp(0.0, 1.0) * (height - 1.0) - 0.5;
+
+            cr.move_to(offset, y(history[0]));
+            for (index, value) in history.iter().enumerate().skip(1) {
+                cr.line_to(offset + index as f64 * step, y(*value));
+            }
+
+            cr.set_source_rgba(
+                color.red() as f64,
+                color.green() as f64,
+                color.blue() as f64,
+                color.alpha() as f64,
+            );
+            cr.set_line_width(1.0);
+            let _ = cr.stroke_preserve();
+
+            cr.line_to(width, height);
+            cr.line_to(offset, height);
+            cr.close_path();
+            cr.set_source_rgba(
+                color.red() as f64,
+                color.green() as f64,
+                color.blue() as f64,
+                (color.alpha() * FILL_ALPHA) as f64,
+            );
+            let _ = cr.fill();
+        });
+
+        Sparkline {
+            area,
+            history,
+            length,
+        }
+    }
+
+    /// Add the newest value, dropping the oldest once the graph is full
+    pub fn push(&self, value: f64) {
+        let mut history = self.history.borrow_mut();
+        if history.len() == self.length {
+            history.pop_front();
+        }
+        history.push_back(value);
+        drop(history);
+
+        self.area.queue_draw();
+    }
+}
+
+/// Parse a configured CSS color, white if it is not valid
+pub fn parse_color(color: &str) -> RGBA {
+    RGBA::parse(color).unwrap_or_else(|_| {
+        warn!("Invalid graph color '{}'", color);
+        RGBA::WHITE
+    })
+}
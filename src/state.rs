@@ -0,0 +1,90 @@
+// Small bits of user state that survive a restart of the bar, kept in
+// `$XDG_STATE_HOME/blade_bar/state.json`. Unlike the configuration this is only
+// written by the bar itself, whenever one of the values changes.
+
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+use tracing::warn;
+
+use crate::config::state_dir;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    /// Bars hidden through the control interface
+    pub hidden: bool,
+    /// Idle inhibitor switched on from the bar
+    pub idle_inhibited: bool,
+    /// Default sink of the audio profile picked last
+    pub audio_sink: Option<String>,
+    /// Group drawers opened or closed by clicking, by group name
+    pub expanded_groups: BTreeMap<String, bool>,
+    /// Tray items past `max_visible` shown on the bar instead of behind the chevron
+    pub tray_overflow_expanded: bool,
+    /// Modules hidden through the control interface, by their name in the layout
+    pub hidden_modules: BTreeSet<String>,
+    /// Accessibility profile switched on
+    pub accessibility: bool,
+    /// Night light temperature picked last with the slider
+    pub night_light_temperature: Option<u32>,
+}
+
+thread_local! {
+    static STATE: RefCell<Option<State>> = const { RefCell::new(None) };
+}
+
+fn path() -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join("state.json"))
+}
+
+/// The saved state, read from disk on first use
+pub fn get() -> State {
+    STATE.with(|state| state.borrow_mut().get_or_insert_with(read).clone())
+}
+
+/// Change the saved state and write it out
+pub fn update(change: impl FnOnce(&mut State)) {
+    let state = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let state = state.get_or_insert_with(read);
+        change(state);
+        state.clone()
+    });
+    write(&state);
+}
+
+fn read() -> State {
+    let Some(path) = path() else {
+        return State::default();
+    };
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return State::default();
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|e| {
+        warn!("Ignoring unreadable state in '{}': {}", path.display(), e);
+        State::default()
+    })
+}
+
+fn write(state: &State) {
+    let Some(path) = path() else {
+        return;
+    };
+    let Ok(json) = serde_json::to_string_pretty(state) else {
+        return;
+    };
+
+    // Write next to it and rename, so a crash never leaves a truncated file behind
+    let partial = path.with_extension("json.tmp");
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&partial, json))
+        .and_then(|_| fs::rename(&partial, &path));
+    if let Err(e) = result {
+        warn!("Failed to save state to '{}': {}", path.display(), e);
+    }
+}
@@ -0,0 +1,233 @@
+use gio::{FileMonitor, FileMonitorEvent, FileMonitorFlags};
+use gtk4::prelude::*;
+use gtk4::{CssProvider, gdk::Display};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+use crate::config::{BarConfig, Config, config_home};
+
+use crate::themes;
+
+/// Theme presets sit between the built-in style and the user stylesheet
+const THEME_PRIORITY: u32 = gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION + 100;
+
+/// Palette colors override theme presets
+const PALETTE_PRIORITY: u32 = THEME_PRIORITY + 25;
+
+/// The configured font overrides theme presets, the user stylesheet still wins
+const FONT_PRIORITY: u32 = THEME_PRIORITY + 50;
+
+/// The accessibility profile overrides theme and font, the user stylesheet still wins
+const ACCESSIBILITY_PRIORITY: u32 = FONT_PRIORITY + 25;
+
+thread_local! {
+    /// Keeps the user stylesheet monitor alive for the lifetime of the bar
+    static USER_STYLE_MONITOR: RefCell<Option<FileMonitor>> = const { RefCell::new(None) };
+    static THEME_PROVIDER: RefCell<Option<CssProvider>> = const { RefCell::new(None) };
+    static PALETTE_PROVIDER: RefCell<Option<CssProvider>> = const { RefCell::new(None) };
+    static FONT_PROVIDER: RefCell<Option<CssProvider>> = const { RefCell::new(None) };
+    static ACCESSIBILITY_PROVIDER: RefCell<Option<CssProvider>> = const { RefCell::new(None) };
+}
+
+/// Path of the user stylesheet, `$XDG_CONFIG_HOME/blade_bar/style.css`
+pub fn user_css_path() -> Option<PathBuf> {
+    Some(config_home()?.join("blade_bar").join("style.css"))
+}
+
+pub fn load_css() {
+    let Some(display) = Display::default() else {
+        return;
+    };
+
+    let css_provider = CssProvider::new();
+
+    // Built-in stylesheet
+    css_provider.load_from_data(include_str!("style.css"));
+
+    // Apply CSS to the default display
+    gtk4::style_context_add_provider_for_display(
+        &display,
+        &css_provider,
+        gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+
+    let theme_provider = CssProvider::new();
+    gtk4::style_context_add_provider_for_display(&display, &theme_provider, THEME_PRIORITY);
+    THEME_PROVIDER.with(|provider| *provider.borrow_mut() = Some(theme_provider));
+
+    let palette_provider = CssProvider::new();
+    gtk4::style_context_add_provider_for_display(&display, &palette_provider, PALETTE_PRIORITY);
+    PALETTE_PROVIDER.with(|provider| *provider.borrow_mut() = Some(palette_provider));
+
+    let font_provider = CssProvider::new();
+    gtk4::style_context_add_provider_for_display(&display, &font_provider, FONT_PRIORITY);
+    FONT_PROVIDER.with(|provider| *provider.borrow_mut() = Some(font_provider));
+
+    let accessibility_provider = CssProvider::new();
+    gtk4::style_context_add_provider_for_display(
+        &display,
+        &accessibility_provider,
+        ACCESSIBILITY_PRIORITY,
+    );
+    ACCESSIBILITY_PROVIDER.with(|provider| *provider.borrow_mut() = Some(accessibility_provider));
+
+    load_user_css(&display);
+}
+
+/// Switch to the bundled theme preset `name`, or back to the built-in look with `None`
+pub fn set_theme(name: Option<&str>) {
+    let css = match name {
+        Some(name) => themes::preset(name).unwrap_or_else(|| {
+            warn!(
+                "Unknown theme '{}', available: {}",
+                name,
+                themes::preset_names().join(", ")
+            );
+            ""
+        }),
+        None => "",
+    };
+
+    THEME_PROVIDER.with(|provider| {
+        if let Some(provider) = provider.borrow().as_ref() {
+            provider.load_from_data(css);
+        }
+    });
+}
+
+/// Define the palette and `colors` as CSS custom properties, e.g. `var(--accent)`,
+/// and color the bar with them. Nothing is loaded when neither is configured.
+pub fn set_palette(config: &Config) {
+    let css = palette_css(config).unwrap_or_default();
+    PALETTE_PROVIDER.with(|provider| {
+        if let Some(provider) = provider.borrow().as_ref() {
+            provider.load_from_data(&css);
+        }
+    });
+}
+
+/// Stylesheet for the configured palette and colors, `None` when there are none
+pub fn palette_css(config: &Config) -> Option<String> {
+    if config.palette.is_none() && config.colors.is_empty() {
+        return None;
+    }
+
+    let name = config.palette.as_deref().unwrap_or(themes::DEFAULT_PALETTE);
+    let mut colors: BTreeMap<&str, &str> = match themes::palette(name) {
+        Some(colors) => colors.collect(),
+        None => {
+            warn!(
+                "Unknown palette '{}', available: {}",
+                name,
+                themes::palette_names().join(", ")
+            );
+            themes::palette(themes::DEFAULT_PALETTE)?.collect()
+        }
+    };
+    for (name, value) in &config.colors {
+        let valid_name = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if name.is_empty() || !valid_name || value.contains([';', '{', '}']) {
+            warn!("Ignoring color '{}' = '{}'", name, value);
+            continue;
+        }
+        colors.insert(name, value);
+    }
+
+    // Custom properties inherit, so every window and popover sees them
+    let mut css = String::from("window {\n");
+    for (name, value) in colors {
+        css.push_str(&format!("    --{}: {};\n", name, value));
+    }
+    css.push_str("}\n\n");
+    css.push_str(themes::PALETTE_CSS);
+    Some(css)
+}
+
+/// Apply the configured font family to every widget of the bar and the size to those
+/// that do not set their own
+pub fn set_font(config: &BarConfig) {
+    let mut css = String::new();
+    if let Some(family) = &config.font_family {
+        let family = family.replace('\\', "\\\\").replace('"', "\\\"");
+        css.push_str(&format!(
+            ".main-window, .main-window * {{ font-family: \"{}\"; }}\n",
+            family
+        ));
+    }
+    if let Some(size) = config.font_size {
+        css.push_str(&format!(".main-window {{ font-size: {}pt; }}\n", size));
+    }
+
+    FONT_PROVIDER.with(|provider| {
+        if let Some(provider) = provider.borrow().as_ref() {
+            provider.load_from_data(&css);
+        }
+    });
+}
+
+/// Replace the accessibility profile stylesheet, empty when the profile is off
+pub fn set_accessibility_css(css: &str) {
+    ACCESSIBILITY_PROVIDER.with(|provider| {
+        if let Some(provider) = provider.borrow().as_ref() {
+            provider.load_from_data(css);
+        }
+    });
+}
+
+/// Layer the user stylesheet over the built-in one and re-apply it whenever it changes on disk
+fn load_user_css(display: &Display) {
+    let Some(path) = user_css_path() else {
+        return;
+    };
+
+    let user_provider = CssProvider::new();
+    user_provider.connect_parsing_error(|_, section, error| {
+        let location = section.start_location();
+        warn!(
+            "style.css:{}:{}: {}",
+            location.lines() + 1,
+            location.line_chars() + 1,
+            error
+        );
+    });
+    gtk4::style_context_add_provider_for_display(
+        display,
+        &user_provider,
+        gtk4::STYLE_PROVIDER_PRIORITY_USER,
+    );
+
+    let file = gio::File::for_path(&path);
+    reload_user_css(&user_provider, &file);
+
+    // Watching a missing file is fine, the monitor reports it once it is created
+    match file.monitor_file(FileMonitorFlags::NONE, gio::Cancellable::NONE) {
+        Ok(monitor) => {
+            monitor.connect_changed(move |_, file, _, event| {
+                if matches!(
+                    event,
+                    FileMonitorEvent::ChangesDoneHint
+                        | FileMonitorEvent::Created
+                        | FileMonitorEvent::Deleted
+                ) {
+                    info!("Reloading {}", file.parse_name());
+                    reload_user_css(&user_provider, file);
+                }
+            });
+            USER_STYLE_MONITOR.with(|stored| *stored.borrow_mut() = Some(monitor));
+        }
+        Err(e) => warn!("Failed to watch '{}': {}", path.display(), e),
+    }
+}
+
+fn reload_user_css(provider: &CssProvider, file: &gio::File) {
+    if file.query_exists(gio::Cancellable::NONE) {
+        provider.load_from_file(file);
+    } else {
+        // Stylesheet removed, fall back to the built-in look
+        provider.load_from_data("");
+    }
+}
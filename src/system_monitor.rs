@@ -1,27 +1,445 @@
 use gtk4::prelude::*;
-use gtk4::{Box, Label, Orientation};
-use glib::timeout_add_local;
-use glib::ControlFlow;
-use sysinfo::System;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use gtk4::{
+    Box, Button, EventSequenceState, GestureClick, Grid, Label, LevelBar, ListBox, Orientation,
+    Popover, SelectionMode,
+};
+use sysinfo::{Networks, System, ThreadKind};
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::command::spawn_shell;
+use crate::config::{CpuDisplay, SystemMonitorConfig};
+use crate::control;
+use crate::module::BarModule;
+use crate::scheduler;
+use crate::sources::{
+    self, CoreReading, InterfaceReading, MemoryReading, ProcessReading,
+    SystemReadings as Readings, SystemSource,
+};
+use crate::sparkline::{Sparkline, parse_color};
+use crate::template::{Template, Value, pick_icon};
+
+type Subscriber = dyn Fn(&Readings);
+
+/// Length of each top process list
+const TOP_PROCESSES: usize = 8;
+
+/// Reads load and network traffic from sysinfo and temperature from sysfs
+pub struct SysinfoSource {
+    system: System,
+    networks: Networks,
+    last_sample: Instant,
+}
+
+impl SysinfoSource {
+    pub fn new() -> Self {
+        SysinfoSource {
+            system: System::new_all(),
+            networks: Networks::new_with_refreshed_list(),
+            last_sample: Instant::now(),
+        }
+    }
+}
+
+impl SystemSource for SysinfoSource {
+    fn sample(&mut self) -> Readings {
+        let sys = &mut self.system;
+        sys.refresh_all();
+
+        // CPU Usage - average of all CPUs
+        let cpu_usage = (!sys.cpus().is_empty()).then(|| {
+            sys.cpus().iter().map(|cpu| cpu.cpu_usage()).sum::<f32>() / sys.cpus().len() as f32
+        });
+        let cores = sys
+            .cpus()
+            .iter()
+            .map(|cpu| CoreReading {
+                usage: cpu.cpu_usage(),
+                frequency: cpu.frequency(),
+            })
+            .collect();
+
+        // Memory Usage
+        let total_memory = sys.total_memory();
+        let memory_percentage = (total_memory > 0)
+            .then(|| (sys.used_memory() as f64 / total_memory as f64) * 100.0);
+        let memory = (total_memory > 0).then(|| MemoryReading {
+            total: total_memory,
+            used: sys.used_memory(),
+            available: sys.available_memory(),
+            buffers_cache: read_buffers_cache(),
+            swap_total: sys.total_swap(),
+            swap_used: sys.used_swap(),
+        });
+
+        // Traffic since the previous sample, interfaces may come and go in between
+        self.networks.refresh_list();
+        let elapsed = self.last_sample.elapsed().as_secs_f64().max(0.001);
+        self.last_sample = Instant::now();
+        let interfaces = self
+            .networks
+            .list()
+            .iter()
+            .map(|(name, data)| InterfaceReading {
+                name: name.clone(),
+                rx_rate: data.received() as f64 / elapsed,
+                tx_rate: data.transmitted() as f64 / elapsed,
+                rx_total: data.total_received(),
+                tx_total: data.total_transmitted(),
+            })
+            .collect();
+
+        // Threads are listed next to their process on Linux, only count processes
+        let mut processes: Vec<ProcessReading> = sys
+            .processes()
+            .values()
+            .filter(|process| process.thread_kind() != Some(ThreadKind::Userland))
+            .map(|process| ProcessReading {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string(),
+                cpu: process.cpu_usage(),
+                memory: process.memory(),
+            })
+            .collect();
+        processes.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+        let top_cpu = processes.iter().take(TOP_PROCESSES).cloned().collect();
+        processes.sort_by_key(|process| Reverse(process.memory));
+        processes.truncate(TOP_PROCESSES);
+
+        Readings {
+            cpu_usage,
+            cores,
+            memory_percentage,
+            memory,
+            temperature: SystemMonitor::get_cpu_temperature(),
+            interfaces,
+            top_cpu,
+            top_memory: processes,
+        }
+    }
+}
+
+/// Samples the system once per tick for every system monitor on every bar
+struct Sampler {
+    source: RefCell<std::boxed::Box<dyn SystemSource>>,
+    subscribers: RefCell<Vec<Weak<Subscriber>>>,
+}
+
+thread_local! {
+    static SAMPLER: RefCell<Option<Rc<Sampler>>> = const { RefCell::new(None) };
+    /// Last level and notification time of each metric, shared by the monitors of all
+    /// bars so each alert is only sent once
+    static ALERTS: RefCell<HashMap<&'static str, (Level, Option<Instant>)>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Warning and critical level of a metric, 0 disables a level
+#[derive(Debug, Clone, Copy)]
+struct Thresholds {
+    warning: f64,
+    critical: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl Thresholds {
+    fn level(self, value: f64) -> Level {
+        if self.critical > 0.0 && value >= self.critical {
+            Level::Critical
+        } else if self.warning > 0.0 && value >= self.warning {
+            Level::Warning
+        } else {
+            Level::Normal
+        }
+    }
+}
+
+impl Sampler {
+    /// Register `subscriber`, starting the shared sampling timer on first use.
+    /// The subscription ends once the caller drops its `Rc`.
+    fn subscribe(subscriber: Rc<Subscriber>) {
+        let sampler = SAMPLER.with(|sampler| {
+            sampler
+                .borrow_mut()
+                .get_or_insert_with(|| {
+                    let sampler = Rc::new(Sampler {
+                        source: RefCell::new(sources::system_source()),
+                        subscribers: RefCell::new(Vec::new()),
+                    });
+
+                    // Update every 2 seconds (less often when running on a low battery)
+                    let sampler_tick = sampler.clone();
+                    scheduler::every(Duration::from_secs(2), move || sampler_tick.sample());
+                    sampler
+                })
+                .clone()
+        });
+
+        sampler.subscribers.borrow_mut().push(Rc::downgrade(&subscriber));
+    }
+
+    fn sample(&self) {
+        // Drop monitors that have been removed from the bar
+        self.subscribers
+            .borrow_mut()
+            .retain(|subscriber| subscriber.strong_count() > 0);
+        if self.subscribers.borrow().is_empty() {
+            return;
+        }
+
+        let readings = self.source.borrow_mut().sample();
+
+        let subscribers = self.subscribers.borrow().clone();
+        for subscriber in subscribers.iter().filter_map(Weak::upgrade) {
+            subscriber(&readings);
+        }
+
+        control::module_updated("system_monitor");
+    }
+}
 
 pub struct SystemMonitor {
     pub container: Box,
     cpu_label: Label,
     memory_label: Label,
     temp_label: Label,
-    system: Arc<Mutex<System>>,
+    /// One bar per core next to the CPU label, only filled with `per_core`
+    cores_box: Box,
+    /// Usage and frequency of every core, opened by clicking the monitor
+    core_list: Grid,
+    cpu_display: CpuDisplay,
+    templates: Rc<LabelTemplates>,
+    /// Recent CPU and memory usage, when graphs are enabled
+    graphs: Option<(Rc<Sparkline>, Rc<Sparkline>)>,
+    /// Download and upload rate, hidden unless enabled
+    network_label: Label,
+    /// Interfaces counted for the rates, all but loopback when empty
+    interfaces: Vec<String>,
+    network_totals: bool,
+    /// CPU, memory and temperature levels
+    thresholds: [Thresholds; 3],
+    /// Minimum time between notifications about a metric, `None` when alerts are off
+    alert_interval: Option<Duration>,
+    /// Busiest processes, opened by clicking the CPU or memory label
+    processes: Rc<ProcessPopover>,
+    // Keeps this monitor subscribed to the shared sampler
+    subscription: RefCell<Option<Rc<Subscriber>>>,
+}
+
+/// Label templates from the configuration, a `None` metric keeps its built-in label
+struct LabelTemplates {
+    cpu: Option<Template>,
+    memory: Option<Template>,
+    temperature: Option<Template>,
+    memory_format: Template,
+    icons: Vec<String>,
+    /// Temperature at which the last icon is reached
+    temperature_max: f64,
+}
+
+impl LabelTemplates {
+    fn icon(&self, value: f64, max: f64) -> Value {
+        pick_icon(&self.icons, value, max).into()
+    }
+}
+
+/// A line of the core list
+struct CoreRow {
+    level: LevelBar,
+    usage: Label,
+    frequency: Label,
+}
+
+/// Which top list the process popover shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ProcessSort {
+    Cpu,
+    Memory,
+}
+
+/// Busiest processes by CPU or memory, refreshed with every sample while open
+struct ProcessPopover {
+    popover: Popover,
+    title: Label,
+    list: ListBox,
+    terminate: Button,
+    sort: Cell<ProcessSort>,
+    /// Processes of the rows in order, to keep the selection across refreshes
+    shown: RefCell<Vec<ProcessReading>>,
+    /// Latest sample, so the list is filled as soon as it opens
+    latest: RefCell<Option<Readings>>,
+}
+
+impl ProcessPopover {
+    fn new(parent: &Box) -> Rc<Self> {
+        let content = Box::new(Orientation::Vertical, 6);
+        content.add_css_class("process-list");
+
+        let title = Label::new(None);
+        title.add_css_class("process-list-title");
+        title.set_xalign(0.0);
+
+        let list = ListBox::new();
+        list.set_selection_mode(SelectionMode::Single);
+
+        let terminate = Button::with_label("End process");
+        terminate.add_css_class("destructive-action");
+        terminate.set_sensitive(false);
+        terminate.set_halign(gtk4::Align::End);
+
+        content.append(&title);
+        content.append(&list);
+        content.append(&terminate);
+
+        let popover = Popover::new();
+        popover.set_child(Some(&content));
+        popover.set_parent(parent);
+
+        let processes = Rc::new(ProcessPopover {
+            popover,
+            title,
+            list,
+            terminate,
+            sort: Cell::new(ProcessSort::Cpu),
+            shown: RefCell::new(Vec::new()),
+            latest: RefCell::new(None),
+        });
+
+        let terminate_button = processes.terminate.clone();
+        processes.list.connect_row_selected(move |_, row| {
+            terminate_button.set_sensitive(row.is_some());
+        });
+
+        let processes_terminate = Rc::downgrade(&processes);
+        processes.terminate.connect_clicked(move |_| {
+            if let Some(processes) = processes_terminate.upgrade() {
+                processes.terminate_selected();
+            }
+        });
+
+        processes
+    }
+
+    fn open(&self, sort: ProcessSort) {
+        self.sort.set(sort);
+        self.title.set_text(match sort {
+            ProcessSort::Cpu => "Top processes by CPU",
+            ProcessSort::Memory => "Top processes by memory",
+        });
+        // Rows of the other list would keep a selection that means nothing here
+        self.shown.borrow_mut().clear();
+        self.refresh();
+        self.popover.popup();
+    }
+
+    fn update(&self, readings: &Readings) {
+        *self.latest.borrow_mut() = Some(readings.clone());
+        if self.popover.is_visible() {
+            self.refresh();
+        }
+    }
+
+    fn refresh(&self) {
+        let latest = self.latest.borrow();
+        let Some(readings) = latest.as_ref() else {
+            return;
+        };
+        let sort = self.sort.get();
+        let processes = match sort {
+            ProcessSort::Cpu => &readings.top_cpu,
+            ProcessSort::Memory => &readings.top_memory,
+        };
+        let total_memory = readings.memory.map(|memory| memory.total).unwrap_or(0);
+
+        let selected = self.list.selected_row().and_then(|row| {
+            let shown = self.shown.borrow();
+            shown.get(row.index() as usize).map(|process| process.pid)
+        });
+
+        self.list.remove_all();
+        for process in processes {
+            let row = Box::new(Orientation::Horizontal, 8);
+            let name = Label::new(Some(&process.name));
+            name.set_hexpand(true);
+            name.set_xalign(0.0);
+            name.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+            name.set_max_width_chars(24);
+            let pid = Label::new(Some(&process.pid.to_string()));
+            pid.add_css_class("process-pid");
+            let value = Label::new(Some(&match sort {
+                ProcessSort::Cpu => format!("{:.1}%", process.cpu),
+                ProcessSort::Memory if total_memory > 0 => format!(
+                    "{} ({:.0}%)",
+                    format_bytes(process.memory as f64, false),
+                    process.memory as f64 / total_memory as f64 * 100.0
+                ),
+                ProcessSort::Memory => format_bytes(process.memory as f64, false),
+            }));
+            value.add_css_class("process-value");
+            value.set_xalign(1.0);
+            row.append(&name);
+            row.append(&pid);
+            row.append(&value);
+            self.list.append(&row);
+        }
+        *self.shown.borrow_mut() = processes.clone();
+
+        let selected_index =
+            selected.and_then(|pid| processes.iter().position(|process| process.pid == pid));
+        match selected_index.and_then(|index| self.list.row_at_index(index as i32)) {
+            Some(row) => self.list.select_row(Some(&row)),
+            None => self.terminate.set_sensitive(false),
+        }
+    }
+
+    /// Ask the selected process to quit with SIGTERM
+    fn terminate_selected(&self) {
+        let Some(row) = self.list.selected_row() else {
+            return;
+        };
+        let Some(process) = self.shown.borrow().get(row.index() as usize).cloned() else {
+            return;
+        };
+
+        // 0 or a negative pid would signal a whole process group
+        let Some(pid) = libc::pid_t::try_from(process.pid).ok().filter(|pid| *pid > 0) else {
+            return;
+        };
+        // SAFETY: kill only sends a signal and touches no memory of ours
+        if unsafe { libc::kill(pid, libc::SIGTERM) } == 0 {
+            info!("Sent SIGTERM to {} ({})", process.name, process.pid);
+        } else {
+            warn!(
+                "Failed to terminate {} ({}): {}",
+                process.name,
+                process.pid,
+                std::io::Error::last_os_error()
+            );
+        }
+    }
 }
 
 impl SystemMonitor {
-    pub fn new() -> Self {
-        let container = Box::new(Orientation::Horizontal, 10);
+    pub fn new(config: &SystemMonitorConfig, orientation: Orientation) -> Self {
+        let container = Box::new(orientation, 10);
         container.add_css_class("system-monitor");
 
         // Create labels for each metric
         let cpu_label = Label::new(Some("CPU: ---%"));
         cpu_label.add_css_class("cpu-label");
+
+        let cores_box = Box::new(orientation, 1);
+        cores_box.add_css_class("cpu-cores");
+        cores_box.set_visible(config.cpu_display == CpuDisplay::PerCore);
         
         let memory_label = Label::new(Some("MEM: ---%"));
         memory_label.add_css_class("memory-label");
@@ -29,66 +447,401 @@ impl SystemMonitor {
         let temp_label = Label::new(Some("TEMP: ---°C"));
         temp_label.add_css_class("temp-label");
 
+        let network_label = Label::new(Some("NET: ---"));
+        network_label.add_css_class("network-label");
+        network_label.set_visible(config.network);
+
+        let graphs = config.graphs.then(|| {
+            (
+                Rc::new(Sparkline::new(config.history, 100.0, parse_color(&config.cpu_color))),
+                Rc::new(Sparkline::new(config.history, 100.0, parse_color(&config.memory_color))),
+            )
+        });
+
         container.append(&cpu_label);
+        container.append(&cores_box);
+        if let Some((cpu_graph, _)) = &graphs {
+            container.append(&cpu_graph.area);
+        }
         container.append(&memory_label);
+        if let Some((_, memory_graph)) = &graphs {
+            container.append(&memory_graph.area);
+        }
         container.append(&temp_label);
+        container.append(&network_label);
+
+        let core_list = Grid::new();
+        core_list.add_css_class("cpu-core-list");
+        core_list.set_row_spacing(4);
+        core_list.set_column_spacing(8);
+        let popover = Popover::new();
+        popover.set_child(Some(&core_list));
+        popover.set_parent(&container);
 
-        let system = Arc::new(Mutex::new(System::new_all()));
+        let click = GestureClick::new();
+        click.connect_released(move |_, _, _, _| {
+            popover.popup();
+        });
+        container.add_controller(click);
+
+        // CPU and memory list the busiest processes unless they run a configured action
+        let processes = ProcessPopover::new(&container);
+        for (label, action, sort) in [
+            (&cpu_label, &config.on_click.cpu, Some(ProcessSort::Cpu)),
+            (&memory_label, &config.on_click.memory, Some(ProcessSort::Memory)),
+            (&temp_label, &config.on_click.temperature, None),
+            (&network_label, &config.on_click.network, None),
+        ] {
+            match (action.clone(), sort) {
+                (Some(action), _) => Self::on_label_click(label, move || spawn_shell(&action)),
+                (None, Some(sort)) => {
+                    let processes = processes.clone();
+                    Self::on_label_click(label, move || processes.open(sort));
+                }
+                (None, None) => {}
+            }
+        }
 
         let monitor = SystemMonitor {
             container,
             cpu_label,
             memory_label,
             temp_label,
-            system,
+            cores_box,
+            core_list,
+            cpu_display: config.cpu_display,
+            templates: Rc::new(LabelTemplates {
+                cpu: config.labels.cpu.as_deref().map(Template::parse),
+                memory: config.labels.memory.as_deref().map(Template::parse),
+                temperature: config.labels.temperature.as_deref().map(Template::parse),
+                memory_format: Template::parse(&config.memory_format),
+                icons: config.icons.clone(),
+                temperature_max: if config.temperature_critical > 0.0 {
+                    config.temperature_critical
+                } else {
+                    100.0
+                },
+            }),
+            graphs,
+            network_label,
+            interfaces: config.interfaces.clone(),
+            network_totals: config.network_totals,
+            thresholds: [
+                Thresholds {
+                    warning: config.cpu_warning,
+                    critical: config.cpu_critical,
+                },
+                Thresholds {
+                    warning: config.memory_warning,
+                    critical: config.memory_critical,
+                },
+                Thresholds {
+                    warning: config.temperature_warning,
+                    critical: config.temperature_critical,
+                },
+            ],
+            alert_interval: config
+                .alerts
+                .then(|| Duration::from_secs(config.alert_interval)),
+            processes,
+            subscription: RefCell::new(None),
         };
 
-        monitor.start_monitoring();
+        monitor.start_monitoring(orientation == Orientation::Vertical);
         monitor
     }
 
-    fn start_monitoring(&self) {
+    /// Vertical bars stack the name above a rounded value to stay narrow
+    fn start_monitoring(&self, vertical: bool) {
         let cpu_label = self.cpu_label.clone();
         let memory_label = self.memory_label.clone();
         let temp_label = self.temp_label.clone();
-        let system = self.system.clone();
-
-        // Update every 2 seconds
-        timeout_add_local(Duration::from_secs(2), move || {
-            if let Ok(mut sys) = system.lock() {
-                sys.refresh_all();
-
-                // CPU Usage - average of all CPUs
-                if !sys.cpus().is_empty() {
-                    let cpu_usage: f32 = sys.cpus().iter()
-                        .map(|cpu| cpu.cpu_usage())
-                        .sum::<f32>() / sys.cpus().len() as f32;
-                    cpu_label.set_text(&format!("CPU: {:.1}%", cpu_usage));
-                }
+        let cores_box = self.cores_box.clone();
+        let core_list = self.core_list.clone();
+        let cpu_display = self.cpu_display;
+        let templates = self.templates.clone();
+        let graphs = self.graphs.clone();
+        let network_label = self.network_label.clone();
+        let interfaces = self.interfaces.clone();
+        let network_totals = self.network_totals;
+        let [cpu_thresholds, memory_thresholds, temp_thresholds] = self.thresholds;
+        let alert_interval = self.alert_interval;
+        let processes = self.processes.clone();
+        let core_bars: RefCell<Vec<LevelBar>> = RefCell::new(Vec::new());
+        let core_rows: RefCell<Vec<CoreRow>> = RefCell::new(Vec::new());
+
+        for label in [&cpu_label, &memory_label, &temp_label, &network_label] {
+            label.set_justify(gtk4::Justification::Center);
+        }
 
-                // Memory Usage
-                let total_memory = sys.total_memory();
-                let used_memory = sys.used_memory();
-                if total_memory > 0 {
-                    let memory_percentage = (used_memory as f64 / total_memory as f64) * 100.0;
-                    memory_label.set_text(&format!("MEM: {:.1}%", memory_percentage));
+        let subscriber: Rc<Subscriber> = Rc::new(move |readings: &Readings| {
+            let busiest = readings
+                .cores
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.usage.total_cmp(&b.usage));
+            let (name, usage) = match (cpu_display, busiest) {
+                (CpuDisplay::PerCore, _) if !readings.cores.is_empty() => ("CPU", None),
+                (CpuDisplay::MaxCore, Some((_, core))) => ("MAX", Some(core.usage)),
+                _ => ("CPU", readings.cpu_usage),
+            };
+            if let Some(usage) = usage {
+                cpu_label.set_text(&match &templates.cpu {
+                    Some(template) => template.render(|key| match key {
+                        "usage" => Some(usage.into()),
+                        "name" => Some(name.into()),
+                        "icon" => Some(templates.icon(usage as f64, 100.0)),
+                        _ => None,
+                    }),
+                    None if vertical => format!("{}\n{:.0}%", name, usage),
+                    None => format!("{}: {:.1}%", name, usage),
+                });
+            } else if cpu_display == CpuDisplay::PerCore && !readings.cores.is_empty() {
+                cpu_label.set_text(name);
+            }
+            cpu_label.set_tooltip_text(
+                busiest
+                    .map(|(index, core)| format!("Busiest core: {} at {:.0}%", index, core.usage))
+                    .as_deref(),
+            );
+            if let Some(usage) = usage.or(readings.cpu_usage) {
+                let level = cpu_thresholds.level(usage as f64);
+                show_level(&cpu_label, level);
+                alert(alert_interval, "cpu", level, || {
+                    format!("{} usage at {:.0}%", name, usage)
+                });
+            }
+
+            if let Some((cpu_graph, memory_graph)) = &graphs {
+                if let Some(cpu_usage) = readings.cpu_usage {
+                    cpu_graph.push(cpu_usage as f64);
                 }
+                if let Some(memory_percentage) = readings.memory_percentage {
+                    memory_graph.push(memory_percentage);
+                }
+            }
 
-                // CPU Temperature - try to read from thermal zones
-                let temp = SystemMonitor::get_cpu_temperature();
-                if temp > 0.0 {
-                    temp_label.set_text(&format!("TEMP: {:.0}°C", temp));
+            if cpu_display == CpuDisplay::PerCore {
+                Self::update_core_bars(
+                    &cores_box,
+                    &mut core_bars.borrow_mut(),
+                    &readings.cores,
+                    vertical,
+                );
+            }
+            Self::update_core_list(&core_list, &mut core_rows.borrow_mut(), &readings.cores);
+
+            if let Some(memory_percentage) = readings.memory_percentage {
+                let memory = readings.memory.as_ref();
+                memory_label.set_text(&match &templates.memory {
+                    Some(template) => template.render(|key| match key {
+                        "usage" => Some(memory_percentage.into()),
+                        "icon" => Some(templates.icon(memory_percentage, 100.0)),
+                        _ => memory_value(key, memory_percentage, memory, vertical),
+                    }),
+                    None => {
+                        let text = templates
+                            .memory_format
+                            .render(|key| memory_value(key, memory_percentage, memory, vertical));
+                        if vertical {
+                            format!("MEM\n{}", text)
+                        } else {
+                            format!("MEM: {}", text)
+                        }
+                    }
+                });
+                memory_label.set_tooltip_text(readings.memory.map(memory_tooltip).as_deref());
+                let level = memory_thresholds.level(memory_percentage);
+                show_level(&memory_label, level);
+                alert(alert_interval, "memory", level, || {
+                    format!("Memory usage at {:.0}%", memory_percentage)
+                });
+            }
+
+            // CPU Temperature - read from thermal zones
+            let separator = if vertical { "\n" } else { ": " };
+            if readings.temperature > 0.0 {
+                temp_label.set_text(&match &templates.temperature {
+                    Some(template) => template.render(|key| match key {
+                        "temperature" => Some(readings.temperature.into()),
+                        "icon" => Some(templates.icon(
+                            readings.temperature as f64,
+                            templates.temperature_max,
+                        )),
+                        _ => None,
+                    }),
+                    None => format!("TEMP{}{:.0}°C", separator, readings.temperature),
+                });
+                let level = temp_thresholds.level(readings.temperature as f64);
+                show_level(&temp_label, level);
+                alert(alert_interval, "temperature", level, || {
+                    format!("CPU temperature at {:.0}°C", readings.temperature)
+                });
+            } else if let Some(template) = &templates.temperature {
+                temp_label.set_text(&template.render(|key| match key {
+                    "temperature" => Some("N/A".into()),
+                    "icon" => Some("".into()),
+                    _ => None,
+                }));
+            } else {
+                temp_label.set_text(&format!("TEMP{}N/A", separator));
+            }
+
+            if network_label.is_visible() {
+                Self::update_network(
+                    &network_label,
+                    readings,
+                    &interfaces,
+                    network_totals,
+                    vertical,
+                );
+            }
+
+            processes.update(readings);
+        });
+
+        Sampler::subscribe(subscriber.clone());
+        *self.subscription.borrow_mut() = Some(subscriber);
+    }
+
+    /// Call `on_click` when `label` is clicked, claiming the click so the core list stays closed
+    fn on_label_click(label: &Label, on_click: impl Fn() + 'static) {
+        label.add_css_class("clickable");
+        label.set_cursor_from_name(Some("pointer"));
+
+        let click = GestureClick::new();
+        click.connect_pressed(|gesture, _, _, _| {
+            gesture.set_state(EventSequenceState::Claimed);
+        });
+        click.connect_released(move |_, _, _, _| on_click());
+        label.add_controller(click);
+    }
+
+    /// Show the summed rates of the selected interfaces
+    fn update_network(
+        label: &Label,
+        readings: &Readings,
+        interfaces: &[String],
+        totals: bool,
+        vertical: bool,
+    ) {
+        let selected: Vec<&InterfaceReading> = readings
+            .interfaces
+            .iter()
+            .filter(|interface| {
+                if interfaces.is_empty() {
+                    interface.name != "lo"
                 } else {
-                    temp_label.set_text("TEMP: N/A");
+                    interfaces.contains(&interface.name)
                 }
-            }
+            })
+            .collect();
+        if selected.is_empty() {
+            label.set_text(if vertical { "NET\nN/A" } else { "NET: N/A" });
+            label.set_tooltip_text(None);
+            return;
+        }
+
+        let rx: f64 = selected.iter().map(|interface| interface.rx_rate).sum();
+        let tx: f64 = selected.iter().map(|interface| interface.tx_rate).sum();
+        label.set_text(&if vertical {
+            format!("↓{}\n↑{}", format_bytes(rx, true), format_bytes(tx, true))
+        } else {
+            format!("↓{}/s ↑{}/s", format_bytes(rx, false), format_bytes(tx, false))
+        });
 
-            ControlFlow::Continue
+        let tooltip = totals.then(|| {
+            selected
+                .iter()
+                .map(|interface| {
+                    format!(
+                        "{}: ↓{} ↑{} since boot",
+                        interface.name,
+                        format_bytes(interface.rx_total as f64, false),
+                        format_bytes(interface.tx_total as f64, false)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
         });
+        label.set_tooltip_text(tooltip.as_deref());
+    }
+
+    /// Fill one small bar per core, across the bar so they stack along it
+    fn update_core_bars(
+        cores_box: &Box,
+        bars: &mut Vec<LevelBar>,
+        cores: &[CoreReading],
+        vertical: bool,
+    ) {
+        while bars.len() > cores.len() {
+            if let Some(bar) = bars.pop() {
+                cores_box.remove(&bar);
+            }
+        }
+        while bars.len() < cores.len() {
+            let bar = LevelBar::for_interval(0.0, 100.0);
+            bar.add_css_class("cpu-core-bar");
+            if vertical {
+                bar.set_orientation(Orientation::Horizontal);
+                bar.set_size_request(-1, 4);
+            } else {
+                bar.set_orientation(Orientation::Vertical);
+                bar.set_inverted(true);
+                bar.set_size_request(4, -1);
+            }
+            cores_box.append(&bar);
+            bars.push(bar);
+        }
+
+        for (bar, core) in bars.iter().zip(cores) {
+            bar.set_value(core.usage.clamp(0.0, 100.0) as f64);
+        }
     }
 
-    pub fn widget(&self) -> &Box {
-        &self.container
+    /// Keep the popover list in step with the cores, rebuilding it if their number changes
+    fn update_core_list(grid: &Grid, rows: &mut Vec<CoreRow>, cores: &[CoreReading]) {
+        if rows.len() != cores.len() {
+            while let Some(child) = grid.first_child() {
+                grid.remove(&child);
+            }
+            rows.clear();
+
+            for index in 0..cores.len() {
+                let name = Label::new(Some(&format!("Core {}", index)));
+                name.set_halign(gtk4::Align::Start);
+                let level = LevelBar::for_interval(0.0, 100.0);
+                level.set_size_request(100, -1);
+                level.set_valign(gtk4::Align::Center);
+                let usage = Label::new(None);
+                usage.add_css_class("cpu-core-usage");
+                usage.set_xalign(1.0);
+                let frequency = Label::new(None);
+                frequency.add_css_class("cpu-core-frequency");
+                frequency.set_xalign(1.0);
+
+                let row = index as i32;
+                grid.attach(&name, 0, row, 1, 1);
+                grid.attach(&level, 1, row, 1, 1);
+                grid.attach(&usage, 2, row, 1, 1);
+                grid.attach(&frequency, 3, row, 1, 1);
+                rows.push(CoreRow {
+                    level,
+                    usage,
+                    frequency,
+                });
+            }
+        }
+
+        for (row, core) in rows.iter().zip(cores) {
+            row.level.set_value(core.usage.clamp(0.0, 100.0) as f64);
+            row.usage.set_text(&format!("{:.0}%", core.usage));
+            row.frequency.set_text(&if core.frequency > 0 {
+                format!("{:.2} GHz", core.frequency as f64 / 1000.0)
+            } else {
+                "--".to_string()
+            });
+        }
     }
 
     fn get_cpu_temperature() -> f32 {
@@ -155,3 +908,164 @@ impl SystemMonitor {
         0.0 // Return 0 if no temperature found
     }
 }
+
+impl BarModule for SystemMonitor {
+    fn name(&self) -> &'static str {
+        "system_monitor"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.container.upcast_ref()
+    }
+
+    fn refresh(&self) {
+        let sampler = SAMPLER.with(|sampler| sampler.borrow().clone());
+        if let Some(sampler) = sampler {
+            sampler.sample();
+        }
+    }
+}
+
+/// A `memory_format` placeholder, vertical bars get rounder numbers and short units
+fn memory_value(
+    key: &str,
+    percentage: f64,
+    memory: Option<&MemoryReading>,
+    vertical: bool,
+) -> Option<Value> {
+    let percent = |value: f64| {
+        if vertical {
+            format!("{:.0}%", value)
+        } else {
+            format!("{:.1}%", value)
+        }
+    };
+    let bytes = |value: Option<u64>| match value {
+        Some(value) => format_bytes(value as f64, vertical),
+        None => "--".to_string(),
+    };
+
+    let text = match key {
+        "percent" => percent(percentage),
+        "used" => bytes(memory.map(|memory| memory.used)),
+        "total" => bytes(memory.map(|memory| memory.total)),
+        "available" => bytes(memory.map(|memory| memory.available)),
+        "swap_used" => bytes(memory.map(|memory| memory.swap_used)),
+        "swap_total" => bytes(memory.map(|memory| memory.swap_total)),
+        "swap_percent" => match memory {
+            Some(memory) if memory.swap_total > 0 => {
+                percent(memory.swap_used as f64 / memory.swap_total as f64 * 100.0)
+            }
+            Some(_) => "off".to_string(),
+            None => "--".to_string(),
+        },
+        _ => return None,
+    };
+    Some(text.into())
+}
+
+fn memory_tooltip(memory: MemoryReading) -> String {
+    let bytes = |value: u64| format_bytes(value as f64, false);
+    let mut tooltip = format!(
+        "Used: {} of {}\nAvailable: {}\nBuffers/cache: {}",
+        bytes(memory.used),
+        bytes(memory.total),
+        bytes(memory.available),
+        bytes(memory.buffers_cache)
+    );
+    if memory.swap_total > 0 {
+        tooltip.push_str(&format!(
+            "\nSwap: {} of {}",
+            bytes(memory.swap_used),
+            bytes(memory.swap_total)
+        ));
+    } else {
+        tooltip.push_str("\nSwap: off");
+    }
+    tooltip
+}
+
+/// Page cache, buffers and reclaimable slab from /proc/meminfo, in bytes
+fn read_buffers_cache() -> u64 {
+    let Ok(meminfo) = std::fs::read_to_string("/proc/meminfo") else {
+        return 0;
+    };
+    meminfo
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            if !matches!(key, "Buffers" | "Cached" | "SReclaimable") {
+                return None;
+            }
+            let kib: u64 = value.trim().trim_end_matches("kB").trim().parse().ok()?;
+            Some(kib * 1024)
+        })
+        .sum()
+}
+
+/// Set the `warning` or `critical` class of a metric label
+fn show_level(label: &Label, level: Level) {
+    for (class, active) in [
+        ("warning", level == Level::Warning),
+        ("critical", level == Level::Critical),
+    ] {
+        if active {
+            label.add_css_class(class);
+        } else {
+            label.remove_css_class(class);
+        }
+    }
+}
+
+/// Notify when `metric` becomes critical, at most once per `interval`
+fn alert(
+    interval: Option<Duration>,
+    metric: &'static str,
+    level: Level,
+    message: impl FnOnce() -> String,
+) {
+    let Some(interval) = interval else {
+        return;
+    };
+
+    let due = ALERTS.with(|alerts| {
+        let mut alerts = alerts.borrow_mut();
+        let (previous, last_sent) = alerts.entry(metric).or_insert((Level::Normal, None));
+        let rising = level == Level::Critical && *previous != Level::Critical;
+        *previous = level;
+        if !rising || last_sent.is_some_and(|sent| sent.elapsed() < interval) {
+            return false;
+        }
+        *last_sent = Some(Instant::now());
+        true
+    });
+    if !due {
+        return;
+    }
+
+    let Some(app) = gio::Application::default() else {
+        return;
+    };
+    let notification = gio::Notification::new("System monitor");
+    notification.set_body(Some(&message()));
+    notification.set_priority(gio::NotificationPriority::Urgent);
+    app.send_notification(Some(&format!("system-monitor-{}", metric)), &notification);
+}
+
+/// Human readable byte count in binary units, e.g. `1.2 MB`, or `1.2M` when short
+pub fn format_bytes(bytes: f64, short: bool) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes.max(0.0);
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    let precision = if unit == 0 || value >= 100.0 { 0 } else { 1 };
+    if short {
+        format!("{:.*}{}", precision, value, &UNITS[unit][..1])
+    } else {
+        format!("{:.*} {}", precision, value, UNITS[unit])
+    }
+}
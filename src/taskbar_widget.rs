@@ -0,0 +1,392 @@
+use glib::{ControlFlow, IOCondition, Priority};
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, GestureClick, Image, Label, Orientation};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::os::fd::AsRawFd;
+use std::rc::Rc;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use tracing::{debug, warn};
+use wayland_client::backend::{ObjectId, WaylandError};
+use wayland_client::globals::{GlobalListContents, registry_queue_init};
+use wayland_client::protocol::wl_registry::WlRegistry;
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, event_created_child};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_handle_v1::{
+    self, ZwlrForeignToplevelHandleV1,
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_manager_v1::{
+    self, ZwlrForeignToplevelManagerV1,
+};
+
+use crate::config::TaskbarConfig;
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+
+/// `activated` in the toplevel state array
+const STATE_ACTIVATED: u32 = 2;
+
+/// Last state the compositor committed for a toplevel
+#[derive(Debug, Clone, Default)]
+struct Toplevel {
+    title: String,
+    app_id: String,
+    activated: bool,
+}
+
+/// Toplevel changes sent from the toplevel connection to the bar
+enum ToplevelEvent {
+    Changed(ZwlrForeignToplevelHandleV1, Toplevel),
+    Closed(ZwlrForeignToplevelHandleV1),
+}
+
+/// Open windows from the wlr-foreign-toplevel-management protocol, one button per
+/// window: click activates it, middle click closes it
+pub struct TaskbarWidget {
+    pub container: GtkBox,
+    state: Rc<TaskbarState>,
+}
+
+struct TaskbarState {
+    config: TaskbarConfig,
+    container: GtkBox,
+    /// The bar's own connection to the compositor, requests from the buttons are
+    /// flushed through it
+    connection: RefCell<Option<(Connection, WlSeat)>>,
+    /// Buttons in the order the windows were opened
+    buttons: RefCell<Vec<(ZwlrForeignToplevelHandleV1, Button)>>,
+}
+
+impl TaskbarWidget {
+    pub fn new(config: &TaskbarConfig, orientation: Orientation) -> Self {
+        let container = GtkBox::new(orientation, 2);
+        container.add_css_class("taskbar");
+
+        let state = Rc::new(TaskbarState {
+            config: config.clone(),
+            container: container.clone(),
+            connection: RefCell::new(None),
+            buttons: RefCell::new(Vec::new()),
+        });
+
+        TaskbarWidget { container, state }
+    }
+}
+
+impl TaskbarState {
+    fn apply(self: &Rc<Self>, event: ToplevelEvent) {
+        match event {
+            ToplevelEvent::Changed(handle, toplevel) => {
+                let existing = self
+                    .buttons
+                    .borrow()
+                    .iter()
+                    .find(|(known, _)| known.id() == handle.id())
+                    .map(|(_, button)| button.clone());
+                let button = existing.unwrap_or_else(|| self.add_button(&handle));
+                self.show(&button, &toplevel);
+            }
+            ToplevelEvent::Closed(handle) => {
+                self.buttons.borrow_mut().retain(|(known, button)| {
+                    if known.id() == handle.id() {
+                        self.container.remove(button);
+                        false
+                    } else {
+                        true
+                    }
+                });
+            }
+        }
+        control::module_updated("taskbar");
+    }
+
+    fn add_button(self: &Rc<Self>, handle: &ZwlrForeignToplevelHandleV1) -> Button {
+        let button = Button::new();
+        button.add_css_class("taskbar-button");
+
+        let content = GtkBox::new(Orientation::Horizontal, 4);
+        let icon = Image::new();
+        icon.set_pixel_size(self.config.icon_size);
+        content.append(&icon);
+        if self.config.show_titles {
+            let title = Label::new(None);
+            title.add_css_class("taskbar-title");
+            title.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+            title.set_max_width_chars(self.config.title_length);
+            content.append(&title);
+        }
+        button.set_child(Some(&content));
+
+        let state = Rc::downgrade(self);
+        let handle_click = handle.clone();
+        button.connect_clicked(move |_| {
+            if let Some(state) = state.upgrade() {
+                state.request(|seat| handle_click.activate(seat));
+            }
+        });
+
+        let middle_click = GestureClick::new();
+        middle_click.set_button(2);
+        let state = Rc::downgrade(self);
+        let handle_close = handle.clone();
+        middle_click.connect_pressed(move |_, _, _, _| {
+            if let Some(state) = state.upgrade() {
+                state.request(|_| handle_close.close());
+            }
+        });
+        button.add_controller(middle_click);
+
+        self.container.append(&button);
+        self.buttons.borrow_mut().push((handle.clone(), button.clone()));
+        button
+    }
+
+    fn show(&self, button: &Button, toplevel: &Toplevel) {
+        let Some(content) = button.child().and_downcast::<GtkBox>() else {
+            return;
+        };
+        if let Some(icon) = content.first_child().and_downcast::<Image>() {
+            match app_icon(&toplevel.app_id) {
+                Some(gicon) => icon.set_from_gicon(&gicon),
+                None => icon.set_icon_name(Some("application-x-executable")),
+            }
+        }
+        if let Some(title) = content.last_child().and_downcast::<Label>() {
+            title.set_text(&toplevel.title);
+        }
+
+        button.set_tooltip_text(Some(&toplevel.title));
+        if toplevel.activated {
+            button.add_css_class("focused");
+        } else {
+            button.remove_css_class("focused");
+        }
+    }
+
+    /// Send a request on a toplevel and flush it to the compositor right away,
+    /// the toplevel source only dispatches incoming events
+    fn request(&self, send: impl FnOnce(&WlSeat)) {
+        let connection = self.connection.borrow();
+        let Some((connection, seat)) = connection.as_ref() else {
+            return;
+        };
+        send(seat);
+        if let Err(e) = connection.flush() {
+            warn!("Failed to send taskbar request: {}", e);
+        }
+    }
+}
+
+/// Icon of the application from its desktop file, falling back to an icon named
+/// like the app id
+fn app_icon(app_id: &str) -> Option<gio::Icon> {
+    if app_id.is_empty() {
+        return None;
+    }
+    let desktop = gio::DesktopAppInfo::new(&format!("{}.desktop", app_id))
+        .or_else(|| gio::DesktopAppInfo::new(&format!("{}.desktop", app_id.to_lowercase())))
+        .or_else(|| {
+            // Desktop files named after the reverse domain, e.g. org.gnome.Nautilus
+            gio::DesktopAppInfo::search(app_id)
+                .into_iter()
+                .flatten()
+                .find_map(|id| gio::DesktopAppInfo::new(&id))
+        });
+    desktop
+        .and_then(|info| info.icon())
+        .or_else(|| Some(gio::ThemedIcon::new(&app_id.to_lowercase()).upcast()))
+}
+
+/// State of the toplevel connection, toplevels collect their events until `done`
+struct ToplevelTracker {
+    pending: HashMap<ObjectId, Toplevel>,
+    events: UnboundedSender<ToplevelEvent>,
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for ToplevelTracker {
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: <WlRegistry as Proxy>::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlSeat, ()> for ToplevelTracker {
+    fn event(
+        _: &mut Self,
+        _: &WlSeat,
+        _: <WlSeat as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for ToplevelTracker {
+    fn event(
+        tracker: &mut Self,
+        _: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } => {
+                tracker.pending.insert(toplevel.id(), Toplevel::default());
+            }
+            zwlr_foreign_toplevel_manager_v1::Event::Finished => {
+                debug!("Compositor stopped sending toplevels");
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(ToplevelTracker, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for ToplevelTracker {
+    fn event(
+        tracker: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let toplevel = tracker.pending.entry(handle.id()).or_default();
+        match event {
+            zwlr_foreign_toplevel_handle_v1::Event::Title { title } => toplevel.title = title,
+            zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => toplevel.app_id = app_id,
+            zwlr_foreign_toplevel_handle_v1::Event::State { state } => {
+                // An array of native endian u32
+                toplevel.activated = state
+                    .chunks_exact(4)
+                    .filter_map(|value| value.try_into().ok().map(u32::from_ne_bytes))
+                    .any(|value| value == STATE_ACTIVATED);
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Done => {
+                let _ = tracker
+                    .events
+                    .send(ToplevelEvent::Changed(handle.clone(), toplevel.clone()));
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Closed => {
+                tracker.pending.remove(&handle.id());
+                let _ = tracker.events.send(ToplevelEvent::Closed(handle.clone()));
+                handle.destroy();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Main loop source dispatching the toplevel events, detached when the taskbar's init
+/// task ends or is aborted on rebuild
+struct ToplevelWatch {
+    source: glib::Source,
+}
+
+impl Drop for ToplevelWatch {
+    fn drop(&mut self) {
+        // Also fine when the source already removed itself after the connection failed
+        self.source.destroy();
+    }
+}
+
+/// Bind the toplevel manager on a separate connection and dispatch its events from
+/// the main loop whenever the connection is readable, the seat is needed to activate
+/// windows
+fn watch_toplevels(
+    events: UnboundedSender<ToplevelEvent>,
+) -> Option<(Connection, WlSeat, ToplevelWatch)> {
+    let connection = match Connection::connect_to_env() {
+        Ok(connection) => connection,
+        Err(e) => {
+            warn!("Taskbar could not connect to the Wayland display: {}", e);
+            return None;
+        }
+    };
+    let (globals, mut queue) = registry_queue_init::<ToplevelTracker>(&connection).ok()?;
+    let qh = queue.handle();
+    if let Err(e) = globals.bind::<ZwlrForeignToplevelManagerV1, _, _>(&qh, 1..=3, ()) {
+        warn!("Compositor does not support wlr-foreign-toplevel-management: {}", e);
+        return None;
+    }
+    let seat: WlSeat = globals.bind(&qh, 1..=1, ()).ok()?;
+
+    let mut tracker = ToplevelTracker {
+        pending: HashMap::new(),
+        events,
+    };
+    // Send the bind requests, the toplevels arrive in reply
+    if let Err(e) = queue.flush() {
+        warn!("Taskbar could not talk to the compositor: {}", e);
+        return None;
+    }
+
+    let fd = connection.backend().poll_fd().as_raw_fd();
+    let source = glib::unix_fd_source_new(
+        fd,
+        IOCondition::IN | IOCondition::HUP | IOCondition::ERR,
+        Some("taskbar toplevels"),
+        Priority::DEFAULT,
+        move |_, condition| {
+            if let Some(guard) = queue.prepare_read() {
+                match guard.read() {
+                    Ok(_) => {}
+                    Err(WaylandError::Io(e)) if e.kind() == ErrorKind::WouldBlock => {}
+                    Err(e) => {
+                        warn!("Taskbar Wayland connection ended: {}", e);
+                        return ControlFlow::Break;
+                    }
+                }
+            }
+            if let Err(e) = queue.dispatch_pending(&mut tracker) {
+                warn!("Taskbar Wayland connection ended: {}", e);
+                return ControlFlow::Break;
+            }
+            if condition.intersects(IOCondition::HUP | IOCondition::ERR) {
+                warn!("Taskbar Wayland connection closed by the compositor");
+                return ControlFlow::Break;
+            }
+            ControlFlow::Continue
+        },
+    );
+    source.attach(None);
+
+    Some((connection, seat, ToplevelWatch { source }))
+}
+
+impl BarModule for TaskbarWidget {
+    fn name(&self) -> &'static str {
+        "taskbar"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.container.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            let (events_tx, mut events_rx) = unbounded_channel::<ToplevelEvent>();
+            let Some((connection, seat, _watch)) = watch_toplevels(events_tx) else {
+                self.container.set_visible(false);
+                return;
+            };
+            *self.state.connection.borrow_mut() = Some((connection, seat));
+
+            // Ends when the connection fails and the source drops the sender
+            while let Some(event) = events_rx.recv().await {
+                self.state.apply(event);
+            }
+        })
+    }
+}
@@ -0,0 +1,198 @@
+// Label templates shared by the modules: `{name}` placeholders with an optional
+// format spec after a colon, as in Rust's `format!` (`{usage:>5.1}`), and icons
+// picked by where a value lies in its range.
+
+/// A value substituted into a template
+#[derive(Debug, Clone)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Number(value as f64)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// `[[fill]align][width][.precision]`
+#[derive(Debug, Clone, Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<Align>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Self {
+        let mut parsed = Spec::default();
+        let align = |c: char| match c {
+            '<' => Some(Align::Left),
+            '>' => Some(Align::Right),
+            '^' => Some(Align::Center),
+            _ => None,
+        };
+
+        let mut rest = spec;
+        let mut chars = spec.chars();
+        let first = chars.next();
+        let second = chars.next();
+        if let (Some(fill), Some(Some(alignment))) = (first, second.map(align)) {
+            parsed.fill = Some(fill);
+            parsed.align = Some(alignment);
+            rest = &spec[fill.len_utf8() + 1..];
+        } else if let Some(Some(alignment)) = first.map(align) {
+            parsed.align = Some(alignment);
+            rest = &spec[1..];
+        }
+
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, precision.parse().ok()),
+            None => (rest, None),
+        };
+        parsed.width = width.parse().unwrap_or(0);
+        parsed.precision = precision;
+        parsed
+    }
+
+    fn apply(&self, value: &Value) -> String {
+        let (text, default_align) = match value {
+            Value::Number(number) => match self.precision {
+                Some(precision) => (format!("{:.*}", precision, number), Align::Right),
+                None => (number.to_string(), Align::Right),
+            },
+            Value::Text(text) => match self.precision {
+                Some(precision) => (text.chars().take(precision).collect(), Align::Left),
+                None => (text.clone(), Align::Left),
+            },
+        };
+
+        let length = text.chars().count();
+        if length >= self.width {
+            return text;
+        }
+        let padding = self.width - length;
+        let (before, after) = match self.align.unwrap_or(default_align) {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+        let fill = self.fill.unwrap_or(' ');
+        let mut padded = String::with_capacity(text.len() + padding);
+        padded.extend(std::iter::repeat_n(fill, before));
+        padded.push_str(&text);
+        padded.extend(std::iter::repeat_n(fill, after));
+        padded
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Field {
+        name: String,
+        spec: Spec,
+        /// The placeholder as written, kept in the output when nothing fills it
+        raw: String,
+    },
+}
+
+/// A parsed label template, `{{` and `}}` stand for literal braces
+#[derive(Debug, Clone)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Self {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = source;
+
+        while let Some(index) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..index]);
+            let brace = &rest[index..];
+
+            if brace.starts_with("{{") || brace.starts_with("}}") {
+                literal.push_str(&brace[..1]);
+                rest = &brace[2..];
+                continue;
+            }
+            let Some(end) = brace.starts_with('{').then(|| brace.find('}')).flatten() else {
+                // Unmatched brace, taken literally
+                literal.push_str(&brace[..1]);
+                rest = &brace[1..];
+                continue;
+            };
+
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            let field = &brace[1..end];
+            let (name, spec) = field.split_once(':').unwrap_or((field, ""));
+            segments.push(Segment::Field {
+                name: name.trim().to_string(),
+                spec: Spec::parse(spec),
+                raw: brace[..=end].to_string(),
+            });
+            rest = &brace[end + 1..];
+        }
+
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Template { segments }
+    }
+
+    /// Fill in the placeholders, unknown ones are left as written so typos show up
+    pub fn render(&self, lookup: impl Fn(&str) -> Option<Value>) -> String {
+        let mut output = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => output.push_str(text),
+                Segment::Field { name, spec, raw } => match lookup(name) {
+                    Some(value) => output.push_str(&spec.apply(&value)),
+                    None => output.push_str(raw),
+                },
+            }
+        }
+        output
+    }
+}
+
+/// The icon for `value` between 0 and `max`, the range split evenly between the icons
+pub fn pick_icon(icons: &[String], value: f64, max: f64) -> &str {
+    if icons.is_empty() || max <= 0.0 {
+        return "";
+    }
+    let position = (value / max).clamp(0.0, 1.0);
+    let index = ((position * icons.len() as f64) as usize).min(icons.len() - 1);
+    &icons[index]
+}
@@ -0,0 +1,160 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, DropDown, Image, Label, Orientation, Popover};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use tracing::warn;
+
+use crate::module::BarModule;
+
+const INTERFACE_SCHEMA: &str = "org.gnome.desktop.interface";
+
+/// Lists installed GTK and cursor themes and applies the selection through GSettings
+pub struct ThemeSwitcherWidget {
+    pub button: Button,
+}
+
+impl ThemeSwitcherWidget {
+    pub fn new() -> Option<Self> {
+        // GSettings panics on unknown schemas, so check it is installed first
+        let schema_installed = gio::SettingsSchemaSource::default()
+            .and_then(|source| source.lookup(INTERFACE_SCHEMA, true))
+            .is_some();
+        if !schema_installed {
+            warn!("Theme switcher needs the '{}' GSettings schema", INTERFACE_SCHEMA);
+            return None;
+        }
+        let settings = gio::Settings::new(INTERFACE_SCHEMA);
+
+        let button = Button::new();
+        button.add_css_class("theme-switcher-button");
+        button.set_child(Some(&Image::from_icon_name("preferences-desktop-theme-symbolic")));
+        button.set_tooltip_text(Some("Themes"));
+
+        let popover = Popover::new();
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let content = GtkBox::new(Orientation::Vertical, 6);
+        content.add_css_class("theme-switcher");
+
+        let gtk_themes = find_themes(&theme_dirs("themes"), &["gtk-3.0", "gtk-4.0"]);
+        let cursor_themes = find_themes(&theme_dirs("icons"), &["cursors"]);
+
+        content.append(&Self::create_row(
+            "GTK theme",
+            &gtk_themes,
+            &settings,
+            "gtk-theme",
+        ));
+        content.append(&Self::create_row(
+            "Cursor theme",
+            &cursor_themes,
+            &settings,
+            "cursor-theme",
+        ));
+
+        popover.set_child(Some(&content));
+
+        button.connect_clicked(move |_| {
+            popover.popup();
+        });
+
+        Some(ThemeSwitcherWidget { button })
+    }
+
+    /// Labelled dropdown bound to a GSettings key
+    fn create_row(
+        title: &str,
+        themes: &[String],
+        settings: &gio::Settings,
+        key: &'static str,
+    ) -> GtkBox {
+        let row = GtkBox::new(Orientation::Horizontal, 8);
+
+        let label = Label::new(Some(title));
+        label.set_halign(gtk4::Align::Start);
+        label.set_hexpand(true);
+        row.append(&label);
+
+        let names: Vec<&str> = themes.iter().map(String::as_str).collect();
+        let dropdown = DropDown::from_strings(&names);
+
+        let current = settings.string(key);
+        if let Some(index) = themes.iter().position(|theme| *theme == current.as_str()) {
+            dropdown.set_selected(index as u32);
+        }
+
+        let themes = themes.to_vec();
+        let settings = settings.clone();
+        dropdown.connect_selected_notify(move |dropdown| {
+            let Some(theme) = themes.get(dropdown.selected() as usize) else {
+                return;
+            };
+
+            if let Err(e) = settings.set_string(key, theme) {
+                warn!("Failed to set {} to '{}': {}", key, theme, e);
+            }
+
+            // The bar itself doesn't follow GSettings changes, apply to it directly
+            if let Some(gtk_settings) = gtk4::Settings::default() {
+                match key {
+                    "gtk-theme" => gtk_settings.set_gtk_theme_name(Some(theme)),
+                    "cursor-theme" => gtk_settings.set_gtk_cursor_theme_name(Some(theme)),
+                    _ => {}
+                }
+            }
+        });
+        row.append(&dropdown);
+
+        row
+    }
+}
+
+impl BarModule for ThemeSwitcherWidget {
+    fn name(&self) -> &'static str {
+        "theme_switcher"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+}
+
+/// System and user directories that may contain themes of the given kind
+fn theme_dirs(kind: &str) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        dirs.push(home.join(format!(".{}", kind)));
+        dirs.push(home.join(".local").join("share").join(kind));
+    }
+
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
+    for dir in data_dirs.split(':').filter(|dir| !dir.is_empty()) {
+        dirs.push(PathBuf::from(dir).join(kind));
+    }
+
+    dirs
+}
+
+/// Sorted, de-duplicated names of themes containing one of the marker subdirectories
+fn find_themes(dirs: &[PathBuf], markers: &[&str]) -> Vec<String> {
+    let mut themes = BTreeSet::new();
+
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if markers.iter().any(|marker| path.join(marker).is_dir()) {
+                themes.insert(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+
+    themes.into_iter().collect()
+}
@@ -0,0 +1,94 @@
+/// Stylesheets bundled with the bar, layered over the built-in style
+pub const PRESETS: &[(&str, &str)] = &[
+    ("default", ""),
+    ("catppuccin-mocha", include_str!("themes/catppuccin-mocha.css")),
+    ("gruvbox", include_str!("themes/gruvbox.css")),
+    ("nord", include_str!("themes/nord.css")),
+    ("minimal-transparent", include_str!("themes/minimal-transparent.css")),
+    ("solid", include_str!("themes/solid.css")),
+    ("light", include_str!("themes/light.css")),
+    ("high-contrast", include_str!("themes/high-contrast.css")),
+];
+
+/// Rules coloring the bar from the palette custom properties
+pub const PALETTE_CSS: &str = include_str!("themes/palette.css");
+
+/// Color names every palette defines, as `--<name>` custom properties
+pub const PALETTE_COLORS: &[&str] = &[
+    "background",
+    "surface",
+    "border",
+    "foreground",
+    "accent",
+    "warning",
+    "critical",
+];
+
+/// Palette the `colors` table is layered over when none is chosen
+pub const DEFAULT_PALETTE: &str = "catppuccin-mocha";
+
+/// Built-in palettes, values in the order of `PALETTE_COLORS`
+pub const PALETTES: &[(&str, [&str; 7])] = &[
+    (
+        "catppuccin-mocha",
+        [
+            "rgba(30, 30, 46, 0.95)",
+            "#313244",
+            "#45475a",
+            "#cdd6f4",
+            "#cba6f7",
+            "#f9e2af",
+            "#f38ba8",
+        ],
+    ),
+    (
+        "gruvbox",
+        [
+            "rgba(40, 40, 40, 0.95)",
+            "#3c3836",
+            "#504945",
+            "#ebdbb2",
+            "#d79921",
+            "#fabd2f",
+            "#fb4934",
+        ],
+    ),
+    (
+        "nord",
+        [
+            "rgba(46, 52, 64, 0.95)",
+            "#3b4252",
+            "#4c566a",
+            "#eceff4",
+            "#88c0d0",
+            "#ebcb8b",
+            "#bf616a",
+        ],
+    ),
+];
+
+/// Colors of the built-in palette called `name`, by color name
+pub fn palette(name: &str) -> Option<impl Iterator<Item = (&'static str, &'static str)>> {
+    PALETTES
+        .iter()
+        .find(|(palette, _)| *palette == name)
+        .map(|(_, colors)| PALETTE_COLORS.iter().copied().zip(colors.iter().copied()))
+}
+
+/// Names of all built-in palettes, for error messages
+pub fn palette_names() -> Vec<&'static str> {
+    PALETTES.iter().map(|(name, _)| *name).collect()
+}
+
+/// CSS of the bundled preset called `name`
+pub fn preset(name: &str) -> Option<&'static str> {
+    PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|(_, css)| *css)
+}
+
+/// Names of all bundled presets, for error messages
+pub fn preset_names() -> Vec<&'static str> {
+    PRESETS.iter().map(|(name, _)| *name).collect()
+}
@@ -0,0 +1,145 @@
+use gtk4::glib::ControlFlow;
+use gtk4::prelude::*;
+use gtk4::{
+    Box as GtkBox, EventControllerMotion, Label, Orientation, Overlay, PolicyType, ScrolledWindow,
+};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::command::run_shell;
+use crate::config::TickerConfig;
+use crate::module::{BarModule, InitFuture};
+use crate::power::is_power_saving;
+use crate::scheduler::next_tick;
+
+/// Gap placed between the end of the text and its repetition
+const SEPARATOR: &str = "   •   ";
+
+/// Horizontally scrolling text, fed from static text or a command
+pub struct TickerWidget {
+    pub overlay: Overlay,
+    label: Label,
+    text: Option<String>,
+    command: Option<String>,
+    interval: u64,
+}
+
+impl TickerWidget {
+    pub fn new(config: &TickerConfig, orientation: Orientation) -> Option<Self> {
+        if config.text.is_none() && config.command.is_none() {
+            warn!("Ticker module needs either 'text' or 'command' to be configured");
+            return None;
+        }
+
+        // Scrolling text has no sensible vertical form
+        if orientation == Orientation::Vertical {
+            warn!("Ticker module is not available on vertical bars");
+            return None;
+        }
+
+        let label = Label::new(None);
+        label.add_css_class("ticker-label");
+        label.set_single_line_mode(true);
+
+        let scrolled = ScrolledWindow::new();
+        scrolled.set_policy(PolicyType::External, PolicyType::Never);
+        scrolled.set_size_request(config.width, -1);
+        scrolled.set_child(Some(&label));
+
+        // Fade edges are plain overlays so they can be themed from CSS
+        let overlay = Overlay::new();
+        overlay.add_css_class("ticker");
+        overlay.set_child(Some(&scrolled));
+        for (css_class, align) in [
+            ("ticker-fade-start", gtk4::Align::Start),
+            ("ticker-fade-end", gtk4::Align::End),
+        ] {
+            let fade = GtkBox::new(Orientation::Horizontal, 0);
+            fade.add_css_class(css_class);
+            fade.set_halign(align);
+            fade.set_can_target(false);
+            overlay.add_overlay(&fade);
+        }
+
+        // Scrolling also pauses while power saving is active
+        let paused = Rc::new(Cell::new(false));
+        if config.pause_on_hover {
+            let motion = EventControllerMotion::new();
+            let paused_enter = paused.clone();
+            motion.connect_enter(move |_, _, _| paused_enter.set(true));
+            let paused_leave = paused.clone();
+            motion.connect_leave(move |_| paused_leave.set(false));
+            overlay.add_controller(motion);
+        }
+
+        // Advance the scroll position on every frame, wrapping at the repeated text
+        let speed = config.speed;
+        let last_frame = Cell::new(None::<i64>);
+        let label_tick = label.clone();
+        scrolled.add_tick_callback(move |scrolled, clock| {
+            let now = clock.frame_time();
+            let elapsed = last_frame
+                .replace(Some(now))
+                .map(|last| (now - last) as f64 / 1_000_000.0)
+                .unwrap_or(0.0);
+
+            let adjustment = scrolled.hadjustment();
+            let overflowing = adjustment.upper() > adjustment.page_size();
+            if !overflowing {
+                adjustment.set_value(0.0);
+            } else if !paused.get() && !is_power_saving() {
+                // The label holds the text twice, so half its width is one full cycle
+                let cycle = label_tick.width() as f64 / 2.0;
+                let mut value = adjustment.value() + speed * elapsed;
+                if cycle > 0.0 && value >= cycle {
+                    value -= cycle;
+                }
+                adjustment.set_value(value);
+            }
+
+            ControlFlow::Continue
+        });
+
+        Some(TickerWidget {
+            overlay,
+            label,
+            text: config.text.clone(),
+            command: config.command.clone(),
+            interval: config.interval,
+        })
+    }
+
+    fn set_text(&self, text: &str) {
+        let text = text.replace('\n', SEPARATOR);
+        self.label.set_text(&format!("{}{}{}{}", text, SEPARATOR, text, SEPARATOR));
+        self.overlay.set_tooltip_text(Some(&text));
+    }
+}
+
+impl BarModule for TickerWidget {
+    fn name(&self) -> &'static str {
+        "ticker"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.overlay.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            let Some(command) = &self.command else {
+                self.set_text(self.text.as_deref().unwrap_or_default());
+                return;
+            };
+
+            loop {
+                if let Some(output) = run_shell(command).await {
+                    self.set_text(&output);
+                }
+                next_tick(Duration::from_secs(self.interval.max(1))).await;
+            }
+        })
+    }
+}
@@ -0,0 +1,313 @@
+use gtk4::glib::{DateTime, TimeZone};
+use gtk4::prelude::*;
+use gtk4::{Button, Label};
+use serde_json::{Value, json};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::command::{curl_quote, is_available, spawn_piped, spawn_piped_input};
+use crate::config::{TimeTrackerBackend, TimeTrackerConfig};
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+
+const TOGGL_API: &str = "https://api.track.toggl.com/api/v9";
+
+/// The running Toggl timer, with start and stop from the bar, or the time
+/// ActivityWatch saw the user active today
+pub struct TimeTrackerWidget {
+    pub button: Button,
+    state: Rc<TimeTrackerState>,
+}
+
+struct TimeTrackerState {
+    config: TimeTrackerConfig,
+    label: Label,
+    /// Id and workspace of the running Toggl entry
+    running: Cell<Option<(u64, u64)>>,
+    /// Toggl workspace new timers go to, looked up once when not configured
+    workspace: Cell<Option<u64>>,
+}
+
+impl TimeTrackerWidget {
+    pub fn new(config: &TimeTrackerConfig) -> Option<Self> {
+        if !is_available("curl") {
+            return None;
+        }
+        if config.backend == TimeTrackerBackend::Toggl && config.api_token.is_none() {
+            warn!("Module 'time_tracker' needs 'api_token' to be configured for Toggl");
+            return None;
+        }
+
+        let button = Button::new();
+        button.add_css_class("time-tracker-button");
+
+        let label = Label::new(Some("⏱ --"));
+        label.add_css_class("time-tracker-label");
+        button.set_child(Some(&label));
+
+        let state = Rc::new(TimeTrackerState {
+            config: config.clone(),
+            label,
+            running: Cell::new(None),
+            workspace: Cell::new(config.workspace),
+        });
+
+        // Toggl: start the default project or stop the running timer.
+        // ActivityWatch only counts, a click refreshes the total.
+        let state_click = state.clone();
+        button.connect_clicked(move |_| {
+            let state = state_click.clone();
+            glib::spawn_future_local(async move {
+                if state.config.backend == TimeTrackerBackend::Toggl {
+                    state.toggle().await;
+                }
+                state.update().await;
+            });
+        });
+
+        Some(TimeTrackerWidget { button, state })
+    }
+}
+
+impl TimeTrackerState {
+    async fn update(&self) {
+        match self.config.backend {
+            TimeTrackerBackend::Toggl => self.update_toggl().await,
+            TimeTrackerBackend::Activitywatch => self.update_activitywatch().await,
+        }
+        control::module_updated("time_tracker");
+    }
+
+    async fn update_toggl(&self) {
+        let button = self.label.parent();
+        let Some(entry) = self.toggl("GET", "/me/time_entries/current", None).await else {
+            self.running.set(None);
+            self.label.set_text("⏱ N/A");
+            if let Some(button) = button {
+                button.set_tooltip_text(Some("Toggl is not reachable"));
+            }
+            return;
+        };
+
+        let (Some(id), Some(workspace)) = (entry["id"].as_u64(), entry["workspace_id"].as_u64())
+        else {
+            // No timer running
+            self.running.set(None);
+            self.label.set_text("⏱ --");
+            if let Some(button) = button {
+                button.set_tooltip_text(Some("No timer running, click to start one"));
+                button.remove_css_class("running");
+            }
+            return;
+        };
+        self.running.set(Some((id, workspace)));
+
+        let elapsed = entry["start"]
+            .as_str()
+            .and_then(|start| DateTime::from_iso8601(start, None).ok())
+            .zip(DateTime::now_utc().ok())
+            .map(|(start, now)| now.difference(&start).as_seconds().max(0) as u64)
+            .unwrap_or(0);
+        let description = entry["description"].as_str().unwrap_or_default();
+
+        self.label.set_text(&if description.is_empty() {
+            format!("⏱ {}", format_duration(elapsed))
+        } else {
+            format!("⏱ {} {}", format_duration(elapsed), description)
+        });
+        if let Some(button) = button {
+            button.set_tooltip_text(Some(&format!(
+                "Running for {}{}\nClick to stop",
+                format_duration(elapsed),
+                if description.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", description)
+                }
+            )));
+            button.add_css_class("running");
+        }
+    }
+
+    async fn update_activitywatch(&self) {
+        let button = self.label.parent();
+        let Some(active) = self.activitywatch_today().await else {
+            self.label.set_text("⏱ N/A");
+            if let Some(button) = button {
+                button.set_tooltip_text(Some("ActivityWatch is not reachable"));
+            }
+            return;
+        };
+
+        self.label.set_text(&format!("⏱ {}", format_duration(active)));
+        if let Some(button) = button {
+            button.set_tooltip_text(Some(&format!(
+                "Active today: {}",
+                format_duration(active)
+            )));
+        }
+    }
+
+    async fn toggle(&self) {
+        if let Some((id, workspace)) = self.running.get() {
+            let path = format!("/workspaces/{}/time_entries/{}/stop", workspace, id);
+            if self.toggl("PATCH", &path, None).await.is_none() {
+                warn!("Failed to stop Toggl time entry {}", id);
+            }
+            return;
+        }
+
+        let Some(workspace) = self.workspace().await else {
+            warn!("No Toggl workspace to start a timer in");
+            return;
+        };
+        let Ok(now) = DateTime::now_utc() else {
+            return;
+        };
+        let Ok(start) = now.format_iso8601() else {
+            return;
+        };
+        let entry = json!({
+            "created_with": "blade_bar",
+            "workspace_id": workspace,
+            "project_id": self.config.project,
+            "description": self.config.description,
+            "start": start.as_str(),
+            // Negative while running
+            "duration": -1,
+        });
+        let path = format!("/workspaces/{}/time_entries", workspace);
+        if self.toggl("POST", &path, Some(entry.to_string())).await.is_none() {
+            warn!("Failed to start a Toggl timer");
+        }
+    }
+
+    async fn workspace(&self) -> Option<u64> {
+        if let Some(workspace) = self.workspace.get() {
+            return Some(workspace);
+        }
+        let me = self.toggl("GET", "/me", None).await?;
+        let workspace = me["default_workspace_id"].as_u64()?;
+        self.workspace.set(Some(workspace));
+        Some(workspace)
+    }
+
+    /// Call the Toggl API, the JSON answer or `None` on any failure
+    async fn toggl(&self, method: &str, path: &str, body: Option<String>) -> Option<Value> {
+        // Credentials and body go through stdin as a curl config, out of the process list
+        let credentials = format!(
+            "{}:api_token",
+            self.config.api_token.as_deref().unwrap_or_default()
+        );
+        let mut config = format!("user = {}\n", curl_quote(&credentials));
+        if let Some(body) = &body {
+            config.push_str("header = \"Content-Type: application/json\"\n");
+            config.push_str(&format!("data-raw = {}\n", curl_quote(body)));
+        }
+        let url = format!("{}{}", TOGGL_API, path);
+        let argv = ["curl", "-s", "--fail", "--max-time", "10", "-X", method, "-K", "-", &url];
+        let output = curl(&argv, Some(config)).await?;
+        match serde_json::from_str(&output) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                debug!("Unexpected answer from Toggl {}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Seconds the AFK watcher saw the user active since midnight
+    async fn activitywatch_today(&self) -> Option<u64> {
+        let server = self
+            .config
+            .url
+            .as_deref()
+            .unwrap_or("http://localhost:5600")
+            .trim_end_matches('/');
+
+        let buckets = curl(
+            &["curl", "-s", "--fail", "--max-time", "5", &format!("{}/api/0/buckets/", server)],
+            None,
+        )
+        .await?;
+        let buckets: Value = serde_json::from_str(&buckets).ok()?;
+        let bucket = buckets
+            .as_object()?
+            .keys()
+            .find(|bucket| bucket.starts_with("aw-watcher-afk"))?
+            .clone();
+
+        let now = DateTime::now_local().ok()?;
+        let midnight = DateTime::new(
+            &TimeZone::local(),
+            now.year(),
+            now.month(),
+            now.day_of_month(),
+            0,
+            0,
+            0.0,
+        )
+        .ok()?;
+        let start = format!("start={}", midnight.format_iso8601().ok()?);
+        let end = format!("end={}", now.format_iso8601().ok()?);
+        let url = format!("{}/api/0/buckets/{}/events", server, bucket);
+        let events = curl(
+            &[
+                "curl", "-s", "--fail", "--max-time", "5", "-G", "--data-urlencode", &start,
+                "--data-urlencode", &end, &url,
+            ],
+            None,
+        )
+        .await?;
+        let events: Vec<Value> = serde_json::from_str(&events).ok()?;
+
+        let active: f64 = events
+            .iter()
+            .filter(|event| event["data"]["status"] == "not-afk")
+            .filter_map(|event| event["duration"].as_f64())
+            .sum();
+        Some(active as u64)
+    }
+}
+
+/// Run curl and return its output, `input` is sent on stdin, e.g. a config read with `-K -`
+async fn curl(argv: &[&str], input: Option<String>) -> Option<String> {
+    let process = if input.is_some() {
+        spawn_piped_input(argv)?
+    } else {
+        spawn_piped(argv)?
+    };
+    let (stdout, _) = process.communicate_utf8_future(input).await.ok()?;
+    if !process.is_successful() {
+        debug!("curl exited with status {}", process.exit_status());
+        return None;
+    }
+    Some(stdout.map(|stdout| stdout.to_string()).unwrap_or_default())
+}
+
+/// 4500 seconds as "1:15"
+fn format_duration(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 3600, seconds % 3600 / 60)
+}
+
+impl BarModule for TimeTrackerWidget {
+    fn name(&self) -> &'static str {
+        "time_tracker"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            loop {
+                self.state.update().await;
+                next_tick(Duration::from_secs(self.state.config.interval.max(1))).await;
+            }
+        })
+    }
+}
@@ -0,0 +1,380 @@
+// Popup toasts for notifications received by the built-in daemon, stacked in a
+// layer-shell window in the top right corner of the focused output.
+
+use glib::SourceId;
+use gtk4::gdk::{MemoryFormat, MemoryTexture};
+use gtk4::glib::Bytes;
+use gtk4::prelude::*;
+use gtk4::{
+    Align, Application, ApplicationWindow, Box as GtkBox, Button, GestureClick, Image, Label,
+    Orientation,
+};
+use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::warn;
+
+use crate::bar;
+
+/// Distance of the toasts from the screen edges
+const MARGIN: i32 = 10;
+
+/// Size of images attached to a notification
+const IMAGE_SIZE: i32 = 64;
+
+/// Action invoked by clicking the toast itself
+const DEFAULT_ACTION: &str = "default";
+
+/// Urgency hint of a notification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    pub fn from_hint(value: u8) -> Self {
+        match value {
+            0 => Urgency::Low,
+            2 => Urgency::Critical,
+            _ => Urgency::Normal,
+        }
+    }
+
+    fn css_class(self) -> &'static str {
+        match self {
+            Urgency::Low => "low",
+            Urgency::Normal => "normal",
+            Urgency::Critical => "critical",
+        }
+    }
+}
+
+/// Image attached to a notification with the `image-data` or `image-path` hints
+#[derive(Debug, Clone)]
+pub enum NotificationImage {
+    /// Raw 8 bit RGB(A) pixels
+    Data {
+        width: i32,
+        height: i32,
+        rowstride: i32,
+        has_alpha: bool,
+        pixels: Vec<u8>,
+    },
+    /// Icon name, path or `file://` URI
+    Path(String),
+}
+
+/// A notification as sent by an application
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub id: u32,
+    pub app_name: String,
+    pub app_icon: String,
+    pub summary: String,
+    pub body: String,
+    pub image: Option<NotificationImage>,
+    /// Action keys and their labels
+    pub actions: Vec<(String, String)>,
+    pub urgency: Urgency,
+    /// How long to show the toast, `None` until dismissed
+    pub timeout: Option<Duration>,
+}
+
+/// Why a notification was closed, as reported in `NotificationClosed`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    Expired = 1,
+    Dismissed = 2,
+    Closed = 3,
+}
+
+/// User interaction with a toast, reported back to the sending application
+#[derive(Debug)]
+pub enum ToastEvent {
+    Closed(u32, CloseReason),
+    ActionInvoked(u32, String),
+}
+
+struct Toast {
+    widget: GtkBox,
+    expiry: Option<SourceId>,
+    /// Tints the bar while it is up
+    tint_bar: bool,
+}
+
+pub struct Toasts {
+    window: ApplicationWindow,
+    stack: GtkBox,
+    toasts: RefCell<HashMap<u32, Toast>>,
+    events: UnboundedSender<ToastEvent>,
+}
+
+impl Toasts {
+    pub fn new(app: &Application, events: UnboundedSender<ToastEvent>) -> Rc<Self> {
+        let window = ApplicationWindow::builder()
+            .application(app)
+            .title("Notifications")
+            .css_classes(["notification-toasts"])
+            .build();
+
+        if gtk4_layer_shell::is_supported() {
+            LayerShell::init_layer_shell(&window);
+            LayerShell::set_layer(&window, Layer::Overlay);
+            LayerShell::set_keyboard_mode(&window, KeyboardMode::None);
+            for edge in [Edge::Top, Edge::Right] {
+                LayerShell::set_anchor(&window, edge, true);
+                LayerShell::set_margin(&window, edge, MARGIN);
+            }
+        }
+
+        let stack = GtkBox::new(Orientation::Vertical, 8);
+        window.set_child(Some(&stack));
+
+        Rc::new(Toasts {
+            window,
+            stack,
+            toasts: RefCell::new(HashMap::new()),
+            events,
+        })
+    }
+
+    /// Show a toast, replacing the one with the same id if it is still up
+    pub fn show(self: &Rc<Self>, notification: Notification, tint_bar: bool) {
+        let widget = self.build_toast(&notification);
+
+        let previous = self.toasts.borrow_mut().remove(&notification.id);
+        match previous {
+            Some(previous) => {
+                if let Some(expiry) = previous.expiry {
+                    expiry.remove();
+                }
+                self.stack
+                    .insert_child_after(&widget, Some(&previous.widget));
+                self.stack.remove(&previous.widget);
+            }
+            None => self.stack.append(&widget),
+        }
+
+        let expiry = notification.timeout.map(|timeout| {
+            let toasts = Rc::downgrade(self);
+            let id = notification.id;
+            glib::timeout_add_local_once(timeout, move || {
+                if let Some(toasts) = toasts.upgrade() {
+                    if let Some(toast) = toasts.toasts.borrow_mut().get_mut(&id) {
+                        toast.expiry.take();
+                    }
+                    toasts.close(id, CloseReason::Expired);
+                }
+            })
+        });
+
+        self.toasts.borrow_mut().insert(
+            notification.id,
+            Toast {
+                widget,
+                expiry,
+                tint_bar,
+            },
+        );
+        self.window.set_visible(true);
+        self.update_bar_tint();
+    }
+
+    /// Keep the bar tinted while any escalated toast is up
+    fn update_bar_tint(&self) {
+        let tinted = self.toasts.borrow().values().any(|toast| toast.tint_bar);
+        bar::set_alert(tinted);
+    }
+
+    /// Remove a toast and report why, does nothing if it is already gone
+    pub fn close(&self, id: u32, reason: CloseReason) {
+        let Some(toast) = self.toasts.borrow_mut().remove(&id) else {
+            return;
+        };
+        if let Some(expiry) = toast.expiry {
+            expiry.remove();
+        }
+        self.stack.remove(&toast.widget);
+        let _ = self.events.send(ToastEvent::Closed(id, reason));
+
+        if self.toasts.borrow().is_empty() {
+            self.window.set_visible(false);
+        }
+        self.update_bar_tint();
+    }
+
+    fn invoke(&self, id: u32, action: &str) {
+        let _ = self
+            .events
+            .send(ToastEvent::ActionInvoked(id, action.to_string()));
+        self.close(id, CloseReason::Dismissed);
+    }
+
+    fn default_action_click(self: &Rc<Self>, id: u32) -> GestureClick {
+        let click = GestureClick::new();
+        let toasts = Rc::downgrade(self);
+        click.connect_released(move |_, _, _, _| {
+            if let Some(toasts) = toasts.upgrade() {
+                toasts.invoke(id, DEFAULT_ACTION);
+            }
+        });
+        click
+    }
+
+    fn build_toast(self: &Rc<Self>, notification: &Notification) -> GtkBox {
+        let id = notification.id;
+
+        let toast = GtkBox::new(Orientation::Vertical, 4);
+        toast.add_css_class("notification-toast");
+        toast.add_css_class(notification.urgency.css_class());
+
+        let header = GtkBox::new(Orientation::Horizontal, 6);
+        if let Some(icon) = icon_image(&notification.app_icon, 24) {
+            icon.add_css_class("toast-icon");
+            header.append(&icon);
+        }
+
+        let summary = Label::new(Some(&notification.summary));
+        summary.add_css_class("toast-summary");
+        summary.set_halign(Align::Start);
+        summary.set_hexpand(true);
+        summary.set_wrap(true);
+        summary.set_max_width_chars(40);
+        summary.set_tooltip_text(Some(&notification.app_name));
+        header.append(&summary);
+
+        // Clicking the text runs the default action, if the application has one
+        let has_default = notification
+            .actions
+            .iter()
+            .any(|(key, _)| key == DEFAULT_ACTION);
+        if has_default {
+            summary.add_controller(self.default_action_click(id));
+        }
+
+        let close = Button::from_icon_name("window-close-symbolic");
+        close.add_css_class("toast-close");
+        let toasts = Rc::downgrade(self);
+        close.connect_clicked(move |_| {
+            if let Some(toasts) = toasts.upgrade() {
+                toasts.close(id, CloseReason::Dismissed);
+            }
+        });
+        header.append(&close);
+        toast.append(&header);
+
+        let content = GtkBox::new(Orientation::Horizontal, 8);
+        if let Some(image) = notification.image.as_ref().and_then(notification_image) {
+            image.add_css_class("toast-image");
+            image.set_valign(Align::Start);
+            content.append(&image);
+        }
+
+        if !notification.body.is_empty() {
+            let body = Label::new(None);
+            body.add_css_class("toast-body");
+            body.set_halign(Align::Start);
+            body.set_xalign(0.0);
+            body.set_wrap(true);
+            body.set_max_width_chars(40);
+            // Applications may send a subset of Pango markup, fall back to plain text
+            if gtk4::pango::parse_markup(&notification.body, '\0').is_ok() {
+                body.set_markup(&notification.body);
+            } else {
+                body.set_text(&notification.body);
+            }
+            if has_default {
+                body.add_controller(self.default_action_click(id));
+            }
+            content.append(&body);
+        }
+        if content.first_child().is_some() {
+            toast.append(&content);
+        }
+
+        let buttons: Vec<&(String, String)> = notification
+            .actions
+            .iter()
+            .filter(|(key, _)| key != DEFAULT_ACTION)
+            .collect();
+        if !buttons.is_empty() {
+            let actions = GtkBox::new(Orientation::Horizontal, 6);
+            actions.add_css_class("toast-actions");
+            actions.set_homogeneous(true);
+            for (key, label) in buttons {
+                let button = Button::with_label(label);
+                button.add_css_class("toast-action");
+                let toasts = Rc::downgrade(self);
+                let key = key.clone();
+                button.connect_clicked(move |_| {
+                    if let Some(toasts) = toasts.upgrade() {
+                        toasts.invoke(id, &key);
+                    }
+                });
+                actions.append(&button);
+            }
+            toast.append(&actions);
+        }
+
+        toast
+    }
+}
+
+/// Icon given as a themed icon name, a path or a `file://` URI, scaled to `size`
+fn icon_image(icon: &str, size: i32) -> Option<Image> {
+    if icon.is_empty() {
+        return None;
+    }
+    let image = match icon
+        .strip_prefix("file://")
+        .or(icon.starts_with('/').then_some(icon))
+    {
+        Some(path) => Image::from_file(path),
+        None => Image::from_icon_name(icon),
+    };
+    image.set_pixel_size(size);
+    Some(image)
+}
+
+fn notification_image(image: &NotificationImage) -> Option<Image> {
+    match image {
+        NotificationImage::Path(path) => icon_image(path, IMAGE_SIZE),
+        NotificationImage::Data {
+            width,
+            height,
+            rowstride,
+            has_alpha,
+            pixels,
+        } => {
+            let (format, channels) = match has_alpha {
+                true => (MemoryFormat::R8g8b8a8, 4),
+                false => (MemoryFormat::R8g8b8, 3),
+            };
+            let row = (*width).max(0) as usize * channels;
+            let stride = (*rowstride).max(0) as usize;
+            // The last row does not need to be padded to the full stride
+            let expected_len = stride * ((*height).max(1) as usize - 1) + row;
+            if row == 0 || *height <= 0 || stride < row || pixels.len() < expected_len {
+                warn!(
+                    "Ignoring malformed notification image ({}x{}, {} bytes)",
+                    width,
+                    height,
+                    pixels.len()
+                );
+                return None;
+            }
+
+            let bytes = Bytes::from(pixels.as_slice());
+            let texture = MemoryTexture::new(*width, *height, format, &bytes, stride);
+            let image = Image::from_paintable(Some(&texture));
+            image.set_pixel_size(IMAGE_SIZE);
+            Some(image)
+        }
+    }
+}
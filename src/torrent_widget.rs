@@ -0,0 +1,394 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Label, Orientation, Popover};
+use serde_json::{Value, json};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::command::{curl_quote, is_available, spawn_piped_input};
+use crate::config::{TorrentClient, TorrentConfig};
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+use crate::system_monitor::format_bytes;
+
+/// Totals over all torrents of the client
+#[derive(Debug, Clone, Copy, Default)]
+struct TorrentStats {
+    /// Bytes per second
+    download: f64,
+    upload: f64,
+    active: u64,
+    total: u64,
+}
+
+/// An HTTP response as printed by `curl --include`
+struct Response {
+    status: u32,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Response {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Transfer rates and active torrents of transmission-daemon or qBittorrent, with
+/// pause-all and resume-all in a popover
+pub struct TorrentWidget {
+    pub button: Button,
+    state: Rc<TorrentState>,
+}
+
+struct TorrentState {
+    client: TorrentClient,
+    url: String,
+    username: Option<String>,
+    password: Option<String>,
+    interval: Duration,
+    label: Label,
+    summary: Label,
+    /// Transmission's CSRF token or qBittorrent's session cookie, renewed when rejected
+    session: RefCell<Option<String>>,
+}
+
+impl TorrentWidget {
+    pub fn new(config: &TorrentConfig) -> Option<Self> {
+        if !is_available("curl") {
+            return None;
+        }
+
+        let url = config.url.clone().unwrap_or_else(|| {
+            match config.client {
+                TorrentClient::Transmission => "http://localhost:9091/transmission/rpc",
+                TorrentClient::Qbittorrent => "http://localhost:8080",
+            }
+            .to_string()
+        });
+
+        let button = Button::new();
+        button.add_css_class("torrent-button");
+
+        let label = Label::new(Some("↓-- ↑--"));
+        label.add_css_class("torrent-label");
+        button.set_child(Some(&label));
+
+        let content = GtkBox::new(Orientation::Vertical, 6);
+        content.add_css_class("torrent-popover");
+        let summary = Label::new(None);
+        summary.set_halign(gtk4::Align::Start);
+        content.append(&summary);
+
+        let actions = GtkBox::new(Orientation::Horizontal, 6);
+        actions.set_homogeneous(true);
+        let pause = Button::with_label("Pause all");
+        let resume = Button::with_label("Resume all");
+        actions.append(&pause);
+        actions.append(&resume);
+        content.append(&actions);
+
+        let popover = Popover::new();
+        popover.set_child(Some(&content));
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let state = Rc::new(TorrentState {
+            client: config.client,
+            url,
+            username: config.username.clone(),
+            password: config.password.clone(),
+            interval: Duration::from_secs(config.interval.max(1)),
+            label,
+            summary,
+            session: RefCell::new(None),
+        });
+
+        for (action, paused) in [(&pause, true), (&resume, false)] {
+            let state = state.clone();
+            action.connect_clicked(move |_| {
+                let state = state.clone();
+                glib::spawn_future_local(async move {
+                    state.set_paused(paused).await;
+                    state.update().await;
+                });
+            });
+        }
+
+        button.connect_clicked(move |_| {
+            popover.popup();
+        });
+
+        Some(TorrentWidget { button, state })
+    }
+}
+
+impl TorrentState {
+    async fn update(&self) {
+        let button = self.label.parent();
+        let Some(stats) = self.read_stats().await else {
+            self.label.set_text("↓-- ↑--");
+            self.summary.set_text(&format!("Cannot reach {}", self.url));
+            if let Some(button) = button {
+                button.set_tooltip_text(Some(&format!("Cannot reach {}", self.url)));
+                button.add_css_class("offline");
+            }
+            return;
+        };
+
+        self.label.set_text(&format!(
+            "↓{} ↑{}",
+            format_bytes(stats.download, true),
+            format_bytes(stats.upload, true)
+        ));
+        let summary = format!(
+            "{} of {} torrents active\nDown: {}/s\nUp: {}/s",
+            stats.active,
+            stats.total,
+            format_bytes(stats.download, false),
+            format_bytes(stats.upload, false)
+        );
+        self.summary.set_text(&summary);
+        if let Some(button) = button {
+            button.set_tooltip_text(Some(&summary));
+            button.remove_css_class("offline");
+            if stats.active > 0 {
+                button.add_css_class("active");
+            } else {
+                button.remove_css_class("active");
+            }
+        }
+        control::module_updated("torrent");
+    }
+
+    async fn read_stats(&self) -> Option<TorrentStats> {
+        match self.client {
+            TorrentClient::Transmission => {
+                let stats = self.transmission("session-stats").await?;
+                Some(TorrentStats {
+                    download: stats["downloadSpeed"].as_f64().unwrap_or(0.0),
+                    upload: stats["uploadSpeed"].as_f64().unwrap_or(0.0),
+                    active: stats["activeTorrentCount"].as_u64().unwrap_or(0),
+                    total: stats["torrentCount"].as_u64().unwrap_or(0),
+                })
+            }
+            TorrentClient::Qbittorrent => {
+                let transfer: Value =
+                    serde_json::from_str(&self.qbittorrent("transfer/info", None).await?).ok()?;
+                let torrents: Vec<Value> =
+                    serde_json::from_str(&self.qbittorrent("torrents/info", None).await?).ok()?;
+                // Active like qBittorrent's own filter: transferring in either direction
+                let active = torrents
+                    .iter()
+                    .filter(|torrent| {
+                        torrent["dlspeed"].as_f64().unwrap_or(0.0) > 0.0
+                            || torrent["upspeed"].as_f64().unwrap_or(0.0) > 0.0
+                    })
+                    .count();
+                Some(TorrentStats {
+                    download: transfer["dl_info_speed"].as_f64().unwrap_or(0.0),
+                    upload: transfer["up_info_speed"].as_f64().unwrap_or(0.0),
+                    active: active as u64,
+                    total: torrents.len() as u64,
+                })
+            }
+        }
+    }
+
+    async fn set_paused(&self, paused: bool) {
+        let done = match self.client {
+            // Without ids Transmission applies the method to every torrent
+            TorrentClient::Transmission => self
+                .transmission(if paused { "torrent-stop" } else { "torrent-start" })
+                .await
+                .is_some(),
+            TorrentClient::Qbittorrent => {
+                // qBittorrent 5 renamed pause and resume to stop and start
+                let (current, legacy) = if paused {
+                    ("torrents/stop", "torrents/pause")
+                } else {
+                    ("torrents/start", "torrents/resume")
+                };
+                self.qbittorrent(current, Some("hashes=all")).await.is_some()
+                    || self.qbittorrent(legacy, Some("hashes=all")).await.is_some()
+            }
+        };
+        if !done {
+            warn!(
+                "Failed to {} torrents at {}",
+                if paused { "pause" } else { "resume" },
+                self.url
+            );
+        }
+    }
+
+    /// Call a Transmission RPC method and return its arguments
+    async fn transmission(&self, method: &str) -> Option<Value> {
+        let body = json!({ "method": method }).to_string();
+        for _ in 0..2 {
+            let mut headers = vec!["Content-Type: application/json".to_string()];
+            if let Some(session) = self.session.borrow().as_ref() {
+                headers.push(format!("X-Transmission-Session-Id: {}", session));
+            }
+            let response = self.request(&self.url, &headers, Some(&body)).await?;
+            match response.status {
+                // A missing or stale session id is answered with the current one
+                409 => {
+                    *self.session.borrow_mut() = response
+                        .header("X-Transmission-Session-Id")
+                        .map(str::to_string);
+                }
+                200 => {
+                    let reply: Value = serde_json::from_str(&response.body).ok()?;
+                    if reply["result"] != "success" {
+                        warn!("Transmission {} failed: {}", method, reply["result"]);
+                        return None;
+                    }
+                    return Some(reply["arguments"].clone());
+                }
+                status => {
+                    debug!("Transmission answered {} with status {}", method, status);
+                    return None;
+                }
+            }
+        }
+        None
+    }
+
+    /// Call a qBittorrent Web API endpoint, logging in first when the session is missing
+    async fn qbittorrent(&self, path: &str, form: Option<&str>) -> Option<String> {
+        let url = format!("{}/api/v2/{}", self.url.trim_end_matches('/'), path);
+        for _ in 0..2 {
+            let mut headers = Vec::new();
+            if let Some(cookie) = self.session.borrow().as_ref() {
+                headers.push(format!("Cookie: {}", cookie));
+            }
+            let response = self.request(&url, &headers, form).await?;
+            match response.status {
+                200 => return Some(response.body),
+                // Not logged in, or the session expired
+                403 if self.username.is_some() => self.login().await?,
+                status => {
+                    debug!("qBittorrent answered {} with status {}", path, status);
+                    return None;
+                }
+            }
+        }
+        None
+    }
+
+    async fn login(&self) -> Option<()> {
+        let username = self.username.as_deref().unwrap_or_default();
+        let form = format!(
+            "username={}&password={}",
+            form_encode(username),
+            form_encode(self.password.as_deref().unwrap_or_default())
+        );
+        let url = format!("{}/api/v2/auth/login", self.url.trim_end_matches('/'));
+        let response = self.request(&url, &[], Some(&form)).await?;
+
+        // Wrong credentials get "Fails." without a cookie
+        let cookie = response
+            .header("Set-Cookie")
+            .and_then(|cookie| cookie.split(';').next())
+            .filter(|cookie| cookie.contains('='));
+        let Some(cookie) = cookie else {
+            warn!("qBittorrent login as '{}' failed", username);
+            return None;
+        };
+        *self.session.borrow_mut() = Some(cookie.to_string());
+        Some(())
+    }
+
+    /// Send a request with curl. Credentials, headers and the body are written to its
+    /// stdin as a config, which keeps passwords and session cookies out of the process
+    /// list.
+    async fn request(&self, url: &str, headers: &[String], body: Option<&str>) -> Option<Response> {
+        let mut config = String::new();
+        if let (TorrentClient::Transmission, Some(username)) = (self.client, &self.username) {
+            let credentials = format!(
+                "{}:{}",
+                username,
+                self.password.as_deref().unwrap_or_default()
+            );
+            config.push_str(&format!("user = {}\n", curl_quote(&credentials)));
+        }
+        for header in headers {
+            config.push_str(&format!("header = {}\n", curl_quote(header)));
+        }
+        if let Some(body) = body {
+            config.push_str(&format!("data-raw = {}\n", curl_quote(body)));
+        }
+
+        let process =
+            spawn_piped_input(&["curl", "-s", "--include", "--max-time", "5", "-K", "-", url])?;
+        let (stdout, _) = process.communicate_utf8_future(Some(config)).await.ok()?;
+        if !process.is_successful() {
+            debug!("curl {} exited with status {}", url, process.exit_status());
+            return None;
+        }
+        parse_response(stdout.as_deref().unwrap_or_default())
+    }
+}
+
+fn parse_response(output: &str) -> Option<Response> {
+    let mut rest = output;
+    loop {
+        let (head, body) = rest.split_once("\r\n\r\n")?;
+        let mut lines = head.lines();
+        let status: u32 = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+        // Interim "100 Continue" responses come first, each with its own headers
+        if status == 100 {
+            rest = body;
+            continue;
+        }
+        let headers = lines
+            .filter_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                Some((key.trim().to_string(), value.trim().to_string()))
+            })
+            .collect();
+        return Some(Response {
+            status,
+            headers,
+            body: body.to_string(),
+        });
+    }
+}
+
+/// Percent-encode a value for an `application/x-www-form-urlencoded` body
+fn form_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+impl BarModule for TorrentWidget {
+    fn name(&self) -> &'static str {
+        "torrent"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            loop {
+                self.state.update().await;
+                next_tick(self.state.interval).await;
+            }
+        })
+    }
+}
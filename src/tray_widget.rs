@@ -1,8 +1,10 @@
 // Re-export the main components
 pub mod controls;
-pub mod events;
 pub mod popover_menu;
-pub mod menu_helpers;
+pub mod module;
+pub mod source;
 pub mod widget;
 
+pub use module::TrayModule;
+pub use source::WatcherTraySource;
 pub use widget::TrayWidget;
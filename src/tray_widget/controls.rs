@@ -1,75 +1,93 @@
-use std::sync::Arc;
+use std::cell::Cell;
+use std::path::Path;
+use std::rc::Rc;
+use tracing::{debug, warn};
 
+use crate::launch;
+use crate::sources::TraySource;
 use crate::tray_widget::TrayWidget;
-use gtk4::gdk_pixbuf::{Colorspace, Pixbuf};
+use gtk4::gdk::{self, MemoryFormat, MemoryTexture};
+use gtk4::glib::Bytes;
 use gtk4::prelude::*;
-use gtk4::{Box as GtkBox, Button, GestureClick, Image, Orientation, Popover};
+use gtk4::glib::Propagation;
+use gtk4::{
+    Box as GtkBox, Button, EventControllerScroll, EventControllerScrollFlags, GestureClick, IconTheme,
+    Image, Orientation, Popover,
+};
 use system_tray::client::ActivateRequest;
 use system_tray::item::IconPixmap;
-use system_tray::item::StatusNotifierItem;
+use system_tray::item::{Status, StatusNotifierItem};
 use system_tray::item::Tooltip;
 
+/// Logical size of tray icons
+const ICON_SIZE: i32 = 16;
+
+/// Logical size of the emblem drawn over a tray icon
+const OVERLAY_SIZE: i32 = 8;
+
+/// Texture made from an item's pixmap, reused by later redraws while the pixels are
+/// the same so icon updates do not upload it again
+pub struct PixmapTexture {
+    width: i32,
+    height: i32,
+    pixels: Bytes,
+    texture: MemoryTexture,
+}
+
 pub fn create_tray_button(
     item: &StatusNotifierItem,
     service_key: &str,
-    tray_widget: Arc<TrayWidget>,
+    tray_widget: &Rc<TrayWidget>,
 ) -> Button {
     let button = Button::new();
     button.add_css_class("tray-button");
 
     let title = item.title.as_deref().clone().unwrap_or("Unknown");
 
-    set_button_icon(item.icon_name.as_deref(), item.icon_pixmap.clone(), &button);
     set_tooltip(&button, item.tool_tip.clone(), Some(title));
 
     // Handle left-click (primary button) using gesture
-    let left_click = get_button_left_click(item, &tray_widget, service_key);
+    let left_click = get_button_left_click(item, tray_widget, service_key);
 
     button.add_controller(left_click);
 
-    let right_click = get_button_right_click(item, &tray_widget, Arc::from(service_key));
+    // Middle-click is used e.g. by media players for play/pause
+    let middle_click = get_button_middle_click(item, tray_widget, service_key);
+    button.add_controller(middle_click);
+
+    let right_click = get_button_right_click(item, tray_widget, Rc::from(service_key));
     button.add_controller(right_click);
 
+    button.add_controller(get_button_scroll(tray_widget, service_key));
+
     button
 }
 
-fn create_button_icon(
-    icon_name: Option<&str>,
-    icon_pixmap: Option<Vec<IconPixmap>>,
-) -> Option<Image> {
-    match (icon_name, icon_pixmap.as_deref()) {
+fn create_button_icon(item: &StatusNotifierItem, textures: &mut PixmapTextures) -> Option<Image> {
+    // Items asking for attention may provide a separate icon for it
+    let attention_icon = matches!(item.status, Status::NeedsAttention)
+        && (item.attention_icon_name.as_deref().is_some_and(|name| !name.is_empty())
+            || item.attention_icon_pixmap.as_ref().is_some_and(|pixmaps| !pixmaps.is_empty()));
+    let (icon_name, icon_pixmap) = match attention_icon {
+        true => (&item.attention_icon_name, &item.attention_icon_pixmap),
+        false => (&item.icon_name, &item.icon_pixmap),
+    };
+
+    match (icon_name.as_deref(), icon_pixmap.as_ref()) {
         (Some(icon_name), _) if !icon_name.is_empty() => {
+            if let Some(path) = item.icon_theme_path.as_deref() {
+                add_icon_theme_path(path);
+            }
             let image = Image::from_icon_name(icon_name);
-            image.set_pixel_size(16);
+            image.set_pixel_size(ICON_SIZE);
             return Some(image);
         }
-        (_, Some(pixmap)) if pixmap.len() > 0 => {
-            let pixels = &pixmap[0];
-            let data = &pixmap[0].pixels;
-
-            let mut rgba_data = Vec::with_capacity(data.len());
-            // Convert ARGB32 (network byte order) to RGBA
-            for chunk in data.chunks_exact(4) {
-                let argb = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
-                let a = ((argb >> 24) & 0xff) as u8;
-                let r = ((argb >> 16) & 0xff) as u8;
-                let g = ((argb >> 8) & 0xff) as u8;
-                let b = (argb & 0xff) as u8;
-                rgba_data.extend_from_slice(&[r, g, b, a]);
-            }
-
-            let pixbuf = Pixbuf::from_mut_slice(
-                rgba_data,
-                Colorspace::Rgb,
-                true, // has_alpha
-                8,    // bits_per_sample
-                pixels.width as i32,
-                pixels.height as i32,
-                (pixels.width * 4) as i32, // rowstride (width * 4 bytes per pixel)
-            );
+        (_, Some(pixmaps)) if pixmaps.len() > 0 => {
+            let pixmap = closest_pixmap(pixmaps, ICON_SIZE * display_scale())?;
+            let texture = textures.get(pixmap)?;
 
-            let image = Image::from_pixbuf(Some(&pixbuf));
-            image.set_pixel_size(16);
+            let image = Image::from_paintable(Some(&texture));
+            image.set_pixel_size(ICON_SIZE);
             return Some(image);
         }
         _ => {
@@ -78,15 +96,147 @@ fn create_button_icon(
     }
 }
 
+/// Small emblem in the corner of the icon for extra state, e.g. a sync client's
+/// paused or error badge
+fn create_overlay_icon(item: &StatusNotifierItem, textures: &mut PixmapTextures) -> Option<Image> {
+    let image = match (item.overlay_icon_name.as_deref(), item.overlay_icon_pixmap.as_ref()) {
+        (Some(icon_name), _) if !icon_name.is_empty() => Image::from_icon_name(icon_name),
+        (_, Some(pixmaps)) if !pixmaps.is_empty() => {
+            let pixmap = closest_pixmap(pixmaps, OVERLAY_SIZE * display_scale())?;
+            Image::from_paintable(Some(&textures.get(pixmap)?))
+        }
+        _ => return None,
+    };
+    image.set_pixel_size(OVERLAY_SIZE);
+    image.set_halign(gtk4::Align::End);
+    image.set_valign(gtk4::Align::End);
+    image.add_css_class("tray-overlay-icon");
+    Some(image)
+}
+
+/// Make icons shipped in an item's own theme directory (Dropbox, Syncthing-GTK) findable
+fn add_icon_theme_path(path: &str) {
+    if path.is_empty() {
+        return;
+    }
+    let Some(display) = gdk::Display::default() else {
+        return;
+    };
+    let theme = IconTheme::for_display(&display);
+    let registered = theme
+        .search_path()
+        .iter()
+        .any(|existing| existing.as_path() == Path::new(path));
+    if !registered {
+        theme.add_search_path(path);
+    }
+}
+
+/// Highest scale factor among the connected monitors
+fn display_scale() -> i32 {
+    let Some(display) = gdk::Display::default() else {
+        return 1;
+    };
+    let monitors = display.monitors();
+    (0..monitors.n_items())
+        .filter_map(|i| monitors.item(i).and_downcast::<gdk::Monitor>())
+        .map(|monitor| monitor.scale_factor())
+        .max()
+        .unwrap_or(1)
+}
+
+/// The pixmap nearest to `size` pixels, preferring larger ones which scale down cleanly
+fn closest_pixmap(pixmaps: &[IconPixmap], size: i32) -> Option<&IconPixmap> {
+    pixmaps
+        .iter()
+        .min_by_key(|pixmap| {
+            let edge = pixmap.width.max(pixmap.height);
+            (edge < size, (edge - size).abs())
+        })
+}
+
+/// Textures of the last redraw to pick from and the ones used by the current one
+struct PixmapTextures {
+    previous: Vec<PixmapTexture>,
+    used: Vec<PixmapTexture>,
+}
+
+impl PixmapTextures {
+    /// Texture for `pixmap`, the previous one if its pixels did not change
+    fn get(&mut self, pixmap: &IconPixmap) -> Option<MemoryTexture> {
+        let position = self.previous.iter().position(|previous| {
+            previous.width == pixmap.width
+                && previous.height == pixmap.height
+                && previous.pixels[..] == pixmap.pixels[..]
+        });
+        let texture = match position {
+            Some(position) => self.previous.swap_remove(position),
+            None => create_pixmap_texture(pixmap)?,
+        };
+        self.used.push(texture);
+        self.used.last().map(|texture| texture.texture.clone())
+    }
+}
+
+/// Wrap SNI pixmap data in a texture, copying it once and without converting it.
+/// SNI pixmaps are ARGB32 in network byte order, i.e. the bytes are laid out
+/// A, R, G, B, which is exactly GDK's `A8r8g8b8` memory format.
+fn create_pixmap_texture(pixmap: &IconPixmap) -> Option<PixmapTexture> {
+    let stride = pixmap.width.max(0) as usize * 4;
+    let expected_len = stride * pixmap.height.max(0) as usize;
+    if expected_len == 0 || pixmap.pixels.len() < expected_len {
+        warn!(
+            "Ignoring malformed tray pixmap ({}x{}, {} bytes)",
+            pixmap.width,
+            pixmap.height,
+            pixmap.pixels.len()
+        );
+        return None;
+    }
+
+    let pixels = Bytes::from(&pixmap.pixels[..]);
+    let texture = MemoryTexture::new(
+        pixmap.width,
+        pixmap.height,
+        MemoryFormat::A8r8g8b8,
+        &pixels,
+        stride,
+    );
+    Some(PixmapTexture {
+        width: pixmap.width,
+        height: pixmap.height,
+        pixels,
+        texture,
+    })
+}
+
+/// Show the item's icon on the button. `textures` are those of the previous redraw and
+/// are replaced by the ones this one uses.
 pub fn set_button_icon(
-    icon_name: Option<&str>,
-    icon_pixmap: Option<Vec<IconPixmap>>,
+    item: &StatusNotifierItem,
     button: &Button,
+    textures: &mut Vec<PixmapTexture>,
 ) {
-    match create_button_icon(icon_name, icon_pixmap) {
-        Some(image) => {
-            button.set_child(Some(&image));
-        }
+    let mut pixmap_textures = PixmapTextures {
+        previous: std::mem::take(textures),
+        used: Vec::new(),
+    };
+    let image = create_button_icon(item, &mut pixmap_textures);
+    let overlay_icon = image
+        .as_ref()
+        .and_then(|_| create_overlay_icon(item, &mut pixmap_textures));
+    *textures = pixmap_textures.used;
+
+    match image {
+        Some(image) => match overlay_icon {
+            Some(overlay_icon) => {
+                let overlay = gtk4::Overlay::new();
+                overlay.set_child(Some(&image));
+                overlay.add_overlay(&overlay_icon);
+                button.set_child(Some(&overlay));
+            }
+            None => button.set_child(Some(&image)),
+        },
         None => {
             // Fallback to text label if no icon is available
             button.set_label("");
@@ -94,6 +244,16 @@ pub fn set_button_icon(
     }
 }
 
+/// Hide passive items unless `show_passive` and mark those asking for attention
+pub fn set_button_status(item: &StatusNotifierItem, button: &Button, show_passive: bool) {
+    button.set_visible(show_passive || !matches!(item.status, Status::Passive));
+    if matches!(item.status, Status::NeedsAttention) {
+        button.add_css_class("needs-attention");
+    } else {
+        button.remove_css_class("needs-attention");
+    }
+}
+
 pub fn set_tooltip(button: &Button, tooltip: Option<Tooltip>, title: Option<&str>) {
     let tooltip_ref = tooltip.as_ref();
 
@@ -113,7 +273,7 @@ pub fn set_tooltip(button: &Button, tooltip: Option<Tooltip>, title: Option<&str
 
 fn get_button_left_click(
     item: &StatusNotifierItem,
-    tray_widget: &Arc<TrayWidget>,
+    tray_widget: &Rc<TrayWidget>,
     service_key: &str,
 ) -> gtk4::GestureClick {
     let left_click = gtk4::GestureClick::new();
@@ -121,22 +281,24 @@ fn get_button_left_click(
 
     let item_id_left = item.id.clone();
     let service_key_left = service_key.to_string();
-    let tray_widget_weak = Arc::downgrade(&tray_widget);
+    let tray_widget_weak = Rc::downgrade(tray_widget);
 
     left_click.connect_pressed(move |_, _, _x, _y| {
         if let Some(tray_widget) = tray_widget_weak.upgrade() {
             let item_id = item_id_left.clone();
             let service_key = service_key_left.clone();
 
-            println!(
+            debug!(
                 "Left-click on tray item: {} (service: {})",
                 item_id, service_key
             );
 
             // Activate the tray item using the service key
+            let token = launch::activation_token();
             glib::spawn_future_local(async move {
-                if let Err(e) = tray_widget
-                    .system_tray_client
+                let source = tray_widget.source();
+                provide_activation_token(source.as_ref(), &service_key, token).await;
+                if let Err(e) = source
                     .activate(ActivateRequest::Default {
                         address: service_key.clone(),
                         x: 0,
@@ -144,12 +306,12 @@ fn get_button_left_click(
                     })
                     .await
                 {
-                    eprintln!(
+                    warn!(
                         "Failed to activate tray item '{}' (service: '{}'): {}",
                         item_id, service_key, e
                     );
                 } else {
-                    println!(
+                    debug!(
                         "Successfully activated tray item: {} (service: {})",
                         item_id, service_key
                     );
@@ -161,38 +323,142 @@ fn get_button_left_click(
     return left_click;
 }
 
+fn get_button_middle_click(
+    item: &StatusNotifierItem,
+    tray_widget: &Rc<TrayWidget>,
+    service_key: &str,
+) -> gtk4::GestureClick {
+    let middle_click = gtk4::GestureClick::new();
+    middle_click.set_button(2);
+
+    let item_id_middle = item.id.clone();
+    let service_key_middle = service_key.to_string();
+    let tray_widget_weak = Rc::downgrade(tray_widget);
+
+    middle_click.connect_pressed(move |_, _, _x, _y| {
+        if let Some(tray_widget) = tray_widget_weak.upgrade() {
+            let item_id = item_id_middle.clone();
+            let service_key = service_key_middle.clone();
+
+            let token = launch::activation_token();
+            glib::spawn_future_local(async move {
+                let source = tray_widget.source();
+                provide_activation_token(source.as_ref(), &service_key, token).await;
+                if let Err(e) = source
+                    .activate(ActivateRequest::Secondary {
+                        address: service_key.clone(),
+                        x: 0,
+                        y: 0,
+                    })
+                    .await
+                {
+                    warn!(
+                        "Failed to secondary activate tray item '{}' (service: '{}'): {}",
+                        item_id, service_key, e
+                    );
+                }
+            });
+        }
+    });
+
+    middle_click
+}
+
+/// Forward scrolling to the item's `Scroll` method, one call per whole step.
+/// Smooth (touchpad) deltas are accumulated until they add up to a step.
+fn get_button_scroll(tray_widget: &Rc<TrayWidget>, service_key: &str) -> EventControllerScroll {
+    let scroll = EventControllerScroll::new(EventControllerScrollFlags::BOTH_AXES);
+
+    let service_key = service_key.to_string();
+    let tray_widget_weak = Rc::downgrade(tray_widget);
+    let accumulated = Rc::new((Cell::new(0.0), Cell::new(0.0)));
+    scroll.connect_scroll(move |_, dx, dy| {
+        let (horizontal, vertical) = &*accumulated;
+        for (total, delta, orientation) in [
+            (horizontal, dx, "horizontal"),
+            (vertical, dy, "vertical"),
+        ] {
+            total.set(total.get() + delta);
+            let steps = total.get().trunc();
+            if steps == 0.0 {
+                continue;
+            }
+            total.set(total.get() - steps);
+
+            let Some(tray_widget) = tray_widget_weak.upgrade() else {
+                continue;
+            };
+            // SNI deltas are positive when scrolling up or left, GTK's the other way round
+            let delta = -steps as i32;
+            let service_key = service_key.clone();
+            glib::spawn_future_local(async move {
+                let source = tray_widget.source();
+                if let Err(e) = source.scroll(&service_key, delta, orientation).await {
+                    warn!(
+                        "Failed to scroll tray item (service: '{}'): {}",
+                        service_key, e
+                    );
+                }
+            });
+        }
+        Propagation::Stop
+    });
+
+    scroll
+}
+
+/// Hand the item an activation token before activating it, so a window it opens
+/// gets focus. Older items do not implement the method, which is fine.
+async fn provide_activation_token(
+    source: &dyn TraySource,
+    service_key: &str,
+    token: Option<String>,
+) {
+    let Some(token) = token else {
+        return;
+    };
+    if let Err(e) = source
+        .provide_xdg_activation_token(service_key, token)
+        .await
+    {
+        debug!(
+            "Tray item does not take activation tokens (service: '{}'): {}",
+            service_key, e
+        );
+    }
+}
+
 fn get_button_right_click(
     item: &StatusNotifierItem,
-    tray_widget: &Arc<TrayWidget>,
-    service_key: Arc<str>,
+    tray_widget: &Rc<TrayWidget>,
+    service_key: Rc<str>,
 ) -> gtk4::GestureClick {
     let right_click = gtk4::GestureClick::new();
     right_click.set_button(3);
 
     let service_key = service_key.clone();
     let item_id_right = item.id.clone();
-    let tray_widget_weak = Arc::downgrade(&tray_widget);
+    let tray_widget_weak = Rc::downgrade(tray_widget);
 
     right_click.connect_pressed(move |_, _, x, y| {
         if let Some(tray_widget) = tray_widget_weak.upgrade() {
             let item_id = item_id_right.clone();
             let service_key = service_key.clone();
 
-            // Check for manual popover first (with icon support), then fallback to PopoverMenu
+            // Show the item's menu, items without one are activated instead
             if let Some(manual_popover) =
                 tray_widget.get_manual_popover_for_service_key(&service_key)
             {
                 // Use popup() to show the manual popover
                 manual_popover.popup();
-            } else if let Some(popover_menu) = tray_widget.get_menu_for_service_key(&service_key) {
-                // Use popup() to show the popover at the current position
-                popover_menu.popup();
             } else {
                 let service_key = service_key.clone();
-                let tray_widget_clone: Arc<TrayWidget> = tray_widget.clone();
+                let tray_widget_clone: Rc<TrayWidget> = tray_widget.clone();
+                let token = launch::activation_token();
                 glib::spawn_future_local(async move {
-                    if let Err(e) = tray_widget_clone
-                        .system_tray_client
+                    let source = tray_widget_clone.source();
+                    provide_activation_token(source.as_ref(), &service_key, token).await;
+                    if let Err(e) = source
                         .activate(ActivateRequest::Default {
                             address: service_key.clone().to_string(),
                             x: 0,
@@ -200,12 +466,12 @@ fn get_button_right_click(
                         })
                         .await
                     {
-                        eprintln!(
+                        warn!(
                             "Failed to activate tray item '{}' (service: '{}'): {}",
                             item_id, service_key, e
                         );
                     } else {
-                        println!(
+                        debug!(
                             "Fallback activation successful for item: {} (service: {})",
                             item_id, service_key
                         );
@@ -213,7 +479,7 @@ fn get_button_right_click(
                 });
             }
         } else {
-            println!("TrayWidget weak reference upgrade failed in right-click handler");
+            debug!("TrayWidget weak reference upgrade failed in right-click handler");
         }
     });
 
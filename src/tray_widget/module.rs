@@ -0,0 +1,60 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Orientation, Widget};
+use std::time::Duration;
+use tracing::error;
+
+use crate::config::{Config, TrayConfig};
+use crate::module::{BarModule, InitFuture};
+use crate::tray_widget::TrayWidget;
+
+/// Bar module wrapping the tray, which can only connect once GTK is fully running
+pub struct TrayModule {
+    slot: GtkBox,
+    orientation: Orientation,
+    config: TrayConfig,
+}
+
+impl TrayModule {
+    pub fn new(config: &TrayConfig, orientation: Orientation) -> Self {
+        // Reserve the tray position now, the tray itself is created in init()
+        let slot = GtkBox::new(orientation, 0);
+
+        TrayModule {
+            slot,
+            orientation,
+            config: config.clone(),
+        }
+    }
+}
+
+impl BarModule for TrayModule {
+    fn name(&self) -> &'static str {
+        "tray"
+    }
+
+    fn widget(&self) -> &Widget {
+        self.slot.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            // Give the window time to be presented before connecting to the watcher
+            glib::timeout_future(Duration::from_millis(500)).await;
+
+            match TrayWidget::new(self.orientation, self.config.clone()).await {
+                Ok(tray_widget) => {
+                    self.slot.append(tray_widget.widget());
+                    // Follows the tray until the module is removed, which aborts this task
+                    tray_widget.run().await;
+                }
+                Err(e) => error!("Failed to create tray widget: {}", e),
+            }
+        })
+    }
+
+    // Recreating the tray rebuilds every item and its menu, only worth it when the tray
+    // settings changed
+    fn keep_on_reload(&self, config: &Config) -> bool {
+        config.tray == self.config
+    }
+}
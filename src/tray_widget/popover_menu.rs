@@ -3,45 +3,115 @@
 use gio::glib::translate::FromGlibPtrArrayContainerAsVec;
 use gtk4::gdk_pixbuf::{InterpType, Pixbuf};
 use gtk4::prelude::*;
-use gtk4::{Box as GtkBox, Button, Image, Label, Popover, Orientation};
+use gtk4::glib::WeakRef;
+use gtk4::{Box as GtkBox, Button, Image, Label, Popover, Orientation, Stack, StackTransitionType};
 use std::io::Cursor;
 use std::sync::Arc;
+use system_tray::client::ActivateRequest;
 use system_tray::menu::MenuItem;
+use tracing::{debug, warn};
+
+use crate::sources::TraySource;
+
+/// Name of the top level page in the menu stack
+const ROOT_PAGE: &str = "root";
+
+/// What every page of a menu needs to navigate and activate items
+#[derive(Clone)]
+struct MenuContext {
+    stack: Stack,
+    popover: WeakRef<Popover>,
+    service_key: String,
+    source: Arc<dyn TraySource>,
+}
 
 /// Create a manual popover menu with proper icon support
+///
+/// Submenus are pages of a sliding stack inside the popover, entered through their
+/// parent item and left with a back button, so they can be nested arbitrarily deep.
 pub fn create_popover_menu(
     button: &Button,
     menu_items: &[MenuItem],
     service_key: &str,
-    system_tray_client: Arc<system_tray::client::Client>,
+    source: Arc<dyn TraySource>,
 ) -> Popover {
     let popover = Popover::new();
     popover.set_parent(button);
     popover.set_has_arrow(true);
 
+    let stack = Stack::new();
+    stack.add_css_class("menu-stack");
+    stack.set_transition_type(StackTransitionType::SlideLeftRight);
+    stack.set_vhomogeneous(false);
+    stack.set_hhomogeneous(false);
+    stack.set_interpolate_size(true);
+
+    let context = MenuContext {
+        stack: stack.clone(),
+        popover: popover.downgrade(),
+        service_key: service_key.to_string(),
+        source,
+    };
+    let root = create_page(&context, menu_items, ROOT_PAGE, None);
+    stack.add_named(&root, Some(ROOT_PAGE));
+
+    // Always open at the top level
+    let stack_closed = stack.clone();
+    popover.connect_closed(move |_| {
+        stack_closed.set_visible_child_full(ROOT_PAGE, StackTransitionType::None);
+    });
+
+    popover.set_child(Some(&stack));
+    popover
+}
+
+/// Build the page `page_name`, `parent` is the page to go back to and the submenu title
+fn create_page(
+    context: &MenuContext,
+    menu_items: &[MenuItem],
+    page_name: &str,
+    parent: Option<(&str, &str)>,
+) -> GtkBox {
     // Create a vertical box to hold menu items
     let menu_box = GtkBox::new(Orientation::Vertical, 0);
     menu_box.add_css_class("menu");
 
+    if let Some((parent_page, title)) = parent {
+        let back_button = Button::new();
+        back_button.add_css_class("flat");
+        back_button.add_css_class("submenu-back");
+        back_button.set_can_focus(false);
+
+        let back_box = GtkBox::new(Orientation::Horizontal, 8);
+        back_box.set_margin_start(8);
+        back_box.set_margin_end(8);
+        back_box.set_margin_top(4);
+        back_box.set_margin_bottom(4);
+        back_box.append(&Image::from_icon_name("go-previous-symbolic"));
+        let title_label = Label::new(Some(title));
+        title_label.set_halign(gtk4::Align::Start);
+        back_box.append(&title_label);
+        back_button.set_child(Some(&back_box));
+
+        let stack = context.stack.clone();
+        let parent_page = parent_page.to_string();
+        back_button.connect_clicked(move |_| {
+            stack.set_visible_child_name(&parent_page);
+        });
+        menu_box.append(&back_button);
+
+        let separator = gtk4::Separator::new(Orientation::Horizontal);
+        separator.add_css_class("menu-separator");
+        menu_box.append(&separator);
+    }
+
     // Add menu items
+    let mut has_items = false;
     for menu_item in menu_items {
         if !menu_item.visible {
             continue;
         }
 
-        menu_item.submenu.iter().for_each(|submenu: &MenuItem| {
-            // Handle submenu items
-            let submenu_popover = create_popover_menu(button, &[submenu.clone()], service_key, Arc::clone(&system_tray_client));
-            let submenu_button = Button::new();
-            submenu_button.add_css_class("submenu-button");
-            submenu_button.set_child(Some(&Image::from_icon_name("go-next")));
-            submenu_button.connect_clicked(move |_| {
-                submenu_popover.popup();
-            });
-            menu_box.append(&submenu_button);
-            return;
-        });
-
         // Handle separator items
         if format!("{:?}", menu_item.menu_type).contains("Separator") {
             let separator = gtk4::Separator::new(Orientation::Horizontal);
@@ -50,90 +120,53 @@ pub fn create_popover_menu(
             continue;
         }
 
-        if let Some(label) = &menu_item.label {
-            if !label.is_empty() {
-                // Create menu item button
-                let item_button = Button::new();
-                item_button.add_css_class("flat");
-                item_button.add_css_class("menu-item");
-                item_button.set_can_focus(false);
-
-                // Create horizontal box for icon and label
-                let item_box = GtkBox::new(Orientation::Horizontal, 8);
-                item_box.set_margin_start(8);
-                item_box.set_margin_end(8);
-                item_box.set_margin_top(4);
-                item_box.set_margin_bottom(4);
-
-                // Add icon if available
-                let mut icon_added = false;
-                match create_icon(menu_item) {
-                    Some(icon) => {
-                        item_box.append(&icon);
-                    },
-                    None => {
-                        let spacer = GtkBox::new(Orientation::Horizontal, 0);
-                        spacer.set_size_request(16, 16);
-                        item_box.append(&spacer);
-                    }
-                }
+        let Some(label) = menu_item.label.as_ref().filter(|label| !label.is_empty()) else {
+            continue;
+        };
 
-                // Add label
-                let label_widget = Label::new(Some(label));
-                label_widget.set_halign(gtk4::Align::Start);
-                item_box.append(&label_widget);
+        // Create menu item button
+        let item_button = Button::new();
+        item_button.add_css_class("flat");
+        item_button.add_css_class("menu-item");
+        item_button.set_can_focus(false);
 
-                item_button.set_child(Some(&item_box));
+        let item_box = create_item_box(menu_item, label);
+        item_button.set_child(Some(&item_box));
 
-                // Set up click handler
-                let item_id = menu_item.id;
-                let label_clone = label.clone();
-                let service_key_clone = service_key.to_string();
-                let client = Arc::clone(&system_tray_client);
-                let popover_weak = popover.downgrade();
+        // Set enabled state
+        item_button.set_sensitive(menu_item.enabled);
 
-                item_button.connect_clicked(move |_| {
-                    println!("Manual menu item activated: '{}' (id: {})", label_clone, item_id);
+        if menu_item.submenu.is_empty() {
+            connect_activate(context, &item_button, menu_item.id, label);
+        } else {
+            // Submenu items open their own page, built up front like the rest of the menu
+            item_button.add_css_class("submenu-item");
+            let arrow = Image::from_icon_name("go-next-symbolic");
+            arrow.set_hexpand(true);
+            arrow.set_halign(gtk4::Align::End);
+            item_box.append(&arrow);
 
-                    // Close popover
-                    if let Some(popover) = popover_weak.upgrade() {
-                        popover.popdown();
-                    }
+            let submenu_page = format!("submenu-{}", menu_item.id);
+            let page = create_page(
+                context,
+                &menu_item.submenu,
+                &submenu_page,
+                Some((page_name, label)),
+            );
+            context.stack.add_named(&page, Some(&submenu_page));
 
-                    // Trigger menu item activation
-                    let service_key = service_key_clone.clone();
-                    let client = client.clone();
-
-                    gtk4::glib::spawn_future_local(async move {
-                        let menu_path = "/MenuBar".to_string();
-                        if let Err(e) = client
-                            .activate(system_tray::client::ActivateRequest::MenuItem {
-                                address: service_key.clone(),
-                                menu_path,
-                                submenu_id: item_id,
-                            })
-                            .await
-                        {
-                            eprintln!(
-                                "Failed to trigger menu event for item {}: {}",
-                                item_id, e
-                            );
-                        } else {
-                            println!("Successfully triggered menu event for item: {}", item_id);
-                        }
-                    });
-                });
-
-                // Set enabled state
-                item_button.set_sensitive(menu_item.enabled);
-
-                menu_box.append(&item_button);
-            }
+            let stack = context.stack.clone();
+            item_button.connect_clicked(move |_| {
+                stack.set_visible_child_name(&submenu_page);
+            });
         }
+
+        menu_box.append(&item_button);
+        has_items = true;
     }
 
     // If no items were added, add a placeholder
-    if menu_box.first_child().is_none() {
+    if !has_items {
         let placeholder = Label::new(Some("No menu items"));
         placeholder.add_css_class("dim-label");
         placeholder.set_margin_start(8);
@@ -143,8 +176,76 @@ pub fn create_popover_menu(
         menu_box.append(&placeholder);
     }
 
-    popover.set_child(Some(&menu_box));
-    popover
+    menu_box
+}
+
+/// Icon and label of a menu item
+fn create_item_box(menu_item: &MenuItem, label: &str) -> GtkBox {
+    // Create horizontal box for icon and label
+    let item_box = GtkBox::new(Orientation::Horizontal, 8);
+    item_box.set_margin_start(8);
+    item_box.set_margin_end(8);
+    item_box.set_margin_top(4);
+    item_box.set_margin_bottom(4);
+
+    // Add icon if available
+    match create_icon(menu_item) {
+        Some(icon) => {
+            item_box.append(&icon);
+        },
+        None => {
+            let spacer = GtkBox::new(Orientation::Horizontal, 0);
+            spacer.set_size_request(16, 16);
+            item_box.append(&spacer);
+        }
+    }
+
+    // Add label
+    let label_widget = Label::new(Some(label));
+    label_widget.set_halign(gtk4::Align::Start);
+    item_box.append(&label_widget);
+
+    item_box
+}
+
+/// Activate the item on the application's side when clicked and close the menu
+fn connect_activate(context: &MenuContext, item_button: &Button, item_id: i32, label: &str) {
+    let label_clone = label.to_string();
+    let service_key_clone = context.service_key.clone();
+    let source = Arc::clone(&context.source);
+    let popover_weak = context.popover.clone();
+
+    item_button.connect_clicked(move |_| {
+        debug!("Manual menu item activated: '{}' (id: {})", label_clone, item_id);
+
+        // Close popover
+        if let Some(popover) = popover_weak.upgrade() {
+            popover.popdown();
+        }
+
+        // Trigger menu item activation
+        let service_key = service_key_clone.clone();
+        let source = source.clone();
+
+        gtk4::glib::spawn_future_local(async move {
+            let menu_path = "/MenuBar".to_string();
+            if let Err(e) = source
+                .activate(ActivateRequest::MenuItem {
+                    address: service_key.clone(),
+                    menu_path,
+                    submenu_id: item_id,
+                })
+                .await
+            {
+                warn!(
+                    "Failed to trigger menu event for item {}: {}",
+                    item_id, e
+                );
+            } else {
+                debug!("Successfully triggered menu event for item: {}", item_id);
+            }
+        });
+    });
 }
 
 fn create_icon(menu_item: &MenuItem) -> Option<Image> {
@@ -169,7 +270,7 @@ fn create_icon(menu_item: &MenuItem) -> Option<Image> {
                     }
                 }
                 Err(e) => {
-                    eprintln!("Failed to load icon from PNG data: {}", e);
+                    warn!("Failed to load icon from PNG data: {}", e);
                     // Use fallback icon
                     return Some(Image::from_icon_name("image-x-generic"));
                 }
@@ -0,0 +1,142 @@
+// Tray source backed by the system-tray client, registered as a host with the
+// StatusNotifierWatcher
+
+use futures_util::StreamExt;
+use system_tray::client::{ActivateRequest, Client, Event as TrayEvent};
+use system_tray::item::StatusNotifierItem;
+use system_tray::menu::TrayMenu;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+use crate::sources::{SourceFuture, TraySource};
+
+/// Well-known name of the StatusNotifierWatcher
+const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+
+/// Interface of the items, for the methods the client does not wrap
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+
+pub struct WatcherTraySource {
+    client: Client,
+    connection: zbus::Connection,
+}
+
+impl WatcherTraySource {
+    pub async fn connect() -> Result<Self, String> {
+        let client = Client::new().await.map_err(|e| e.to_string())?;
+        let connection = zbus::Connection::session()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(WatcherTraySource { client, connection })
+    }
+
+    /// Call an item method directly, the item addressed the same way as by the client:
+    /// bus name, optionally followed by the object path
+    async fn call_item<B>(&self, service_key: &str, method: &str, body: &B) -> Result<(), String>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        let (destination, path) = match service_key.split_once('/') {
+            Some((destination, path)) => (destination, format!("/{}", path)),
+            None => (service_key, "/StatusNotifierItem".to_string()),
+        };
+
+        self.connection
+            .call_method(
+                Some(destination),
+                path.as_str(),
+                Some(ITEM_INTERFACE),
+                method,
+                body,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+impl TraySource for WatcherTraySource {
+    fn items(&self) -> Vec<(String, StatusNotifierItem)> {
+        let items = self.client.items();
+        let Ok(items) = items.lock() else {
+            return Vec::new();
+        };
+        items
+            .iter()
+            .map(|(key, (item, _menu))| (key.clone(), item.clone()))
+            .collect()
+    }
+
+    fn menu(&self, service_key: &str) -> Option<TrayMenu> {
+        let items = self.client.items();
+        let items = items.lock().ok()?;
+        items.get(service_key)?.1.clone()
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<TrayEvent> {
+        self.client.subscribe()
+    }
+
+    fn lost(&self) -> SourceFuture<'static, ()> {
+        Box::pin(watcher_restarts())
+    }
+
+    fn activate(&self, request: ActivateRequest) -> SourceFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            self.client
+                .activate(request)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn scroll<'a>(
+        &'a self,
+        service_key: &'a str,
+        delta: i32,
+        orientation: &'a str,
+    ) -> SourceFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            self.call_item(service_key, "Scroll", &(delta, orientation))
+                .await
+        })
+    }
+
+    fn provide_xdg_activation_token<'a>(
+        &'a self,
+        service_key: &'a str,
+        token: String,
+    ) -> SourceFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            self.call_item(service_key, "ProvideXdgActivationToken", &(token,))
+                .await
+        })
+    }
+}
+
+/// Resolves when the watcher name is taken by a new owner after it had none, i.e. the
+/// watcher was restarted and knows nothing about our host. Our client's own watcher
+/// taking over from another one is handled by the client itself and does not count.
+async fn watcher_restarts() {
+    let restarts = async {
+        let connection = zbus::Connection::session().await?;
+        let proxy = zbus::fdo::DBusProxy::new(&connection).await?;
+        let mut changes = proxy
+            .receive_name_owner_changed_with_args(&[(0, WATCHER_NAME)])
+            .await?;
+        while let Some(change) = changes.next().await {
+            let Ok(args) = change.args() else {
+                continue;
+            };
+            if args.old_owner().is_none() && args.new_owner().is_some() {
+                break;
+            }
+        }
+        Ok::<(), zbus::Error>(())
+    };
+
+    if let Err(e) = restarts.await {
+        warn!("Not watching for tray watcher restarts: {}", e);
+    }
+    std::future::pending::<()>().await
+}
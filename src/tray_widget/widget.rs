@@ -1,134 +1,246 @@
 use gtk4::prelude::*;
-use gtk4::{Box as GtkBox, Button, Orientation};
+use gtk4::{Box as GtkBox, Button, Grid, Label, MenuButton, Orientation, Popover, Switch};
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use std::thread::{self, JoinHandle};
-use system_tray::client::{Client, Event as TrayEvent};
-use system_tray::error::Error;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+use system_tray::client::{Event as TrayEvent, UpdateEvent};
 use system_tray::item::StatusNotifierItem;
 use tokio::sync::broadcast;
+use tracing::warn;
 
-use crate::tray_widget::controls::create_tray_button;
+use crate::config::TrayConfig;
+use crate::sources::{self, TraySource};
+use crate::state;
+use crate::tray_widget::controls::{PixmapTexture, create_tray_button};
 
-/// The main tray widget that manages system tray items
+/// Icons per row in the overflow popover
+const OVERFLOW_COLUMNS: i32 = 4;
+
+/// First wait before reconnecting a lost tray, doubled after every failed attempt
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+thread_local! {
+    /// Tray source shared by the tray widgets of every bar, replaced when it is lost
+    static SHARED_SOURCE: Rc<tokio::sync::Mutex<Option<Arc<dyn TraySource>>>> =
+        Rc::new(tokio::sync::Mutex::new(None));
+}
+
+/// Connect to the StatusNotifierWatcher once and hand out the same source afterwards
+async fn shared_source() -> Result<Arc<dyn TraySource>, String> {
+    connect_source(None).await
+}
+
+/// Replace a source that stopped working. Every tray widget notices on its own, only
+/// the first one to ask creates the new source.
+async fn reconnect_source(failed: &Arc<dyn TraySource>) -> Result<Arc<dyn TraySource>, String> {
+    connect_source(Some(failed)).await
+}
+
+async fn connect_source(
+    failed: Option<&Arc<dyn TraySource>>,
+) -> Result<Arc<dyn TraySource>, String> {
+    let cell = SHARED_SOURCE.with(|cell| cell.clone());
+    let mut current = cell.lock().await;
+    if let Some(source) = current.as_ref() {
+        if !failed.is_some_and(|failed| Arc::ptr_eq(source, failed)) {
+            return Ok(source.clone());
+        }
+    }
+
+    let source = sources::tray_source().await?;
+    if failed.is_none() {
+        sources::tray_fixtures();
+    }
+    *current = Some(source.clone());
+    Ok(source)
+}
+
+/// The main tray widget that manages system tray items. Everything lives on the GTK
+/// thread, the source's events are followed by [`TrayWidget::run`].
 pub struct TrayWidget {
     pub container: GtkBox,
-    items: Arc<Mutex<HashMap<String, StatusNotifierItem>>>,
-    item_buttons: Arc<Mutex<HashMap<String, Button>>>,
-    item_menus: Arc<Mutex<HashMap<String, gtk4::PopoverMenu>>>,
+    items: RefCell<HashMap<String, StatusNotifierItem>>,
+    item_buttons: RefCell<HashMap<String, Button>>,
+    // Textures of the pixmap icons each button shows
+    pixmap_textures: RefCell<HashMap<String, Vec<PixmapTexture>>>,
     // Store manual popovers with icon support
-    item_manual_popovers: Arc<Mutex<HashMap<String, gtk4::Popover>>>,
-    // Store action groups to keep them alive
-    action_groups: Arc<Mutex<HashMap<String, gio::SimpleActionGroup>>>,
-    // Map from item ID to service key for activation
-    item_to_service_key: Arc<Mutex<HashMap<String, String>>>,
-    pub system_tray_client: Arc<Client>,
-    shutdown_tx: broadcast::Sender<()>,
-    thread_handle: Arc<JoinHandle<()>>,
+    item_manual_popovers: RefCell<HashMap<String, gtk4::Popover>>,
+    // Service keys in the order the items appeared
+    arrival: RefCell<Vec<String>>,
+    // Chevron holding the items past `max_visible`
+    overflow: MenuButton,
+    overflow_grid: Grid,
+    // Shows the items past `max_visible` on the bar instead of in the popover
+    overflow_expand: Switch,
+    overflow_expanded: Cell<bool>,
+    // Swapped for a new source when the watcher restarts
+    source: RefCell<Arc<dyn TraySource>>,
+    /// Visibility and ordering rules
+    config: TrayConfig,
 }
 
 impl TrayWidget {
-    /// Create a new TrayWidget
-    pub async fn new() -> Result<Arc<Self>, Error> {
-        let container = GtkBox::new(Orientation::Horizontal, 5);
-        container.add_css_class("tray-widget");
+    /// Create a new TrayWidget on the shared tray source
+    pub async fn new(orientation: Orientation, config: TrayConfig) -> Result<Rc<Self>, String> {
+        let source = shared_source().await?;
+        let tray_widget = Self::with_source(orientation, config, source);
+
+        // Expanded or collapsed the way it was left before a restart
+        let expanded = state::get().tray_overflow_expanded;
+        tray_widget.overflow_expand.set_active(expanded);
+        Ok(tray_widget)
+    }
 
-        let client = Arc::new(Client::new().await?);
-        let client_copy = Arc::clone(&client);
+    /// Create a TrayWidget for the items of `source`, shown once it runs
+    pub fn with_source(
+        orientation: Orientation,
+        config: TrayConfig,
+        source: Arc<dyn TraySource>,
+    ) -> Rc<Self> {
+        let container = GtkBox::new(orientation, 5);
+        container.add_css_class("tray-widget");
 
-        let (thread_handle, shutdown_tx, mut event_rx) = 
-            Self::start_event_listener(&client_copy);
+        let overflow_grid = Grid::new();
+        overflow_grid.add_css_class("tray-overflow-grid");
+        overflow_grid.set_row_spacing(4);
+        overflow_grid.set_column_spacing(4);
+        let expand_row = GtkBox::new(Orientation::Horizontal, 8);
+        let expand_label = Label::new(Some("Show all on the bar"));
+        expand_label.set_hexpand(true);
+        expand_label.set_halign(gtk4::Align::Start);
+        let overflow_expand = Switch::new();
+        expand_row.append(&expand_label);
+        expand_row.append(&overflow_expand);
+        let overflow_content = GtkBox::new(Orientation::Vertical, 6);
+        overflow_content.append(&overflow_grid);
+        overflow_content.append(&expand_row);
+        let overflow_popover = Popover::new();
+        overflow_popover.set_child(Some(&overflow_content));
+        let overflow = MenuButton::new();
+        overflow.add_css_class("tray-overflow");
+        overflow.set_icon_name(match orientation {
+            Orientation::Vertical => "pan-end-symbolic",
+            _ => "pan-down-symbolic",
+        });
+        overflow.set_tooltip_text(Some("More items"));
+        overflow.set_popover(Some(&overflow_popover));
+        overflow.set_visible(false);
+        container.append(&overflow);
 
-        let tray_widget = Arc::new(TrayWidget {
+        let tray_widget = Rc::new(TrayWidget {
             container,
-            items: Arc::new(Mutex::new(HashMap::new())),
-            item_buttons: Arc::new(Mutex::new(HashMap::new())),
-            item_menus: Arc::new(Mutex::new(HashMap::new())),
-            item_manual_popovers: Arc::new(Mutex::new(HashMap::new())),
-            action_groups: Arc::new(Mutex::new(HashMap::new())),
-            item_to_service_key: Arc::new(Mutex::new(HashMap::new())),
-            system_tray_client: client,
-            shutdown_tx,
-            thread_handle: Arc::new(thread_handle),
+            items: RefCell::new(HashMap::new()),
+            item_buttons: RefCell::new(HashMap::new()),
+            pixmap_textures: RefCell::new(HashMap::new()),
+            item_manual_popovers: RefCell::new(HashMap::new()),
+            arrival: RefCell::new(Vec::new()),
+            overflow,
+            overflow_grid,
+            overflow_expand,
+            overflow_expanded: Cell::new(false),
+            source: RefCell::new(source),
+            config,
         });
 
-        let tray_ptr = tray_widget.clone();
-
-        // Handle events on the main thread
-        glib::MainContext::default().spawn_local(async move {
-            while let Some(event) = event_rx.recv().await {
-                let tray_ptr = tray_ptr.clone();
-                tray_ptr.handle_tray_event(event);
-            }
-        });
+        let tray_expand = Rc::downgrade(&tray_widget);
+        tray_widget
+            .overflow_expand
+            .connect_state_set(move |_, expanded| {
+                if let Some(tray_widget) = tray_expand.upgrade() {
+                    tray_widget.set_overflow_expanded(expanded);
+                }
+                if state::get().tray_overflow_expanded != expanded {
+                    state::update(|state| state.tray_overflow_expanded = expanded);
+                }
+                gtk4::glib::Propagation::Proceed
+            });
 
-        Ok(tray_widget)
+        tray_widget
     }
 
-    fn start_event_listener(
-        system_tray_client: &Arc<Client>,
-    ) -> (
-        JoinHandle<()>,
-        broadcast::Sender<()>,
-        tokio::sync::mpsc::UnboundedReceiver<TrayEvent>,
-    ) {
-        let (shutdown_tx, shutdown_rx) = broadcast::channel::<()>(1);
-        let (event_tx, event_rx) = tokio::sync::mpsc::unbounded_channel::<TrayEvent>();
-
-        let client = system_tray_client.clone();
+    /// Show the items past `max_visible` on the bar as well, or put them back behind
+    /// the chevron
+    fn set_overflow_expanded(&self, expanded: bool) {
+        self.overflow_expanded.set(expanded);
+        if expanded {
+            self.overflow.add_css_class("expanded");
+        } else {
+            self.overflow.remove_css_class("expanded");
+        }
+        self.arrange();
+    }
 
-        let thread_handle = thread::spawn(move || {
-            let rt: tokio::runtime::Runtime =
-                tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
+    /// Source to talk to the items through
+    pub fn source(&self) -> Arc<dyn TraySource> {
+        self.source.borrow().clone()
+    }
 
-            rt.block_on(async {
-                let mut tray_rx = client.subscribe();
-                let initial_items = client.items();
+    /// Show the source's items and follow its events, connecting again whenever the
+    /// source is lost. Runs until dropped, with the init task of the tray module.
+    pub async fn run(self: &Rc<Self>) {
+        loop {
+            let source = self.source();
+            self.follow(&source).await;
+
+            warn!("Tray connection lost, reconnecting");
+            let keys = self.arrival.borrow().clone();
+            for key in keys {
+                self.remove_tray_item(&key);
+            }
 
-                // Process initial items
-                for (key, (sni_item, _menu)) in initial_items.lock().unwrap().iter() {
-                    if event_tx
-                        .send(TrayEvent::Add(key.clone(), Box::new(sni_item.clone())))
-                        .is_err()
-                    {
+            let mut delay = RECONNECT_DELAY;
+            loop {
+                glib::timeout_future(delay).await;
+                match reconnect_source(&source).await {
+                    Ok(source) => {
+                        *self.source.borrow_mut() = source;
                         break;
                     }
-                }
-
-                // Listen for updates with cancellation
-                let mut shutdown_rx = shutdown_rx;
-                loop {
-                    tokio::select! {
-                        event = tray_rx.recv() => {
-                            match event {
-                                Ok(ev) => {
-                                    if event_tx.send(ev).is_err() {
-                                        break;
-                                    }
-                                }
-                                Err(_) => break,
-                            }
-                        }
-                        _ = shutdown_rx.recv() => {
-                            println!("Shutting down tray listener");
-                            break;
-                        }
+                    Err(e) => {
+                        warn!(
+                            "Failed to reconnect the tray, retrying in {:?}: {}",
+                            delay, e
+                        );
+                        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
                     }
                 }
-            });
-        });
+            }
+        }
+    }
 
-        (thread_handle, shutdown_tx, event_rx)
+    /// Add the items of `source` and apply its events until it is lost
+    async fn follow(self: &Rc<Self>, source: &Arc<dyn TraySource>) {
+        // Subscribe before taking the items so nothing in between is missed
+        let mut events = source.subscribe();
+        for (key, item) in source.items() {
+            self.add_tray_item(&key, &item);
+        }
+
+        let mut lost = source.lost();
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => self.handle_tray_event(event),
+                    // Missed some events, the next ones are still valid
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = &mut lost => return,
+            }
+        }
     }
 
     pub fn widget(&self) -> &GtkBox {
         &self.container
     }
 
-    fn handle_tray_event(self: &Arc<Self>, event: TrayEvent) {
+    fn handle_tray_event(self: &Rc<Self>, event: TrayEvent) {
         match event {
             TrayEvent::Add(service_key, item) => {
-                self.add_tray_item(&service_key, &item, self);
+                self.add_tray_item(&service_key, &item);
             }
             TrayEvent::Update(service_key, update_event) => {
                 self.update_tray_item(&service_key, &update_event);
@@ -139,377 +251,279 @@ impl TrayWidget {
         }
     }
 
-    fn add_tray_item(
-        &self,
-        service_key: &str,
-        item: &StatusNotifierItem,
-        tray_widget_arc: &Arc<Self>,
-    ) {
-        // Store the item
-        if let Ok(mut items) = self.items.lock() {
-            items.insert(service_key.to_string(), item.clone());
+    fn add_tray_item(self: &Rc<Self>, service_key: &str, item: &StatusNotifierItem) {
+        if self
+            .config
+            .hidden
+            .iter()
+            .any(|pattern| matches_item(pattern, item))
+        {
+            return;
         }
 
-        // Store the item ID to service key mapping
-        if let Ok(mut mapping) = self.item_to_service_key.lock() {
-            mapping.insert(item.id.clone(), service_key.to_string());
-        }
+        // Store the item
+        self.items
+            .borrow_mut()
+            .insert(service_key.to_string(), item.clone());
 
         // Create button using the controls module
-        let button = create_tray_button(
-            item,
-            service_key,
-            Arc::clone(tray_widget_arc),
-        );
+        let button = create_tray_button(item, service_key, self);
 
         // Store the button
-        if let Ok(mut buttons) = self.item_buttons.lock() {
-            buttons.insert(service_key.to_string(), button.clone());
-        }
+        self.item_buttons
+            .borrow_mut()
+            .insert(service_key.to_string(), button.clone());
+
+        crate::tray_widget::controls::set_button_icon(
+            item,
+            &button,
+            self.pixmap_textures
+                .borrow_mut()
+                .entry(service_key.to_string())
+                .or_default(),
+        );
+        crate::tray_widget::controls::set_button_status(item, &button, self.config.show_passive);
 
         // Create a basic menu for the tray item
         self.create_menu_for_item(service_key, item, &button);
 
         // Add to container
-        self.container.append(&button);
+        self.arrival.borrow_mut().push(service_key.to_string());
+        self.arrange();
     }
 
-    fn update_tray_item(
-        &self,
-        service_key: &str,
-        _update_event: &system_tray::client::UpdateEvent,
-    ) {
-        // For now, just update the button if it exists
-        if let Ok(buttons) = self.item_buttons.lock() {
-            if let Some(button) = buttons.get(service_key) {
-                // Get the current item to extract icon information
-                if let Ok(items) = self.items.lock() {
-                    if let Some(item) = items.get(service_key) {
-                        // Update button icon and tooltip using the current item data
-                        crate::tray_widget::controls::set_button_icon(
-                            item.icon_name.as_deref(),
-                            item.icon_pixmap.clone(),
-                            button,
-                        );
-                        crate::tray_widget::controls::set_tooltip(
-                            button,
-                            item.tool_tip.clone(),
-                            item.title.as_deref(),
-                        );
-                    }
+    /// Position of an item in the configured order, unlisted items come last
+    fn rank(&self, item: &StatusNotifierItem) -> usize {
+        self.config
+            .order
+            .iter()
+            .position(|pattern| matches_item(pattern, item))
+            .unwrap_or(self.config.order.len())
+    }
+
+    /// Place the buttons in configured order, moving those past `max_visible` behind
+    /// the overflow chevron unless it is expanded
+    fn arrange(&self) {
+        let expanded = self.overflow_expanded.get();
+        let mut buttons: Vec<(usize, Button)> = {
+            let (buttons, items) = (self.item_buttons.borrow(), self.items.borrow());
+            self.arrival
+                .borrow()
+                .iter()
+                .filter_map(|key| Some((self.rank(items.get(key)?), buttons.get(key)?.clone())))
+                .collect()
+        };
+        // Stable, so items ranked the same stay in order of appearance
+        buttons.sort_by_key(|(rank, _)| *rank);
+
+        let mut shown = 0;
+        let mut previous: Option<gtk4::Widget> = None;
+        let mut overflow = Vec::new();
+        let mut past_limit = false;
+        for (_, button) in buttons {
+            // Hidden (passive) buttons stay in the bar without taking a slot
+            let visible = button.is_visible();
+            if visible && self.config.max_visible > 0 && shown >= self.config.max_visible {
+                past_limit = true;
+                if !expanded {
+                    overflow.push(button);
+                    continue;
                 }
             }
-        }
-    }
+            if visible {
+                shown += 1;
+            }
 
-    fn remove_tray_item(&self, service_key: &str) {
-        // Remove from container
-        if let Ok(mut buttons) = self.item_buttons.lock() {
-            if let Some(button) = buttons.remove(service_key) {
-                self.container.remove(&button);
+            if button.parent().as_ref() == Some(self.container.upcast_ref()) {
+                self.container.reorder_child_after(&button, previous.as_ref());
+            } else {
+                self.detach(&button);
+                self.container.insert_child_after(&button, previous.as_ref());
             }
+            previous = Some(button.upcast());
         }
 
-        // Remove menu and action group
-        if let Ok(mut menus) = self.item_menus.lock() {
-            menus.remove(service_key);
+        while let Some(child) = self.overflow_grid.first_child() {
+            self.overflow_grid.remove(&child);
         }
-        if let Ok(mut manual_popovers) = self.item_manual_popovers.lock() {
-            manual_popovers.remove(service_key);
-        }
-        if let Ok(mut action_groups) = self.action_groups.lock() {
-            action_groups.remove(service_key);
+        for (index, button) in overflow.iter().enumerate() {
+            self.detach(button);
+            let index = index as i32;
+            self.overflow_grid
+                .attach(button, index % OVERFLOW_COLUMNS, index / OVERFLOW_COLUMNS, 1, 1);
         }
 
-        // Remove from items
-        if let Ok(mut items) = self.items.lock() {
-            if let Some(item) = items.remove(service_key) {
-                // Remove from item ID mapping
-                if let Ok(mut mapping) = self.item_to_service_key.lock() {
-                    mapping.remove(&item.id);
-                }
-            }
-        }
+        // The chevron always comes last, and stays while expanded to collapse again
+        self.container.reorder_child_after(&self.overflow, previous.as_ref());
+        self.overflow.set_visible(past_limit);
     }
 
-    /// Get the PopoverMenu for a given service key
-    pub fn get_menu_for_service_key(&self, service_key: &str) -> Option<gtk4::PopoverMenu> {
-        if let Ok(menus) = self.item_menus.lock() {
-            menus.get(service_key).cloned()
-        } else {
-            None
+    /// Take a button out of the bar or the overflow grid
+    fn detach(&self, button: &Button) {
+        match button.parent() {
+            Some(parent) if &parent == self.container.upcast_ref::<gtk4::Widget>() => {
+                self.container.remove(button)
+            }
+            Some(parent) if &parent == self.overflow_grid.upcast_ref::<gtk4::Widget>() => {
+                self.overflow_grid.remove(button)
+            }
+            _ => {}
         }
     }
 
-    /// Get the manual Popover for a given service key (with icon support)
-    pub fn get_manual_popover_for_service_key(&self, service_key: &str) -> Option<gtk4::Popover> {
-        if let Ok(manual_popovers) = self.item_manual_popovers.lock() {
-            manual_popovers.get(service_key).cloned()
-        } else {
-            None
+    fn update_tray_item(
+        &self,
+        service_key: &str,
+        update_event: &system_tray::client::UpdateEvent,
+    ) {
+        // The source has already applied the new layout or diff to its copy of the menu
+        if matches!(
+            update_event,
+            UpdateEvent::Menu(_) | UpdateEvent::MenuDiff(_) | UpdateEvent::MenuConnect(_)
+        ) {
+            self.rebuild_menu(service_key);
+            return;
         }
-    }
 
-    /// Create a basic menu for a tray item
-    fn create_menu_for_item(&self, service_key: &str, item: &StatusNotifierItem, button: &Button) {
-        // Check if the system-tray client has menu data for this item
-        if let Ok(items) = self.system_tray_client.items().lock() {
-            if let Some((_item, menu_opt)) = items.get(service_key) {
-                if let Some(menu) = menu_opt {
-                    // Create a menu from actual menu data using manual approach for better icon support
-                    let popover = crate::tray_widget::popover_menu::create_popover_menu(
-                        button,
-                        &menu.submenus,
-                        service_key,
-                        Arc::clone(&self.system_tray_client),
-                    );
-
-                    // Store the manual popover for display
-                    if let Ok(mut manual_popovers) = self.item_manual_popovers.lock() {
-                        manual_popovers.insert(service_key.to_string(), popover);
+        // The event carries the new value, the stored item is only what was last sent
+        {
+            let mut items = self.items.borrow_mut();
+            if let Some(item) = items.get_mut(service_key) {
+                match update_event {
+                    UpdateEvent::Status(status) => item.status = *status,
+                    UpdateEvent::AttentionIcon(icon_name) => {
+                        item.attention_icon_name = icon_name.clone()
+                    }
+                    UpdateEvent::Icon {
+                        icon_name,
+                        icon_pixmap,
+                    } => {
+                        item.icon_name = icon_name.clone();
+                        item.icon_pixmap = icon_pixmap.clone();
                     }
-                    return;
+                    UpdateEvent::OverlayIcon(icon_name) => {
+                        item.overlay_icon_name = icon_name.clone()
+                    }
+                    UpdateEvent::Title(title) => item.title = title.clone(),
+                    UpdateEvent::Tooltip(tooltip) => item.tool_tip = tooltip.clone(),
+                    UpdateEvent::Menu(_)
+                    | UpdateEvent::MenuDiff(_)
+                    | UpdateEvent::MenuConnect(_) => {}
                 }
             }
         }
-    }
 
-    /// Create a PopoverMenu from system-tray menu data
-    fn create_popover_from_menu(
-        &self,
-        button: &Button,
-        menu: &system_tray::menu::TrayMenu,
-        service_key: &str,
-    ) -> gtk4::PopoverMenu {
-        use gio::Menu as GMenu;
-
-        // Create a GMenu from the TrayMenu structure
-        let gmenu = GMenu::new();
-
-        // Create an action group for this menu
-        let action_group = gio::SimpleActionGroup::new();
-
-        // Add menu items recursively
-        self.add_menu_items_recursive(
-            &gmenu,
-            &action_group,
-            &menu.submenus,
-            service_key,
-            String::new(),
-        );
-
-        // If no items were added, add a placeholder
-        if gmenu.n_items() == 0 {
-            gmenu.append(Some("No menu items"), None);
+        // Redraw the button from the updated item
+        let status_changed = matches!(update_event, UpdateEvent::Status(_));
+        let buttons = self.item_buttons.borrow();
+        let items = self.items.borrow();
+        if let (Some(button), Some(item)) = (buttons.get(service_key), items.get(service_key)) {
+            // Update button icon and tooltip using the current item data
+            crate::tray_widget::controls::set_button_icon(
+                item,
+                button,
+                self.pixmap_textures
+                    .borrow_mut()
+                    .entry(service_key.to_string())
+                    .or_default(),
+            );
+            crate::tray_widget::controls::set_button_status(item, button, self.config.show_passive);
+            crate::tray_widget::controls::set_tooltip(
+                button,
+                item.tool_tip.clone(),
+                item.title.as_deref(),
+            );
         }
+        drop((buttons, items));
 
-        // Create a PopoverMenu
-        let popover = gtk4::PopoverMenu::from_model(Some(&gmenu));
-        popover.set_parent(button);
-
-        // Associate the action group with the popover
-        popover.insert_action_group("menu", Some(&action_group));
-
-        // Enable icons in PopoverMenu (GTK4 feature)
-        popover.set_has_arrow(true);
-        // Try to enable icons (this may not work with GMenu approach)
-        if let Some(settings) = gtk4::Settings::default() {
-            // Some GTK themes may support menu icons
-            settings.set_property("gtk-menu-images", &true);
+        // Items becoming passive or active free or take an overflow slot
+        if status_changed {
+            self.arrange();
         }
-
-        println!(
-            "Inserted action group 'menu' with {} actions into popover for service: {}",
-            action_group.list_actions().len(),
-            service_key
-        );
-
-        // Store the action group to keep it alive
-        if let Ok(mut action_groups) = self.action_groups.lock() {
-            action_groups.insert(service_key.to_string(), action_group);
-        }
-
-        println!(
-            "PopoverMenu created with {} items for service key: {}",
-            gmenu.n_items(),
-            service_key
-        );
-        popover
     }
 
-    /// Recursively add menu items and submenus to a GMenu
-    fn add_menu_items_recursive(
-        &self,
-        gmenu: &gio::Menu,
-        action_group: &gio::SimpleActionGroup,
-        menu_items: &[system_tray::menu::MenuItem],
-        service_key: &str,
-        path_prefix: String,
-    ) {
-        for (index, menu_item) in menu_items.iter().enumerate() {
-            if !menu_item.visible {
-                continue;
-            }
-
-            // Handle separator items - check menu_type field
-            if format!("{:?}", menu_item.menu_type).contains("Separator") {
-                // GTK doesn't have direct separator support in GMenu, but we can add a disabled item
-                let separator = gio::MenuItem::new(Some("---"), None);
-                separator.set_attribute_value(
-                    "custom",
-                    Some(&format!("separator_{}", index).to_variant()),
-                );
-                gmenu.append_item(&separator);
-                continue;
+    /// Replace the menu of an item with one built from its current layout, keeping it
+    /// open if it was
+    fn rebuild_menu(&self, service_key: &str) {
+        let Some(button) = self.item_buttons.borrow().get(service_key).cloned() else {
+            return;
+        };
+        let Some(item) = self.items.borrow().get(service_key).cloned() else {
+            return;
+        };
+
+        // The menu and its submenu popovers are all attached to the button
+        let mut old_popovers = Vec::new();
+        let mut child = button.first_child();
+        while let Some(widget) = child {
+            child = widget.next_sibling();
+            if let Ok(popover) = widget.downcast::<gtk4::Popover>() {
+                old_popovers.push(popover);
             }
+        }
+        let was_open = old_popovers.iter().any(|popover| popover.is_visible());
 
-            if let Some(label) = &menu_item.label {
-                if !label.is_empty() {
-                    // Make action names unique by including service key
-                    let action_name = format!(
-                        "{}__item_{}",
-                        service_key.replace(":", "_").replace(".", "_"),
-                        menu_item.id
-                    );
-
-                    // Check if this item has children (submenus)
-                    if !menu_item.submenu.is_empty() {
-                        println!(
-                            "Creating submenu '{}' with {} children",
-                            label,
-                            menu_item.submenu.len()
-                        );
-
-                        // Create a submenu
-                        let submenu = gio::Menu::new();
-                        let submenu_path = format!("{}{}_", path_prefix, index);
-
-                        // Recursively add children to the submenu
-                        self.add_menu_items_recursive(
-                            &submenu,
-                            action_group,
-                            &menu_item.submenu,
-                            service_key,
-                            submenu_path,
-                        );
-
-                        // Create a submenu item
-                        let submenu_item = gio::MenuItem::new_submenu(Some(label), &submenu);
-
-                        // Add icon if available
-                        crate::tray_widget::menu_helpers::add_icon_to_menu_item(
-                            &submenu_item,
-                            menu_item,
-                            label,
-                        );
-
-                        gmenu.append_item(&submenu_item);
-                    } else {
-                        // Regular menu item (leaf node)
-                        let action = gio::SimpleAction::new(&action_name, None);
-
-                        // Store the menu item information for the action callback
-                        let item_id = menu_item.id;
-                        let label_clone = label.clone();
-                        let service_key_clone = service_key.to_string();
-                        let system_tray_client = Arc::clone(&self.system_tray_client);
-
-                        println!(
-                            "Creating action '{}' for menu item '{}'",
-                            action_name, label
-                        );
-
-                        action.connect_activate(move |_, _| {
-                            println!("Menu item activated: '{}' (id: {})", label_clone, item_id);
-
-                            // Trigger menu item activation via the system-tray client
-                            let service_key = service_key_clone.clone();
-                            let client = system_tray_client.clone();
-
-                            gtk4::glib::spawn_future_local(async move {
-                                let menu_path = "/MenuBar".to_string();
-                                if let Err(e) = client
-                                    .activate(system_tray::client::ActivateRequest::MenuItem {
-                                        address: service_key.clone(),
-                                        menu_path,
-                                        submenu_id: item_id,
-                                    })
-                                    .await
-                                {
-                                    eprintln!(
-                                        "Failed to trigger menu event for item {}: {}",
-                                        item_id, e
-                                    );
-                                } else {
-                                    println!(
-                                        "Successfully triggered menu event for item: {}",
-                                        item_id
-                                    );
-                                }
-                            });
-                        });
-
-                        // Set action sensitivity based on item.enabled
-                        action.set_enabled(menu_item.enabled);
-                        action_group.add_action(&action);
-
-                        // Create a menu item with icon support
-                        let g_menu_item =
-                            gio::MenuItem::new(Some(label), Some(&format!("menu.{}", action_name)));
-
-                        println!(
-                            "Created GMenuItem '{}' with action 'menu.{}'",
-                            label, action_name
-                        );
-
-                        // Add icon if available
-                        crate::tray_widget::menu_helpers::add_icon_to_menu_item(
-                            &g_menu_item,
-                            menu_item,
-                            label,
-                        );
+        self.item_manual_popovers.borrow_mut().remove(service_key);
+        self.create_menu_for_item(service_key, &item, &button);
 
-                        gmenu.append_item(&g_menu_item);
-                    }
-                }
+        // Show the new menu before dropping the old one so it does not flicker
+        if was_open {
+            if let Some(popover) = self.get_manual_popover_for_service_key(service_key) {
+                popover.popup();
             }
         }
+        for popover in old_popovers {
+            popover.popdown();
+            popover.unparent();
+        }
     }
 
-    /// Helper method to clone self for controls module use
-    fn clone_for_controls(&self) -> TrayWidget {
-        TrayWidget {
-            container: self.container.clone(),
-            items: Arc::clone(&self.items),
-            item_buttons: Arc::clone(&self.item_buttons),
-            item_menus: Arc::clone(&self.item_menus),
-            item_manual_popovers: Arc::clone(&self.item_manual_popovers),
-            action_groups: Arc::clone(&self.action_groups),
-            item_to_service_key: Arc::clone(&self.item_to_service_key),
-            system_tray_client: Arc::clone(&self.system_tray_client),
-            shutdown_tx: self.shutdown_tx.clone(),
-            thread_handle: Arc::clone(&self.thread_handle),
+    fn remove_tray_item(&self, service_key: &str) {
+        // Remove from container
+        if let Some(button) = self.item_buttons.borrow_mut().remove(service_key) {
+            self.detach(&button);
         }
-    }
-}
+        self.arrival.borrow_mut().retain(|key| key != service_key);
+        self.arrange();
 
-impl Drop for TrayWidget {
-    fn drop(&mut self) {
-        // Send shutdown signal to the thread
-        let _ = self.shutdown_tx.send(());
+        self.item_manual_popovers.borrow_mut().remove(service_key);
+        self.pixmap_textures.borrow_mut().remove(service_key);
+        self.items.borrow_mut().remove(service_key);
+    }
 
-        let thread_handle = self.thread_handle.clone();
+    /// Get the manual Popover for a given service key (with icon support)
+    pub fn get_manual_popover_for_service_key(&self, service_key: &str) -> Option<gtk4::Popover> {
+        self.item_manual_popovers.borrow().get(service_key).cloned()
+    }
 
-        if let Ok(thread_handle) = Arc::try_unwrap(thread_handle) {
-            // If we can unwrap, it means there are no other references to the thread handle
-            // and we can safely join it.
-            if let Err(e) = thread_handle.join() {
-                eprintln!("Error joining tray thread: {:?}", e);
-            }
+    /// Create a basic menu for a tray item
+    fn create_menu_for_item(&self, service_key: &str, item: &StatusNotifierItem, button: &Button) {
+        // Check if the tray source has menu data for this item
+        if let Some(menu) = self.source().menu(service_key) {
+            // Create a menu from actual menu data using manual approach for better icon support
+            let popover = crate::tray_widget::popover_menu::create_popover_menu(
+                button,
+                &menu.submenus,
+                service_key,
+                self.source(),
+            );
+
+            // Store the manual popover for display
+            self.item_manual_popovers
+                .borrow_mut()
+                .insert(service_key.to_string(), popover);
         }
-
-        // Clear items and buttons
-        self.items.lock().unwrap().clear();
-        self.item_buttons.lock().unwrap().clear();
     }
 }
+
+/// Whether a `hidden` or `order` entry refers to `item`
+fn matches_item(pattern: &str, item: &StatusNotifierItem) -> bool {
+    pattern.eq_ignore_ascii_case(&item.id)
+        || item
+            .title
+            .as_deref()
+            .is_some_and(|title| pattern.eq_ignore_ascii_case(title))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,346 @@
+// The tray widget fed by a scripted source, checked through the buttons it ends up
+// showing. GTK only runs on the thread that initialized it, so every test hands its
+// body to one shared GTK thread. Without a display, e.g. broadwayd with
+// `GDK_BACKEND=broadway`, the tests are skipped.
+
+use super::*;
+use std::ops::Deref;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use system_tray::client::ActivateRequest;
+use system_tray::item::Status;
+use system_tray::menu::TrayMenu;
+
+use crate::sources::SourceFuture;
+
+/// How long to wait for events to travel from the source to the widget
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Body of a test, told whether GTK could be initialized
+type Job = Box<dyn FnOnce(bool) + Send>;
+
+/// Run `test` on the GTK thread, re-raising its panic on the calling one
+fn on_gtk_thread(test: impl FnOnce() + Send + 'static) {
+    static GTK_THREAD: OnceLock<mpsc::Sender<Job>> = OnceLock::new();
+    let jobs = GTK_THREAD.get_or_init(|| {
+        let (sender, jobs) = mpsc::channel::<Job>();
+        std::thread::spawn(move || {
+            let available = gtk4::init().is_ok();
+            let runtime = tokio::runtime::Runtime::new().expect("Failed to start Tokio");
+            let _runtime = runtime.enter();
+            for job in jobs {
+                job(available);
+            }
+        });
+        sender
+    });
+
+    let (done_tx, done) = mpsc::channel();
+    let job: Job = Box::new(move |available| {
+        if !available {
+            eprintln!("No display to initialize GTK on, skipping");
+            let _ = done_tx.send(Ok(()));
+            return;
+        }
+        let _ = done_tx.send(std::panic::catch_unwind(AssertUnwindSafe(test)));
+    });
+    jobs.send(job).expect("GTK thread is gone");
+
+    if let Err(panic) = done.recv().expect("GTK thread is gone") {
+        std::panic::resume_unwind(panic);
+    }
+}
+
+/// Run the main loop until `done` holds, giving up after `SETTLE_TIMEOUT`
+fn settle(mut done: impl FnMut() -> bool) {
+    let context = glib::MainContext::default();
+    let deadline = Instant::now() + SETTLE_TIMEOUT;
+    while !done() && Instant::now() < deadline {
+        while context.iteration(false) {}
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Tray whose items and events come from the test
+struct ScriptedTray {
+    items: Vec<(String, StatusNotifierItem)>,
+    events: broadcast::Sender<TrayEvent>,
+    /// Service key, delta and orientation of every scroll sent to an item
+    scrolls: Mutex<Vec<(String, i32, String)>>,
+}
+
+impl ScriptedTray {
+    fn new(items: Vec<StatusNotifierItem>) -> Arc<Self> {
+        let (events, _) = broadcast::channel(16);
+        Arc::new(ScriptedTray {
+            items: items
+                .into_iter()
+                .map(|item| (key(&item.id), item))
+                .collect(),
+            events,
+            scrolls: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn send(&self, event: TrayEvent) {
+        assert!(
+            self.events.send(event).is_ok(),
+            "Nothing listens to the tray"
+        );
+    }
+}
+
+impl TraySource for ScriptedTray {
+    fn items(&self) -> Vec<(String, StatusNotifierItem)> {
+        self.items.clone()
+    }
+
+    fn menu(&self, _service_key: &str) -> Option<TrayMenu> {
+        None
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<TrayEvent> {
+        self.events.subscribe()
+    }
+
+    fn lost(&self) -> SourceFuture<'static, ()> {
+        Box::pin(std::future::pending())
+    }
+
+    fn activate(&self, _request: ActivateRequest) -> SourceFuture<'_, Result<(), String>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn scroll<'a>(
+        &'a self,
+        service_key: &'a str,
+        delta: i32,
+        orientation: &'a str,
+    ) -> SourceFuture<'a, Result<(), String>> {
+        self.scrolls.lock().unwrap().push((
+            service_key.to_string(),
+            delta,
+            orientation.to_string(),
+        ));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn provide_xdg_activation_token<'a>(
+        &'a self,
+        _service_key: &'a str,
+        _token: String,
+    ) -> SourceFuture<'a, Result<(), String>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+fn key(id: &str) -> String {
+    format!(":1.{}/StatusNotifierItem", id)
+}
+
+fn item(id: &str) -> StatusNotifierItem {
+    StatusNotifierItem {
+        id: id.to_string(),
+        title: Some(id.to_string()),
+        status: Status::Active,
+        ..Default::default()
+    }
+}
+
+/// Tray running the way the tray module runs it, stopped when dropped
+struct RunningTray {
+    tray: Rc<TrayWidget>,
+    task: glib::JoinHandle<()>,
+}
+
+impl Deref for RunningTray {
+    type Target = TrayWidget;
+
+    fn deref(&self) -> &TrayWidget {
+        &self.tray
+    }
+}
+
+impl Drop for RunningTray {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Tray on `source`, once it listens for events
+fn tray_on(source: &Arc<ScriptedTray>, config: TrayConfig) -> RunningTray {
+    let tray = TrayWidget::with_source(Orientation::Horizontal, config, source.clone());
+    let task = glib::spawn_future_local({
+        let tray = tray.clone();
+        async move { tray.run().await }
+    });
+    settle(|| source.events.receiver_count() > 0);
+    RunningTray { tray, task }
+}
+
+/// Ids of the items whose buttons are shown on the bar, in order
+fn on_bar(tray: &TrayWidget) -> Vec<String> {
+    let keys: Vec<(String, Button)> = tray
+        .item_buttons
+        .borrow()
+        .iter()
+        .map(|(key, button)| (key.clone(), button.clone()))
+        .collect();
+    let items = tray.items.borrow();
+
+    let mut shown = Vec::new();
+    let mut child = tray.container.first_child();
+    while let Some(widget) = child {
+        child = widget.next_sibling();
+        if !widget.is_visible() {
+            continue;
+        }
+        let key = keys
+            .iter()
+            .find(|(_, button)| button.upcast_ref::<gtk4::Widget>() == &widget)
+            .map(|(key, _)| key);
+        if let Some(key) = key {
+            shown.push(items[key].id.clone());
+        }
+    }
+    shown
+}
+
+fn button_of(tray: &TrayWidget, id: &str) -> Button {
+    tray.item_buttons.borrow()[&key(id)].clone()
+}
+
+#[test]
+fn shows_initial_items_in_configured_order() {
+    on_gtk_thread(|| {
+        let source = ScriptedTray::new(vec![item("chat"), item("mail"), item("sync")]);
+        let config = TrayConfig {
+            order: vec!["SYNC".to_string()],
+            hidden: vec!["mail".to_string()],
+            ..Default::default()
+        };
+        let tray = tray_on(&source, config);
+
+        settle(|| on_bar(&tray).len() == 2);
+        assert_eq!(on_bar(&tray), ["sync", "chat"]);
+    });
+}
+
+#[test]
+fn adds_and_removes_items() {
+    on_gtk_thread(|| {
+        let source = ScriptedTray::new(vec![item("chat")]);
+        let tray = tray_on(&source, TrayConfig::default());
+
+        source.send(TrayEvent::Add(key("mail"), Box::new(item("mail"))));
+        settle(|| on_bar(&tray).len() == 2);
+        assert_eq!(on_bar(&tray), ["chat", "mail"]);
+
+        source.send(TrayEvent::Remove(key("chat")));
+        settle(|| on_bar(&tray).len() == 1);
+        assert_eq!(on_bar(&tray), ["mail"]);
+        assert!(!tray.items.borrow().contains_key(&key("chat")));
+    });
+}
+
+#[test]
+fn passive_items_free_their_overflow_slot() {
+    on_gtk_thread(|| {
+        let source = ScriptedTray::new(vec![item("chat"), item("mail"), item("sync")]);
+        let config = TrayConfig {
+            max_visible: 2,
+            ..Default::default()
+        };
+        let tray = tray_on(&source, config);
+
+        settle(|| tray.overflow.is_visible());
+        assert_eq!(on_bar(&tray), ["chat", "mail"]);
+        assert!(tray.overflow.is_visible());
+
+        source.send(TrayEvent::Update(
+            key("chat"),
+            UpdateEvent::Status(Status::Passive),
+        ));
+        settle(|| !tray.overflow.is_visible());
+        assert_eq!(on_bar(&tray), ["mail", "sync"]);
+        assert!(!tray.overflow.is_visible());
+    });
+}
+
+#[test]
+fn expanded_overflow_keeps_every_item_on_the_bar() {
+    on_gtk_thread(|| {
+        let source = ScriptedTray::new(vec![item("chat"), item("mail"), item("sync")]);
+        let config = TrayConfig {
+            max_visible: 2,
+            ..Default::default()
+        };
+        let tray = tray_on(&source, config);
+        settle(|| tray.overflow.is_visible());
+
+        tray.set_overflow_expanded(true);
+        assert_eq!(on_bar(&tray), ["chat", "mail", "sync"]);
+        assert!(tray.overflow.is_visible());
+
+        tray.set_overflow_expanded(false);
+        assert_eq!(on_bar(&tray), ["chat", "mail"]);
+    });
+}
+
+#[test]
+fn updates_apply_the_new_values() {
+    on_gtk_thread(|| {
+        let source = ScriptedTray::new(vec![item("mail")]);
+        let tray = tray_on(&source, TrayConfig::default());
+        settle(|| on_bar(&tray).len() == 1);
+
+        source.send(TrayEvent::Update(
+            key("mail"),
+            UpdateEvent::Title(Some("3 unread".to_string())),
+        ));
+        source.send(TrayEvent::Update(
+            key("mail"),
+            UpdateEvent::Status(Status::NeedsAttention),
+        ));
+        settle(|| button_of(&tray, "mail").has_css_class("needs-attention"));
+
+        let button = button_of(&tray, "mail");
+        assert!(button.has_css_class("needs-attention"));
+        assert_eq!(button.tooltip_text().as_deref(), Some("3 unread"));
+    });
+}
+
+#[test]
+fn scrolling_sends_whole_steps() {
+    on_gtk_thread(|| {
+        let source = ScriptedTray::new(vec![item("volume")]);
+        let tray = tray_on(&source, TrayConfig::default());
+        settle(|| on_bar(&tray).len() == 1);
+
+        let scroll = button_of(&tray, "volume")
+            .observe_controllers()
+            .iter::<gtk4::EventController>()
+            .filter_map(Result::ok)
+            .find_map(|controller| controller.downcast::<gtk4::EventControllerScroll>().ok())
+            .expect("Tray buttons take scrolling");
+        let scroll_by = |dx: f64, dy: f64| scroll.emit_by_name::<bool>("scroll", &[&dx, &dy]);
+
+        // Smooth scrolling adds up to a step, whatever is left carries over
+        scroll_by(0.0, 0.4);
+        scroll_by(0.0, 0.4);
+        scroll_by(0.0, 0.4);
+        // Down and right in GTK are negative in SNI
+        scroll_by(-2.0, 0.0);
+        settle(|| source.scrolls.lock().unwrap().len() == 2);
+
+        assert_eq!(
+            *source.scrolls.lock().unwrap(),
+            [
+                (key("volume"), -1, "vertical".to_string()),
+                (key("volume"), 2, "horizontal".to_string()),
+            ]
+        );
+    });
+}
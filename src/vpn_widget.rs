@@ -0,0 +1,201 @@
+use gtk4::prelude::*;
+use gtk4::{Button, Label};
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::command::{is_available, run_shell};
+use crate::config::VpnConfig;
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::scheduler::next_tick;
+
+/// An active tunnel interface
+#[derive(Debug, Clone, PartialEq)]
+struct Tunnel {
+    interface: String,
+    /// NetworkManager connection using the interface, when there is one
+    connection: Option<String>,
+}
+
+/// Lock icon while a WireGuard, Tailscale, Mullvad or other tunnel interface is up,
+/// click brings the configured VPN up or down
+pub struct VpnWidget {
+    pub button: Button,
+    state: Rc<VpnState>,
+}
+
+struct VpnState {
+    config: VpnConfig,
+    label: Label,
+    tunnels: RefCell<Vec<Tunnel>>,
+    nmcli: bool,
+}
+
+impl VpnWidget {
+    pub fn new(config: &VpnConfig) -> Self {
+        let button = Button::new();
+        button.add_css_class("vpn-button");
+
+        let label = Label::new(None);
+        label.add_css_class("vpn-label");
+        button.set_child(Some(&label));
+
+        let state = Rc::new(VpnState {
+            config: config.clone(),
+            label,
+            tunnels: RefCell::new(Vec::new()),
+            nmcli: is_available("nmcli"),
+        });
+        state.show();
+
+        let state_click = state.clone();
+        button.connect_clicked(move |_| {
+            let state = state_click.clone();
+            glib::spawn_future_local(async move {
+                state.toggle().await;
+                state.update().await;
+            });
+        });
+
+        VpnWidget { button, state }
+    }
+}
+
+impl VpnState {
+    async fn update(&self) {
+        let mut tunnels: Vec<Tunnel> = active_interfaces(&self.config.interfaces)
+            .into_iter()
+            .map(|interface| Tunnel {
+                interface,
+                connection: None,
+            })
+            .collect();
+
+        if self.nmcli && !tunnels.is_empty() {
+            // Lines of "name:device" for the active connections
+            let active = run_shell("nmcli -t -f NAME,DEVICE connection show --active")
+                .await
+                .unwrap_or_default();
+            for line in active.lines() {
+                let Some((name, device)) = line.rsplit_once(':') else {
+                    continue;
+                };
+                if let Some(tunnel) = tunnels.iter_mut().find(|tunnel| tunnel.interface == device) {
+                    tunnel.connection = Some(name.replace("\\:", ":"));
+                }
+            }
+        }
+
+        if *self.tunnels.borrow() != tunnels {
+            *self.tunnels.borrow_mut() = tunnels;
+            self.show();
+        }
+    }
+
+    fn show(&self) {
+        let tunnels = self.tunnels.borrow();
+        let name = |tunnel: &Tunnel| {
+            tunnel
+                .connection
+                .clone()
+                .unwrap_or_else(|| tunnel.interface.clone())
+        };
+
+        match tunnels.first() {
+            Some(tunnel) if tunnels.len() == 1 => {
+                self.label.set_text(&format!("{} {}", self.config.active_icon, name(tunnel)))
+            }
+            Some(_) => self
+                .label
+                .set_text(&format!("{} {}", self.config.active_icon, tunnels.len())),
+            None => self.label.set_text(&self.config.inactive_icon),
+        }
+
+        if let Some(button) = self.label.parent() {
+            if tunnels.is_empty() {
+                button.remove_css_class("connected");
+                button.set_tooltip_text(Some("No VPN connected"));
+            } else {
+                button.add_css_class("connected");
+                let lines: Vec<String> = tunnels
+                    .iter()
+                    .map(|tunnel| match &tunnel.connection {
+                        Some(connection) => format!("{} ({})", connection, tunnel.interface),
+                        None => tunnel.interface.clone(),
+                    })
+                    .collect();
+                button.set_tooltip_text(Some(&format!("VPN connected\n{}", lines.join("\n"))));
+            }
+        }
+        control::module_updated("vpn");
+    }
+
+    /// Bring the VPN down when a tunnel is up, otherwise up, with the configured
+    /// commands or the NetworkManager connection
+    async fn toggle(&self) {
+        let connected = !self.tunnels.borrow().is_empty();
+        let configured = if connected { &self.config.down } else { &self.config.up };
+        let command = configured.clone().or_else(|| {
+            let connection = self.config.connection.as_deref()?;
+            let action = if connected { "down" } else { "up" };
+            Some(format!("nmcli connection {} id {}", action, quote(connection)))
+        });
+        let Some(command) = command else {
+            warn!("Module 'vpn' needs 'up' and 'down' or 'connection' to toggle the VPN");
+            return;
+        };
+        // Waits for the command so the next update sees the new state
+        run_shell(&command).await;
+    }
+}
+
+fn quote(value: &str) -> String {
+    gtk4::glib::shell_quote(value).to_string_lossy().into_owned()
+}
+
+/// Network interfaces that are up and match one of `patterns`, `*` at the end
+/// matching any suffix
+fn active_interfaces(patterns: &[String]) -> Vec<String> {
+    let Ok(entries) = fs::read_dir("/sys/class/net") else {
+        return Vec::new();
+    };
+    let mut interfaces: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| {
+            patterns.iter().any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern,
+            })
+        })
+        .filter(|name| {
+            // Tunnels have no carrier detection and report "unknown" while up
+            let operstate = fs::read_to_string(format!("/sys/class/net/{}/operstate", name));
+            operstate.is_ok_and(|state| state.trim() != "down")
+        })
+        .collect();
+    interfaces.sort();
+    interfaces
+}
+
+impl BarModule for VpnWidget {
+    fn name(&self) -> &'static str {
+        "vpn"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            loop {
+                self.state.update().await;
+                next_tick(Duration::from_secs(self.state.config.interval.max(1))).await;
+            }
+        })
+    }
+}
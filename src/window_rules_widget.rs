@@ -0,0 +1,229 @@
+use gtk4::prelude::*;
+use gtk4::{
+    Box as GtkBox, Button, CheckButton, Entry, Image, Label, Orientation, Popover, SpinButton,
+};
+use std::cell::RefCell;
+use std::process::Command;
+use std::rc::Rc;
+use tracing::warn;
+
+use crate::compositor::{Compositor, run_json};
+use crate::module::BarModule;
+
+/// The window the popover was opened for
+#[derive(Debug, Clone)]
+struct TargetWindow {
+    /// Hex address used to address the window in dispatchers, e.g. `0x55d1c2a8e0f0`
+    address: String,
+    class: String,
+    title: String,
+}
+
+/// A rule offered in the popover, applied to the window now and optionally to its class
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    Float,
+    Pin,
+    Opacity,
+    Workspace,
+}
+
+/// Apply common window rules (float, pin, opacity, workspace) to the focused Hyprland window
+pub struct WindowRulesWidget {
+    pub button: Button,
+}
+
+struct WindowRulesState {
+    window: RefCell<Option<TargetWindow>>,
+    title: Label,
+    class_entry: Entry,
+    /// Also add the rule for future windows of the class, until Hyprland reloads
+    remember: CheckButton,
+    opacity: SpinButton,
+    workspace: SpinButton,
+    actions: GtkBox,
+}
+
+impl WindowRulesWidget {
+    pub fn new() -> Option<Self> {
+        if Compositor::detect()? != Compositor::Hyprland {
+            return None;
+        }
+
+        let button = Button::new();
+        button.add_css_class("window-rules-button");
+        button.set_child(Some(&Image::from_icon_name("window-new-symbolic")));
+        button.set_tooltip_text(Some("Window rules"));
+
+        let popover = Popover::new();
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+
+        let content = GtkBox::new(Orientation::Vertical, 6);
+        content.add_css_class("window-rules");
+
+        let title = Label::new(None);
+        title.add_css_class("window-rules-title");
+        title.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+        title.set_max_width_chars(40);
+        title.set_xalign(0.0);
+        content.append(&title);
+
+        let class_row = GtkBox::new(Orientation::Horizontal, 8);
+        let class_label = Label::new(Some("Class"));
+        let class_entry = Entry::new();
+        class_entry.set_hexpand(true);
+        class_row.append(&class_label);
+        class_row.append(&class_entry);
+        content.append(&class_row);
+
+        let remember = CheckButton::with_label("Also for new windows of this class");
+        content.append(&remember);
+
+        let actions = GtkBox::new(Orientation::Vertical, 4);
+        let opacity = SpinButton::with_range(0.1, 1.0, 0.05);
+        opacity.set_value(0.9);
+        opacity.set_digits(2);
+        let workspace = SpinButton::with_range(1.0, 99.0, 1.0);
+        content.append(&actions);
+        popover.set_child(Some(&content));
+
+        let state = Rc::new(WindowRulesState {
+            window: RefCell::new(None),
+            title,
+            class_entry,
+            remember,
+            opacity: opacity.clone(),
+            workspace: workspace.clone(),
+            actions: actions.clone(),
+        });
+
+        let quick = GtkBox::new(Orientation::Horizontal, 4);
+        quick.set_homogeneous(true);
+        for (rule, label) in [(Rule::Float, "Float"), (Rule::Pin, "Pin")] {
+            quick.append(&state.action_button(rule, label));
+        }
+        actions.append(&quick);
+        actions.append(&value_row("Opacity", &opacity, state.action_button(Rule::Opacity, "Set")));
+        actions.append(&value_row(
+            "Workspace",
+            &workspace,
+            state.action_button(Rule::Workspace, "Move"),
+        ));
+
+        let state_click = state.clone();
+        button.connect_clicked(move |_| {
+            // Read before the popover opens, while the window still has focus
+            state_click.load_focused();
+            popover.popup();
+        });
+
+        Some(WindowRulesWidget { button })
+    }
+}
+
+/// A label, a value and the button applying it
+fn value_row(title: &str, value: &SpinButton, apply: Button) -> GtkBox {
+    let row = GtkBox::new(Orientation::Horizontal, 8);
+    let label = Label::new(Some(title));
+    label.set_hexpand(true);
+    label.set_xalign(0.0);
+    row.append(&label);
+    row.append(value);
+    row.append(&apply);
+    row
+}
+
+impl WindowRulesState {
+    fn action_button(self: &Rc<Self>, rule: Rule, label: &str) -> Button {
+        let button = Button::with_label(label);
+        button.add_css_class("window-rules-action");
+        let state = Rc::downgrade(self);
+        button.connect_clicked(move |_| {
+            if let Some(state) = state.upgrade() {
+                state.apply(rule);
+            }
+        });
+        button
+    }
+
+    fn load_focused(&self) {
+        let window = focused_window();
+        match &window {
+            Some(window) => {
+                self.title.set_text(&window.title);
+                self.class_entry.set_text(&window.class);
+            }
+            None => {
+                self.title.set_text("No focused window");
+                self.class_entry.set_text("");
+            }
+        }
+        self.actions.set_sensitive(window.is_some());
+        *self.window.borrow_mut() = window;
+    }
+
+    fn apply(&self, rule: Rule) {
+        let Some(window) = self.window.borrow().clone() else {
+            return;
+        };
+        let target = format!("address:{}", window.address);
+        let opacity = format!("{:.2}", self.opacity.value());
+        let workspace = self.workspace.value_as_int().to_string();
+
+        let dispatch: Vec<String> = match rule {
+            Rule::Float => vec!["togglefloating".into(), target],
+            Rule::Pin => vec!["pin".into(), target],
+            Rule::Opacity => vec!["setprop".into(), target, "alpha".into(), opacity.clone()],
+            Rule::Workspace => {
+                vec!["movetoworkspacesilent".into(), format!("{},{}", workspace, target)]
+            }
+        };
+        hyprctl("dispatch", &dispatch);
+
+        let class = self.class_entry.text();
+        if self.remember.is_active() && !class.is_empty() {
+            let effect = match rule {
+                Rule::Float => "float".to_string(),
+                Rule::Pin => "pin".to_string(),
+                Rule::Opacity => format!("opacity {}", opacity),
+                Rule::Workspace => format!("workspace {} silent", workspace),
+            };
+            let rule = format!("{}, class:^({})$", effect, regex::escape(&class));
+            hyprctl("keyword", &["windowrulev2".to_string(), rule]);
+        }
+    }
+}
+
+/// The focused window, `None` when nothing has focus
+fn focused_window() -> Option<TargetWindow> {
+    let window = run_json(Command::new("hyprctl").args(["activewindow", "-j"]))?;
+    Some(TargetWindow {
+        address: window["address"].as_str()?.to_string(),
+        class: window["class"].as_str().unwrap_or_default().to_string(),
+        title: window["title"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+fn hyprctl(command: &str, args: &[String]) {
+    match Command::new("hyprctl").arg(command).args(args).output() {
+        // hyprctl reports most failures on stdout with a successful exit code
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            if !output.status.success() || !stdout.trim().eq_ignore_ascii_case("ok") {
+                warn!("hyprctl {} {} failed: {}", command, args.join(" "), stdout.trim());
+            }
+        }
+        Err(e) => warn!("Failed to run hyprctl {}: {}", command, e),
+    }
+}
+
+impl BarModule for WindowRulesWidget {
+    fn name(&self) -> &'static str {
+        "window_rules"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+}
@@ -0,0 +1,297 @@
+use gtk4::prelude::*;
+use gtk4::{
+    Box as GtkBox, Button, Image, Label, ListBox, ListBoxRow, Orientation, Popover,
+    ScrolledWindow, SearchEntry, SelectionMode,
+};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::command::spawn_detached;
+use crate::compositor::{Compositor, query_json};
+use crate::module::BarModule;
+
+/// A single open window reported by the compositor
+#[derive(Debug, Clone)]
+struct WindowInfo {
+    /// Compositor specific identifier used to focus the window
+    id: String,
+    app_id: String,
+    title: String,
+    workspace: String,
+}
+
+/// Enumerate open windows over compositor IPC
+async fn list_windows(compositor: Compositor) -> Vec<WindowInfo> {
+    match compositor {
+        Compositor::Hyprland => list_hyprland_windows().await,
+        Compositor::Sway => list_sway_windows().await,
+    }
+}
+
+fn focus_window(compositor: Compositor, window: &WindowInfo) {
+    match compositor {
+        Compositor::Hyprland => spawn_detached(&[
+            "hyprctl",
+            "dispatch",
+            "focuswindow",
+            &format!("address:{}", window.id),
+        ]),
+        Compositor::Sway => spawn_detached(&["swaymsg", &format!("[con_id={}] focus", window.id)]),
+    }
+}
+
+async fn list_hyprland_windows() -> Vec<WindowInfo> {
+    let Some(clients) = query_json(&["hyprctl", "clients", "-j"]).await else {
+        return Vec::new();
+    };
+
+    clients
+        .as_array()
+        .map(|clients| {
+            clients
+                .iter()
+                .filter(|client| client["mapped"].as_bool().unwrap_or(true))
+                .map(|client| WindowInfo {
+                    id: client["address"].as_str().unwrap_or_default().to_string(),
+                    app_id: client["class"].as_str().unwrap_or_default().to_string(),
+                    title: client["title"].as_str().unwrap_or_default().to_string(),
+                    workspace: client["workspace"]["name"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+async fn list_sway_windows() -> Vec<WindowInfo> {
+    let Some(tree) = query_json(&["swaymsg", "-t", "get_tree", "-r"]).await else {
+        return Vec::new();
+    };
+
+    let mut windows = Vec::new();
+    collect_sway_windows(&tree, "", &mut windows);
+    windows
+}
+
+/// Recursively walk the sway tree collecting every view together with its workspace
+fn collect_sway_windows(node: &Value, workspace: &str, windows: &mut Vec<WindowInfo>) {
+    let workspace = if node["type"] == "workspace" {
+        node["name"].as_str().unwrap_or(workspace)
+    } else {
+        workspace
+    };
+
+    // Views are leaf containers that have a pid
+    if node["pid"].is_number() {
+        let app_id = node["app_id"]
+            .as_str()
+            .or_else(|| node["window_properties"]["class"].as_str())
+            .unwrap_or_default();
+
+        windows.push(WindowInfo {
+            id: node["id"].to_string(),
+            app_id: app_id.to_string(),
+            title: node["name"].as_str().unwrap_or_default().to_string(),
+            workspace: workspace.to_string(),
+        });
+    }
+
+    for key in ["nodes", "floating_nodes"] {
+        if let Some(children) = node[key].as_array() {
+            for child in children {
+                collect_sway_windows(child, workspace, windows);
+            }
+        }
+    }
+}
+
+pub struct WindowSwitcher {
+    pub button: Button,
+}
+
+impl WindowSwitcher {
+    pub fn new() -> Option<Self> {
+        // Only available when running under a compositor we can talk to
+        let compositor = Compositor::detect()?;
+
+        let button = Button::new();
+        button.add_css_class("window-switcher-button");
+        button.set_child(Some(&Image::from_icon_name("view-app-grid-symbolic")));
+        button.set_tooltip_text(Some("Switch window"));
+
+        let popover = Popover::new();
+        popover.set_parent(&button);
+        popover.set_has_arrow(true);
+        popover.add_css_class("window-switcher");
+
+        let content = GtkBox::new(Orientation::Vertical, 6);
+
+        let search_entry = SearchEntry::new();
+        search_entry.set_placeholder_text(Some("Filter windows…"));
+        content.append(&search_entry);
+
+        let list_box = ListBox::new();
+        list_box.set_selection_mode(SelectionMode::Browse);
+        list_box.add_css_class("window-switcher-list");
+
+        let scrolled = ScrolledWindow::new();
+        scrolled.set_min_content_width(360);
+        scrolled.set_max_content_height(400);
+        scrolled.set_propagate_natural_height(true);
+        scrolled.set_child(Some(&list_box));
+        content.append(&scrolled);
+
+        popover.set_child(Some(&content));
+
+        let windows: Rc<RefCell<Vec<WindowInfo>>> = Rc::new(RefCell::new(Vec::new()));
+
+        // Filter rows as the user types
+        let search_entry_filter = search_entry.clone();
+        let windows_filter = windows.clone();
+        list_box.set_filter_func(move |row| {
+            let query = search_entry_filter.text().to_lowercase();
+            if query.is_empty() {
+                return true;
+            }
+
+            windows_filter
+                .borrow()
+                .get(row.index() as usize)
+                .map(|window| {
+                    window.title.to_lowercase().contains(&query)
+                        || window.app_id.to_lowercase().contains(&query)
+                })
+                .unwrap_or(false)
+        });
+
+        let list_box_changed = list_box.clone();
+        search_entry.connect_search_changed(move |_| {
+            list_box_changed.invalidate_filter();
+            Self::select_first_visible(&list_box_changed);
+        });
+
+        // Enter in the search entry focuses the selected (or first visible) window
+        let list_box_activate = list_box.clone();
+        search_entry.connect_activate(move |_| {
+            let row = list_box_activate
+                .selected_row()
+                .filter(|row| row.is_child_visible())
+                .or_else(|| Self::first_visible_row(&list_box_activate));
+
+            if let Some(row) = row {
+                row.activate();
+            }
+        });
+
+        let windows_activate = windows.clone();
+        let popover_weak = popover.downgrade();
+        list_box.connect_row_activated(move |_, row| {
+            if let Some(window) = windows_activate.borrow().get(row.index() as usize) {
+                focus_window(compositor, window);
+            }
+            if let Some(popover) = popover_weak.upgrade() {
+                popover.popdown();
+            }
+        });
+
+        // Refresh the window list every time the popover is opened
+        let list_box_refresh = list_box.clone();
+        let search_entry_refresh = search_entry.clone();
+        let windows_refresh = windows.clone();
+        popover.connect_show(move |_| {
+            search_entry_refresh.set_text("");
+            search_entry_refresh.grab_focus();
+            let list_box = list_box_refresh.clone();
+            let windows = windows_refresh.clone();
+            glib::spawn_future_local(async move {
+                let listed = list_windows(compositor).await;
+                Self::populate(&list_box, &windows, listed);
+            });
+        });
+
+        let popover_click = popover.clone();
+        button.connect_clicked(move |_| {
+            popover_click.popup();
+        });
+
+        Some(WindowSwitcher { button })
+    }
+
+    fn populate(
+        list_box: &ListBox,
+        windows: &Rc<RefCell<Vec<WindowInfo>>>,
+        listed: Vec<WindowInfo>,
+    ) {
+        while let Some(row) = list_box.row_at_index(0) {
+            list_box.remove(&row);
+        }
+
+        // Rows are filtered by their index into the list as they are added
+        *windows.borrow_mut() = listed;
+        for window in windows.borrow().iter() {
+            list_box.append(&Self::create_row(window));
+        }
+
+        Self::select_first_visible(list_box);
+    }
+
+    fn create_row(window: &WindowInfo) -> ListBoxRow {
+        let row_box = GtkBox::new(Orientation::Horizontal, 8);
+        row_box.add_css_class("window-switcher-row");
+
+        let icon = Image::from_icon_name(&window.app_id.to_lowercase());
+        icon.set_pixel_size(20);
+        row_box.append(&icon);
+
+        let title = if window.title.is_empty() {
+            &window.app_id
+        } else {
+            &window.title
+        };
+        let title_label = Label::new(Some(title));
+        title_label.set_halign(gtk4::Align::Start);
+        title_label.set_hexpand(true);
+        title_label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+        title_label.set_max_width_chars(40);
+        row_box.append(&title_label);
+
+        let workspace_label = Label::new(Some(&window.workspace));
+        workspace_label.add_css_class("window-switcher-workspace");
+        row_box.append(&workspace_label);
+
+        let row = ListBoxRow::new();
+        row.set_child(Some(&row_box));
+        row.set_tooltip_text(Some(&window.app_id));
+        row
+    }
+
+    fn first_visible_row(list_box: &ListBox) -> Option<ListBoxRow> {
+        let mut index = 0;
+        while let Some(row) = list_box.row_at_index(index) {
+            if row.is_child_visible() {
+                return Some(row);
+            }
+            index += 1;
+        }
+        None
+    }
+
+    fn select_first_visible(list_box: &ListBox) {
+        if let Some(row) = Self::first_visible_row(list_box) {
+            list_box.select_row(Some(&row));
+        }
+    }
+}
+
+impl BarModule for WindowSwitcher {
+    fn name(&self) -> &'static str {
+        "window_switcher"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.button.upcast_ref()
+    }
+}
@@ -0,0 +1,151 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Image, Label, Orientation};
+use regex::Regex;
+use tracing::warn;
+
+use crate::compositor::{ActiveWindow, CompositorBackend};
+use crate::config::WindowTitleConfig;
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::sources;
+
+/// Compiled form of a configured title rewrite rule
+struct RewriteRule {
+    app_id: Option<String>,
+    pattern: Regex,
+    replace: String,
+}
+
+/// Title of the focused window, updated from compositor events.
+/// On vertical bars the title collapses into the application icon.
+pub struct WindowTitleWidget {
+    pub container: GtkBox,
+    label: Label,
+    icon: Image,
+    vertical: bool,
+    backend: Box<dyn CompositorBackend>,
+    show_app_id: bool,
+    rules: Vec<RewriteRule>,
+}
+
+impl WindowTitleWidget {
+    pub fn new(config: &WindowTitleConfig, orientation: Orientation) -> Option<Self> {
+        let backend = sources::compositor_backend()?;
+        let vertical = orientation == Orientation::Vertical;
+
+        let label = Label::new(None);
+        label.add_css_class("window-title");
+        label.set_single_line_mode(true);
+        label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+        label.set_max_width_chars(config.max_length);
+        label.set_visible(!vertical);
+
+        let icon = Image::new();
+        icon.add_css_class("window-title-icon");
+        icon.set_pixel_size(16);
+        icon.set_visible(false);
+
+        let container = GtkBox::new(orientation, 0);
+        container.append(&icon);
+        container.append(&label);
+
+        let rules = config
+            .rewrite
+            .iter()
+            .filter_map(|rule| match Regex::new(&rule.pattern) {
+                Ok(pattern) => Some(RewriteRule {
+                    app_id: rule.app_id.clone(),
+                    pattern,
+                    replace: rule.replace.clone(),
+                }),
+                Err(e) => {
+                    warn!("Invalid window title rewrite pattern '{}': {}", rule.pattern, e);
+                    None
+                }
+            })
+            .collect();
+
+        Some(WindowTitleWidget {
+            container,
+            label,
+            icon,
+            vertical,
+            backend,
+            show_app_id: config.show_app_id,
+            rules,
+        })
+    }
+
+    fn format_title(&self, window: &ActiveWindow) -> String {
+        let mut title = window.title.clone();
+
+        for rule in &self.rules {
+            let applies = rule
+                .app_id
+                .as_ref()
+                .is_none_or(|app_id| app_id.eq_ignore_ascii_case(&window.app_id));
+            if applies {
+                title = rule.pattern.replace_all(&title, rule.replace.as_str()).into_owned();
+            }
+        }
+
+        if self.show_app_id && !window.app_id.is_empty() {
+            format!("{}: {}", window.app_id, title)
+        } else {
+            title
+        }
+    }
+
+    fn refresh(&self) {
+        match self.backend.active_window() {
+            Some(window) => {
+                let text = self.format_title(&window);
+                self.label.set_text(&text);
+                self.container.set_tooltip_text(Some(&window.title));
+
+                if self.vertical {
+                    self.icon.set_icon_name(Some(&window.app_id.to_lowercase()));
+                    self.icon.set_visible(!window.app_id.is_empty());
+                } else {
+                    self.label.set_visible(!text.is_empty());
+                }
+            }
+            None => {
+                self.label.set_text("");
+                self.label.set_visible(false);
+                self.icon.set_visible(false);
+                self.container.set_tooltip_text(None);
+            }
+        }
+
+        control::module_updated("window_title");
+    }
+}
+
+impl BarModule for WindowTitleWidget {
+    fn name(&self) -> &'static str {
+        "window_title"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.container.upcast_ref()
+    }
+
+    fn refresh(&self) {
+        WindowTitleWidget::refresh(self);
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            self.refresh();
+
+            let (changed_tx, mut changed_rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+            self.backend.watch_active_window(changed_tx);
+
+            while changed_rx.recv().await.is_some() {
+                while changed_rx.try_recv().is_ok() {}
+                self.refresh();
+            }
+        })
+    }
+}
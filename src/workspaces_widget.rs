@@ -0,0 +1,96 @@
+use gtk4::prelude::*;
+use gtk4::{Box as GtkBox, Button, Orientation};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::bar;
+use crate::compositor::CompositorBackend;
+use crate::control;
+use crate::module::{BarModule, InitFuture};
+use crate::sources;
+
+/// Workspace buttons for the running compositor, updated from its event stream
+pub struct WorkspacesWidget {
+    pub container: GtkBox,
+    backend: Rc<dyn CompositorBackend>,
+    urgent: Cell<bool>,
+}
+
+impl WorkspacesWidget {
+    pub fn new(orientation: Orientation) -> Option<Self> {
+        let backend: Rc<dyn CompositorBackend> = sources::compositor_backend()?.into();
+
+        let container = GtkBox::new(orientation, 0);
+        container.add_css_class("workspaces");
+
+        Some(WorkspacesWidget {
+            container,
+            backend,
+            urgent: Cell::new(false),
+        })
+    }
+
+    fn refresh(&self) {
+        while let Some(child) = self.container.first_child() {
+            self.container.remove(&child);
+        }
+
+        let workspaces = self.backend.workspaces();
+
+        // Reveal an auto-hidden bar when a workspace becomes urgent
+        let urgent = workspaces.iter().any(|workspace| workspace.urgent);
+        if urgent && !self.urgent.replace(urgent) {
+            bar::request_attention();
+        }
+        self.urgent.set(urgent);
+
+        for workspace in workspaces {
+            let button = Button::with_label(&workspace.name);
+            button.add_css_class("workspace-button");
+            if workspace.focused {
+                button.add_css_class("active");
+            }
+            if workspace.urgent {
+                button.add_css_class("urgent");
+            }
+
+            let backend = self.backend.clone();
+            button.connect_clicked(move |_| {
+                backend.focus_workspace(&workspace);
+            });
+
+            self.container.append(&button);
+        }
+
+        control::module_updated("workspaces");
+    }
+}
+
+impl BarModule for WorkspacesWidget {
+    fn name(&self) -> &'static str {
+        "workspaces"
+    }
+
+    fn widget(&self) -> &gtk4::Widget {
+        self.container.upcast_ref()
+    }
+
+    fn refresh(&self) {
+        WorkspacesWidget::refresh(self);
+    }
+
+    fn init(&self) -> InitFuture<'_> {
+        Box::pin(async move {
+            self.refresh();
+
+            let (changed_tx, mut changed_rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+            self.backend.watch_workspaces(changed_tx);
+
+            while changed_rx.recv().await.is_some() {
+                // Collapse bursts of events (e.g. moving a workspace) into one refresh
+                while changed_rx.try_recv().is_ok() {}
+                self.refresh();
+            }
+        })
+    }
+}